@@ -0,0 +1,213 @@
+//! Removes or hashes the sensitive parts of frames - credentials, configured headers and bodies -
+//! so that captured frames can be kept in traces and attached to support tickets.
+//!
+//! Hashed values are replaced with `sha256-` followed by the first 16 hex digits of their SHA-256
+//! digest, so that equal values can still be matched across captures. As short values such as
+//! passwords can be recovered from an unsalted digest by guessing, the default policy removes the
+//! `passcode` header rather than hashing it.
+//!
+//! # Example
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::anonymize::AnonymizePolicy;
+//! use stomp_parser::client::ClientFrame;
+//!
+//! let frame = ClientFrame::try_from(
+//!     b"CONNECT\nhost:h\naccept-version:1.2\nlogin:alice\npasscode:secret\n\n\x00".to_vec(),
+//! )
+//! .unwrap();
+//!
+//! let anonymized = frame.anonymized(&AnonymizePolicy::default()).unwrap();
+//! assert_eq!(
+//!     &b"CONNECT\nhost:h\naccept-version:1.2\nlogin:sha256-2bd806c97f0e00af\n\n\x00"[..],
+//!     anonymized.as_bytes()
+//! );
+//! ```
+use std::convert::TryFrom;
+
+use crate::client::ClientFrame;
+use crate::common::functions::encode_str;
+use crate::common::raw::RawParts;
+use crate::digest::Sha256;
+use crate::error::StompParseError;
+use crate::headers::HeaderName;
+use crate::server::ServerFrame;
+
+/// The number of bytes of the digest included in hashed values.
+const HASH_LEN: usize = 8;
+
+/// What is done with a sensitive header or body.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Redaction {
+    /// The header or body is left out.
+    Remove,
+    /// The value or body is replaced with a prefix of its digest.
+    Hash,
+}
+
+/// Which headers, and whether the body, of a frame are sensitive, and what is done with them.
+///
+/// The default policy hashes `login` headers and bodies, and removes `passcode` headers.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AnonymizePolicy {
+    headers: Vec<(String, Redaction)>,
+    body: Option<Redaction>,
+}
+
+impl Default for AnonymizePolicy {
+    fn default() -> AnonymizePolicy {
+        AnonymizePolicy::keep_all()
+            .header("login", Redaction::Hash)
+            .header("passcode", Redaction::Remove)
+            .body(Some(Redaction::Hash))
+    }
+}
+
+impl AnonymizePolicy {
+    /// A policy which leaves frames unchanged, to which sensitive headers can then be added.
+    pub fn keep_all() -> AnonymizePolicy {
+        AnonymizePolicy {
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// This policy, treating the header `name` with `redaction`, in place of any earlier
+    /// treatment.
+    pub fn header<N: Into<String>>(mut self, name: N, redaction: Redaction) -> AnonymizePolicy {
+        let name = name.into();
+        self.headers.retain(|(header, _)| *header != name);
+        self.headers.push((name, redaction));
+        self
+    }
+
+    /// This policy, treating bodies with `redaction`, or leaving them unchanged if `None`.
+    pub fn body(mut self, redaction: Option<Redaction>) -> AnonymizePolicy {
+        self.body = redaction;
+        self
+    }
+
+    fn redaction(&self, name: &str) -> Option<Redaction> {
+        self.headers
+            .iter()
+            .find(|(header, _)| encode_str(header) == name)
+            .map(|(_, redaction)| *redaction)
+    }
+}
+
+/// The serialised frame `raw` anonymized according to `policy`. The `content-length` header is
+/// kept, with the new length, if the frame retains a body.
+pub fn anonymize(raw: &[u8], policy: &AnonymizePolicy) -> Vec<u8> {
+    let parts = RawParts::split(raw);
+
+    let body = match policy.body {
+        _ if parts.body.is_empty() => None,
+        None => Some(parts.body.to_vec()),
+        Some(Redaction::Hash) => Some(hash(parts.body).into_bytes()),
+        Some(Redaction::Remove) => None,
+    };
+
+    let mut anonymized = Vec::with_capacity(raw.len());
+    anonymized.extend_from_slice(parts.command.as_bytes());
+    anonymized.push(b'\n');
+
+    let mut has_content_length = false;
+    for (name, value) in &parts.headers {
+        let value = if name == HeaderName::ContentLength.as_str() {
+            if has_content_length || body.is_none() {
+                continue;
+            }
+            has_content_length = true;
+            body.as_ref().map_or(0, Vec::len).to_string()
+        } else {
+            match policy.redaction(name) {
+                None => value.to_string(),
+                Some(Redaction::Hash) => hash(value.as_bytes()),
+                Some(Redaction::Remove) => continue,
+            }
+        };
+        anonymized.extend_from_slice(name.as_bytes());
+        anonymized.push(b':');
+        anonymized.extend_from_slice(value.as_bytes());
+        anonymized.push(b'\n');
+    }
+
+    anonymized.push(b'\n');
+    anonymized.extend_from_slice(&body.unwrap_or_default());
+    anonymized.push(b'\0');
+    anonymized
+}
+
+fn hash(value: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(value);
+    let digest = hasher.finish();
+
+    let mut hashed = String::from("sha256-");
+    for byte in &digest[..HASH_LEN] {
+        hashed.push_str(&format!("{:02x}", byte));
+    }
+    hashed
+}
+
+/// Implements `anonymized` for a group of frames.
+macro_rules! anonymized {
+    ( $group:ident ) => {
+        impl $group {
+            /// A copy of this frame anonymized according to `policy`. Fails if the copy does not
+            /// parse, such as when the policy removes a header which the frame requires.
+            pub fn anonymized(&self, policy: &AnonymizePolicy) -> Result<$group, StompParseError> {
+                $group::try_from(anonymize(self.as_bytes(), policy))
+            }
+        }
+    };
+}
+
+anonymized!(ClientFrame);
+anonymized!(ServerFrame);
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{AnonymizePolicy, Redaction};
+    use crate::client::ClientFrame;
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn applies_policies() {
+        let frame = ServerFrame::try_from(
+            b"MESSAGE\r\ndestination:a\nmessage-id:1\nsubscription:0\ncontent-length:3\nx-account:42\n\nabc\x00"
+                .to_vec(),
+        )
+        .unwrap();
+
+        let policy = AnonymizePolicy::default().header("x-account", Redaction::Hash);
+        assert_eq!(
+            &b"MESSAGE\ndestination:a\nmessage-id:1\nsubscription:0\ncontent-length:23\n\
+               x-account:sha256-73475cb40a568e8d\n\nsha256-ba7816bf8f01cfea\x00"[..],
+            frame.anonymized(&policy).unwrap().as_bytes()
+        );
+
+        let policy = AnonymizePolicy::keep_all()
+            .header("x-account", Redaction::Remove)
+            .body(Some(Redaction::Remove));
+        assert_eq!(
+            &b"MESSAGE\ndestination:a\nmessage-id:1\nsubscription:0\n\n\x00"[..],
+            frame.anonymized(&policy).unwrap().as_bytes()
+        );
+
+        let policy = AnonymizePolicy::keep_all().header("destination", Redaction::Remove);
+        assert!(frame.anonymized(&policy).is_err());
+
+        let frame = ClientFrame::try_from(b"SEND\ndestination:a\n\nhi\x00".to_vec()).unwrap();
+        assert_eq!(
+            frame.as_bytes(),
+            frame
+                .anonymized(&AnonymizePolicy::keep_all())
+                .unwrap()
+                .as_bytes()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Encodes bodies as base64 (RFC 4648), so that frames with binary bodies can be carried over
+//! text-only channels, such as WebSocket text frames. A base64 body is marked by the
+//! `content-transfer-encoding` header.
+use std::borrow::Cow;
+
+use crate::error::StompParseError;
+
+/// The name of the header marking a body as encoded for transfer.
+pub const CONTENT_TRANSFER_ENCODING: &str = "content-transfer-encoding";
+
+/// The value of the `content-transfer-encoding` header for a base64 body.
+pub const BASE64: &str = "base64";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` as base64, with padding.
+pub fn encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| {
+            bits | u32::from(*byte) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes the base64 text `text`. Padding is optional, and whitespace is ignored, as base64 text
+/// is often wrapped.
+pub fn decode(text: &[u8]) -> Result<Vec<u8>, StompParseError> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    let mut padding = 0;
+
+    for byte in text.iter().filter(|byte| !byte.is_ascii_whitespace()) {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding += 1;
+                continue;
+            }
+            _ => {
+                return Err(StompParseError::new(format!(
+                    "Invalid base64: unexpected character {:?}",
+                    char::from(*byte)
+                )))
+            }
+        };
+        if padding > 0 {
+            return Err(StompParseError::new("Invalid base64: data after padding"));
+        }
+
+        bits = bits << 6 | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+
+    if count >= 6 || padding > 2 {
+        return Err(StompParseError::new("Invalid base64: truncated input"));
+    }
+    Ok(out)
+}
+
+/// The body of a frame with the `content-transfer-encoding` header `encoding`, encoded as base64
+/// unless it is already.
+pub(crate) fn encode_body(encoding: Option<&[u8]>, body: &[u8]) -> String {
+    match encoding {
+        Some(encoding) if encoding.eq_ignore_ascii_case(BASE64.as_bytes()) => {
+            String::from_utf8_lossy(body).into_owned()
+        }
+        _ => encode(body),
+    }
+}
+
+/// The body of a frame with the `content-transfer-encoding` header `encoding`, decoded if it is
+/// `base64`, and otherwise unchanged.
+pub(crate) fn decode_body<'a>(
+    encoding: Option<&[u8]>,
+    body: &'a [u8],
+) -> Result<Cow<'a, [u8]>, StompParseError> {
+    match encoding {
+        Some(encoding) if encoding.eq_ignore_ascii_case(BASE64.as_bytes()) => {
+            decode(body).map(Cow::Owned)
+        }
+        _ => Ok(Cow::Borrowed(body)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{decode, encode};
+    use crate::server::{MessageFrameBuilder, ServerFrame};
+
+    #[test]
+    fn round_trips_data() {
+        let cases: [(&[u8], &str); 5] = [
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"\x00\xff\x10binary", "AP8QYmluYXJ5"),
+        ];
+
+        for (data, text) in cases.iter() {
+            assert_eq!(*text, encode(data));
+            assert_eq!(*data, &decode(text.as_bytes()).unwrap()[..]);
+        }
+        assert_eq!(b"fo", &decode(b"Zm8").unwrap()[..]);
+        assert!(decode(b"Zm9v!").is_err());
+        assert!(decode(b"Z").is_err());
+    }
+
+    #[test]
+    fn carries_binary_body_as_text() {
+        let body = vec![0, 1, 2, 0xff, b'\n', 0];
+        let frame =
+            MessageFrameBuilder::new("m1".to_owned(), "/queue/a".to_owned(), "s1".to_owned())
+                .base64_body(body.clone())
+                .build();
+        let bytes: Vec<u8> = frame.into();
+
+        assert!(std::str::from_utf8(&bytes[..bytes.len() - 1]).is_ok());
+
+        if let ServerFrame::Message(frame) = ServerFrame::try_from(bytes).unwrap() {
+            assert_eq!(Some("AAEC/woA".to_owned()), frame.body_base64());
+            assert_eq!(
+                body,
+                frame.body_from_base64().unwrap().unwrap().into_owned()
+            );
+        } else {
+            panic!("Message Frame not parsed correctly");
+        }
+    }
+}
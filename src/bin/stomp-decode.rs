@@ -0,0 +1,201 @@
+//! Decodes STOMP frames from a file, or from stdin, and prints them in a human-readable form or as
+//! JSON, one object per line. With `--encode`, builds a frame from a text description instead.
+//!
+//! A text description is the frame as it would be written by hand: the command on the first line,
+//! one `name:value` header per line, a blank line, and the body. A single newline ending the body
+//! is removed, a `content-length` header is added if the body is not empty, and the null octet is
+//! appended. The frame is parsed before being written, so that only valid frames are produced.
+//!
+//! ```text
+//! stomp-decode [--json] [--lenient] [FILE]
+//! stomp-decode --encode [FILE]
+//! ```
+use std::io::{Read, Write};
+use std::process::ExitCode;
+
+use stomp_parser::any::AnyFrame;
+use stomp_parser::config::ParserConfig;
+use stomp_parser::decoder::{DecodedItem, FrameDecoder};
+use stomp_parser::parse_any;
+
+const USAGE: &str = "usage: stomp-decode [--json | --encode] [--lenient] [FILE]";
+
+enum Mode {
+    Pretty,
+    Json,
+    Encode,
+}
+
+struct Options {
+    mode: Mode,
+    lenient: bool,
+    path: Option<String>,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        mode: Mode::Pretty,
+        lenient: false,
+        path: None,
+    };
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => options.mode = Mode::Json,
+            "--encode" => options.mode = Mode::Encode,
+            "--lenient" => options.lenient = true,
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option {}\n{}", flag, USAGE))
+            }
+            _ if options.path.is_some() => return Err(USAGE.to_owned()),
+            path => options.path = Some(path.to_owned()),
+        }
+    }
+
+    Ok(options)
+}
+
+fn read_input(path: &Option<String>) -> std::io::Result<Vec<u8>> {
+    let mut input = Vec::new();
+    match path {
+        Some(path) => input = std::fs::read(path)?,
+        None => {
+            std::io::stdin().read_to_end(&mut input)?;
+        }
+    }
+    Ok(input)
+}
+
+/// Decodes every frame in `input`, printing each one, and any errors. Returns false if any errors
+/// were encountered.
+fn decode(input: &[u8], options: &Options) -> bool {
+    let config = if options.lenient {
+        ParserConfig::lenient()
+    } else {
+        ParserConfig::default()
+    };
+
+    let mut decoder = FrameDecoder::<AnyFrame>::with_config(config);
+    decoder.extend(input);
+
+    let mut succeeded = true;
+    loop {
+        match decoder.decode() {
+            Ok(Some(DecodedItem::Frame(frame))) => match options.mode {
+                Mode::Json => println!("{}", frame.to_json()),
+                _ => println!("{}", frame.pretty()),
+            },
+            Ok(Some(DecodedItem::HeartBeat)) => {}
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("error: {}", error.message());
+                succeeded = false;
+            }
+        }
+    }
+
+    if decoder.is_mid_frame() {
+        eprintln!(
+            "error: incomplete frame at end of input ({} bytes)",
+            decoder.buffered_len()
+        );
+        succeeded = false;
+    }
+
+    succeeded
+}
+
+/// Builds the bytes of a frame from its text `description`.
+fn encode(description: &[u8]) -> Vec<u8> {
+    let description = description.strip_suffix(b"\n").unwrap_or(description);
+
+    let (head, body) = match find(description, b"\n\n") {
+        Some(index) => (&description[..index + 1], &description[index + 2..]),
+        None => (description, &[][..]),
+    };
+
+    let mut frame = head.to_vec();
+    if !frame.ends_with(b"\n") {
+        frame.push(b'\n');
+    }
+    let has_content_length = head
+        .split(|b| *b == b'\n')
+        .any(|line| line.starts_with(b"content-length:"));
+    if !body.is_empty() && !has_content_length {
+        frame.extend_from_slice(format!("content-length:{}\n", body.len()).as_bytes());
+    }
+    frame.push(b'\n');
+    frame.extend_from_slice(body);
+    frame.push(b'\0');
+
+    frame
+}
+
+fn find(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let input = match read_input(&options.path) {
+        Ok(input) => input,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Mode::Encode = options.mode {
+        let frame = encode(&input);
+        if let Err(error) = parse_any(frame.clone()) {
+            eprintln!("error: {}", error.message());
+            return ExitCode::FAILURE;
+        }
+        if let Err(error) = std::io::stdout().write_all(&frame) {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    if decode(&input, &options) {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::encode;
+    use stomp_parser::parse_any;
+
+    #[test]
+    fn encodes_description() {
+        let frame = encode(b"SEND\ndestination:/queue/a\n\nhello\n");
+
+        assert_eq!(
+            b"SEND\ndestination:/queue/a\ncontent-length:5\n\nhello\x00".to_vec(),
+            frame
+        );
+        assert!(parse_any(frame).is_ok());
+    }
+
+    #[test]
+    fn encodes_description_without_body() {
+        assert_eq!(
+            b"BEGIN\ntransaction:a\n\n\x00".to_vec(),
+            encode(b"BEGIN\ntransaction:a\n")
+        );
+    }
+}
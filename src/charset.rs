@@ -0,0 +1,153 @@
+//! Decodes bodies as text according to their `content-type`, following the specification: a body
+//! with a `text/*` type and no `charset` parameter is UTF-8, while a body without a `content-type`
+//! is an opaque sequence of octets, which is never decoded implicitly.
+//!
+//! # Example
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::client::ClientFrame;
+//!
+//! let frame = ClientFrame::try_from(
+//!     b"SEND\ndestination:a\ncontent-type:text/plain;charset=iso-8859-1\n\ncaf\xe9\x00".to_vec(),
+//! )
+//! .unwrap();
+//!
+//! if let ClientFrame::Send(frame) = frame {
+//!     assert_eq!("caf\u{e9}", frame.body_text().unwrap().unwrap());
+//! }
+//! ```
+use std::borrow::Cow;
+
+use crate::error::StompParseError;
+
+/// The name of the header giving the MIME type of the body.
+pub const CONTENT_TYPE: &str = "content-type";
+
+/// The charset named by the `charset` parameter of the MIME type `content_type`, if any, without
+/// any quotes.
+pub fn charset(content_type: &str) -> Option<&str> {
+    parameter(content_type, "charset")
+}
+
+/// The type and subtype of the MIME type `content_type`, without parameters, such as `text/plain`.
+pub fn essence(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or_default().trim()
+}
+
+/// The parameters of the MIME type `content_type`, as names and values without any quotes.
+pub fn parameters(content_type: &str) -> impl Iterator<Item = (&str, &str)> {
+    content_type.split(';').skip(1).filter_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        Some((name.trim(), value.trim().trim_matches('"')))
+    })
+}
+
+/// The value of the parameter `name` of the MIME type `content_type`, ignoring the case of the
+/// name.
+pub fn parameter<'a>(content_type: &'a str, name: &str) -> Option<&'a str> {
+    parameters(content_type)
+        .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// `body` decoded as text according to the `content-type` header `content_type`.
+///
+/// The charset is taken from the `charset` parameter, or is UTF-8 for `text/*` types. UTF-8,
+/// US-ASCII and ISO-8859-1 are supported. Fails if there is no `content-type`, if a type other than
+/// `text/*` has no charset, or if the body is not valid in the charset.
+pub fn decode_text<'a>(
+    content_type: Option<&[u8]>,
+    body: &'a [u8],
+) -> Result<Cow<'a, str>, StompParseError> {
+    let content_type = content_type
+        .map(std::str::from_utf8)
+        .transpose()
+        .map_err(|_| StompParseError::new("Invalid content-type"))?
+        .ok_or_else(|| StompParseError::new("Body has no content-type, so is not text"))?;
+
+    let charset = match charset(content_type) {
+        Some(charset) => charset,
+        None if is_text(content_type) => "utf-8",
+        None => {
+            return Err(StompParseError::new(format!(
+                "Body of type {} has no charset, so is not text",
+                content_type
+            )))
+        }
+    };
+
+    let invalid = || StompParseError::new(format!("Body is not valid {}", charset));
+    match charset.to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => std::str::from_utf8(body)
+            .map(Cow::Borrowed)
+            .map_err(|_| invalid()),
+        "us-ascii" | "ascii" if body.is_ascii() => {
+            // ASCII is a subset of UTF-8
+            Ok(Cow::Borrowed(
+                std::str::from_utf8(body).map_err(|_| invalid())?,
+            ))
+        }
+        "us-ascii" | "ascii" => Err(invalid()),
+        "iso-8859-1" | "latin1" => Ok(Cow::Owned(body.iter().map(|b| char::from(*b)).collect())),
+        _ => Err(StompParseError::new(format!(
+            "Unsupported charset: {}",
+            charset
+        ))),
+    }
+}
+
+fn is_text(content_type: &str) -> bool {
+    essence(content_type)
+        .get(..5)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("text/"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{charset, decode_text, essence, parameter};
+    use crate::client::ClientFrame;
+
+    #[test]
+    fn applies_charset_rules() {
+        assert_eq!(Some("UTF-8"), charset("text/plain; Charset=\"UTF-8\""));
+        assert_eq!(None, charset("text/plain"));
+        assert_eq!("text/plain", essence(" text/plain ;level=1"));
+        assert_eq!(Some("1"), parameter("text/plain;LEVEL=1", "level"));
+
+        assert_eq!(
+            "h\u{e9}",
+            decode_text(Some(b"TEXT/plain"), "h\u{e9}".as_bytes()).unwrap()
+        );
+        assert_eq!(
+            "{}",
+            decode_text(Some(b"application/json;charset=utf-8"), b"{}").unwrap()
+        );
+        assert_eq!(
+            "\u{e9}",
+            decode_text(Some(b"text/plain;charset=latin1"), b"\xe9").unwrap()
+        );
+
+        assert!(decode_text(None, b"hello").is_err());
+        assert!(decode_text(Some(b"application/octet-stream"), b"hello").is_err());
+        assert!(decode_text(Some(b"text/plain"), b"\xe9").is_err());
+        assert!(decode_text(Some(b"text/plain;charset=us-ascii"), b"\xe9").is_err());
+        assert!(decode_text(Some(b"text/plain;charset=koi8-r"), b"a").is_err());
+    }
+
+    #[test]
+    fn keeps_decoded_copy_of_body() {
+        let bytes = b"SEND\ndestination:a\ncontent-type:text/plain;charset=latin1\n\n\xe9t\xe9\x00";
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes.to_vec()) else {
+            panic!("Send Frame not parsed correctly")
+        };
+        let parsed_size = frame.approximate_heap_size();
+
+        let first = frame.body_text().unwrap().unwrap();
+        assert_eq!("\u{e9}t\u{e9}", first);
+        assert!(frame.approximate_heap_size() > parsed_size);
+        assert_eq!(first.as_ptr(), frame.body_text().unwrap().unwrap().as_ptr());
+    }
+}
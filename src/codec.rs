@@ -0,0 +1,117 @@
+//! A codec over `bytes::BytesMut` buffers, whose `decode` and `encode` methods have the shape of
+//! those of the `Decoder` and `Encoder` traits of codec frameworks such as `actix-codec` and
+//! `tokio-util`, so that implementing those traits only requires delegating to them.
+//!
+//! # Example
+//! ```
+//! use bytes::BytesMut;
+//! use stomp_parser::client::{ClientFrame, SendFrameBuilder};
+//! use stomp_parser::codec::FrameCodec;
+//! use stomp_parser::decoder::DecodedItem;
+//!
+//! let mut codec = FrameCodec::<ClientFrame>::new();
+//! let mut buffer = BytesMut::new();
+//! codec.encode(SendFrameBuilder::new("/queue/a").build(), &mut buffer);
+//!
+//! let item = codec.decode(&mut buffer).unwrap();
+//! assert!(matches!(item, Some(DecodedItem::Frame(ClientFrame::Send(_)))));
+//! assert!(buffer.is_empty());
+//! ```
+use bytes::BytesMut;
+
+use crate::decoder::{DecodableFrame, DecodedItem, FrameDecoder};
+use crate::error::StompParseError;
+
+/// Decodes frames of type `F` from, and encodes frames into, `BytesMut` buffers.
+pub struct FrameCodec<F> {
+    decoder: FrameDecoder<F>,
+}
+
+impl<F: DecodableFrame> Default for FrameCodec<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: DecodableFrame> FrameCodec<F> {
+    /// A codec decoding with the default `ParserConfig`.
+    pub fn new() -> Self {
+        Self::with_decoder(FrameDecoder::new())
+    }
+
+    /// A codec decoding with `decoder`, which may be configured as required.
+    pub fn with_decoder(decoder: FrameDecoder<F>) -> Self {
+        FrameCodec { decoder }
+    }
+
+    /// The decoder, which holds any partially received frame.
+    pub fn decoder(&self) -> &FrameDecoder<F> {
+        &self.decoder
+    }
+
+    /// Decodes the next item, taking all the bytes in `src` into the decoder's own buffer.
+    pub fn decode(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<DecodedItem<F>>, StompParseError> {
+        self.decoder.extend_from_buf(src);
+        self.decoder.decode()
+    }
+
+    /// Like `decode`, but called once no more bytes will be received; fails if a partial frame
+    /// remains.
+    pub fn decode_eof(
+        &mut self,
+        src: &mut BytesMut,
+    ) -> Result<Option<DecodedItem<F>>, StompParseError> {
+        match self.decode(src)? {
+            None if self.decoder.is_mid_frame() || self.decoder.is_discarding() => {
+                Err(StompParseError::new("Stream ended within a frame"))
+            }
+            item => Ok(item),
+        }
+    }
+
+    /// Appends the serialised `frame` to `dst`.
+    pub fn encode<T: Into<Vec<u8>>>(&mut self, frame: T, dst: &mut BytesMut) {
+        dst.extend_from_slice(&frame.into());
+    }
+
+    /// Appends a heart-beat, a single EOL, to `dst`.
+    pub fn encode_heartbeat(&mut self, dst: &mut BytesMut) {
+        dst.extend_from_slice(b"\n");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+
+    use super::FrameCodec;
+    use crate::decoder::{DecodedItem, FrameDecoder};
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn decodes_across_buffers() {
+        let mut codec =
+            FrameCodec::<ServerFrame>::with_decoder(FrameDecoder::new().report_heartbeats(true));
+
+        let mut buffer = BytesMut::from(&b"RECEIPT\nreceipt"[..]);
+        assert!(codec.decode(&mut buffer).unwrap().is_none());
+        assert!(buffer.is_empty());
+
+        buffer.extend_from_slice(b"-id:1\n\n\x00");
+        codec.encode_heartbeat(&mut buffer);
+        assert!(matches!(
+            codec.decode(&mut buffer),
+            Ok(Some(DecodedItem::Frame(ServerFrame::Receipt(_))))
+        ));
+        assert!(matches!(
+            codec.decode_eof(&mut buffer),
+            Ok(Some(DecodedItem::HeartBeat))
+        ));
+
+        buffer.extend_from_slice(b"RECEIPT\n");
+        assert!(codec.decode_eof(&mut buffer).is_err());
+    }
+}
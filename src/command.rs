@@ -0,0 +1,98 @@
+//! Identifies the command of a frame, so that code such as logging and metrics can label frames
+//! without matching on their variants.
+
+/// The command of a frame.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Command {
+    Connect,
+    Send,
+    Subscribe,
+    Unsubscribe,
+    Ack,
+    Nack,
+    Begin,
+    Commit,
+    Abort,
+    Disconnect,
+    Connected,
+    Message,
+    Receipt,
+    Error,
+    /// The command of a frame not defined by the specification, such as those defined with
+    /// `frames!`.
+    Other(&'static str),
+}
+
+impl Command {
+    /// The command named `name`. The `STOMP` alias of `CONNECT` is `Command::Connect`.
+    pub fn from_name(name: &'static str) -> Command {
+        match name {
+            "CONNECT" | "STOMP" => Command::Connect,
+            "SEND" => Command::Send,
+            "SUBSCRIBE" => Command::Subscribe,
+            "UNSUBSCRIBE" => Command::Unsubscribe,
+            "ACK" => Command::Ack,
+            "NACK" => Command::Nack,
+            "BEGIN" => Command::Begin,
+            "COMMIT" => Command::Commit,
+            "ABORT" => Command::Abort,
+            "DISCONNECT" => Command::Disconnect,
+            "CONNECTED" => Command::Connected,
+            "MESSAGE" => Command::Message,
+            "RECEIPT" => Command::Receipt,
+            "ERROR" => Command::Error,
+            other => Command::Other(other),
+        }
+    }
+
+    /// The command as it appears on the wire.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Command::Connect => "CONNECT",
+            Command::Send => "SEND",
+            Command::Subscribe => "SUBSCRIBE",
+            Command::Unsubscribe => "UNSUBSCRIBE",
+            Command::Ack => "ACK",
+            Command::Nack => "NACK",
+            Command::Begin => "BEGIN",
+            Command::Commit => "COMMIT",
+            Command::Abort => "ABORT",
+            Command::Disconnect => "DISCONNECT",
+            Command::Connected => "CONNECTED",
+            Command::Message => "MESSAGE",
+            Command::Receipt => "RECEIPT",
+            Command::Error => "ERROR",
+            Command::Other(name) => name,
+        }
+    }
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::Command;
+    use crate::any::parse_any;
+    use crate::client::ClientFrame;
+
+    #[test]
+    fn frames_report_their_command() {
+        let frame =
+            ClientFrame::try_from(b"STOMP\nhost:h\naccept-version:1.2\n\n\x00".to_vec()).unwrap();
+        assert_eq!(Command::Connect, frame.command());
+        assert_eq!("CONNECT", frame.command_name());
+
+        let frame = parse_any(b"RECEIPT\nreceipt-id:1\n\n\x00".to_vec()).unwrap();
+        assert_eq!(Command::Receipt, frame.command());
+        assert_eq!("RECEIPT", frame.command().to_string());
+
+        assert_eq!(Command::Other("PURGE"), Command::from_name("PURGE"));
+        assert_eq!("PURGE", Command::Other("PURGE").as_str());
+    }
+}
@@ -1,6 +1,10 @@
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
 use either::Either;
 
-use crate::error::StompParseError;
+use crate::error::{BuildError, ErrorKind, NumberError, StompParseError};
+use crate::headers::FromHeaderValue;
 
 use super::constants::{HEADER_PARTS_SEPARATOR, LINE_SEPARATOR, TERMINATOR};
 
@@ -87,6 +91,45 @@ pub fn decode_str(raw: &str) -> Result<Either<&str, String>, StompParseError> {
     }
 }
 
+/// The unescaped text of a header value taken from a frame, for passing on to a builder, which
+/// escapes it again; a value with an invalid escape sequence is passed on as it was written.
+pub fn decode_or_raw(raw: &str) -> String {
+    decode_str(raw).map_or_else(|_| raw.to_owned(), |decoded| decoded.either_into())
+}
+
+/// Escapes `value` for use as a header value, the inverse of `decode_str`.
+pub fn encode_str(value: &str) -> String {
+    let mut buffer = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => buffer.push_str("\\\\"),
+            '\r' => buffer.push_str("\\r"),
+            '\n' => buffer.push_str("\\n"),
+            ':' => buffer.push_str("\\c"),
+            c => buffer.push(c),
+        }
+    }
+    buffer
+}
+
+/// The text of a header name or value as written by the builders: escaped, unless `escape` is
+/// false, as for CONNECT and CONNECTED frames, whose headers are not. Characters which cannot be
+/// written - null octets, and, without escaping, EOLs and colons - are replaced by U+FFFD, so
+/// that the frame always parses.
+pub fn encode_header_text(text: &str, escape: bool) -> String {
+    let text = if escape {
+        encode_str(text)
+    } else {
+        text.to_owned()
+    };
+    let unwritable = |c: char| c == '\0' || (!escape && matches!(c, '\r' | '\n' | ':'));
+    if text.contains(unwritable) {
+        text.replace(unwritable, "\u{fffd}")
+    } else {
+        text
+    }
+}
+
 fn decode_at_and_continue(
     buffer: &mut String,
     slice: &str,
@@ -111,10 +154,94 @@ fn decode_slice(buffer: &mut String, slice: &str) -> Result<(), StompParseError>
     }
 }
 
+/// Checks that `value` can be written as the header `name` (neither is empty or contains null
+/// octets or EOLs, and the name contains no colon), then parses it, for the `try_` setters of
+/// builders.
+pub fn parse_builder_value<T: FromHeaderValue>(name: &str, value: &str) -> Result<T, BuildError> {
+    if name.is_empty() || name.contains(['\0', ':', '\r', '\n']) {
+        return Err(BuildError::InvalidHeaderName {
+            name: name.to_owned(),
+        });
+    }
+
+    let invalid = |reason: &str| BuildError::InvalidHeaderValue {
+        name: name.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    if value.is_empty() {
+        return Err(invalid("the value is empty"));
+    }
+    if value.contains('\0') {
+        return Err(invalid("the value contains a null octet"));
+    }
+    if value.contains(['\r', '\n']) {
+        return Err(invalid("the value contains an EOL"));
+    }
+
+    T::from_header_value(value).map_err(|error| invalid(error.message()))
+}
+
+/// Parses a non-negative decimal integer, reporting precisely why the input is not one.
+pub fn parse_number<T: FromStr<Err = ParseIntError>>(input: &str) -> Result<T, StompParseError> {
+    let digits = input.strip_prefix('-').unwrap_or(input);
+
+    let result = if digits.starts_with('+') {
+        Err(NumberError::NotNumeric)
+    } else {
+        T::from_str(input).map_err(|error| match error.kind() {
+            IntErrorKind::Empty => NumberError::Empty,
+            IntErrorKind::PosOverflow => NumberError::Overflow,
+            IntErrorKind::NegOverflow => NumberError::Negative,
+            _ if digits.len() < input.len()
+                && !digits.is_empty()
+                && digits.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                NumberError::Negative
+            }
+            _ => NumberError::NotNumeric,
+        })
+    };
+
+    result.map_err(|error| {
+        StompParseError::with_kind(
+            ErrorKind::InvalidNumber(error),
+            format!("Invalid number '{}': {}", input, error),
+        )
+    })
+}
+
 #[cfg(test)]
 mod test {
     use crate::common::constants::*;
     use crate::common::functions::*;
+    use crate::error::{ErrorKind, NumberError};
+
+    fn number_error<T: std::str::FromStr<Err = std::num::ParseIntError> + std::fmt::Debug>(
+        input: &str,
+    ) -> NumberError {
+        match parse_number::<T>(input).unwrap_err().kind() {
+            ErrorKind::InvalidNumber(error) => *error,
+            kind => panic!("Unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    pub fn parse_number_parses_digits() {
+        assert_eq!(1234usize, parse_number("1234").unwrap());
+        assert_eq!(0u32, parse_number("0").unwrap());
+    }
+
+    #[test]
+    pub fn parse_number_reports_kind_of_error() {
+        assert_eq!(NumberError::Empty, number_error::<usize>(""));
+        assert_eq!(NumberError::NotNumeric, number_error::<usize>("12a"));
+        assert_eq!(NumberError::NotNumeric, number_error::<usize>("+12"));
+        assert_eq!(NumberError::NotNumeric, number_error::<usize>("-"));
+        assert_eq!(NumberError::NotNumeric, number_error::<usize>(" 12"));
+        assert_eq!(NumberError::Negative, number_error::<usize>("-12"));
+        assert_eq!(NumberError::Overflow, number_error::<u32>("4294967296"));
+    }
 
     #[test]
     pub fn write_command_appends_and_separates() {
@@ -191,7 +318,7 @@ mod test {
 
         let (name_range, value_range) = extend_name_value(&mut bytes, name, &mut extension);
 
-        let name_length = name.as_bytes().len();
+        let name_length = name.len();
 
         let expected_value_begin = 3 + name_length + HEADER_PARTS_SEPARATOR.len();
         let expected_value_end = expected_value_begin + 4;
@@ -213,7 +340,7 @@ mod test {
 
         let (name_range, value_range) = extend_name_value_line(&mut bytes, name, &mut extension);
 
-        let name_length = name.as_bytes().len();
+        let name_length = name.len();
 
         let expected_value_begin = 3 + name_length + HEADER_PARTS_SEPARATOR.len();
         let expected_value_end = expected_value_begin + 4;
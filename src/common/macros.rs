@@ -1,11 +1,15 @@
 #![doc(hidden)]
 /// This macro is useful for forcing repeat expression - particularly optional
 /// items - without actually outputting anything depending on the input.
+#[doc(hidden)]
+#[macro_export]
 macro_rules! blank {
     ($in:ident) => {};
 }
 
 /// Outputs true if an input is present, otherwise false.
+#[doc(hidden)]
+#[macro_export]
 macro_rules! true_if_present {
     ($in:ident) => {
         true
@@ -17,6 +21,8 @@ macro_rules! true_if_present {
 }
 
 /// Outputs the first token tree if present, otherwise the second.
+#[doc(hidden)]
+#[macro_export]
 macro_rules! or_else {
     ($present:tt,$absent:tt) => {
         $present
@@ -28,6 +34,8 @@ macro_rules! or_else {
 }
 
 /// Outputs the first type if present, otherwise the second.
+#[doc(hidden)]
+#[macro_export]
 macro_rules! or_else_type {
     ($present:ty,$default:ty) => {
         $present
@@ -39,6 +47,8 @@ macro_rules! or_else_type {
 }
 
 /// If the first token-tree is present it chooses the second one as output; if not, the third
+#[doc(hidden)]
+#[macro_export]
 macro_rules! choose_from_presence {
     ($in:tt $present:tt, $absent:tt) => {
         $present
@@ -49,6 +59,41 @@ macro_rules! choose_from_presence {
     };
 }
 
+/// Applies the checks configured for a header of the specified type, if there are any.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! check_header {
+    (Destination, $config:expr, $value:expr) => {
+        $crate::macro_support::check_destination($config, $value.value())?
+    };
+
+    ($header_type:ident, $config:expr, $value:expr) => {};
+}
+
+/// Fails to compile unless each of the types is `Send` and `Sync`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! assert_send_sync {
+    ($($type:ty),* $(,)?) => {
+        const _: fn() = || {
+            fn assert_send_sync<T: Send + Sync>() {}
+            $(assert_send_sync::<$type>();)*
+        };
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! if_present {
+    ($in:tt ($present:item)) => {
+        $present
+    };
+
+    (($present:item)) => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
 macro_rules! if_not_present {
     ($in:tt ($absent:item)) => {};
 
@@ -56,3 +101,128 @@ macro_rules! if_not_present {
         $absent
     };
 }
+
+/// Outputs the builder method which encodes the body as base64, for frames with both custom headers
+/// and a body.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! base64_body_builder {
+    ($builder:ident, $has_custom:ident, $has_body:ident) => {
+        /// Sets the body to `body` encoded as base64, marked by the `content-transfer-encoding`
+        /// header, so that the frame can be carried over channels which only allow text.
+        pub fn base64_body(mut self, body: Vec<u8>) -> $builder {
+            use $crate::base64::CONTENT_TRANSFER_ENCODING;
+
+            self.custom
+                .retain(|(name, _)| name != CONTENT_TRANSFER_ENCODING);
+            self.custom.push((
+                CONTENT_TRANSFER_ENCODING.to_owned(),
+                $crate::base64::BASE64.to_owned(),
+            ));
+
+            self.body = Some($crate::base64::encode(&body).into_bytes());
+            self
+        }
+    };
+
+    ($($ignored:tt)*) => {};
+}
+
+/// Outputs the builder method which compresses the body, for frames with both custom headers and a
+/// body, if the `compression` feature is enabled.
+#[cfg(feature = "compression")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! compressed_body_builder {
+    ($builder:ident, $has_custom:ident, $has_body:ident; $($opt_header_name:ident : $opt_header_type:ident),*) => {
+        $crate::macro_support::paste::paste! {
+            /// Sets the body to `body` compressed with `encoding`, adding the `content-encoding`
+            /// header and setting `content-length` to the length of the compressed body.
+            pub fn compressed_body(mut self, body: Vec<u8>, encoding: $crate::compression::ContentEncoding) -> $builder {
+                use $crate::compression::CONTENT_ENCODING;
+
+                let body = encoding.encode(&body);
+                let len = body.len().to_string();
+
+                self.custom.retain(|(name, _)| name != CONTENT_ENCODING);
+                self.custom.push((CONTENT_ENCODING.to_owned(), encoding.to_string()));
+
+                let mut needs_content_length = true;
+                $(
+                    if $crate::macro_support::[<$opt_header_type Value>]::NAME == "content-length" {
+                        self.$opt_header_name = $crate::macro_support::FromHeaderValue::from_header_value(&len).ok();
+                        needs_content_length = false;
+                    }
+                )*
+                if needs_content_length {
+                    self.custom.retain(|(name, _)| name != "content-length");
+                    self.custom.push(("content-length".to_owned(), len));
+                }
+
+                self.body = Some(body);
+                self
+            }
+        }
+    };
+
+    ($($ignored:tt)*) => {};
+}
+
+#[cfg(not(feature = "compression"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! compressed_body_builder {
+    ($($ignored:tt)*) => {};
+}
+
+/// Outputs the accessor for the body decoded according to its `content-encoding`, if the
+/// `compression` feature is enabled.
+#[cfg(feature = "compression")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! decoded_body_accessor {
+    ($has_body:ident) => {
+        /// The body decoded according to the `content-encoding` header, or `None` if the frame has
+        /// no body. Without the header, the body is returned unchanged. Decoding fails with
+        /// `ErrorKind::DecodedBodyTooLarge` rather than produce more than `max_len` bytes, as a
+        /// small compressed body can expand enormously.
+        pub fn body_decoded(
+            &self,
+            max_len: usize,
+        ) -> Result<Option<std::borrow::Cow<'_, [u8]>>, $crate::error::StompParseError> {
+            let encoding = self.raw_header($crate::compression::CONTENT_ENCODING);
+            self.body()
+                .map(|body| $crate::macro_support::decode_body(encoding, body, max_len))
+                .transpose()
+        }
+    };
+}
+
+#[cfg(not(feature = "compression"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! decoded_body_accessor {
+    ($has_body:ident) => {};
+}
+
+/// Outputs the method verifying the signature of a frame, if the `signing` feature is enabled.
+#[cfg(feature = "signing")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! signature_verifier {
+    ($($has_body:ident)?) => {
+        /// Checks the signature in the default signature header against `key`, returning an error
+        /// if it is missing or does not match. See `signing::Signer` for other headers.
+        pub fn verify_signature(&self, key: &[u8]) -> Result<(), $crate::error::StompParseError> {
+            let body: Option<&[u8]> = $crate::choose_from_presence!($($has_body)? (self.body()), (None));
+            $crate::macro_support::verify_signature(key, self.raw.as_ref(), body)
+        }
+    };
+}
+
+#[cfg(not(feature = "signing"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! signature_verifier {
+    ($($has_body:ident)?) => {};
+}
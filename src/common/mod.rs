@@ -3,3 +3,5 @@ mod macros;
 
 pub mod constants;
 pub mod functions;
+pub mod raw;
+pub mod span;
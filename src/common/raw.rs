@@ -0,0 +1,127 @@
+//! Splits a serialised frame into its parts without validating it, for tooling which inspects
+//! frames rather than parsing them.
+use std::borrow::Cow;
+
+use crate::parser::trim_trailing_eols;
+
+/// The parts of a serialised frame. Header names and values are as they appear on the wire, i.e.
+/// not unescaped, and are lossily converted to UTF-8.
+pub struct RawParts<'a> {
+    pub command: Cow<'a, str>,
+    pub headers: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    pub body: &'a [u8],
+}
+
+impl<'a> RawParts<'a> {
+    pub fn split(raw: &'a [u8]) -> RawParts<'a> {
+        let mut lines = raw.split(|b| *b == b'\n');
+        let mut consumed = 0;
+        let mut line = || {
+            lines.next().map(|line| {
+                consumed += line.len() + 1;
+                String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line))
+            })
+        };
+
+        let command = line().unwrap_or_default();
+
+        let mut headers = Vec::new();
+        while let Some(header) = line().filter(|line| !line.is_empty()) {
+            headers.push(match header {
+                Cow::Borrowed(header) => {
+                    let (name, value) = header.split_once(':').unwrap_or((header, ""));
+                    (Cow::Borrowed(name), Cow::Borrowed(value))
+                }
+                Cow::Owned(header) => {
+                    let (name, value) = header.split_once(':').unwrap_or((&header, ""));
+                    (Cow::Owned(name.to_owned()), Cow::Owned(value.to_owned()))
+                }
+            });
+        }
+
+        let body = trim_trailing_eols(raw.get(consumed..).unwrap_or(&[]));
+        let body = body.strip_suffix(b"\0").unwrap_or(body);
+
+        RawParts {
+            command,
+            headers,
+            body,
+        }
+    }
+
+    /// The value of the first occurrence of the header with the specified name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_ref())
+    }
+}
+
+/// The value, as it appears on the wire, of the first header in the serialised frame `raw` with
+/// the specified name.
+pub(crate) fn raw_header<'a>(raw: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    raw.split(|b| *b == b'\n')
+        .skip(1)
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| {
+            line.strip_prefix(name.as_bytes())
+                .and_then(|rest| rest.strip_prefix(b":"))
+        })
+}
+
+/// A copy of the head of the serialised frame `raw` - its command and headers, up to and
+/// including the blank line ending them - lossily converted to UTF-8, or `None` if the head is
+/// valid UTF-8 already. The body is not copied.
+pub(crate) fn lossy_copy(raw: &[u8]) -> Option<Box<[u8]>> {
+    match String::from_utf8_lossy(&raw[..head_end(raw)]) {
+        Cow::Borrowed(_) => None,
+        Cow::Owned(head) => Some(head.into_bytes().into_boxed_slice()),
+    }
+}
+
+/// The end of the head of the serialised frame `raw`, after the blank line ending it, or the length
+/// of `raw` if it has no blank line. Unlike `decoder::head_len`, the headers are not read.
+pub(crate) fn head_end(raw: &[u8]) -> usize {
+    let blank_line = |eol: &[u8]| {
+        raw.windows(eol.len())
+            .position(|window| window == eol)
+            .map(|index| index + eol.len())
+    };
+    blank_line(b"\n\n")
+        .into_iter()
+        .chain(blank_line(b"\n\r\n"))
+        .min()
+        .unwrap_or(raw.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{head_end, lossy_copy, raw_header, RawParts};
+
+    #[test]
+    fn splits_frame() {
+        let parts = RawParts::split(b"SEND\r\ndestination:a:b\nx:1\nx:2\n\nbody\x00\r\n");
+
+        assert_eq!("SEND", parts.command);
+        assert_eq!(3, parts.headers.len());
+        assert_eq!(Some("a:b"), parts.header("destination"));
+        assert_eq!(Some("1"), parts.header("x"));
+        assert_eq!(b"body", parts.body);
+    }
+
+    #[test]
+    fn finds_raw_header() {
+        let raw = b"SEND\ndestination:a\nx:\xff\n\nx:body\x00";
+
+        assert_eq!(Some(&b"\xff"[..]), raw_header(raw, "x"));
+        assert_eq!(None, raw_header(raw, "destination:"));
+        assert!(lossy_copy(b"SEND\ndestination:a\n\n\xff\x00").is_none());
+        assert_eq!(
+            &b"SEND\nx:\xef\xbf\xbd\n\n"[..],
+            &*lossy_copy(b"SEND\nx:\xff\n\n\xff\x00").unwrap()
+        );
+        assert_eq!(12, head_end(b"SEND\nx:1\r\n\r\nbody\x00"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Locates the parts of a frame within its serialised form by their offsets, so that a frame can
+//! refer to its headers and body without holding references into the storage it owns.
+
+/// The position of a part of a frame within the frame's serialised form.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The span of `part` within `base`, of which it must be a part. An empty part, which may not
+    /// point into `base`, has an empty span.
+    pub fn of(part: &[u8], base: &[u8]) -> Span {
+        if part.is_empty() {
+            return Span::default();
+        }
+
+        let start = (part.as_ptr() as usize)
+            .checked_sub(base.as_ptr() as usize)
+            .filter(|start| start + part.len() <= base.len())
+            .expect("Part is not within the frame");
+
+        Span::new(start, start + part.len())
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The bytes of `base` in this span.
+    pub fn bytes<'a>(&self, base: &'a [u8]) -> &'a [u8] {
+        &base[self.start..self.end]
+    }
+
+    /// The text of `base` in this span, which was checked to be UTF-8 when the span was created.
+    /// Panics if it is not, which can only happen if the storage of the frame changed its bytes.
+    pub fn str<'a>(&self, base: &'a [u8]) -> &'a str {
+        std::str::from_utf8(self.bytes(base)).expect("Frame storage changed after parsing")
+    }
+}
+
+/// The text of a part of a frame: either its position within the frame's serialised form, or a
+/// shared copy of it.
+#[doc(hidden)]
+#[derive(Debug, Clone)]
+pub enum StoredStr {
+    Span(Span),
+    Shared(std::sync::Arc<str>),
+}
+
+impl StoredStr {
+    /// The text, where `base` is the serialised form of the frame.
+    pub fn str<'a>(&'a self, base: &'a [u8]) -> &'a str {
+        match self {
+            StoredStr::Span(span) => span.str(base),
+            StoredStr::Shared(shared) => shared,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Span;
+
+    #[test]
+    fn locates_parts() {
+        let base = b"SEND\ndestination:a\n\n\x00";
+        let span = Span::of(&base[17..18], base);
+
+        assert_eq!(Span::new(17, 18), span);
+        assert_eq!("a", span.str(base));
+        assert_eq!(Span::default(), Span::of(b"", base));
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_parts_of_other_slices() {
+        Span::of(b"a", b"SEND\n\n\x00");
+    }
+}
@@ -0,0 +1,688 @@
+//! Supports the `content-encoding` extension, with which some deployments compress the bodies of
+//! large messages using gzip or deflate. Both formats are implemented here, as the crate has no
+//! compression dependencies; the compressor favours simplicity over ratio, using fixed Huffman
+//! codes.
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+
+use crate::error::{ErrorKind, StompParseError};
+
+/// The name of the header giving the encoding of a frame's body.
+pub const CONTENT_ENCODING: &str = "content-encoding";
+
+/// An encoding of a frame's body, as given by the `content-encoding` header.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ContentEncoding {
+    /// The gzip format (RFC 1952).
+    Gzip,
+    /// The zlib format (RFC 1950), named `deflate` as in HTTP.
+    Deflate,
+}
+
+impl ContentEncoding {
+    /// The value of the `content-encoding` header for this encoding.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    /// Compresses `data` in this encoding.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len() / 2 + 32);
+        match self {
+            ContentEncoding::Gzip => {
+                out.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+                deflate(data, &mut out);
+                out.extend_from_slice(&crc32(data).to_le_bytes());
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            }
+            ContentEncoding::Deflate => {
+                out.extend_from_slice(&[0x78, 0x9c]);
+                deflate(data, &mut out);
+                out.extend_from_slice(&adler32(data).to_be_bytes());
+            }
+        }
+        out
+    }
+
+    /// Decompresses `data`, which is in this encoding, failing with
+    /// `ErrorKind::DecodedBodyTooLarge` rather than producing more than `max_len` bytes. For
+    /// `Deflate`, a raw deflate stream without the zlib wrapper is accepted too, as some
+    /// implementations send one.
+    pub fn decode(&self, data: &[u8], max_len: usize) -> Result<Vec<u8>, StompParseError> {
+        match self {
+            ContentEncoding::Gzip => decode_gzip(data, max_len),
+            ContentEncoding::Deflate => {
+                let zlib = data.len() >= 2
+                    && data[0] & 0x0f == 8
+                    && (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 == 0;
+                if !zlib {
+                    return inflate(data, max_len).map(|(out, _)| out);
+                }
+
+                let (out, used) = inflate(&data[2..], max_len)?;
+                let checksum = data
+                    .get(2 + used..2 + used + 4)
+                    .ok_or_else(|| error("missing adler32 checksum"))?;
+                if checksum != adler32(&out).to_be_bytes() {
+                    return Err(error("adler32 checksum mismatch"));
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+impl Display for ContentEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<&str> for ContentEncoding {
+    type Error = StompParseError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim() {
+            "gzip" | "x-gzip" => Ok(ContentEncoding::Gzip),
+            "deflate" => Ok(ContentEncoding::Deflate),
+            other => Err(StompParseError::new(format!(
+                "Unsupported content-encoding: {}",
+                other
+            ))),
+        }
+    }
+}
+
+/// The body of a frame with the `content-encoding` header `encoding`, decoded to at most `max_len`
+/// bytes. Without the header, or with the `identity` encoding, the body is returned unchanged.
+pub(crate) fn decode_body<'a>(
+    encoding: Option<&[u8]>,
+    body: &'a [u8],
+    max_len: usize,
+) -> Result<std::borrow::Cow<'a, [u8]>, StompParseError> {
+    let encoding = match encoding.map(std::str::from_utf8) {
+        None => return Ok(std::borrow::Cow::Borrowed(body)),
+        Some(Ok(encoding)) if encoding.trim() == "identity" => {
+            return Ok(std::borrow::Cow::Borrowed(body))
+        }
+        Some(Ok(encoding)) => ContentEncoding::try_from(encoding)?,
+        Some(Err(_)) => return Err(StompParseError::new("Invalid content-encoding")),
+    };
+
+    encoding.decode(body, max_len).map(std::borrow::Cow::Owned)
+}
+
+fn error(reason: &str) -> StompParseError {
+    StompParseError::new(format!("Invalid compressed body: {}", reason))
+}
+
+/// Fails unless `len` more bytes can be added to `out` without exceeding `max_len`.
+fn reserve(out: &[u8], len: usize, max_len: usize) -> Result<(), StompParseError> {
+    if len > max_len.saturating_sub(out.len()) {
+        return Err(StompParseError::with_kind(
+            ErrorKind::DecodedBodyTooLarge { limit: max_len },
+            format!("Decoded body exceeds the limit of {} bytes", max_len),
+        ));
+    }
+    Ok(())
+}
+
+fn decode_gzip(data: &[u8], max_len: usize) -> Result<Vec<u8>, StompParseError> {
+    const FHCRC: u8 = 2;
+    const FEXTRA: u8 = 4;
+    const FNAME: u8 = 8;
+    const FCOMMENT: u8 = 16;
+
+    if data.len() < 18 || data[0..3] != [0x1f, 0x8b, 8] {
+        return Err(error("not in gzip format"));
+    }
+    let flags = data[3];
+    let mut pos = 10;
+
+    if flags & FEXTRA != 0 {
+        let len = data
+            .get(pos..pos + 2)
+            .map(|len| usize::from(u16::from_le_bytes([len[0], len[1]])))
+            .ok_or_else(|| error("truncated gzip header"))?;
+        pos += 2 + len;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data
+                .get(pos..)
+                .and_then(|rest| rest.iter().position(|b| *b == 0))
+                .ok_or_else(|| error("truncated gzip header"))?;
+            pos += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        pos += 2;
+    }
+
+    let (out, used) = inflate(
+        data.get(pos..)
+            .ok_or_else(|| error("truncated gzip header"))?,
+        max_len,
+    )?;
+    let trailer = data
+        .get(pos + used..pos + used + 8)
+        .ok_or_else(|| error("missing gzip trailer"))?;
+    if trailer[0..4] != crc32(&out).to_le_bytes() {
+        return Err(error("crc32 checksum mismatch"));
+    }
+    if trailer[4..8] != (out.len() as u32).to_le_bytes() {
+        return Err(error("length mismatch"));
+    }
+    Ok(out)
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MAX_MATCH: usize = 258;
+const MIN_MATCH: usize = 3;
+const MAX_CHAIN: usize = 64;
+
+/// The code lengths of the fixed literal/length code.
+fn fixed_literal_lengths() -> [u8; 288] {
+    let mut lengths = [8; 288];
+    lengths[144..256].iter_mut().for_each(|len| *len = 9);
+    lengths[256..280].iter_mut().for_each(|len| *len = 7);
+    lengths
+}
+
+/// A canonical Huffman code, for decoding.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        lengths
+            .iter()
+            .for_each(|len| counts[usize::from(*len)] += 1);
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                symbols[usize::from(offsets[usize::from(*len)])] = symbol as u16;
+                offsets[usize::from(*len)] += 1;
+            }
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    /// The canonical codes for the symbols with the specified lengths, as (code, length) pairs.
+    fn codes(lengths: &[u8]) -> Vec<(u16, u8)> {
+        let mut counts = [0u16; 16];
+        lengths
+            .iter()
+            .for_each(|len| counts[usize::from(*len)] += 1);
+        counts[0] = 0;
+
+        let mut next = [0u16; 16];
+        let mut code = 0;
+        for len in 1..16 {
+            code = (code + counts[len - 1]) << 1;
+            next[len] = code;
+        }
+
+        lengths
+            .iter()
+            .map(|len| {
+                let code = next[usize::from(*len)];
+                next[usize::from(*len)] += 1;
+                (code, *len)
+            })
+            .collect()
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, need: u32) -> Result<u32, StompParseError> {
+        while self.count < need {
+            let byte = *self
+                .data
+                .get(self.pos)
+                .ok_or_else(|| error("unexpected end of data"))?;
+            self.pos += 1;
+            self.bits |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << need) - 1) as u32;
+        self.bits >>= need;
+        self.count -= need;
+        Ok(value)
+    }
+
+    fn decode(&mut self, huffman: &Huffman) -> Result<u16, StompParseError> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= self.bits(1)? as i32;
+            let count = i32::from(huffman.counts[len]);
+            if code - first < count {
+                return Ok(huffman.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(error("invalid Huffman code"))
+    }
+}
+
+/// Decompresses the raw deflate stream at the start of `data`, returning the decompressed bytes and
+/// the number of bytes of `data` used.
+fn inflate(data: &[u8], max_len: usize) -> Result<(Vec<u8>, usize), StompParseError> {
+    let mut reader = BitReader {
+        data,
+        pos: 0,
+        bits: 0,
+        count: 0,
+    };
+    let mut out = Vec::with_capacity(data.len().saturating_mul(3).min(max_len));
+
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.bits = 0;
+                reader.count = 0;
+                let header = data
+                    .get(reader.pos..reader.pos + 4)
+                    .ok_or_else(|| error("truncated stored block"))?;
+                let len = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                if u16::from_le_bytes([header[2], header[3]]) != !(len as u16) {
+                    return Err(error("invalid stored block length"));
+                }
+                reader.pos += 4;
+                reserve(&out, len, max_len)?;
+                out.extend_from_slice(
+                    data.get(reader.pos..reader.pos + len)
+                        .ok_or_else(|| error("truncated stored block"))?,
+                );
+                reader.pos += len;
+            }
+            1 => {
+                let literals = Huffman::new(&fixed_literal_lengths());
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &mut out, &literals, &distances, max_len)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut out, &literals, &distances, max_len)?;
+            }
+            _ => return Err(error("invalid block type")),
+        }
+        if last {
+            return Ok((out, reader.pos));
+        }
+    }
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), StompParseError> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(code_count) {
+        code_lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (value, repeat) = match reader.decode(&code_lengths)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (
+                *lengths
+                    .last()
+                    .ok_or_else(|| error("repeat with no length"))?,
+                3 + reader.bits(2)?,
+            ),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.resize(lengths.len() + repeat as usize, value);
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(error("too many code lengths"));
+    }
+
+    let (literals, distances) = lengths.split_at(literal_count);
+    Ok((Huffman::new(literals), Huffman::new(distances)))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    max_len: usize,
+) -> Result<(), StompParseError> {
+    loop {
+        let symbol = usize::from(reader.decode(literals)?);
+        if symbol < 256 {
+            reserve(out, 1, max_len)?;
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let index = symbol - 257;
+        if index >= LENGTH_BASE.len() {
+            return Err(error("invalid length code"));
+        }
+        let len =
+            usize::from(LENGTH_BASE[index]) + reader.bits(u32::from(LENGTH_EXTRA[index]))? as usize;
+
+        let index = usize::from(reader.decode(distances)?);
+        if index >= DISTANCE_BASE.len() {
+            return Err(error("invalid distance code"));
+        }
+        let distance = usize::from(DISTANCE_BASE[index])
+            + reader.bits(u32::from(DISTANCE_EXTRA[index]))? as usize;
+        if distance > out.len() {
+            return Err(error("distance too far back"));
+        }
+
+        reserve(out, len, max_len)?;
+        let start = out.len() - distance;
+        for offset in 0..len {
+            out.push(out[start + offset]);
+        }
+    }
+}
+
+struct BitWriter<'a> {
+    out: &'a mut Vec<u8>,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitWriter<'a> {
+    fn bits(&mut self, value: u32, count: u32) {
+        self.bits |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which is packed starting with its most significant bit.
+    fn code(&mut self, (code, len): (u16, u8)) {
+        let reversed = u32::from(code.reverse_bits() >> (16 - len));
+        self.bits(reversed, u32::from(len));
+    }
+
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+/// Compresses `data` as a single deflate block with fixed Huffman codes, appending it to `out`.
+fn deflate(data: &[u8], out: &mut Vec<u8>) {
+    let literals = Huffman::codes(&fixed_literal_lengths());
+    let distances = Huffman::codes(&[5; 30]);
+    let mut writer = BitWriter {
+        out,
+        bits: 0,
+        count: 0,
+    };
+    writer.bits(1, 1);
+    writer.bits(1, 2);
+
+    let hash = |pos: usize| {
+        ((usize::from(data[pos]) << 10)
+            ^ (usize::from(data[pos + 1]) << 5)
+            ^ usize::from(data[pos + 2]))
+            & 0x7fff
+    };
+    let mut head = vec![usize::MAX; 0x8000];
+    let mut prev = vec![usize::MAX; data.len()];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + MIN_MATCH <= data.len() {
+            let hash = hash(pos);
+            prev[pos] = head[hash];
+            head[hash] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (mut best_len, mut best_distance) = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(pos)];
+            let mut chain = 0;
+            let max_len = MAX_MATCH.min(data.len() - pos);
+            while candidate != usize::MAX && pos - candidate <= WINDOW_SIZE && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max_len])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_distance = pos - candidate;
+                    if len == max_len {
+                        break;
+                    }
+                }
+                candidate = prev[candidate];
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            let index = LENGTH_BASE
+                .iter()
+                .rposition(|base| usize::from(*base) <= best_len)
+                .unwrap();
+            writer.code(literals[257 + index]);
+            writer.bits(
+                (best_len - usize::from(LENGTH_BASE[index])) as u32,
+                u32::from(LENGTH_EXTRA[index]),
+            );
+            let index = DISTANCE_BASE
+                .iter()
+                .rposition(|base| usize::from(*base) <= best_distance)
+                .unwrap();
+            writer.code(distances[index]);
+            writer.bits(
+                (best_distance - usize::from(DISTANCE_BASE[index])) as u32,
+                u32::from(DISTANCE_EXTRA[index]),
+            );
+
+            for offset in 0..best_len {
+                insert(pos + offset, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            writer.code(literals[usize::from(data[pos])]);
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+
+    writer.code(literals[256]);
+    writer.flush();
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut value = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            value = if value & 1 == 1 {
+                0xedb8_8320 ^ (value >> 1)
+            } else {
+                value >> 1
+            };
+            bit += 1;
+        }
+        table[index] = value;
+        index += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{crc32, ContentEncoding};
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn round_trips_bodies() {
+        let repetitive = b"hello, hello, hello, world! ".repeat(200);
+        let varied: Vec<u8> = (0..5000u32).map(|i| (i * 7919 % 251) as u8).collect();
+
+        for encoding in [ContentEncoding::Gzip, ContentEncoding::Deflate] {
+            for data in [&b""[..], b"a", &repetitive, &varied] {
+                let encoded = encoding.encode(data);
+                assert_eq!(data, &encoding.decode(&encoded, data.len()).unwrap()[..]);
+            }
+            assert!(encoding.encode(&repetitive).len() < repetitive.len() / 10);
+        }
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn decodes_streams_from_other_implementations() {
+        // `printf hello | gzip -n`, and a zlib stream using a dynamic Huffman block
+        let gzip = [
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xcb, 0x48, 0xcd, 0xc9,
+            0xc9, 0x07, 0x00, 0x86, 0xa6, 0x10, 0x36, 0x05, 0x00, 0x00, 0x00,
+        ];
+        let zlib = [
+            0x78, 0xda, 0x1d, 0x8d, 0x31, 0x0e, 0x00, 0x41, 0x08, 0x02, 0xdf, 0x4a, 0x41, 0xa2,
+            0x8d, 0x24, 0x2e, 0xff, 0xcf, 0xe1, 0x95, 0x0e, 0x30, 0x72, 0x68, 0x0a, 0x2a, 0x90,
+            0x22, 0xf0, 0x08, 0xf3, 0x18, 0x59, 0x4d, 0x2c, 0x71, 0x58, 0x1d, 0xb2, 0x17, 0xa1,
+            0x2b, 0x89, 0xe9, 0x6c, 0x96, 0xf6, 0x48, 0x98, 0x7f, 0x99, 0xab, 0x37, 0x85, 0x55,
+            0x4c, 0xb0, 0x02, 0xfd, 0x66, 0x50, 0x27, 0x26, 0x3b, 0x36, 0x79, 0x0c, 0x6f, 0xe7,
+            0xc9, 0x07, 0xa6, 0x59, 0x31, 0x88,
+        ];
+        let text =
+            "eneteoaohaeeoeaaseateneteeehieareaeoeaoietereateaiheehtetoaorettnooaneoeaaretirtea\
+                    roeeoatoeoetsnnaheeoeeeiieaotntatrisea";
+
+        assert_eq!(
+            b"hello",
+            &ContentEncoding::Gzip.decode(&gzip, 1024).unwrap()[..]
+        );
+        assert_eq!(
+            text.as_bytes(),
+            &ContentEncoding::Deflate.decode(&zlib, 1024).unwrap()[..]
+        );
+        assert!(ContentEncoding::Gzip.decode(&gzip[..20], 1024).is_err());
+    }
+
+    #[test]
+    fn limits_decoded_length() {
+        let bomb = vec![0u8; 1 << 20];
+        let frame = SendFrameBuilder::new("/queue/a".to_owned())
+            .compressed_body(bomb.clone(), ContentEncoding::Deflate)
+            .build();
+        assert!(frame.body_len() < 16 * 1024);
+
+        let error = frame.body_decoded(64 * 1024).unwrap_err();
+        assert_eq!(
+            &ErrorKind::DecodedBodyTooLarge { limit: 64 * 1024 },
+            error.kind()
+        );
+        assert_eq!(
+            &bomb[..],
+            &frame.body_decoded(bomb.len()).unwrap().unwrap()[..]
+        );
+
+        let encoded = ContentEncoding::Gzip.encode(b"hello");
+        assert!(ContentEncoding::Gzip.decode(&encoded, 4).is_err());
+    }
+
+    #[test]
+    fn compresses_body_on_build() {
+        let body = b"{\"values\": [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1]}".to_vec();
+        let frame = SendFrameBuilder::new("/queue/a".to_owned())
+            .compressed_body(body.clone(), ContentEncoding::Gzip)
+            .build();
+        let bytes: Vec<u8> = frame.into();
+
+        if let ClientFrame::Send(frame) = ClientFrame::try_from(bytes).unwrap() {
+            assert_eq!(
+                "gzip",
+                *frame.custom_header("content-encoding").unwrap().value()
+            );
+            assert_eq!(frame.body_len(), *frame.content_length().unwrap().value());
+            assert_eq!(
+                body,
+                frame.body_decoded(1024).unwrap().unwrap().into_owned()
+            );
+        } else {
+            panic!("Send Frame not parsed correctly");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Defines the options which control how leniently frames are parsed.
+use crate::destination::{DestinationValidator, SharedDestinationValidator};
+use crate::error::StompParseError;
+use crate::interner::{Interner, SharedInterner};
+
+/// Configures the behaviour of the parser. The default configuration parses strictly according
+/// to the [STOMP Protocol Specification,Version 1.2](https://stomp.github.io/stomp-specification-1.2.html).
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ParserConfig {
+    /// If true, header names are matched against the known headers ignoring (ASCII) case, so that
+    /// a `Content-Type` header is parsed as `content-type`. The original spelling is retained in
+    /// the frame's bytes, and in the name of any custom header.
+    pub case_insensitive_headers: bool,
+    /// The maximum length, in bytes, of a frame accepted by a `FrameDecoder`, or `None` for no
+    /// limit. A frame exceeding the limit is discarded, and reported as `ErrorKind::FrameTooLarge`.
+    pub max_frame_len: Option<usize>,
+    /// Validates the destinations of frames as they are parsed, if set; a frame whose destination
+    /// is rejected fails to parse with `ErrorKind::InvalidDestination`.
+    pub destination_validator: Option<SharedDestinationValidator>,
+    /// How header names and values which are not valid UTF-8 are handled.
+    pub utf8_policy: Utf8Policy,
+    /// Interns the names and values of custom headers as they are parsed, if set, so that frames
+    /// share a single copy of each, rather than referring to their own bytes.
+    pub interner: Option<SharedInterner>,
+    /// If true, `heart-beat` headers are parsed with `HeartBeatIntervalls::parse_lenient`, which
+    /// accepts whitespace, a missing second value and an empty value.
+    pub lenient_heart_beat: bool,
+    /// How a frame is delimited when its `content-length` header disagrees with the position of
+    /// its terminating null octet.
+    pub content_length_policy: ContentLengthPolicy,
+    /// If true, parsed frames record the position of each of their headers, which is available from
+    /// their `header_spans`.
+    pub record_header_spans: bool,
+}
+
+/// How the parser handles header names and values which are not valid UTF-8.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Utf8Policy {
+    /// The frame fails to parse, as required by the specification.
+    #[default]
+    Strict,
+    /// Invalid sequences are replaced with U+FFFD, the replacement character. The original bytes
+    /// remain available from the frame's `as_bytes` and `raw_header`.
+    Lossy,
+}
+
+/// How the parser delimits a frame whose body is not followed by a null octet at the position given
+/// by its `content-length` header.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ContentLengthPolicy {
+    /// The frame fails to parse, with `ErrorKind::ContentLengthMismatch`.
+    #[default]
+    Strict,
+    /// The `content-length` header is ignored, and the body ends at the first null octet, so
+    /// cannot itself contain one.
+    TrustNull,
+    /// The body is `content-length` bytes long, and may contain null octets, but must be followed
+    /// by the null octet ending the frame, or the frame fails to parse, with
+    /// `ErrorKind::ContentLengthMismatch`. Unlike `Strict`, a complete frame passed to the parser
+    /// may continue after that null octet; the remainder is ignored.
+    TrustContentLength,
+}
+
+impl ParserConfig {
+    /// A configuration which parses strictly according to the specification.
+    pub fn new() -> ParserConfig {
+        ParserConfig::default()
+    }
+
+    /// A configuration which accepts the deviations from the specification commonly produced by
+    /// non-compliant peers.
+    pub fn lenient() -> ParserConfig {
+        ParserConfig {
+            case_insensitive_headers: true,
+            utf8_policy: Utf8Policy::Lossy,
+            lenient_heart_beat: true,
+            ..ParserConfig::default()
+        }
+    }
+
+    /// This configuration, validating destinations with `validator`.
+    pub fn with_destination_validator<V: DestinationValidator + 'static>(
+        mut self,
+        validator: V,
+    ) -> ParserConfig {
+        self.destination_validator = Some(SharedDestinationValidator::new(validator));
+        self
+    }
+
+    /// This configuration, interning the names and values of custom headers with `interner`.
+    pub fn with_interner(mut self, interner: Interner) -> ParserConfig {
+        self.interner = Some(SharedInterner::new(interner));
+        self
+    }
+
+    pub(crate) fn check_destination(&self, destination: &str) -> Result<(), StompParseError> {
+        match &self.destination_validator {
+            Some(validator) => validator.check(destination),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Checks an exchange of frames between a client and a server against the rules of STOMP 1.2, and
+//! reports every violation found, so that the developers of brokers and clients can validate their
+//! implementations against captured traffic or a live exchange.
+//!
+//! Each frame is checked as it is supplied: that it parses, that it satisfies the constraints of
+//! `validate`, that a client frame is allowed by the state of the session, as checked by
+//! `SessionState`, and that a server frame answers the client: a CONNECTED frame follows a
+//! CONNECT frame, a MESSAGE frame is for an active subscription, and a RECEIPT frame acknowledges
+//! a frame which requested one.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::conformance::{ConformanceChecker, Issue};
+//!
+//! let mut checker = ConformanceChecker::new();
+//! checker.client_frame(b"CONNECT\nhost:h\naccept-version:1.2\n\n\x00");
+//! checker.server_frame(b"CONNECTED\nversion:1.2\n\n\x00");
+//! checker.server_frame(b"RECEIPT\nreceipt-id:r1\n\n\x00");
+//!
+//! let report = checker.finish();
+//! assert_eq!(3, report.frames);
+//! assert!(matches!(report.findings[0].issue, Issue::UnexpectedReceipt(_)));
+//! ```
+use std::collections::HashSet;
+
+use crate::client::ClientFrame;
+use crate::common::raw::raw_header;
+use crate::config::{ContentLengthPolicy, ParserConfig};
+use crate::error::StompParseError;
+use crate::headers::HeaderName;
+use crate::headers::StompVersion;
+use crate::server::ServerFrame;
+use crate::session::SessionState;
+use crate::validate::Violation;
+
+/// Which party sent a frame.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Direction {
+    FromClient,
+    FromServer,
+}
+
+/// A violation of the rules found in a frame.
+#[derive(Debug)]
+pub enum Issue {
+    /// The frame could not be parsed.
+    Unparseable(StompParseError),
+    /// The frame violates a constraint checked by `validate`.
+    Invalid(Violation),
+    /// The client frame is not allowed by the state of the session.
+    Session(String),
+    /// A CONNECTED frame was sent without a preceding CONNECT frame.
+    UnexpectedConnected,
+    /// A MESSAGE frame was sent for a subscription which is not active.
+    UnknownSubscription(String),
+    /// A RECEIPT frame acknowledges a receipt which was not requested, or was already acknowledged.
+    UnexpectedReceipt(String),
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Unparseable(error) => write!(f, "unparseable frame: {}", error.message()),
+            Issue::Invalid(violation) => violation.fmt(f),
+            Issue::Session(reason) => f.write_str(reason),
+            Issue::UnexpectedConnected => f.write_str("CONNECTED sent without a CONNECT"),
+            Issue::UnknownSubscription(id) => write!(f, "MESSAGE for unknown subscription {}", id),
+            Issue::UnexpectedReceipt(id) => write!(f, "RECEIPT for unrequested receipt {}", id),
+        }
+    }
+}
+
+/// An issue, with the frame in which it was found.
+#[derive(Debug)]
+pub struct Finding {
+    /// The position of the frame in the exchange, counting from 0.
+    pub index: usize,
+    /// Which party sent the frame.
+    pub direction: Direction,
+    pub issue: Issue,
+}
+
+/// The result of checking an exchange.
+#[derive(Debug, Default)]
+pub struct ConformanceReport {
+    /// The number of frames checked.
+    pub frames: usize,
+    /// The issues found, in the order of the frames in which they were found.
+    pub findings: Vec<Finding>,
+}
+
+impl ConformanceReport {
+    /// Returns true if no issues were found.
+    pub fn is_conformant(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl std::fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} frames checked, {} issues found",
+            self.frames,
+            self.findings.len()
+        )?;
+        for finding in &self.findings {
+            let sender = match finding.direction {
+                Direction::FromClient => "client",
+                Direction::FromServer => "server",
+            };
+            writeln!(f, "#{} ({}): {}", finding.index, sender, finding.issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the frames of an exchange in the order they were sent.
+#[derive(Debug, Default)]
+pub struct ConformanceChecker {
+    session: SessionState,
+    connect_pending: bool,
+    receipts: HashSet<String>,
+    report: ConformanceReport,
+}
+
+impl ConformanceChecker {
+    /// A checker for an exchange which has not yet started.
+    pub fn new() -> ConformanceChecker {
+        ConformanceChecker::default()
+    }
+
+    /// Checks the serialised frame `bytes` sent by the client.
+    pub fn client_frame(&mut self, bytes: &[u8]) {
+        let index = self.next_index();
+        let frame = match ClientFrame::parse_with(bytes.to_vec(), &parser_config()) {
+            Ok(frame) => frame,
+            Err(error) => {
+                return self.record(index, Direction::FromClient, Issue::Unparseable(error))
+            }
+        };
+
+        self.check_valid(
+            index,
+            Direction::FromClient,
+            frame.validate(&self.version()),
+        );
+
+        if let Err(error) = self.session.check(&frame) {
+            let reason = error
+                .message()
+                .map_or_else(String::new, |message| message.value().to_owned());
+            return self.record(index, Direction::FromClient, Issue::Session(reason));
+        }
+
+        if let ClientFrame::Connect(_) = frame {
+            self.connect_pending = true;
+        }
+        if let Some(receipt) = raw_header(bytes, HeaderName::Receipt.as_str()) {
+            self.receipts
+                .insert(String::from_utf8_lossy(receipt).into_owned());
+        }
+    }
+
+    /// Checks the serialised frame `bytes` sent by the server.
+    pub fn server_frame(&mut self, bytes: &[u8]) {
+        let index = self.next_index();
+        let frame = match ServerFrame::parse_with(bytes.to_vec(), &parser_config()) {
+            Ok(frame) => frame,
+            Err(error) => {
+                return self.record(index, Direction::FromServer, Issue::Unparseable(error))
+            }
+        };
+
+        self.check_valid(
+            index,
+            Direction::FromServer,
+            frame.validate(&self.version()),
+        );
+
+        let issue = match &frame {
+            ServerFrame::Connected(_) if !self.connect_pending => Some(Issue::UnexpectedConnected),
+            ServerFrame::Connected(connected) => {
+                self.connect_pending = false;
+                self.session
+                    .set_version(connected.version().value().clone());
+                None
+            }
+            ServerFrame::Message(message) => {
+                let subscription = message.subscription().value();
+                (!self.session.subscriptions().any(|id| id == subscription))
+                    .then(|| Issue::UnknownSubscription(subscription.to_owned()))
+            }
+            ServerFrame::Receipt(receipt) => {
+                let id = receipt.receipt_id().value();
+                (!self.receipts.remove(id)).then(|| Issue::UnexpectedReceipt(id.to_owned()))
+            }
+            ServerFrame::Error(_) => None,
+        };
+        if let Some(issue) = issue {
+            self.record(index, Direction::FromServer, issue);
+        }
+    }
+
+    /// The report of the frames checked so far.
+    pub fn report(&self) -> &ConformanceReport {
+        &self.report
+    }
+
+    /// Ends the check, returning the report.
+    pub fn finish(self) -> ConformanceReport {
+        self.report
+    }
+
+    /// The version in use: that negotiated, or 1.2 until a CONNECTED frame has been sent.
+    fn version(&self) -> StompVersion {
+        self.session
+            .version()
+            .cloned()
+            .unwrap_or(StompVersion::V1_2)
+    }
+
+    fn next_index(&mut self) -> usize {
+        self.report.frames += 1;
+        self.report.frames - 1
+    }
+
+    fn check_valid(
+        &mut self,
+        index: usize,
+        direction: Direction,
+        result: Result<(), Vec<Violation>>,
+    ) {
+        for violation in result.err().unwrap_or_default() {
+            self.record(index, direction, Issue::Invalid(violation));
+        }
+    }
+
+    fn record(&mut self, index: usize, direction: Direction, issue: Issue) {
+        self.report.findings.push(Finding {
+            index,
+            direction,
+            issue,
+        });
+    }
+}
+
+/// Frames are delimited by their null octet, so that a wrong `content-length` is reported as a
+/// violation rather than leaving the frame unparseable.
+fn parser_config() -> ParserConfig {
+    ParserConfig {
+        content_length_policy: ContentLengthPolicy::TrustNull,
+        ..ParserConfig::default()
+    }
+}
+
+/// Checks the frames of a captured exchange, each with the party which sent it.
+pub fn check_exchange<'a, I>(frames: I) -> ConformanceReport
+where
+    I: IntoIterator<Item = (Direction, &'a [u8])>,
+{
+    let mut checker = ConformanceChecker::new();
+    for (direction, bytes) in frames {
+        match direction {
+            Direction::FromClient => checker.client_frame(bytes),
+            Direction::FromServer => checker.server_frame(bytes),
+        }
+    }
+    checker.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_exchange, Direction::*, Issue};
+    use crate::validate::Violation;
+
+    #[test]
+    fn reports_each_violation() {
+        let report = check_exchange(vec![
+            (FromServer, &b"CONNECTED\nversion:1.2\n\n\x00"[..]),
+            (FromClient, b"SEND\ndestination:a\n\n\x00"),
+            (FromClient, b"CONNECT\nhost:h\naccept-version:1.2\n\n\x00"),
+            (FromServer, b"CONNECTED\nversion:1.2\n\n\x00"),
+            (
+                FromClient,
+                b"SUBSCRIBE\ndestination:a\nid:0\nreceipt:r\n\n\x00",
+            ),
+            (FromServer, b"RECEIPT\nreceipt-id:r\n\n\x00"),
+            (
+                FromServer,
+                b"MESSAGE\ndestination:a\nmessage-id:1\nsubscription:0\n\n\x00",
+            ),
+            (
+                FromServer,
+                b"MESSAGE\ndestination:a\nmessage-id:2\nsubscription:1\n\n\x00",
+            ),
+            (
+                FromClient,
+                b"SEND\ndestination:a\ncontent-length:9\n\nabc\x00",
+            ),
+            (FromClient, b"SEND\n\n\x00"),
+        ]);
+
+        assert_eq!(10, report.frames);
+        let issues: Vec<_> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.index, &finding.issue))
+            .collect();
+        assert_eq!(5, issues.len(), "{}", report);
+        assert!(matches!(issues[0], (0, Issue::UnexpectedConnected)));
+        assert!(matches!(issues[1], (1, Issue::Session(reason)) if reason == "Not connected"));
+        assert!(matches!(issues[2], (7, Issue::UnknownSubscription(id)) if id == "1"));
+        assert!(matches!(
+            issues[3],
+            (
+                8,
+                Issue::Invalid(Violation::ContentLengthMismatch {
+                    declared: 9,
+                    actual: 3
+                })
+            )
+        ));
+        assert!(matches!(issues[4], (9, Issue::Unparseable(_))));
+        assert!(!report.is_conformant());
+    }
+}
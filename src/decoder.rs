@@ -0,0 +1,922 @@
+//! Provides a decoder which extracts frames from a stream of bytes, such as is received over a
+//! network connection. The decoder does not perform any IO itself; received bytes are supplied via
+//! `extend` and complete frames retrieved via `decode`.
+//!
+//! As allowed by the specification, frames may be separated by any number of EOLs. These are
+//! consumed by the decoder, and can optionally be reported as heart-beats.
+use std::marker::PhantomData;
+
+use crate::any::AnyFrame;
+use crate::client::ClientFrame;
+use crate::common::functions::parse_number;
+use crate::config::{ContentLengthPolicy, ParserConfig};
+use crate::error::{ErrorKind, NumberError, StompParseError};
+use crate::headers::HeaderName;
+use crate::metrics::{NoMetrics, ParserMetrics};
+use crate::parser::content_length_mismatch;
+use crate::server::ServerFrame;
+use crate::storage::{FrameAllocator, GlobalAllocator, Storage};
+
+/// A frame type which the decoder can produce from storage of type `S`.
+pub trait DecodableFrame<S = Vec<u8>>: Sized {
+    /// Parses the frame from bytes containing exactly one frame.
+    fn decode_frame(bytes: S, config: &ParserConfig) -> Result<Self, StompParseError>;
+}
+
+impl<S: Storage> DecodableFrame<S> for ClientFrame<S> {
+    fn decode_frame(bytes: S, config: &ParserConfig) -> Result<Self, StompParseError> {
+        ClientFrame::parse_with(bytes, config)
+    }
+}
+
+impl<S: Storage> DecodableFrame<S> for ServerFrame<S> {
+    fn decode_frame(bytes: S, config: &ParserConfig) -> Result<Self, StompParseError> {
+        ServerFrame::parse_with(bytes, config)
+    }
+}
+
+impl DecodableFrame for AnyFrame {
+    fn decode_frame(bytes: Vec<u8>, config: &ParserConfig) -> Result<Self, StompParseError> {
+        crate::any::parse_any_with(bytes, config)
+    }
+}
+
+/// An item produced by the decoder.
+#[derive(Debug)]
+pub enum DecodedItem<F> {
+    /// A complete frame.
+    Frame(F),
+    /// An EOL received between frames, which the peer sent as a heart-beat.
+    HeartBeat,
+}
+
+/// The part of a frame received so far by a `FrameDecoder`: its command, and the headers whose
+/// lines have been completely received. Names and values are as they appear on the wire, lossily
+/// converted to UTF-8.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartialFrame {
+    pub command: String,
+    pub headers: Vec<(String, String)>,
+    /// True if all the headers have been received, so that only the body remains.
+    pub headers_complete: bool,
+}
+
+impl PartialFrame {
+    /// The value of the first header received with the specified name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// The bytes still to be discarded from a frame which was too large.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Discard {
+    /// The frame ends with the next null octet.
+    UntilNull,
+    /// The frame ends after the specified number of bytes, as given by its `content-length`.
+    Bytes(usize),
+}
+
+/// Decodes frames of type `F` from a stream of bytes, storing each frame in a buffer from `A`.
+pub struct FrameDecoder<F, A = GlobalAllocator> {
+    buffer: Vec<u8>,
+    config: ParserConfig,
+    discard: Option<Discard>,
+    report_heartbeats: bool,
+    metrics: Box<dyn ParserMetrics>,
+    allocator: A,
+    frame: PhantomData<F>,
+}
+
+impl<F: DecodableFrame> Default for FrameDecoder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: DecodableFrame> FrameDecoder<F> {
+    /// Creates a decoder using the default `ParserConfig`.
+    pub fn new() -> Self {
+        Self::with_config(ParserConfig::default())
+    }
+
+    /// Creates a decoder which parses frames using the provided configuration.
+    pub fn with_config(config: ParserConfig) -> Self {
+        Self::with_allocator(config, GlobalAllocator)
+    }
+}
+
+impl<F: DecodableFrame<A::Buffer>, A: FrameAllocator> FrameDecoder<F, A> {
+    /// Creates a decoder which parses frames using the provided configuration, storing each in a
+    /// buffer from `allocator`. The bytes received but not yet decoded are still held in a `Vec`.
+    pub fn with_allocator(config: ParserConfig, allocator: A) -> Self {
+        FrameDecoder {
+            buffer: Vec::new(),
+            config,
+            discard: None,
+            report_heartbeats: false,
+            metrics: Box::new(NoMetrics),
+            allocator,
+            frame: PhantomData,
+        }
+    }
+
+    /// If true, each EOL received between frames is returned as `DecodedItem::HeartBeat`;
+    /// otherwise such EOLs are silently discarded. Defaults to false.
+    pub fn report_heartbeats(mut self, report_heartbeats: bool) -> Self {
+        self.report_heartbeats = report_heartbeats;
+        self
+    }
+
+    /// Reports what is decoded to the provided metrics. To retain access to the metrics, pass an
+    /// `Arc` and keep a clone of it.
+    pub fn with_metrics<M: ParserMetrics + 'static>(mut self, metrics: M) -> Self {
+        self.metrics = Box::new(metrics);
+        self
+    }
+
+    /// Appends received bytes to those awaiting decoding.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Appends the received bytes held by `buf`, which need not be contiguous, such as a chain of
+    /// buffers from a scatter-gather read, consuming them from `buf`.
+    #[cfg(feature = "bytes")]
+    pub fn extend_from_buf<B: bytes::Buf>(&mut self, buf: &mut B) {
+        self.buffer.reserve(buf.remaining());
+        while buf.has_remaining() {
+            let chunk = buf.chunk();
+            let chunk_len = chunk.len();
+            self.buffer.extend_from_slice(chunk);
+            buf.advance(chunk_len);
+        }
+    }
+
+    /// Discards all bytes received but not yet decoded, including any partial frame, so that
+    /// decoding starts afresh with the next bytes received, with the same configuration and metrics.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.discard = None;
+    }
+
+    /// The number of bytes received but not yet decoded.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns true if part of a frame has been received, or a frame which was too large is being
+    /// discarded; false if the decoder is between frames.
+    pub fn is_mid_frame(&self) -> bool {
+        self.discard.is_some() || self.buffer.iter().any(|b| *b != b'\n' && *b != b'\r')
+    }
+
+    /// Returns true if a frame which was too large is being discarded.
+    pub fn is_discarding(&self) -> bool {
+        self.discard.is_some()
+    }
+
+    /// The command and headers of the frame being received, as far as they are known, or `None`
+    /// if not even the command has been received completely.
+    pub fn partial_frame(&self) -> Option<PartialFrame> {
+        match self.discard {
+            Some(_) => None,
+            None => partial_frame(trim_leading_eols(&self.buffer)),
+        }
+    }
+
+    /// Returns the next item which can be decoded from the bytes received so far, or `None` if
+    /// more bytes are required.
+    ///
+    /// If an error is returned, the bytes of the offending frame are discarded so that decoding
+    /// can continue with the next frame. This includes a frame exceeding the configured
+    /// `max_frame_len`, which is discarded as its bytes are received, rather than buffered.
+    pub fn decode(&mut self) -> Result<Option<DecodedItem<F>>, StompParseError> {
+        if self.continue_discard() {
+            return Ok(None);
+        }
+
+        loop {
+            match leading_eol_len(&self.buffer) {
+                Some(0) => break,
+                Some(eol_len) => {
+                    self.buffer.drain(..eol_len);
+                    self.metrics.bytes_consumed(eol_len);
+                    self.metrics.heartbeat_received();
+                    if self.report_heartbeats {
+                        return Ok(Some(DecodedItem::HeartBeat));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+
+        match self.frame_len() {
+            Ok(Some(frame_len)) if self.exceeds_limit(frame_len) => {
+                Err(self.too_large(Discard::Bytes(frame_len)))
+            }
+            Ok(Some(frame_len)) => {
+                let frame = self.allocator.allocate(&self.buffer[..frame_len]);
+                self.buffer.drain(..frame_len);
+                self.metrics.bytes_consumed(frame_len);
+
+                let command = Self::command(frame.as_ref());
+
+                F::decode_frame(frame, &self.config)
+                    .map(|frame| {
+                        self.metrics.frame_parsed(&command);
+                        Some(DecodedItem::Frame(frame))
+                    })
+                    .inspect_err(|error| self.metrics.parse_error(error.kind()))
+            }
+            Ok(None) => match self.known_frame_len() {
+                Some(frame_len) if self.exceeds_limit(frame_len) => {
+                    Err(self.too_large(Discard::Bytes(frame_len)))
+                }
+                _ if self.exceeds_limit(self.buffer.len()) => {
+                    Err(self.too_large(Discard::UntilNull))
+                }
+                _ => Ok(None),
+            },
+            Err(error) => {
+                self.discard_frame();
+                self.metrics.parse_error(error.kind());
+                Err(error)
+            }
+        }
+    }
+
+    /// The command line of the frame, without its EOL.
+    fn command(frame: &[u8]) -> String {
+        let line = frame.split(|b| *b == b'\n').next().unwrap_or(frame);
+        String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)).into_owned()
+    }
+
+    /// The length, including the null octet, of the frame at the start of the buffer, or `None`
+    /// if the frame has not yet been completely received.
+    fn frame_len(&self) -> Result<Option<usize>, StompParseError> {
+        match head_len(&self.buffer, &self.config)? {
+            Some((head_len, content_length)) => {
+                Ok(
+                    body_bounds(&self.buffer, head_len, content_length, &self.config)?
+                        .map(|(_, null_index)| null_index + 1),
+                )
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The length of the frame at the start of the buffer, if it is known from the frame's
+    /// `content-length` header.
+    fn known_frame_len(&self) -> Option<usize> {
+        if self.config.content_length_policy != ContentLengthPolicy::Strict {
+            return None;
+        }
+
+        match head_len(&self.buffer, &self.config) {
+            Ok(Some((head_len, Some(content_length)))) => body_end(head_len, content_length)
+                .ok()
+                .map(|body_end| body_end + 1),
+            _ => None,
+        }
+    }
+
+    fn exceeds_limit(&self, len: usize) -> bool {
+        self.config.max_frame_len.is_some_and(|max| len > max)
+    }
+
+    /// Starts discarding the frame at the start of the buffer, returning the error reporting it.
+    fn too_large(&mut self, discard: Discard) -> StompParseError {
+        let error = frame_too_large(&self.buffer, self.config.max_frame_len.unwrap_or_default());
+
+        self.discard = Some(discard);
+        self.continue_discard();
+
+        self.metrics.parse_error(error.kind());
+        error
+    }
+
+    /// Discards the bytes of a frame which was too large, returning true if more remain to be
+    /// discarded.
+    fn continue_discard(&mut self) -> bool {
+        let discard_len = match self.discard {
+            None => return false,
+            Some(Discard::UntilNull) => match self.buffer.iter().position(|b| *b == 0) {
+                Some(null_index) => {
+                    self.discard = None;
+                    null_index + 1
+                }
+                None => self.buffer.len(),
+            },
+            Some(Discard::Bytes(remaining)) => {
+                let discard_len = remaining.min(self.buffer.len());
+                self.discard = match remaining - discard_len {
+                    0 => None,
+                    remaining => Some(Discard::Bytes(remaining)),
+                };
+                discard_len
+            }
+        };
+
+        self.buffer.drain(..discard_len);
+        self.metrics.bytes_consumed(discard_len);
+
+        self.discard.is_some()
+    }
+
+    /// Discards the bytes up to and including the next null octet, or all bytes if there is none.
+    fn discard_frame(&mut self) {
+        let frame_len = self
+            .buffer
+            .iter()
+            .position(|b| *b == 0)
+            .map(|null_index| null_index + 1)
+            .unwrap_or(self.buffer.len());
+
+        self.buffer.drain(..frame_len);
+        self.metrics.bytes_consumed(frame_len);
+    }
+}
+
+/// The length of the EOL at the start of `buffer`, zero if there is none, or `None` if this
+/// cannot yet be determined.
+pub(crate) fn leading_eol_len(buffer: &[u8]) -> Option<usize> {
+    match buffer {
+        [] | [b'\r'] => None,
+        [b'\n', ..] => Some(1),
+        [b'\r', b'\n', ..] => Some(2),
+        _ => Some(0),
+    }
+}
+
+/// Removes the EOLs preceding a frame at the start of `buffer`.
+fn trim_leading_eols(mut buffer: &[u8]) -> &[u8] {
+    while let Some(eol_len) = leading_eol_len(buffer).filter(|eol_len| *eol_len > 0) {
+        buffer = &buffer[eol_len..];
+    }
+    buffer
+}
+
+/// The error reporting that the frame at the start of `buffer` exceeds `limit` bytes.
+pub(crate) fn frame_too_large(buffer: &[u8], limit: usize) -> StompParseError {
+    let (command, destination) = match partial_frame(buffer) {
+        Some(frame) => {
+            let destination = frame
+                .header(HeaderName::Destination.as_str())
+                .map(str::to_owned);
+            (Some(frame.command), destination)
+        }
+        None => (None, None),
+    };
+
+    StompParseError::with_kind(
+        ErrorKind::FrameTooLarge {
+            limit,
+            command: command.clone(),
+            destination,
+        },
+        format!(
+            "{} frame exceeds the maximum length of {} bytes",
+            command.as_deref().unwrap_or("Unknown"),
+            limit
+        ),
+    )
+}
+
+/// The command and headers of the frame at the start of `buffer`, as far as they have been
+/// completely received.
+pub(crate) fn partial_frame(buffer: &[u8]) -> Option<PartialFrame> {
+    let mut lines = buffer
+        .split(|b| *b == b'\n')
+        // The last part is not terminated by an EOL, so is incomplete
+        .take(buffer.split(|b| *b == b'\n').count() - 1)
+        .map(|line| String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)));
+
+    let command = lines.next()?.into_owned();
+    let mut headers = Vec::new();
+    let mut headers_complete = false;
+    for line in lines {
+        if line.is_empty() {
+            headers_complete = true;
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap_or((&line, ""));
+        headers.push((name.to_owned(), value.to_owned()));
+    }
+
+    Some(PartialFrame {
+        command,
+        headers,
+        headers_complete,
+    })
+}
+
+/// The length of the head - the command and headers, including the blank line ending them - of
+/// the frame at the start of `buffer`, along with the value of its `content-length` header, if
+/// any; or `None` if the head has not yet been completely received.
+pub(crate) fn head_len(
+    buffer: &[u8],
+    config: &ParserConfig,
+) -> Result<Option<(usize, Option<usize>)>, StompParseError> {
+    let mut line_start = 0;
+    let mut content_length = None;
+    let mut is_command = true;
+
+    while let Some(line_len) = buffer[line_start..].iter().position(|b| *b == b'\n') {
+        let line = &buffer[line_start..line_start + line_len];
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        line_start += line_len + 1;
+
+        if line.is_empty() && !is_command {
+            return Ok(Some((line_start, content_length)));
+        }
+
+        if content_length.is_none() && !is_command {
+            content_length = parse_content_length(line, config)?;
+        }
+        is_command = false;
+    }
+
+    Ok(None)
+}
+
+/// The length of the body of the frame whose body starts at `body_start` in `buffer`, and the
+/// position of the null octet terminating the frame, as given by `content_length` and the
+/// `content_length_policy` of `config`; or `None` if the frame has not been completely received.
+pub(crate) fn body_bounds(
+    buffer: &[u8],
+    body_start: usize,
+    content_length: Option<usize>,
+    config: &ParserConfig,
+) -> Result<Option<(usize, usize)>, StompParseError> {
+    let null_from = |start: usize| {
+        buffer
+            .get(start..)
+            .and_then(|rest| rest.iter().position(|b| *b == 0))
+            .map(|null_index| start + null_index)
+    };
+
+    match (content_length, config.content_length_policy) {
+        (
+            Some(content_length),
+            ContentLengthPolicy::Strict | ContentLengthPolicy::TrustContentLength,
+        ) => {
+            let body_end = body_end(body_start, content_length)?;
+            match buffer.get(body_end) {
+                None => Ok(None),
+                Some(0) => Ok(Some((content_length, body_end))),
+                Some(_) => Err(content_length_mismatch(content_length)),
+            }
+        }
+        _ => Ok(null_from(body_start).map(|null| (null - body_start, null))),
+    }
+}
+
+/// The index of the end of a body of `content_length` bytes starting at `body_start`, failing if
+/// the frame's length, including its null octet, would overflow.
+pub(crate) fn body_end(body_start: usize, content_length: usize) -> Result<usize, StompParseError> {
+    body_start
+        .checked_add(content_length)
+        .filter(|body_end| body_end.checked_add(1).is_some())
+        .ok_or_else(|| invalid_content_length(NumberError::Overflow))
+}
+
+// The frame cannot be delimited without its content-length, so the error is reported as fatal
+fn invalid_content_length(error: NumberError) -> StompParseError {
+    StompParseError::with_kind(
+        ErrorKind::InvalidContentLength(error),
+        format!("Invalid content-length header: {}", error),
+    )
+}
+
+fn parse_content_length(
+    line: &[u8],
+    config: &ParserConfig,
+) -> Result<Option<usize>, StompParseError> {
+    const NAME: &[u8] = b"content-length:";
+
+    if line.len() < NAME.len() {
+        return Ok(None);
+    }
+
+    let (name, value) = line.split_at(NAME.len());
+
+    let matches = if config.case_insensitive_headers {
+        name.eq_ignore_ascii_case(NAME)
+    } else {
+        name == NAME
+    };
+
+    if !matches {
+        return Ok(None);
+    }
+
+    let value =
+        std::str::from_utf8(value).map_err(|_| invalid_content_length(NumberError::NotNumeric))?;
+    parse_number(value)
+        .map(Some)
+        .map_err(|error| match error.kind() {
+            ErrorKind::InvalidNumber(error) => invalid_content_length(*error),
+            _ => error,
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use super::{DecodedItem, FrameDecoder};
+    use crate::any::AnyFrame;
+    use crate::client::ClientFrame;
+    use crate::config::{ContentLengthPolicy, ParserConfig};
+    use crate::error::ErrorKind;
+    use crate::metrics::ParserMetrics;
+    use crate::server::ServerFrame;
+
+    fn decode_all<F: super::DecodableFrame>(decoder: &mut FrameDecoder<F>) -> Vec<DecodedItem<F>> {
+        let mut items = Vec::new();
+        while let Some(item) = decoder.decode().expect("Decode failed") {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn decodes_consecutive_frames() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend(b"BEGIN\ntransaction:a\n\n\x00SEND\ndestination:b\n\nhello\x00");
+
+        let items = decode_all(&mut decoder);
+
+        assert_eq!(2, items.len());
+        assert!(matches!(
+            items[0],
+            DecodedItem::Frame(ClientFrame::Begin(_))
+        ));
+        assert!(matches!(items[1], DecodedItem::Frame(ClientFrame::Send(_))));
+    }
+
+    #[test]
+    fn reports_partial_frame() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend(b"\n");
+        assert!(!decoder.is_mid_frame());
+        assert_eq!(None, decoder.partial_frame());
+
+        decoder.extend(b"SEND\ndestination:b\ncontent-ty");
+        assert!(decoder.decode().unwrap().is_none());
+
+        assert!(decoder.is_mid_frame());
+        assert_eq!(29, decoder.buffered_len());
+        let partial = decoder.partial_frame().unwrap();
+        assert_eq!("SEND", partial.command);
+        assert_eq!(Some("b"), partial.header("destination"));
+        assert_eq!(1, partial.headers.len());
+        assert!(!partial.headers_complete);
+
+        decoder.extend(b"pe:text/plain\n\nhel");
+        assert!(decoder.partial_frame().unwrap().headers_complete);
+    }
+
+    #[test]
+    fn resets_partial_frame() {
+        let mut decoder = FrameDecoder::<ClientFrame>::with_config(ParserConfig {
+            max_frame_len: Some(24),
+            ..ParserConfig::default()
+        });
+        decoder.extend(b"SEND\ndestination:b\n\nhello, world");
+        assert!(decoder.decode().is_err());
+        assert!(decoder.is_discarding());
+
+        decoder.reset();
+        assert!(!decoder.is_mid_frame());
+
+        decoder.extend(b"BEGIN\ntransaction:t\n\n\x00");
+        assert!(matches!(
+            decoder.decode().unwrap(),
+            Some(DecodedItem::Frame(ClientFrame::Begin(_)))
+        ));
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn decodes_from_chained_buffers() {
+        use bytes::Buf;
+
+        let mut buf = (&b"SEND\ndestination:b\n"[..]).chain(&b"\nhello\x00"[..]);
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend_from_buf(&mut buf);
+
+        assert!(!buf.has_remaining());
+        let Some(DecodedItem::Frame(ClientFrame::Send(frame))) = decoder.decode().unwrap() else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(b"hello", frame.body().unwrap());
+    }
+
+    #[test]
+    fn waits_for_complete_frame() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend(b"SEND\ndestination:b\n\nhel");
+
+        assert!(decoder.decode().unwrap().is_none());
+
+        decoder.extend(b"lo\x00");
+
+        let Some(DecodedItem::Frame(ClientFrame::Send(frame))) = decoder.decode().unwrap() else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(b"hello", frame.body().unwrap());
+    }
+
+    #[test]
+    fn consumes_eols_between_frames() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend(b"\n\r\nBEGIN\ntransaction:a\n\n\x00\n\n\r\nBEGIN\ntransaction:b\n\n\x00\r");
+
+        let items = decode_all(&mut decoder);
+
+        assert_eq!(2, items.len());
+
+        decoder.extend(b"\n");
+        assert!(decode_all(&mut decoder).is_empty());
+    }
+
+    #[test]
+    fn reports_eols_as_heartbeats() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new().report_heartbeats(true);
+        decoder.extend(b"\n\r\nBEGIN\ntransaction:a\n\n\x00\n");
+
+        let items = decode_all(&mut decoder);
+
+        assert_eq!(4, items.len());
+        assert!(matches!(items[0], DecodedItem::HeartBeat));
+        assert!(matches!(items[1], DecodedItem::HeartBeat));
+        assert!(matches!(items[2], DecodedItem::Frame(_)));
+        assert!(matches!(items[3], DecodedItem::HeartBeat));
+    }
+
+    #[test]
+    fn uses_content_length_to_find_end_of_body() {
+        let mut decoder = FrameDecoder::<ServerFrame>::new();
+        decoder.extend(
+            b"MESSAGE\nmessage-id:1\ndestination:a\nsubscription:s\ncontent-length:3\n\n\x00\x01\x02\x00",
+        );
+
+        let Some(DecodedItem::Frame(ServerFrame::Message(frame))) = decoder.decode().unwrap()
+        else {
+            panic!("Not a Message Frame!")
+        };
+        assert_eq!(&[0u8, 1, 2], frame.body().unwrap());
+    }
+
+    #[test]
+    fn applies_content_length_policy() {
+        let bytes =
+            b"SEND\ndestination:a\ncontent-length:3\n\nhello\x00BEGIN\ntransaction:t\n\n\x00";
+        let decode = |policy| {
+            let mut decoder = FrameDecoder::<ClientFrame>::with_config(ParserConfig {
+                content_length_policy: policy,
+                ..ParserConfig::default()
+            });
+            decoder.extend(bytes);
+            let first = match decoder.decode() {
+                Ok(Some(DecodedItem::Frame(ClientFrame::Send(frame)))) => {
+                    Ok(frame.body().unwrap().to_vec())
+                }
+                Ok(_) => panic!("Not a Send Frame!"),
+                Err(error) => Err(error.kind().clone()),
+            };
+            let next = decoder.decode().unwrap();
+            assert!(matches!(
+                next,
+                Some(DecodedItem::Frame(ClientFrame::Begin(_)))
+            ));
+            first
+        };
+
+        assert_eq!(
+            Err(ErrorKind::ContentLengthMismatch { content_length: 3 }),
+            decode(ContentLengthPolicy::Strict)
+        );
+        assert_eq!(
+            Ok(b"hello".to_vec()),
+            decode(ContentLengthPolicy::TrustNull)
+        );
+        assert_eq!(
+            Err(ErrorKind::ContentLengthMismatch { content_length: 3 }),
+            decode(ContentLengthPolicy::TrustContentLength)
+        );
+    }
+
+    #[test]
+    fn ends_body_at_first_null_when_trusting_null() {
+        let bytes = b"SEND\ndestination:a\ncontent-length:3\n\na\x00b\x00";
+        let config = ParserConfig {
+            content_length_policy: ContentLengthPolicy::TrustNull,
+            ..ParserConfig::default()
+        };
+
+        let mut decoder = FrameDecoder::<ClientFrame>::with_config(config.clone());
+        decoder.extend(bytes);
+        let Ok(Some(DecodedItem::Frame(ClientFrame::Send(decoded)))) = decoder.decode() else {
+            panic!("Send Frame not decoded")
+        };
+        let Ok(ClientFrame::Send(parsed)) = ClientFrame::parse_with(bytes.to_vec(), &config) else {
+            panic!("Send Frame not parsed")
+        };
+
+        assert_eq!(Some(&b"a"[..]), decoded.body());
+        assert_eq!(decoded.body(), parsed.body());
+    }
+
+    #[test]
+    fn classifies_fatal_errors() {
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        decoder.extend(b"SEND\n\nhello\x00");
+        assert!(!decoder.decode().unwrap_err().is_fatal());
+
+        decoder.extend(b"SEND\ndestination:a\ncontent-length:x\n\nhello\x00");
+        let error = decoder.decode().unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidContentLength(crate::error::NumberError::NotNumeric),
+            error.kind()
+        );
+        assert!(error.is_fatal());
+
+        decoder.extend(b"SEND\ndestination:a\ncontent-length:2\n\nhello\x00");
+        assert!(decoder.decode().unwrap_err().is_fatal());
+    }
+
+    #[test]
+    fn discards_frame_in_error() {
+        let mut decoder = FrameDecoder::<AnyFrame>::new();
+        decoder.extend(b"SEND\ncontent-length:2\n\nabc\x00RECEIPT\nreceipt-id:1\n\n\x00");
+
+        assert!(decoder.decode().is_err());
+
+        let Some(DecodedItem::Frame(AnyFrame::Server(ServerFrame::Receipt(_)))) =
+            decoder.decode().unwrap()
+        else {
+            panic!("Not a Receipt Frame!")
+        };
+    }
+
+    #[test]
+    fn discards_oversized_frame() {
+        let config = ParserConfig {
+            max_frame_len: Some(64),
+            ..ParserConfig::default()
+        };
+        let mut decoder = FrameDecoder::<ClientFrame>::with_config(config);
+
+        decoder.extend(b"SEND\ndestination:/queue/a\n\n");
+        decoder.extend(&[b'x'; 40]);
+
+        let error = decoder.decode().expect_err("Frame not too large");
+        assert_eq!(
+            &ErrorKind::FrameTooLarge {
+                limit: 64,
+                command: Some("SEND".to_owned()),
+                destination: Some("/queue/a".to_owned())
+            },
+            error.kind()
+        );
+
+        decoder.extend(&[b'x'; 1000]);
+        assert!(decoder.decode().unwrap().is_none());
+
+        decoder.extend(b"\x00BEGIN\ntransaction:a\n\n\x00");
+        assert!(matches!(
+            decoder.decode().unwrap(),
+            Some(DecodedItem::Frame(ClientFrame::Begin(_)))
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_content_length_early() {
+        let config = ParserConfig {
+            max_frame_len: Some(64),
+            ..ParserConfig::default()
+        };
+        let mut decoder = FrameDecoder::<ClientFrame>::with_config(config);
+
+        decoder.extend(b"SEND\ndestination:a\ncontent-length:100\n\n");
+        assert!(decoder.decode().is_err());
+
+        // The body may contain null octets, as its length is known
+        decoder.extend(&[0u8; 100]);
+        decoder.extend(b"\x00BEGIN\ntransaction:a\n\n\x00");
+        assert!(matches!(
+            decoder.decode().unwrap(),
+            Some(DecodedItem::Frame(ClientFrame::Begin(_)))
+        ));
+    }
+
+    #[test]
+    fn rejects_overflowing_content_length() {
+        for config in [
+            ParserConfig::default(),
+            ParserConfig {
+                max_frame_len: Some(64),
+                ..ParserConfig::default()
+            },
+        ] {
+            let mut decoder = FrameDecoder::<ClientFrame>::with_config(config);
+            decoder.extend(b"SEND\ndestination:a\ncontent-length:18446744073709551615\n\nabc");
+
+            let error = decoder.decode().unwrap_err();
+            assert_eq!(
+                &ErrorKind::InvalidContentLength(crate::error::NumberError::Overflow),
+                error.kind()
+            );
+            assert!(error.is_fatal());
+        }
+
+        let mut decoder = FrameDecoder::<ClientFrame>::with_config(ParserConfig {
+            max_frame_len: Some(64),
+            ..ParserConfig::default()
+        });
+        decoder.extend(b"SEND\ndestination:a\ncontent-length:18446744073709551000\n\nabc");
+        assert!(matches!(
+            decoder.decode().unwrap_err().kind(),
+            ErrorKind::FrameTooLarge { limit: 64, .. }
+        ));
+    }
+
+    #[derive(Default)]
+    struct TestMetrics {
+        commands: Mutex<Vec<String>>,
+        bytes: AtomicUsize,
+        heartbeats: AtomicUsize,
+        errors: Mutex<Vec<ErrorKind>>,
+    }
+
+    impl ParserMetrics for TestMetrics {
+        fn frame_parsed(&self, command: &str) {
+            self.commands.lock().unwrap().push(command.to_owned());
+        }
+
+        fn bytes_consumed(&self, bytes: usize) {
+            self.bytes.fetch_add(bytes, Ordering::SeqCst);
+        }
+
+        fn heartbeat_received(&self) {
+            self.heartbeats.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn parse_error(&self, kind: &ErrorKind) {
+            self.errors.lock().unwrap().push(kind.clone());
+        }
+    }
+
+    #[test]
+    fn reports_metrics() {
+        let metrics = Arc::new(TestMetrics::default());
+        let mut decoder = FrameDecoder::<AnyFrame>::new().with_metrics(metrics.clone());
+
+        let input = b"\nSEND\ndestination:a\n\n\x00SEND\ncontent-length:x\n\n\x00\r\nRECEIPT\nreceipt-id:1\n\n\x00";
+        decoder.extend(input);
+
+        assert!(decoder.decode().unwrap().is_some());
+        assert!(decoder.decode().is_err());
+        assert!(decoder.decode().unwrap().is_some());
+        assert!(decoder.decode().unwrap().is_none());
+
+        assert_eq!(
+            vec!["SEND".to_owned(), "RECEIPT".to_owned()],
+            *metrics.commands.lock().unwrap()
+        );
+        assert_eq!(input.len(), metrics.bytes.load(Ordering::SeqCst));
+        assert_eq!(2, metrics.heartbeats.load(Ordering::SeqCst));
+        assert_eq!(1, metrics.errors.lock().unwrap().len());
+    }
+
+    #[test]
+    fn stores_frames_in_allocated_buffers() {
+        let allocations = AtomicUsize::new(0);
+        let allocator = |bytes: &[u8]| -> Arc<[u8]> {
+            allocations.fetch_add(1, Ordering::SeqCst);
+            Arc::from(bytes)
+        };
+        let mut decoder = FrameDecoder::<ClientFrame<Arc<[u8]>>, _>::with_allocator(
+            ParserConfig::default(),
+            allocator,
+        );
+        decoder.extend(b"BEGIN\ntransaction:a\n\n\x00SEND\ndestination:b\n\nhello\x00");
+
+        let Some(DecodedItem::Frame(ClientFrame::Begin(_))) = decoder.decode().unwrap() else {
+            panic!("Begin Frame not decoded")
+        };
+        let Some(DecodedItem::Frame(ClientFrame::Send(frame))) = decoder.decode().unwrap() else {
+            panic!("Send Frame not decoded")
+        };
+
+        assert_eq!(b"hello", frame.body().unwrap());
+        assert_eq!(2, allocations.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,197 @@
+//! Allows brokers to restrict destinations to their naming scheme, so that frames with invalid
+//! destinations are rejected when parsed or built, rather than later by the application.
+use std::sync::Arc;
+
+use crate::client::{SendFrame, SendFrameBuilder, SubscribeFrame, SubscribeFrameBuilder};
+use crate::error::{BuildError, ErrorKind, StompParseError};
+use crate::server::{MessageFrame, MessageFrameBuilder};
+
+/// Decides whether a destination is acceptable.
+pub trait DestinationValidator: Send + Sync {
+    /// Returns `Ok` if `destination` is acceptable, otherwise the reason it is not.
+    fn validate(&self, destination: &str) -> Result<(), String>;
+}
+
+impl<F: Fn(&str) -> Result<(), String> + Send + Sync> DestinationValidator for F {
+    fn validate(&self, destination: &str) -> Result<(), String> {
+        self(destination)
+    }
+}
+
+/// A validator for the common naming schemes: destinations must start with one of the allowed
+/// prefixes, if any are specified, and be no longer than the maximum length, if one is specified.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DestinationRules {
+    prefixes: Vec<String>,
+    max_len: Option<usize>,
+}
+
+impl DestinationRules {
+    pub fn new() -> DestinationRules {
+        DestinationRules::default()
+    }
+
+    /// Allows destinations starting with `prefix`, e.g. `/queue/`.
+    pub fn allow_prefix(mut self, prefix: String) -> DestinationRules {
+        self.prefixes.push(prefix);
+        self
+    }
+
+    /// Rejects destinations longer than `max_len` bytes.
+    pub fn max_len(mut self, max_len: usize) -> DestinationRules {
+        self.max_len = Some(max_len);
+        self
+    }
+}
+
+impl DestinationValidator for DestinationRules {
+    fn validate(&self, destination: &str) -> Result<(), String> {
+        if let Some(max_len) = self.max_len.filter(|max_len| destination.len() > *max_len) {
+            return Err(format!("longer than {} bytes", max_len));
+        }
+
+        if !self.prefixes.is_empty()
+            && !self
+                .prefixes
+                .iter()
+                .any(|prefix| destination.starts_with(prefix.as_str()))
+        {
+            return Err(format!("does not start with one of {:?}", self.prefixes));
+        }
+
+        Ok(())
+    }
+}
+
+/// A validator shared between the clones of a `ParserConfig`. Configurations are equal only if
+/// they share the same validator.
+#[derive(Clone)]
+pub struct SharedDestinationValidator(Arc<dyn DestinationValidator>);
+
+impl SharedDestinationValidator {
+    pub fn new<V: DestinationValidator + 'static>(validator: V) -> SharedDestinationValidator {
+        SharedDestinationValidator(Arc::new(validator))
+    }
+
+    pub(crate) fn check(&self, destination: &str) -> Result<(), StompParseError> {
+        check(self.0.as_ref(), destination)
+    }
+}
+
+impl std::fmt::Debug for SharedDestinationValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedDestinationValidator")
+    }
+}
+
+impl PartialEq for SharedDestinationValidator {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedDestinationValidator {}
+
+fn check(validator: &dyn DestinationValidator, destination: &str) -> Result<(), StompParseError> {
+    validator.validate(destination).map_err(|reason| {
+        StompParseError::with_kind(
+            ErrorKind::InvalidDestination {
+                destination: destination.to_owned(),
+                reason: reason.clone(),
+            },
+            format!("Invalid destination '{}': {}", destination, reason),
+        )
+    })
+}
+
+macro_rules! validated_builder {
+    ( $(($builder:ident, $frame:ident)),* ) => {
+        $(
+            impl $builder {
+                /// Builds the frame, returning an error if its destination is rejected by
+                /// `validator`.
+                pub fn build_validated(
+                    self,
+                    validator: &dyn DestinationValidator,
+                ) -> Result<$frame<'static>, BuildError> {
+                    let frame = self.build();
+                    let destination = frame.destination().value();
+                    validator.validate(destination).map_err(|reason| {
+                        BuildError::InvalidDestination {
+                            destination: destination.to_owned(),
+                            reason,
+                        }
+                    })?;
+                    Ok(frame)
+                }
+            }
+        )*
+    };
+}
+
+validated_builder!(
+    (SendFrameBuilder, SendFrame),
+    (SubscribeFrameBuilder, SubscribeFrame),
+    (MessageFrameBuilder, MessageFrame)
+);
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::DestinationRules;
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::config::ParserConfig;
+    use crate::error::ErrorKind;
+
+    fn rules() -> DestinationRules {
+        DestinationRules::new()
+            .allow_prefix("/queue/".to_owned())
+            .allow_prefix("/topic/".to_owned())
+            .max_len(16)
+    }
+
+    #[test]
+    fn rejects_invalid_destination_when_parsing() {
+        let config = ParserConfig::new().with_destination_validator(rules());
+
+        let error =
+            ClientFrame::parse_with(b"SEND\ndestination:/other/a\n\n\x00".to_vec(), &config)
+                .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::InvalidDestination { destination, .. } if destination == "/other/a"
+        ));
+
+        assert!(
+            ClientFrame::parse_with(b"SEND\ndestination:/queue/a\n\n\x00".to_vec(), &config)
+                .is_ok()
+        );
+        assert!(ClientFrame::try_from(b"SEND\ndestination:/other/a\n\n\x00".to_vec()).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_destination_when_building() {
+        let too_long =
+            SendFrameBuilder::new("/queue/abcdefghijklmnop".to_owned()).build_validated(&rules());
+        assert!(too_long.is_err());
+
+        let valid = SendFrameBuilder::new("/topic/a".to_owned()).build_validated(&rules());
+        assert!(valid.is_ok());
+
+        let closure = |destination: &str| {
+            if destination.contains('*') {
+                Err("wildcards are not allowed".to_owned())
+            } else {
+                Ok(())
+            }
+        };
+        let error = SendFrameBuilder::new("a.*".to_owned())
+            .build_validated(&closure)
+            .unwrap_err();
+        assert_eq!(
+            "invalid destination 'a.*': wildcards are not allowed",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Compares frames, reporting precisely how they differ; intended for tests, where comparing the
+//! serialised frames as strings produces unreadable assertion failures.
+use std::fmt::{self, Display, Formatter};
+
+use crate::common::raw::RawParts;
+
+/// A header whose value differs between the compared frames.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HeaderDiff {
+    pub name: String,
+    pub left: String,
+    pub right: String,
+}
+
+/// The differences between two frames. Headers are compared by name, using the first occurrence
+/// of each, so that the order of the headers is not significant.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FrameDiff {
+    /// The left and right commands, if they differ.
+    pub command: Option<(String, String)>,
+    /// The headers present in both frames, but with different values.
+    pub changed_headers: Vec<HeaderDiff>,
+    /// The headers, with their values, present only in the left frame.
+    pub left_only_headers: Vec<(String, String)>,
+    /// The headers, with their values, present only in the right frame.
+    pub right_only_headers: Vec<(String, String)>,
+    /// The offset of the first byte at which the bodies differ, if they do.
+    pub body_offset: Option<usize>,
+}
+
+impl FrameDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self == &FrameDiff::default()
+    }
+}
+
+impl Display for FrameDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some((left, right)) = &self.command {
+            writeln!(f, "command: {} != {}", left, right)?;
+        }
+        for header in &self.changed_headers {
+            writeln!(
+                f,
+                "header {}: '{}' != '{}'",
+                header.name, header.left, header.right
+            )?;
+        }
+        for (name, value) in &self.left_only_headers {
+            writeln!(f, "header {}: '{}' only in left", name, value)?;
+        }
+        for (name, value) in &self.right_only_headers {
+            writeln!(f, "header {}: '{}' only in right", name, value)?;
+        }
+        if let Some(offset) = self.body_offset {
+            writeln!(f, "bodies differ from byte {}", offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares two serialised frames, which may be the frames themselves or their bytes.
+pub fn diff<L: AsRef<[u8]> + ?Sized, R: AsRef<[u8]> + ?Sized>(left: &L, right: &R) -> FrameDiff {
+    let left = RawParts::split(left.as_ref());
+    let right = RawParts::split(right.as_ref());
+
+    let mut diff = FrameDiff::default();
+
+    if left.command != right.command {
+        diff.command = Some((left.command.to_string(), right.command.to_string()));
+    }
+
+    for (name, left_value) in first_occurrences(&left) {
+        match right.header(name) {
+            Some(right_value) if right_value != left_value => {
+                diff.changed_headers.push(HeaderDiff {
+                    name: name.to_owned(),
+                    left: left_value.to_owned(),
+                    right: right_value.to_owned(),
+                })
+            }
+            Some(_) => {}
+            None => diff
+                .left_only_headers
+                .push((name.to_owned(), left_value.to_owned())),
+        }
+    }
+
+    for (name, right_value) in first_occurrences(&right) {
+        if left.header(name).is_none() {
+            diff.right_only_headers
+                .push((name.to_owned(), right_value.to_owned()));
+        }
+    }
+
+    if left.body != right.body {
+        diff.body_offset = Some(
+            left.body
+                .iter()
+                .zip(right.body)
+                .position(|(left, right)| left != right)
+                .unwrap_or_else(|| left.body.len().min(right.body.len())),
+        );
+    }
+
+    diff
+}
+
+fn first_occurrences<'a>(parts: &'a RawParts) -> impl Iterator<Item = (&'a str, &'a str)> {
+    parts
+        .headers
+        .iter()
+        .enumerate()
+        .filter(move |(index, (name, _))| {
+            !parts.headers[..*index]
+                .iter()
+                .any(|(earlier, _)| earlier == name)
+        })
+        .map(|(_, (name, value))| (name.as_ref(), value.as_ref()))
+}
+
+/// Asserts that two frames are equal, as determined by [`diff`](crate::diff::diff); if they are
+/// not, the panic message lists the differences.
+#[macro_export]
+macro_rules! assert_frames_eq {
+    ($left:expr, $right:expr $(,)?) => {{
+        let diff = $crate::diff::diff(&$left, &$right);
+        if !diff.is_empty() {
+            panic!("assertion failed: frames differ\n{}", diff);
+        }
+    }};
+    ($left:expr, $right:expr, $($arg:tt)+) => {{
+        let diff = $crate::diff::diff(&$left, &$right);
+        if !diff.is_empty() {
+            panic!("assertion failed: frames differ: {}\n{}", format_args!($($arg)+), diff);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, HeaderDiff};
+    use crate::client::SendFrameBuilder;
+
+    #[test]
+    fn reports_differences() {
+        let left = b"SEND\ndestination:a\nreceipt:1\nx:1\n\nhello\x00".to_vec();
+        let right = b"SEND\nx:1\ndestination:b\ny:2\n\nhelp\x00".to_vec();
+
+        let diff = diff(&left, &right);
+
+        assert_eq!(None, diff.command);
+        assert_eq!(
+            vec![HeaderDiff {
+                name: "destination".to_owned(),
+                left: "a".to_owned(),
+                right: "b".to_owned()
+            }],
+            diff.changed_headers
+        );
+        assert_eq!(
+            vec![("receipt".to_owned(), "1".to_owned())],
+            diff.left_only_headers
+        );
+        assert_eq!(
+            vec![("y".to_owned(), "2".to_owned())],
+            diff.right_only_headers
+        );
+        assert_eq!(Some(3), diff.body_offset);
+    }
+
+    #[test]
+    fn equal_frames_have_no_differences() {
+        let frame = SendFrameBuilder::new("a".to_owned())
+            .body(b"hello".to_vec())
+            .build();
+
+        assert_frames_eq!(frame, frame.as_bytes().to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "bodies differ from byte 5")]
+    fn assert_frames_eq_reports_body_offset() {
+        assert_frames_eq!(
+            b"SEND\ndestination:a\n\nhello\x00".to_vec(),
+            b"SEND\ndestination:a\n\nhello world\x00".to_vec()
+        );
+    }
+}
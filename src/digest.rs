@@ -0,0 +1,305 @@
+//! Computes digests of frames: a stable hash of their content, for deduplication, and HMACs, for
+//! signing. The digests use SHA-256, implemented here as the crate has no cryptographic
+//! dependencies.
+use std::collections::BTreeMap;
+
+use either::Either;
+
+use crate::common::functions::decode_str;
+use crate::common::raw::RawParts;
+use crate::headers::HeaderName;
+
+/// The length in bytes of a SHA-256 digest.
+pub const DIGEST_LEN: usize = 32;
+
+const BLOCK_LEN: usize = 64;
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// An incremental SHA-256 computation.
+#[derive(Clone)]
+pub(crate) struct Sha256 {
+    state: [u32; 8],
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    pub(crate) fn new() -> Sha256 {
+        Sha256 {
+            state: INITIAL_STATE,
+            block: [0; BLOCK_LEN],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+
+        while !data.is_empty() {
+            let taken = (BLOCK_LEN - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + taken].copy_from_slice(&data[..taken]);
+            self.block_len += taken;
+            data = &data[taken..];
+
+            if self.block_len == BLOCK_LEN {
+                self.compress();
+                self.block_len = 0;
+            }
+        }
+    }
+
+    /// Writes `field` preceded by its length, so that consecutive fields cannot be confused.
+    pub(crate) fn update_field(&mut self, field: &[u8]) {
+        self.update(&(field.len() as u64).to_be_bytes());
+        self.update(field);
+    }
+
+    pub(crate) fn finish(mut self) -> [u8; DIGEST_LEN] {
+        let bit_len = self.total_len * 8;
+
+        self.update(&[0x80]);
+        while self.block_len != BLOCK_LEN - 8 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_be_bytes());
+
+        let mut digest = [0; DIGEST_LEN];
+        for (chunk, word) in digest.chunks_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut schedule = [0u32; 64];
+        for (word, chunk) in schedule.iter_mut().zip(self.block.chunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for index in 16..64 {
+            let s0 = schedule[index - 15].rotate_right(7)
+                ^ schedule[index - 15].rotate_right(18)
+                ^ (schedule[index - 15] >> 3);
+            let s1 = schedule[index - 2].rotate_right(17)
+                ^ schedule[index - 2].rotate_right(19)
+                ^ (schedule[index - 2] >> 10);
+            schedule[index] = schedule[index - 16]
+                .wrapping_add(s0)
+                .wrapping_add(schedule[index - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for (constant, word) in ROUND_CONSTANTS.iter().zip(schedule.iter()) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(*constant)
+                .wrapping_add(*word);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(majority);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+/// An incremental HMAC-SHA256 computation (RFC 2104).
+#[cfg(feature = "signing")]
+pub(crate) struct HmacSha256 {
+    inner: Sha256,
+    outer_key: [u8; BLOCK_LEN],
+}
+
+#[cfg(feature = "signing")]
+impl HmacSha256 {
+    pub(crate) fn new(key: &[u8]) -> HmacSha256 {
+        let mut block_key = [0u8; BLOCK_LEN];
+        if key.len() > BLOCK_LEN {
+            let mut hasher = Sha256::new();
+            hasher.update(key);
+            block_key[..DIGEST_LEN].copy_from_slice(&hasher.finish());
+        } else {
+            block_key[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha256::new();
+        inner.update(&block_key.map(|byte| byte ^ 0x36));
+
+        HmacSha256 {
+            inner,
+            outer_key: block_key.map(|byte| byte ^ 0x5c),
+        }
+    }
+
+    pub(crate) fn finish(self) -> [u8; DIGEST_LEN] {
+        let mut outer = Sha256::new();
+        outer.update(&self.outer_key);
+        outer.update(&self.inner.finish());
+        outer.finish()
+    }
+}
+
+/// The content hash of the serialised frame `raw`, whose body is `body`.
+///
+/// The hash covers the command, the headers and the body. Headers are canonicalised: only the
+/// first occurrence of each header is included, as only it determines the header's value; values
+/// are unescaped; headers are ordered by name; and the `content-length` header is omitted, as it
+/// is implied by the body.
+pub(crate) fn content_hash(raw: &[u8], body: Option<&[u8]>) -> [u8; DIGEST_LEN] {
+    let mut hasher = Sha256::new();
+    write_canonical(&mut hasher, raw, body, &[]);
+    hasher.finish()
+}
+
+/// The HMAC-SHA256, keyed with `key`, of the canonical form of the frame `raw` used by
+/// `content_hash`, omitting also the headers named in `excluded`.
+#[cfg(feature = "signing")]
+pub(crate) fn hmac(
+    key: &[u8],
+    raw: &[u8],
+    body: Option<&[u8]>,
+    excluded: &[&str],
+) -> [u8; DIGEST_LEN] {
+    let mut hmac = HmacSha256::new(key);
+    write_canonical(&mut hmac.inner, raw, body, excluded);
+    hmac.finish()
+}
+
+fn write_canonical(hasher: &mut Sha256, raw: &[u8], body: Option<&[u8]>, excluded: &[&str]) {
+    let parts = RawParts::split(raw);
+
+    let mut headers = BTreeMap::new();
+    for (name, value) in &parts.headers {
+        let name = unescape(name);
+        if name != HeaderName::ContentLength.as_str() && !excluded.contains(&name.as_str()) {
+            headers.entry(name).or_insert_with(|| unescape(value));
+        }
+    }
+
+    hasher.update_field(parts.command.as_bytes());
+    hasher.update(&(headers.len() as u64).to_be_bytes());
+    for (name, value) in &headers {
+        hasher.update_field(name.as_bytes());
+        hasher.update_field(value.as_bytes());
+    }
+    match body {
+        Some(body) => {
+            hasher.update(&[1]);
+            hasher.update_field(body);
+        }
+        None => hasher.update(&[0]),
+    }
+}
+
+/// The unescaped form of `value`, or `value` itself if it contains an invalid escape sequence.
+fn unescape(value: &str) -> String {
+    match decode_str(value) {
+        Ok(Either::Right(decoded)) => decoded,
+        _ => value.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::Sha256;
+    use crate::client::ClientFrame;
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn computes_sha256() {
+        let digest = |data: &[u8]| {
+            let mut hasher = Sha256::new();
+            hasher.update(data);
+            hex(&hasher.finish())
+        };
+
+        assert_eq!(
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            digest(b"")
+        );
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            digest(b"abc")
+        );
+        assert_eq!(
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+            digest(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")
+        );
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn computes_hmac() {
+        let mut hmac = super::HmacSha256::new(b"Jefe");
+        hmac.inner.update(b"what do ya want for nothing?");
+
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            hex(&hmac.finish())
+        );
+
+        let mut hmac = super::HmacSha256::new(&[0xaa; 131]);
+        hmac.inner
+            .update(b"Test Using Larger Than Block-Size Key - Hash Key First");
+
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            hex(&hmac.finish())
+        );
+    }
+
+    #[test]
+    fn content_hash_ignores_header_order_and_encoding() {
+        let hash = |bytes: &[u8]| {
+            ClientFrame::try_from(bytes.to_vec())
+                .unwrap()
+                .content_hash()
+        };
+
+        let hash_a = hash(b"SEND\ndestination:a\nx-id:1\\c2\n\nhello\x00");
+        let hash_b = hash(b"SEND\ncontent-length:5\nx-id:1\\c2\ndestination:a\n\nhello\x00");
+        let hash_c = hash(b"SEND\ndestination:a\nx-id:1\\c3\n\nhello\x00");
+
+        assert_eq!(hash_a, hash_b);
+        assert_ne!(hash_a, hash_c);
+        assert_ne!(
+            hash(b"SEND\ndestination:a\n\n\x00"),
+            hash(b"SEND\ndestination:a\ncontent-length:0\n\n\x00")
+        );
+    }
+}
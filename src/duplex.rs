@@ -0,0 +1,170 @@
+//! An in-memory connection between a client and a server, for the tests of crates which build on
+//! this one: each half sends frames to the other and decodes those it receives, so that frames
+//! can be exchanged without sockets.
+//!
+//! The halves also implement `Read` and `Write`, so that code written against a stream can be
+//! given one. As the connection is not backed by threads, a read with nothing to receive fails
+//! with `WouldBlock` rather than waiting, while a read once the other half has been dropped
+//! returns 0.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::client::{ClientFrame, ConnectFrameBuilder};
+//! use stomp_parser::duplex::duplex;
+//! use stomp_parser::headers::{StompVersion, StompVersions};
+//! use stomp_parser::server::{ConnectedFrameBuilder, ServerFrame};
+//!
+//! let (mut client, mut server) = duplex();
+//!
+//! client.send(ConnectFrameBuilder::new("broker", StompVersions(vec![StompVersion::V1_2])).build());
+//! assert!(matches!(server.receive(), Ok(Some(ClientFrame::Connect(_)))));
+//!
+//! server.send(ConnectedFrameBuilder::new(StompVersion::V1_2).build());
+//! assert!(matches!(client.receive(), Ok(Some(ServerFrame::Connected(_)))));
+//! ```
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, Weak};
+
+use crate::client::ClientFrame;
+use crate::decoder::{DecodableFrame, DecodedItem, FrameDecoder};
+use crate::error::StompParseError;
+use crate::server::ServerFrame;
+
+type Pipe = Arc<Mutex<VecDeque<u8>>>;
+
+/// One half of an in-memory connection, which receives frames of type `F`.
+pub struct DuplexHalf<F> {
+    incoming: Pipe,
+    outgoing: Weak<Mutex<VecDeque<u8>>>,
+    decoder: FrameDecoder<F>,
+}
+
+/// The half of a connection used by a client, which receives server frames.
+pub type ClientHalf = DuplexHalf<ServerFrame>;
+
+/// The half of a connection used by a server, which receives client frames.
+pub type ServerHalf = DuplexHalf<ClientFrame>;
+
+/// A connected pair of halves.
+pub fn duplex() -> (ClientHalf, ServerHalf) {
+    let to_client = Pipe::default();
+    let to_server = Pipe::default();
+    (
+        DuplexHalf::new(to_client.clone(), &to_server),
+        DuplexHalf::new(to_server, &to_client),
+    )
+}
+
+impl<F: DecodableFrame> DuplexHalf<F> {
+    fn new(incoming: Pipe, outgoing: &Pipe) -> DuplexHalf<F> {
+        DuplexHalf {
+            incoming,
+            outgoing: Arc::downgrade(outgoing),
+            decoder: FrameDecoder::new(),
+        }
+    }
+
+    /// Sends the serialised `frame` to the other half. Frames sent after the other half has been
+    /// dropped are discarded.
+    pub fn send<T: Into<Vec<u8>>>(&mut self, frame: T) {
+        // Writes to a dropped half are discarded, so cannot fail
+        let _ = self.write_all(&frame.into());
+    }
+
+    /// Sends a heart-beat, a single EOL, to the other half.
+    pub fn send_heartbeat(&mut self) {
+        self.send(b"\n".to_vec());
+    }
+
+    /// The next frame sent by the other half, or `None` if no complete frame has been sent.
+    /// Heart-beats are skipped.
+    pub fn receive(&mut self) -> Result<Option<F>, StompParseError> {
+        let received: Vec<u8> = self.lock_incoming().drain(..).collect();
+        self.decoder.extend(&received);
+
+        loop {
+            match self.decoder.decode()? {
+                Some(DecodedItem::Frame(frame)) => return Ok(Some(frame)),
+                Some(DecodedItem::HeartBeat) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn lock_incoming(&self) -> std::sync::MutexGuard<'_, VecDeque<u8>> {
+        self.incoming
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<F> std::fmt::Debug for DuplexHalf<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DuplexHalf")
+            .field("connected", &(self.outgoing.strong_count() > 0))
+            .finish()
+    }
+}
+
+impl<F: DecodableFrame> Read for DuplexHalf<F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut incoming = self.lock_incoming();
+        if incoming.is_empty() && self.outgoing.strong_count() > 0 {
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+        incoming.read(buf)
+    }
+}
+
+impl<F> Write for DuplexHalf<F> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        if let Some(outgoing) = self.outgoing.upgrade() {
+            outgoing
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .extend(bytes);
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{ErrorKind, Read, Write};
+
+    use super::duplex;
+    use crate::client::{ClientFrame, SendFrameBuilder};
+
+    #[test]
+    fn exchanges_frames_through_streams() {
+        let (mut client, mut server) = duplex();
+
+        client.write_all(b"SEND\ndestination:a\n\nhi").unwrap();
+        assert!(server.receive().unwrap().is_none());
+        client.send_heartbeat();
+        client.write_all(b"\x00").unwrap();
+        client.send(SendFrameBuilder::new("b").build());
+
+        let Ok(Some(ClientFrame::Send(first))) = server.receive() else {
+            panic!("Send Frame not received")
+        };
+        assert!(matches!(server.receive(), Ok(Some(ClientFrame::Send(_)))));
+        assert_eq!(Some(&b"hi\n"[..]), first.body());
+
+        server.write_all(b"RECEIPT\nreceipt-id:1\n\n\x00").unwrap();
+        let mut received = [0; 64];
+        assert_eq!(23, client.read(&mut received).unwrap());
+        assert_eq!(
+            ErrorKind::WouldBlock,
+            client.read(&mut received).unwrap_err().kind()
+        );
+
+        drop(server);
+        assert_eq!(0, client.read(&mut received).unwrap());
+    }
+}
@@ -0,0 +1,147 @@
+//! Encodes a frame in two phases: the command and headers are written first, with a
+//! `content-length`, and the body is appended later, in chunks, followed by the terminating null
+//! octet. This allows a frame to be sent while its body is still arriving from another stream.
+//!
+//! # Example
+//! ```
+//! use std::io::Write;
+//!
+//! use stomp_parser::client::SendFrameBuilder;
+//!
+//! let mut body = SendFrameBuilder::new("/queue/a")
+//!     .encode_head(5, Vec::new())
+//!     .unwrap();
+//! body.write_all(b"hel").unwrap();
+//! body.write_all(b"lo").unwrap();
+//! let bytes = body.finish().unwrap();
+//!
+//! assert_eq!(
+//!     &b"SEND\ndestination:/queue/a\ncontent-length:5\n\nhello\x00"[..],
+//!     &bytes[..]
+//! );
+//! ```
+use std::io::Write;
+
+use crate::error::EncodeError;
+
+/// Writes the body of a frame whose command and headers have already been written; obtained from
+/// the `encode_head` method of the builders of frames with a body. The frame is only complete once
+/// `finish` has been called.
+#[derive(Debug)]
+pub struct BodyEncoder<W: Write> {
+    writer: W,
+    content_length: usize,
+    remaining: usize,
+}
+
+impl<W: Write> BodyEncoder<W> {
+    pub(crate) fn new(writer: W, content_length: usize) -> BodyEncoder<W> {
+        BodyEncoder {
+            writer,
+            content_length,
+            remaining: content_length,
+        }
+    }
+
+    /// The number of bytes of the body still to be written.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Writes the terminating null octet, once the whole body has been written, returning the
+    /// writer. Fails if fewer bytes than the `content-length` have been written.
+    pub fn finish(mut self) -> Result<W, EncodeError> {
+        if self.remaining > 0 {
+            return Err(EncodeError::BodyIncomplete {
+                remaining: self.remaining,
+            });
+        }
+        self.writer.write_all(b"\0")?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes body bytes; writes beyond the `content-length` fail, as they would corrupt the frame, with
+/// an error whose inner error is `EncodeError::BodyTooLong`.
+impl<W: Write> Write for BodyEncoder<W> {
+    fn write(&mut self, chunk: &[u8]) -> std::io::Result<usize> {
+        if chunk.len() > self.remaining {
+            return Err(EncodeError::BodyTooLong {
+                content_length: self.content_length,
+            }
+            .into());
+        }
+        let written = self.writer.write(chunk)?;
+        self.remaining -= written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the frame `raw` to `writer` as text, failing without writing anything if it is not
+/// valid UTF-8.
+pub(crate) fn write_str<W: std::fmt::Write + ?Sized>(
+    raw: &[u8],
+    writer: &mut W,
+) -> Result<(), EncodeError> {
+    let text = std::str::from_utf8(raw).map_err(|error| EncodeError::NotUtf8 {
+        valid_up_to: error.valid_up_to(),
+    })?;
+    writer.write_str(text).map_err(EncodeError::Fmt)
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::io::Write;
+
+    use crate::error::EncodeError;
+    use crate::server::{ErrorFrameBuilder, ServerFrame};
+
+    #[test]
+    fn checks_the_body_length() {
+        let mut body = ErrorFrameBuilder::new()
+            .add_custom_header("content-length", "9")
+            .encode_head(3, Vec::new())
+            .unwrap();
+
+        let error = body.write_all(b"abcd").unwrap_err();
+        assert!(matches!(
+            error.get_ref().and_then(|inner| inner.downcast_ref()),
+            Some(EncodeError::BodyTooLong { content_length: 3 })
+        ));
+        body.write_all(b"ab").unwrap();
+        assert_eq!(1, body.remaining());
+
+        body.write_all(b"c").unwrap();
+        let bytes = body.finish().unwrap();
+        assert_eq!(&b"ERROR\ncontent-length:3\n\nabc\x00"[..], &bytes[..]);
+        assert!(ServerFrame::try_from(bytes).is_ok());
+
+        let body = ErrorFrameBuilder::new().encode_head(1, Vec::new()).unwrap();
+        assert!(matches!(
+            body.finish(),
+            Err(EncodeError::BodyIncomplete { remaining: 1 })
+        ));
+    }
+
+    #[test]
+    fn writes_text_frames_as_str() {
+        let frame = ServerFrame::try_from(b"RECEIPT\nreceipt-id:1\n\n\x00".to_vec()).unwrap();
+        let mut text = String::new();
+        frame.write_str_to(&mut text).unwrap();
+        assert_eq!("RECEIPT\nreceipt-id:1\n\n\0", text);
+
+        let frame =
+            ServerFrame::try_from(b"ERROR\ncontent-length:2\n\n\xff\xfe\x00".to_vec()).unwrap();
+        let mut text = String::new();
+        assert!(matches!(
+            frame.write_str_to(&mut text),
+            Err(EncodeError::NotUtf8 { valid_up_to: 24 })
+        ));
+        assert!(text.is_empty());
+    }
+}
@@ -6,18 +6,267 @@ use nom::error::{ContextError, FromExternalError, ParseError, VerboseError};
 #[derive(Debug)]
 pub struct StompParseError {
     message: String,
+    kind: ErrorKind,
+}
+
+/// Classifies the problem reported by a `StompParseError`. Further kinds may be added, so matches
+/// on the kind must include a wildcard arm.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A problem for which there is no more specific kind.
+    Other,
+    /// A value which should be a number could not be parsed as one.
+    InvalidNumber(NumberError),
+    /// A frame exceeded the maximum length of `limit` bytes. The command and destination are those
+    /// of the frame, if they were received before the limit was exceeded.
+    FrameTooLarge {
+        limit: usize,
+        command: Option<String>,
+        destination: Option<String>,
+    },
+    /// A destination was rejected by the configured `DestinationValidator`, for the given reason.
+    InvalidDestination { destination: String, reason: String },
+    /// The header named `header` contained `byte`, which is not allowed in headers: a null octet,
+    /// or a carriage return or line feed which does not end the header's line. `offset` is the
+    /// position of the byte in the frame.
+    InvalidHeaderByte {
+        header: String,
+        offset: usize,
+        byte: u8,
+    },
+    /// The body of a frame was not followed by a null octet after the `content_length` bytes given
+    /// by its `content-length` header.
+    ContentLengthMismatch { content_length: usize },
+    /// The `content-length` header of a frame being decoded from a stream was not a valid number.
+    InvalidContentLength(NumberError),
+    /// Decompressing the body of a frame produced more than `limit` bytes.
+    DecodedBodyTooLarge { limit: usize },
+    /// The frame ended before it was complete, such as before the blank line ending its headers,
+    /// or without the null octet terminating it.
+    Incomplete,
+    /// The frame did not start with a command.
+    MissingCommand,
+    /// The frame's command, `command`, is not one of those which may be parsed.
+    UnknownCommand { command: String },
+    /// The header `name`, which frames with the frame's command require, was missing.
+    MissingHeader { name: String },
+    /// The header `name` is not allowed in frames with the frame's command.
+    UnexpectedHeader { name: String },
+}
+
+impl ErrorKind {
+    /// True if the error leaves the position of the next frame in a stream unknown, so that the
+    /// connection should be closed. Other errors concern only the frame in error, which a decoder
+    /// discards before continuing with the next.
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::ContentLengthMismatch { .. } | ErrorKind::InvalidContentLength(_)
+        )
+    }
+}
+
+/// The reason a value could not be parsed as a number.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum NumberError {
+    /// The value was empty.
+    Empty,
+    /// The value contained characters other than decimal digits.
+    NotNumeric,
+    /// The value was negative, where only non-negative values are allowed.
+    Negative,
+    /// The value was too large for the type of the value.
+    Overflow,
+}
+
+impl std::fmt::Display for NumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            NumberError::Empty => "empty value",
+            NumberError::NotNumeric => "not a number",
+            NumberError::Negative => "negative value",
+            NumberError::Overflow => "value too large",
+        })
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Other => f.write_str("malformed frame"),
+            ErrorKind::InvalidNumber(error) => write!(f, "invalid number: {}", error),
+            ErrorKind::FrameTooLarge { limit, .. } => {
+                write!(f, "frame exceeds the limit of {} bytes", limit)
+            }
+            ErrorKind::InvalidDestination {
+                destination,
+                reason,
+            } => write!(f, "invalid destination '{}': {}", destination, reason),
+            ErrorKind::InvalidHeaderByte {
+                header,
+                offset,
+                byte,
+            } => write!(
+                f,
+                "invalid byte {:#04x} in header '{}' at offset {}",
+                byte, header, offset
+            ),
+            ErrorKind::ContentLengthMismatch { content_length } => write!(
+                f,
+                "body not terminated after {} bytes of content-length",
+                content_length
+            ),
+            ErrorKind::InvalidContentLength(error) => {
+                write!(f, "invalid content-length: {}", error)
+            }
+            ErrorKind::DecodedBodyTooLarge { limit } => {
+                write!(f, "decoded body exceeds the limit of {} bytes", limit)
+            }
+            ErrorKind::Incomplete => f.write_str("incomplete frame"),
+            ErrorKind::MissingCommand => f.write_str("missing command"),
+            ErrorKind::UnknownCommand { command } => write!(f, "unknown command '{}'", command),
+            ErrorKind::MissingHeader { name } => write!(f, "missing header '{}'", name),
+            ErrorKind::UnexpectedHeader { name } => write!(f, "unexpected header '{}'", name),
+        }
+    }
 }
 
 impl StompParseError {
     pub fn new<S: Into<String>>(message: S) -> StompParseError {
+        StompParseError::with_kind(ErrorKind::Other, message)
+    }
+
+    pub fn with_kind<S: Into<String>>(kind: ErrorKind, message: S) -> StompParseError {
         StompParseError {
             message: message.into(),
+            kind,
         }
     }
 
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// True if the connection on which the error occurred should be closed; see
+    /// `ErrorKind::is_fatal`.
+    pub fn is_fatal(&self) -> bool {
+        self.kind.is_fatal()
+    }
+}
+
+impl std::fmt::Display for StompParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StompParseError {}
+
+/// An error building a frame from a builder.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// The destination was rejected by a `DestinationValidator`, for the given reason.
+    InvalidDestination { destination: String, reason: String },
+    /// The header name is empty, or contains a character which cannot be written in one.
+    InvalidHeaderName { name: String },
+    /// The value of the header `name` cannot be written or parsed, for the given reason.
+    InvalidHeaderValue { name: String, reason: String },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidDestination {
+                destination,
+                reason,
+            } => write!(f, "invalid destination '{}': {}", destination, reason),
+            BuildError::InvalidHeaderName { name } => write!(f, "invalid header name {:?}", name),
+            BuildError::InvalidHeaderValue { name, reason } => {
+                write!(f, "invalid value for header {}: {}", name, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// An error encoding a frame with a `BodyEncoder`, or writing it as text.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum EncodeError {
+    /// Writing to the underlying writer failed.
+    Io(std::io::Error),
+    /// More bytes were written to the body than its `content-length` allows.
+    BodyTooLong { content_length: usize },
+    /// The frame was finished with `remaining` bytes of the body not written.
+    BodyIncomplete { remaining: usize },
+    /// The frame cannot be written as text, as it is not valid UTF-8 from byte `valid_up_to`.
+    NotUtf8 { valid_up_to: usize },
+    /// Writing to the underlying `fmt::Write` failed.
+    Fmt(std::fmt::Error),
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::Io(error) => write!(f, "IO error: {}", error),
+            EncodeError::BodyTooLong { content_length } => write!(
+                f,
+                "body is longer than its content-length of {}",
+                content_length
+            ),
+            EncodeError::BodyIncomplete { remaining } => {
+                write!(f, "{} bytes of the body were not written", remaining)
+            }
+            EncodeError::NotUtf8 { valid_up_to } => {
+                write!(f, "frame is not valid UTF-8 from byte {}", valid_up_to)
+            }
+            EncodeError::Fmt(error) => write!(f, "formatting error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Io(error) => Some(error),
+            EncodeError::Fmt(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for EncodeError {
+    fn from(error: std::io::Error) -> Self {
+        EncodeError::Io(error)
+    }
+}
+
+/// Converts the error for use where an `io::Error` is required, such as in `Write`
+/// implementations; errors other than `Io` are carried as the inner error.
+impl From<EncodeError> for std::io::Error {
+    fn from(error: EncodeError) -> Self {
+        match error {
+            EncodeError::Io(error) => error,
+            EncodeError::BodyTooLong { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidInput, error)
+            }
+            EncodeError::BodyIncomplete { .. } => {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, error)
+            }
+            EncodeError::NotUtf8 { .. } => {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+            }
+            EncodeError::Fmt(_) => std::io::Error::other(error),
+        }
+    }
 }
 
 impl From<std::io::Error> for StompParseError {
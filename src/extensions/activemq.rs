@@ -0,0 +1,167 @@
+//! Typed access to the [STOMP headers supported by ActiveMQ](https://activemq.apache.org/components/classic/documentation/stomp)
+//! on SUBSCRIBE frames, which are carried in the custom headers of a frame.
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+use super::parse_header;
+use crate::client::{SubscribeFrame, SubscribeFrameBuilder};
+use crate::error::StompParseError;
+use crate::storage::Storage;
+
+pub const PREFETCH_SIZE: &str = "activemq.prefetchSize";
+pub const PRIORITY: &str = "activemq.priority";
+pub const DISPATCH_ASYNC: &str = "activemq.dispatchAsync";
+
+/// The priority of a consumer, which ActiveMQ restricts to the range 0 to 127.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct ConsumerPriority(u8);
+
+impl ConsumerPriority {
+    pub const MAX: u8 = 127;
+
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for ConsumerPriority {
+    type Error = StompParseError;
+    fn try_from(priority: u8) -> Result<ConsumerPriority, StompParseError> {
+        if priority <= ConsumerPriority::MAX {
+            Ok(ConsumerPriority(priority))
+        } else {
+            Err(StompParseError::new(format!(
+                "Consumer priority out of range: {}",
+                priority
+            )))
+        }
+    }
+}
+
+impl std::fmt::Display for ConsumerPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ConsumerPriority {
+    type Err = StompParseError;
+    fn from_str(input: &str) -> Result<ConsumerPriority, StompParseError> {
+        u8::from_str(input)
+            .map_err(|_| StompParseError::new(format!("Invalid consumer priority: {}", input)))
+            .and_then(ConsumerPriority::try_from)
+    }
+}
+
+/// The prefetch size of a consumer. A size of 0 makes the consumer poll for messages, rather than
+/// have ActiveMQ dispatch them as they arrive.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct PrefetchSize(u32);
+
+impl PrefetchSize {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for PrefetchSize {
+    fn from(size: u32) -> PrefetchSize {
+        PrefetchSize(size)
+    }
+}
+
+impl std::fmt::Display for PrefetchSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for PrefetchSize {
+    type Err = StompParseError;
+    fn from_str(input: &str) -> Result<PrefetchSize, StompParseError> {
+        u32::from_str(input)
+            .map(PrefetchSize)
+            .map_err(|_| StompParseError::new(format!("Invalid prefetch size: {}", input)))
+    }
+}
+
+impl<'a, S: Storage> SubscribeFrame<'a, S> {
+    /// The value of the `activemq.prefetchSize` header.
+    pub fn activemq_prefetch_size(&self) -> Result<Option<PrefetchSize>, StompParseError> {
+        parse_header(self.custom_header(PREFETCH_SIZE))
+    }
+
+    /// The value of the `activemq.priority` header.
+    pub fn activemq_priority(&self) -> Result<Option<ConsumerPriority>, StompParseError> {
+        parse_header(self.custom_header(PRIORITY))
+    }
+
+    /// The value of the `activemq.dispatchAsync` header.
+    pub fn activemq_dispatch_async(&self) -> Result<Option<bool>, StompParseError> {
+        parse_header(self.custom_header(DISPATCH_ASYNC))
+    }
+}
+
+impl SubscribeFrameBuilder {
+    /// Sets the `activemq.prefetchSize` header.
+    pub fn activemq_prefetch_size(self, prefetch_size: PrefetchSize) -> SubscribeFrameBuilder {
+        self.set_custom_header(PREFETCH_SIZE, prefetch_size.to_string())
+    }
+
+    /// Sets the `activemq.priority` header.
+    pub fn activemq_priority(self, priority: ConsumerPriority) -> SubscribeFrameBuilder {
+        self.set_custom_header(PRIORITY, priority.to_string())
+    }
+
+    /// Sets the `activemq.dispatchAsync` header.
+    pub fn activemq_dispatch_async(self, dispatch_async: bool) -> SubscribeFrameBuilder {
+        self.set_custom_header(DISPATCH_ASYNC, dispatch_async.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::{TryFrom, TryInto};
+
+    use super::{ConsumerPriority, PrefetchSize};
+    use crate::client::{ClientFrame, SubscribeFrameBuilder};
+
+    #[test]
+    fn builds_and_parses_headers() {
+        let frame = SubscribeFrameBuilder::new("/queue/a".to_owned(), "sub-1".to_owned())
+            .activemq_prefetch_size(PrefetchSize::from(0))
+            .activemq_prefetch_size(PrefetchSize::from(20))
+            .activemq_priority(ConsumerPriority::try_from(5).unwrap())
+            .activemq_dispatch_async(true)
+            .build();
+
+        let bytes: Vec<u8> = frame.into();
+
+        let Ok(ClientFrame::Subscribe(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Not a Subscribe Frame!")
+        };
+
+        assert_eq!(20, frame.activemq_prefetch_size().unwrap().unwrap().value());
+        assert!(frame.repeated_headers().is_empty());
+        assert_eq!(5, frame.activemq_priority().unwrap().unwrap().value());
+        assert_eq!(Some(true), frame.activemq_dispatch_async().unwrap());
+    }
+
+    #[test]
+    fn validates_values() {
+        assert!(TryInto::<ConsumerPriority>::try_into(128u8).is_err());
+        assert_eq!(PrefetchSize::from(0), "0".parse().unwrap());
+
+        let bytes = b"SUBSCRIBE\ndestination:a\nid:1\nactivemq.priority:200\n\
+            activemq.prefetchSize:-1\nactivemq.dispatchAsync:yes\n\n\x00"
+            .to_vec();
+
+        let Ok(ClientFrame::Subscribe(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Not a Subscribe Frame!")
+        };
+
+        assert!(frame.activemq_priority().is_err());
+        assert!(frame.activemq_prefetch_size().is_err());
+        assert!(frame.activemq_dispatch_async().is_err());
+    }
+}
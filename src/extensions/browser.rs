@@ -0,0 +1,99 @@
+//! Support for queue browsing, as implemented by ActiveMQ and Artemis: a subscription with the
+//! header `browser:true` receives a copy of each message on the queue without consuming it, after
+//! which the broker sends a MESSAGE frame with the header `browser:end` and no body. The headers
+//! are carried in the custom headers of the frames.
+use crate::client::{SubscribeFrame, SubscribeFrameBuilder};
+use crate::error::StompParseError;
+use crate::extensions::delivery::parse_flag;
+use crate::extensions::typed::TypedCustomHeader;
+use crate::server::{MessageFrame, MessageFrameBuilder};
+use crate::storage::Storage;
+
+/// The name of the header marking browsing subscriptions and the end of browsing.
+const BROWSER: &str = "browser";
+
+/// The value of the `browser` header of the MESSAGE frame marking the end of browsing.
+const END: &str = "end";
+
+/// Whether a subscription browses the queue, as requested by the `browser` header of SUBSCRIBE
+/// frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Browser(pub bool);
+
+impl TypedCustomHeader for Browser {
+    const NAME: &'static str = BROWSER;
+
+    fn parse(value: &str) -> Result<Self, StompParseError> {
+        parse_flag(value).map(Browser)
+    }
+
+    fn to_value(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl<'a, S: Storage> SubscribeFrame<'a, S> {
+    /// Returns true if the subscription browses the queue rather than consuming its messages.
+    pub fn is_browser(&self) -> Result<bool, StompParseError> {
+        Ok(self
+            .typed_custom::<Browser>()?
+            .is_some_and(|browser| browser.0))
+    }
+}
+
+impl SubscribeFrameBuilder {
+    /// Sets the `browser` header, which makes the subscription browse the queue if true.
+    pub fn browser(self, browser: bool) -> SubscribeFrameBuilder {
+        self.typed_custom_header(Browser(browser))
+    }
+}
+
+impl<'a, S: Storage> MessageFrame<'a, S> {
+    /// Returns true if this frame marks the end of browsing, rather than carrying a message.
+    pub fn is_browser_end(&self) -> bool {
+        self.custom_header(BROWSER)
+            .is_some_and(|custom| *custom.value() == END)
+    }
+}
+
+impl MessageFrameBuilder {
+    /// Marks the frame as the end of browsing, with the header `browser:end`.
+    pub fn browser_end(self) -> MessageFrameBuilder {
+        self.set_custom_header(BROWSER, END)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::client::{ClientFrame, SubscribeFrameBuilder};
+    use crate::server::{MessageFrameBuilder, ServerFrame};
+
+    #[test]
+    fn marks_browsing() {
+        let bytes: Vec<u8> = SubscribeFrameBuilder::new("/queue/a", "0")
+            .browser(true)
+            .build()
+            .into();
+        let Ok(ClientFrame::Subscribe(subscribe)) = ClientFrame::try_from(bytes) else {
+            panic!("Subscribe Frame not parsed correctly")
+        };
+        assert!(subscribe.is_browser().unwrap());
+
+        let subscribe = SubscribeFrameBuilder::new("/queue/a", "0").build();
+        assert!(!subscribe.is_browser().unwrap());
+
+        let bytes: Vec<u8> = MessageFrameBuilder::new("m", "/queue/a", "0")
+            .browser_end()
+            .build()
+            .into();
+        let Ok(ServerFrame::Message(message)) = ServerFrame::try_from(bytes) else {
+            panic!("Message Frame not parsed correctly")
+        };
+        assert!(message.is_browser_end());
+        assert!(!MessageFrameBuilder::new("m", "/queue/a", "0")
+            .build()
+            .is_browser_end());
+    }
+}
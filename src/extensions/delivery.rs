@@ -0,0 +1,136 @@
+//! Typed access to the `redelivered` and `persistent` flags which brokers add to MESSAGE frames,
+//! and which clients set on SEND frames to request persistence. They are carried in the custom
+//! headers of a frame.
+//!
+//! As brokers differ in how they write the flags, they are read leniently: `true` and `false` in
+//! any case, and `1` and `0`.
+use crate::client::{SendFrame, SendFrameBuilder};
+use crate::error::StompParseError;
+use crate::extensions::typed::TypedCustomHeader;
+use crate::server::{MessageFrame, MessageFrameBuilder};
+use crate::storage::Storage;
+
+/// Whether a message has been delivered before, as reported by the `redelivered` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Redelivered(pub bool);
+
+/// Whether a message is persisted by the broker, as requested or reported by the `persistent`
+/// header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Persistent(pub bool);
+
+pub(crate) fn parse_flag(value: &str) -> Result<bool, StompParseError> {
+    match value.trim() {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        flag if flag.eq_ignore_ascii_case("true") => Ok(true),
+        flag if flag.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(StompParseError::new(format!("Invalid flag: {}", value))),
+    }
+}
+
+impl TypedCustomHeader for Redelivered {
+    const NAME: &'static str = "redelivered";
+
+    fn parse(value: &str) -> Result<Self, StompParseError> {
+        parse_flag(value).map(Redelivered)
+    }
+
+    fn to_value(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl TypedCustomHeader for Persistent {
+    const NAME: &'static str = "persistent";
+
+    fn parse(value: &str) -> Result<Self, StompParseError> {
+        parse_flag(value).map(Persistent)
+    }
+
+    fn to_value(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl<'a, S: Storage> MessageFrame<'a, S> {
+    /// The value of the `redelivered` header.
+    pub fn redelivered(&self) -> Result<Option<bool>, StompParseError> {
+        Ok(self.typed_custom::<Redelivered>()?.map(|flag| flag.0))
+    }
+
+    /// The value of the `persistent` header.
+    pub fn persistent(&self) -> Result<Option<bool>, StompParseError> {
+        Ok(self.typed_custom::<Persistent>()?.map(|flag| flag.0))
+    }
+}
+
+impl MessageFrameBuilder {
+    /// Sets the `redelivered` header.
+    pub fn redelivered(self, redelivered: bool) -> MessageFrameBuilder {
+        self.typed_custom_header(Redelivered(redelivered))
+    }
+
+    /// Sets the `persistent` header.
+    pub fn persistent(self, persistent: bool) -> MessageFrameBuilder {
+        self.typed_custom_header(Persistent(persistent))
+    }
+}
+
+impl<'a, S: Storage> SendFrame<'a, S> {
+    /// The value of the `persistent` header.
+    pub fn persistent(&self) -> Result<Option<bool>, StompParseError> {
+        Ok(self.typed_custom::<Persistent>()?.map(|flag| flag.0))
+    }
+}
+
+impl SendFrameBuilder {
+    /// Sets the `persistent` header.
+    pub fn persistent(self, persistent: bool) -> SendFrameBuilder {
+        self.typed_custom_header(Persistent(persistent))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn reads_flags_leniently() {
+        let message = |flags: &str| {
+            let bytes = format!(
+                "MESSAGE\ndestination:a\nmessage-id:1\nsubscription:0\n{}\n\x00",
+                flags
+            );
+            let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(bytes.into_bytes()) else {
+                panic!("Message Frame not parsed correctly")
+            };
+            (frame.redelivered(), frame.persistent())
+        };
+
+        let (redelivered, persistent) = message("redelivered:TRUE\npersistent:0\n");
+        assert_eq!(Some(true), redelivered.unwrap());
+        assert_eq!(Some(false), persistent.unwrap());
+
+        let (redelivered, persistent) = message("redelivered:maybe\n");
+        assert!(redelivered.is_err());
+        assert_eq!(None, persistent.unwrap());
+    }
+
+    #[test]
+    fn writes_flags() {
+        let bytes: Vec<u8> = SendFrameBuilder::new("a").persistent(true).build().into();
+        assert_eq!(
+            b"SEND\ndestination:a\npersistent:true\n\n\x00".to_vec(),
+            bytes
+        );
+
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Send Frame not parsed correctly")
+        };
+        assert_eq!(Some(true), frame.persistent().unwrap());
+    }
+}
@@ -0,0 +1,190 @@
+//! Support for durable subscriptions, which brokers retain - along with any messages sent to the
+//! destination - while the subscriber is disconnected. The specification does not define durable
+//! subscriptions, so each broker uses its own headers; `DurableNaming` selects which.
+use crate::client::{
+    SubscribeFrame, SubscribeFrameBuilder, UnsubscribeFrame, UnsubscribeFrameBuilder,
+};
+use crate::common::functions::decode_or_raw;
+use crate::headers::CustomValue;
+use crate::storage::Storage;
+
+/// The convention a broker uses to mark a subscription as durable and name it.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum DurableNaming {
+    /// ActiveMQ Classic names the subscription in an `activemq.subscriptionName` header.
+    ActiveMq,
+    /// ActiveMQ Artemis names the subscription in a `durable-subscription-name` header.
+    Artemis,
+    /// RabbitMQ expects `durable:true` and `auto-delete:false`; the subscription is named by its `id`.
+    RabbitMq,
+    /// The subscription is named by the header with the specified name.
+    Custom(String),
+}
+
+const DURABLE: &str = "durable";
+const AUTO_DELETE: &str = "auto-delete";
+
+impl DurableNaming {
+    /// The name of the header carrying the subscription name, if the broker uses one.
+    pub fn name_header(&self) -> Option<&str> {
+        match self {
+            DurableNaming::ActiveMq => Some("activemq.subscriptionName"),
+            DurableNaming::Artemis => Some("durable-subscription-name"),
+            DurableNaming::RabbitMq => None,
+            DurableNaming::Custom(name) => Some(name),
+        }
+    }
+
+    /// The headers which mark a subscription with the specified name as durable.
+    fn subscribe_headers(&self, name: &str) -> Vec<(String, String)> {
+        match self.name_header() {
+            Some(header) => vec![(header.to_owned(), name.to_owned())],
+            None => vec![
+                (DURABLE.to_owned(), "true".to_owned()),
+                (AUTO_DELETE.to_owned(), "false".to_owned()),
+            ],
+        }
+    }
+
+    /// The headers which identify the durable subscription with the specified name when unsubscribing.
+    fn unsubscribe_headers(&self, name: &str) -> Vec<(String, String)> {
+        match self.name_header() {
+            Some(header) => vec![(header.to_owned(), name.to_owned())],
+            None => vec![(DURABLE.to_owned(), "true".to_owned())],
+        }
+    }
+}
+
+impl SubscribeFrameBuilder {
+    /// Makes the subscription durable, with the specified name. For `DurableNaming::RabbitMq`
+    /// the name is ignored, as RabbitMQ names the subscription by its `id`.
+    pub fn durable(self, naming: &DurableNaming, name: &str) -> SubscribeFrameBuilder {
+        naming
+            .subscribe_headers(name)
+            .into_iter()
+            .fold(self, |builder, (name, value)| {
+                builder.set_custom_header(name, value)
+            })
+    }
+}
+
+impl<'a, S: Storage> SubscribeFrame<'a, S> {
+    /// The name of the durable subscription, or `None` if the subscription is not durable.
+    pub fn durable_name(&self, naming: &DurableNaming) -> Option<&str> {
+        durable_name(naming, |name| self.custom_header(name), self.id().value())
+    }
+
+    /// Builds the UNSUBSCRIBE frame which ends this subscription. If the subscription is durable,
+    /// the durable name is included, so that the broker discards the subscription rather than
+    /// just detaching from it.
+    pub fn unsubscribe(&self, naming: &DurableNaming) -> UnsubscribeFrame<'static> {
+        let builder = UnsubscribeFrameBuilder::new(decode_or_raw(self.id().value()));
+
+        match self.durable_name(naming) {
+            Some(name) => naming
+                .unsubscribe_headers(&decode_or_raw(name))
+                .into_iter()
+                .fold(builder, |builder, (name, value)| {
+                    builder.set_custom_header(name, value)
+                }),
+            None => builder,
+        }
+        .build()
+    }
+}
+
+impl<'a, S: Storage> UnsubscribeFrame<'a, S> {
+    /// The name of the durable subscription to discard, or `None` if the frame does not identify
+    /// a durable subscription.
+    pub fn durable_name(&self, naming: &DurableNaming) -> Option<&str> {
+        durable_name(naming, |name| self.custom_header(name), self.id().value())
+    }
+}
+
+fn durable_name<'a>(
+    naming: &DurableNaming,
+    custom_header: impl Fn(&str) -> Option<CustomValue<'a>>,
+    id: &'a str,
+) -> Option<&'a str> {
+    match naming.name_header() {
+        Some(header) => custom_header(header).map(|custom| *custom.value()),
+        None => custom_header(DURABLE)
+            .filter(|custom| *custom.value() == "true")
+            .map(|_| id),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::DurableNaming;
+    use crate::client::{ClientFrame, SubscribeFrameBuilder};
+
+    fn roundtrip(naming: &DurableNaming, expected_headers: &[u8]) {
+        let subscribe = SubscribeFrameBuilder::new("/topic/a".to_owned(), "sub-1".to_owned())
+            .durable(naming, "my-sub")
+            .build();
+
+        let unsubscribe = subscribe.unsubscribe(naming);
+
+        let bytes: Vec<u8> = unsubscribe.into();
+        assert!(bytes
+            .windows(expected_headers.len())
+            .any(|window| window == expected_headers));
+
+        let Ok(ClientFrame::Unsubscribe(unsubscribe)) = ClientFrame::try_from(bytes) else {
+            panic!("Not an Unsubscribe Frame!")
+        };
+        assert_eq!("sub-1", unsubscribe.id().value());
+        assert!(unsubscribe.durable_name(naming).is_some());
+    }
+
+    #[test]
+    fn activemq_unsubscribe_preserves_name() {
+        roundtrip(
+            &DurableNaming::ActiveMq,
+            b"activemq.subscriptionName:my-sub\n",
+        );
+    }
+
+    #[test]
+    fn artemis_unsubscribe_preserves_name() {
+        roundtrip(
+            &DurableNaming::Artemis,
+            b"durable-subscription-name:my-sub\n",
+        );
+    }
+
+    #[test]
+    fn custom_unsubscribe_preserves_name() {
+        roundtrip(
+            &DurableNaming::Custom("x-durable".to_owned()),
+            b"x-durable:my-sub\n",
+        );
+    }
+
+    #[test]
+    fn rabbitmq_uses_id_as_name() {
+        let subscribe = SubscribeFrameBuilder::new("/topic/a".to_owned(), "sub-1".to_owned())
+            .durable(&DurableNaming::RabbitMq, "ignored")
+            .build();
+
+        assert_eq!(
+            Some("sub-1"),
+            subscribe.durable_name(&DurableNaming::RabbitMq)
+        );
+        roundtrip(&DurableNaming::RabbitMq, b"durable:true\n");
+    }
+
+    #[test]
+    fn non_durable_unsubscribe_has_no_name() {
+        let subscribe =
+            SubscribeFrameBuilder::new("/topic/a".to_owned(), "sub-1".to_owned()).build();
+
+        let unsubscribe = subscribe.unsubscribe(&DurableNaming::ActiveMq);
+
+        assert_eq!(None, unsubscribe.durable_name(&DurableNaming::ActiveMq));
+        assert!(unsubscribe.custom_headers().is_empty());
+    }
+}
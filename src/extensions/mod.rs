@@ -0,0 +1,28 @@
+//! Typed support for the non-standard headers used by specific brokers. Support which is specific
+//! to a single broker is enabled by a feature of the same name.
+#[cfg(feature = "activemq")]
+pub mod activemq;
+pub mod browser;
+pub mod delivery;
+pub mod durable;
+#[cfg(feature = "rabbitmq")]
+pub mod rabbitmq;
+pub mod selector;
+pub mod time;
+pub mod typed;
+
+#[cfg(any(feature = "activemq", feature = "rabbitmq"))]
+use crate::{error::StompParseError, headers::CustomValue};
+
+/// Parses the value of an optional custom header.
+#[cfg(any(feature = "activemq", feature = "rabbitmq"))]
+fn parse_header<T: std::str::FromStr>(
+    header: Option<CustomValue>,
+) -> Result<Option<T>, StompParseError> {
+    header
+        .map(|custom| {
+            T::from_str(custom.value())
+                .map_err(|_| StompParseError::new(format!("Invalid value for header: {}", custom)))
+        })
+        .transpose()
+}
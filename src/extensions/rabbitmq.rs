@@ -0,0 +1,177 @@
+//! Typed access to the [STOMP headers supported by RabbitMQ](https://www.rabbitmq.com/docs/stomp),
+//! which are carried in the custom headers of a frame.
+use std::str::FromStr;
+
+use super::parse_header;
+use crate::client::{SendFrame, SendFrameBuilder, SubscribeFrame, SubscribeFrameBuilder};
+use crate::error::StompParseError;
+use crate::headers::CustomValue;
+use crate::server::{MessageFrame, MessageFrameBuilder};
+use crate::storage::Storage;
+
+pub const X_QUEUE_NAME: &str = "x-queue-name";
+pub const X_MAX_PRIORITY: &str = "x-max-priority";
+pub const PREFETCH_COUNT: &str = "prefetch-count";
+pub const X_QUEUE_TYPE: &str = "x-queue-type";
+
+#[derive(Eq, PartialEq, Debug, Clone)]
+/// The type of queue RabbitMQ declares for a destination.
+pub enum QueueType {
+    Classic,
+    Quorum,
+    Stream,
+}
+
+impl std::fmt::Display for QueueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(match self {
+            QueueType::Classic => "classic",
+            QueueType::Quorum => "quorum",
+            QueueType::Stream => "stream",
+        })
+    }
+}
+
+impl FromStr for QueueType {
+    type Err = StompParseError;
+    fn from_str(input: &str) -> Result<QueueType, StompParseError> {
+        match input {
+            "classic" => Ok(QueueType::Classic),
+            "quorum" => Ok(QueueType::Quorum),
+            "stream" => Ok(QueueType::Stream),
+            _ => Err(StompParseError::new(format!(
+                "Unknown queue type: {}",
+                input
+            ))),
+        }
+    }
+}
+
+/// Provides the RabbitMQ headers of a frame. Each accessor returns `Ok(None)` if the header is
+/// absent, and an error if it is present but cannot be parsed.
+pub trait RabbitMqHeaders {
+    /// The custom header with the specified name, if present.
+    fn rabbitmq_header(&self, name: &str) -> Option<CustomValue<'_>>;
+
+    /// The value of the `x-queue-name` header.
+    fn x_queue_name(&self) -> Option<&str> {
+        self.rabbitmq_header(X_QUEUE_NAME)
+            .map(|custom| *custom.value())
+    }
+
+    /// The value of the `x-max-priority` header.
+    fn x_max_priority(&self) -> Result<Option<u8>, StompParseError> {
+        parse_header(self.rabbitmq_header(X_MAX_PRIORITY))
+    }
+
+    /// The value of the `prefetch-count` header.
+    fn prefetch_count(&self) -> Result<Option<u16>, StompParseError> {
+        parse_header(self.rabbitmq_header(PREFETCH_COUNT))
+    }
+
+    /// The value of the `x-queue-type` header.
+    fn x_queue_type(&self) -> Result<Option<QueueType>, StompParseError> {
+        parse_header(self.rabbitmq_header(X_QUEUE_TYPE))
+    }
+}
+
+/// Provides setters for the RabbitMQ headers on a frame builder.
+pub trait RabbitMqHeadersBuilder: Sized {
+    /// Sets the specified header on the frame being built, replacing any previous value.
+    fn rabbitmq_header(self, name: &str, value: String) -> Self;
+
+    /// Sets the `x-queue-name` header.
+    fn x_queue_name(self, queue_name: String) -> Self {
+        self.rabbitmq_header(X_QUEUE_NAME, queue_name)
+    }
+
+    /// Sets the `x-max-priority` header.
+    fn x_max_priority(self, max_priority: u8) -> Self {
+        self.rabbitmq_header(X_MAX_PRIORITY, max_priority.to_string())
+    }
+
+    /// Sets the `prefetch-count` header.
+    fn prefetch_count(self, prefetch_count: u16) -> Self {
+        self.rabbitmq_header(PREFETCH_COUNT, prefetch_count.to_string())
+    }
+
+    /// Sets the `x-queue-type` header.
+    fn x_queue_type(self, queue_type: QueueType) -> Self {
+        self.rabbitmq_header(X_QUEUE_TYPE, queue_type.to_string())
+    }
+}
+
+macro_rules! rabbitmq_headers {
+    ( $( ($frame:ident, $builder:ident) ),* ) => {
+        $(
+            impl<'a, S: Storage> RabbitMqHeaders for $frame<'a, S> {
+                fn rabbitmq_header(&self, name: &str) -> Option<CustomValue<'_>> {
+                    self.custom_header(name)
+                }
+            }
+
+            impl RabbitMqHeadersBuilder for $builder {
+                fn rabbitmq_header(self, name: &str, value: String) -> Self {
+                    self.set_custom_header(name, value)
+                }
+            }
+        )*
+    };
+}
+
+rabbitmq_headers!(
+    (SendFrame, SendFrameBuilder),
+    (SubscribeFrame, SubscribeFrameBuilder),
+    (MessageFrame, MessageFrameBuilder)
+);
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{QueueType, RabbitMqHeaders, RabbitMqHeadersBuilder};
+    use crate::client::{ClientFrame, SubscribeFrameBuilder};
+
+    #[test]
+    fn builds_and_parses_headers() {
+        let frame = SubscribeFrameBuilder::new("/queue/a".to_owned(), "sub-1".to_owned())
+            .x_queue_name("a".to_owned())
+            .x_max_priority(10)
+            .prefetch_count(500)
+            .x_queue_type(QueueType::Quorum)
+            .build();
+
+        let bytes: Vec<u8> = frame.into();
+
+        let Ok(ClientFrame::Subscribe(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Not a Subscribe Frame!")
+        };
+
+        assert_eq!(Some("a"), frame.x_queue_name());
+        assert_eq!(Some(10), frame.x_max_priority().unwrap());
+        assert_eq!(Some(500), frame.prefetch_count().unwrap());
+        assert_eq!(Some(QueueType::Quorum), frame.x_queue_type().unwrap());
+    }
+
+    #[test]
+    fn absent_headers_are_none() {
+        let frame = SubscribeFrameBuilder::new("/queue/a".to_owned(), "sub-1".to_owned()).build();
+
+        assert_eq!(None, frame.x_queue_name());
+        assert_eq!(None, frame.x_max_priority().unwrap());
+        assert_eq!(None, frame.x_queue_type().unwrap());
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        let bytes =
+            b"SEND\ndestination:a\nx-max-priority:1000\nx-queue-type:funky\n\n\x00".to_vec();
+
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Not a Send Frame!")
+        };
+
+        assert!(frame.x_max_priority().is_err());
+        assert!(frame.x_queue_type().is_err());
+    }
+}
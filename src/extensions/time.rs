@@ -0,0 +1,178 @@
+//! Typed access to the `timestamp` and `expires` headers which most brokers, including ActiveMQ
+//! and Artemis, use on SEND and MESSAGE frames. Both hold a time in milliseconds since the Unix
+//! epoch, and are carried in the custom headers of a frame.
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::client::{SendFrame, SendFrameBuilder};
+use crate::custom_header;
+use crate::error::StompParseError;
+use crate::server::{MessageFrame, MessageFrameBuilder};
+use crate::storage::Storage;
+
+custom_header!(
+    /// The time at which a message was sent, in milliseconds since the Unix epoch.
+    pub Timestamp, "timestamp", u64
+);
+
+custom_header!(
+    /// The time after which a message is discarded, in milliseconds since the Unix epoch; 0 means
+    /// that the message never expires.
+    pub Expires, "expires", u64
+);
+
+fn epoch_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_millis().try_into().unwrap_or(u64::MAX))
+}
+
+/// The time `millis` after the epoch, or `None` if the platform cannot represent it.
+fn from_epoch_millis(millis: u64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_millis(millis))
+}
+
+impl Timestamp {
+    /// The timestamp of `time`, which is taken as the epoch if earlier.
+    pub fn from_system_time(time: SystemTime) -> Timestamp {
+        Timestamp(epoch_millis(time))
+    }
+
+    /// The time of the timestamp, or `None` if the platform cannot represent it.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        from_epoch_millis(self.0)
+    }
+}
+
+impl Expires {
+    /// An expiry of `time`. A time no later than the epoch is taken as 1 ms after it, as 0 would
+    /// mean that the message never expires.
+    pub fn from_system_time(time: SystemTime) -> Expires {
+        Expires(epoch_millis(time).max(1))
+    }
+
+    /// The time of expiry, or `None` if the message never expires, or expires later than the
+    /// platform can represent.
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        (self.0 > 0).then(|| from_epoch_millis(self.0)).flatten()
+    }
+
+    /// Returns true if the message has expired at `now`.
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.to_system_time().is_some_and(|expiry| expiry <= now)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Timestamp {
+        Timestamp::from_system_time(time)
+    }
+}
+
+impl From<SystemTime> for Expires {
+    fn from(time: SystemTime) -> Expires {
+        Expires::from_system_time(time)
+    }
+}
+
+/// Implements the accessors and builder methods for the time headers on a frame.
+macro_rules! time_headers {
+    ( $frame:ident, $builder:ident ) => {
+        impl<'a, S: Storage> $frame<'a, S> {
+            /// The value of the `timestamp` header.
+            pub fn timestamp(&self) -> Result<Option<Timestamp>, StompParseError> {
+                self.typed_custom()
+            }
+
+            /// The value of the `expires` header.
+            pub fn expires(&self) -> Result<Option<Expires>, StompParseError> {
+                self.typed_custom()
+            }
+        }
+
+        impl $builder {
+            /// Sets the `timestamp` header to `time`.
+            pub fn timestamp(self, time: SystemTime) -> $builder {
+                self.typed_custom_header(Timestamp::from_system_time(time))
+            }
+
+            /// Sets the `expires` header to `time`.
+            pub fn expires(self, time: SystemTime) -> $builder {
+                self.typed_custom_header(Expires::from_system_time(time))
+            }
+        }
+    };
+}
+
+time_headers!(SendFrame, SendFrameBuilder);
+time_headers!(MessageFrame, MessageFrameBuilder);
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{Expires, Timestamp};
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn round_trips_times() {
+        let sent = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let expiry = sent + Duration::from_secs(60);
+
+        let bytes: Vec<u8> = SendFrameBuilder::new("/queue/a")
+            .timestamp(sent)
+            .expires(expiry)
+            .build()
+            .into();
+        assert!(bytes.starts_with(
+            b"SEND\ndestination:/queue/a\ntimestamp:1700000000123\nexpires:1700000060123\n"
+        ));
+
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Send Frame not parsed correctly")
+        };
+        assert_eq!(
+            Some(sent),
+            frame.timestamp().unwrap().unwrap().to_system_time()
+        );
+        let expires = frame.expires().unwrap().unwrap();
+        assert_eq!(Some(expiry), expires.to_system_time());
+        assert!(!expires.is_expired_at(sent));
+        assert!(expires.is_expired_at(expiry));
+    }
+
+    #[test]
+    fn reads_broker_headers_from_messages() {
+        let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(
+            b"MESSAGE\ndestination:a\nmessage-id:1\nsubscription:0\nexpires:0\ntimestamp:x\n\n\x00"
+                .to_vec(),
+        ) else {
+            panic!("Message Frame not parsed correctly")
+        };
+
+        assert_eq!(Some(Expires(0)), frame.expires().unwrap());
+        assert_eq!(None, Expires(0).to_system_time());
+        assert!(frame.timestamp().is_err());
+        assert_eq!(
+            Timestamp(0),
+            Timestamp::from(UNIX_EPOCH - Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn handles_times_outside_the_representable_range() {
+        let before_epoch = Expires::from(UNIX_EPOCH - Duration::from_secs(1));
+        assert_eq!(Expires(1), before_epoch);
+        assert!(before_epoch.is_expired_at(UNIX_EPOCH + Duration::from_secs(1)));
+
+        let far_future = Expires(u64::MAX);
+        if far_future.to_system_time().is_none() {
+            assert!(!far_future.is_expired_at(UNIX_EPOCH + Duration::from_secs(1)));
+        }
+        assert_eq!(
+            far_future.to_system_time(),
+            Timestamp(u64::MAX).to_system_time()
+        );
+    }
+}
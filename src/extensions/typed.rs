@@ -0,0 +1,147 @@
+//! Typed access to custom headers defined by the user, so that the conversion of a header's value
+//! is defined once, by the type representing the header, rather than wherever the header is used.
+//!
+//! A header type is usually declared with the `custom_header!` macro:
+//!
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::client::{ClientFrame, SendFrameBuilder};
+//! use stomp_parser::custom_header;
+//!
+//! custom_header!(
+//!     /// The priority of a message.
+//!     pub Priority, "x-priority", u8
+//! );
+//!
+//! let bytes: Vec<u8> = SendFrameBuilder::new("/queue/a".to_owned())
+//!     .typed_custom_header(Priority(7))
+//!     .build()
+//!     .into();
+//!
+//! if let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes) {
+//!     assert_eq!(Some(Priority(7)), frame.typed_custom::<Priority>().unwrap());
+//! }
+//! ```
+use crate::error::StompParseError;
+use crate::headers::CustomValue;
+
+/// Declares a header type with an attribute rather than `custom_header!`; see the
+/// `stomp-parser-derive` crate.
+#[cfg(feature = "derive")]
+pub use stomp_parser_derive::stomp_header;
+
+/// A custom header with a typed value.
+pub trait TypedCustomHeader: Sized {
+    /// The name of the header.
+    const NAME: &'static str;
+
+    /// Parses the header from its value.
+    fn parse(value: &str) -> Result<Self, StompParseError>;
+
+    /// The value of the header, as written to a frame.
+    fn to_value(&self) -> String;
+}
+
+/// Declares a tuple struct implementing `TypedCustomHeader` for the header with the specified name,
+/// whose value is of a type implementing `FromHeaderValue` and `Display`.
+#[macro_export]
+macro_rules! custom_header {
+    ( $(#[$attr:meta])* $vis:vis $name:ident, $header_name:literal, $value_type:ty ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name(pub $value_type);
+
+        impl $crate::extensions::typed::TypedCustomHeader for $name {
+            const NAME: &'static str = $header_name;
+
+            fn parse(value: &str) -> Result<Self, $crate::error::StompParseError> {
+                <$value_type as $crate::headers::FromHeaderValue>::from_header_value(value)
+                    .map($name)
+            }
+
+            fn to_value(&self) -> String {
+                self.0.to_string()
+            }
+        }
+    };
+}
+
+/// Parses the custom header `H` from `header`, if present.
+pub(crate) fn parse_typed<H: TypedCustomHeader>(
+    header: Option<CustomValue>,
+) -> Result<Option<H>, StompParseError> {
+    header
+        .map(|custom| {
+            H::parse(custom.value()).map_err(|error| {
+                StompParseError::with_kind(
+                    error.kind().clone(),
+                    format!("Invalid value for header {}: {}", H::NAME, error.message()),
+                )
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::error::{ErrorKind, NumberError};
+    use crate::server::{MessageFrameBuilder, ServerFrame};
+
+    custom_header!(Priority, "x-priority", u8);
+    custom_header!(Redelivered, "x-redelivered", bool);
+
+    #[test]
+    fn builds_and_parses_typed_headers() {
+        let bytes: Vec<u8> =
+            MessageFrameBuilder::new("m".to_owned(), "/queue/a".to_owned(), "s".to_owned())
+                .typed_custom_header(Priority(9))
+                .typed_custom_header(Redelivered(true))
+                .build()
+                .into();
+
+        let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(bytes) else {
+            panic!("Not a Message Frame!")
+        };
+
+        assert_eq!(Some(Priority(9)), frame.typed_custom::<Priority>().unwrap());
+        assert_eq!(
+            Some(Redelivered(true)),
+            frame.typed_custom::<Redelivered>().unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_invalid_value() {
+        let bytes = b"MESSAGE\nmessage-id:m\ndestination:a\nsubscription:s\nx-priority:300\n\n\x00";
+
+        let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(bytes.to_vec()) else {
+            panic!("Not a Message Frame!")
+        };
+
+        let error = frame.typed_custom::<Priority>().unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidNumber(NumberError::Overflow),
+            error.kind()
+        );
+        assert!(error.message().contains("x-priority"));
+        assert_eq!(None, frame.typed_custom::<Redelivered>().unwrap());
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn declares_header_with_attribute() {
+        use super::{stomp_header, TypedCustomHeader};
+
+        #[stomp_header(name = "x-retries")]
+        #[derive(Debug, PartialEq)]
+        struct Retries(pub u32);
+
+        assert_eq!("x-retries", Retries::NAME);
+        assert_eq!(Retries(3), Retries::parse("3").unwrap());
+        assert_eq!("3", Retries(3).to_value());
+        assert!(Retries::parse("x").is_err());
+    }
+}
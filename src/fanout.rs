@@ -0,0 +1,188 @@
+//! Supports delivering one message to many subscribers, as a broker does, by writing variants of a
+//! `MESSAGE` frame which differ only in their per-subscriber headers, without copying the body or
+//! rebuilding the frame for each.
+use std::io::Write;
+
+use crate::common::functions::encode_str;
+use crate::headers::{MessageIdValue, SubscriptionValue};
+use crate::server::MessageFrame;
+use crate::storage::Storage;
+use crate::vectored::VectoredFrame;
+
+/// A `MESSAGE` frame with its `subscription` and/or `message-id` headers replaced, sharing the body
+/// and all other headers with the frame from which it was created; obtained from
+/// `MessageFrame::with_subscription` or `MessageFrame::with_message_id`.
+#[derive(Debug)]
+pub struct MessageVariant<'f, S: Storage = Vec<u8>> {
+    frame: &'f MessageFrame<'f, S>,
+    subscription: Option<String>,
+    message_id: Option<String>,
+}
+
+impl<'a, S: Storage> MessageFrame<'a, S> {
+    /// A variant of this frame with the `subscription` header `id`.
+    pub fn with_subscription(&self, id: String) -> MessageVariant<'_, S> {
+        MessageVariant::new(self).with_subscription(id)
+    }
+
+    /// A variant of this frame with the `message-id` header `id`.
+    pub fn with_message_id(&self, id: String) -> MessageVariant<'_, S> {
+        MessageVariant::new(self).with_message_id(id)
+    }
+}
+
+impl<'f, S: Storage> MessageVariant<'f, S> {
+    fn new(frame: &'f MessageFrame<'f, S>) -> MessageVariant<'f, S> {
+        MessageVariant {
+            frame,
+            subscription: None,
+            message_id: None,
+        }
+    }
+
+    /// Replaces the `subscription` header with `id`.
+    pub fn with_subscription(mut self, id: String) -> MessageVariant<'f, S> {
+        self.subscription = Some(id);
+        self
+    }
+
+    /// Replaces the `message-id` header with `id`.
+    pub fn with_message_id(mut self, id: String) -> MessageVariant<'f, S> {
+        self.message_id = Some(id);
+        self
+    }
+
+    /// The frame from which this variant was created.
+    pub fn frame(&self) -> &'f MessageFrame<'f, S> {
+        self.frame
+    }
+
+    /// The value of the `subscription` header.
+    pub fn subscription(&self) -> &str {
+        self.subscription
+            .as_deref()
+            .unwrap_or_else(|| self.frame.subscription().value())
+    }
+
+    /// The value of the `message-id` header.
+    pub fn message_id(&self) -> &str {
+        self.message_id
+            .as_deref()
+            .unwrap_or_else(|| self.frame.message_id().value())
+    }
+
+    /// The body, which is that of the original frame.
+    pub fn body(&self) -> Option<&'f [u8]> {
+        self.frame.body()
+    }
+
+    /// The variant as a vectored frame, whose body is borrowed from the original frame.
+    pub fn to_vectored(&self) -> VectoredFrame<&'f [u8]> {
+        VectoredFrame::new(self.head(), self.body().unwrap_or_default())
+    }
+
+    /// Writes the variant to `writer`, using vectored writes.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.to_vectored().write_to(writer)
+    }
+
+    /// The serialised form of the variant.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let vectored = self.to_vectored();
+        let mut bytes = Vec::with_capacity(vectored.head().len() + vectored.body().len() + 1);
+        bytes.extend_from_slice(vectored.head());
+        bytes.extend_from_slice(vectored.body());
+        bytes.push(b'\0');
+        bytes
+    }
+
+    /// The command and headers of the original frame, up to and including the blank line which ends
+    /// them, with the replaced headers in place of the originals. Later occurrences of a replaced
+    /// header are dropped.
+    fn head(&self) -> Vec<u8> {
+        let overrides = [
+            (SubscriptionValue::NAME, self.subscription.as_deref()),
+            (MessageIdValue::NAME, self.message_id.as_deref()),
+        ];
+        let mut replaced = [false; 2];
+
+        let raw = self.frame.as_bytes();
+        let mut lines = raw.split_inclusive(|b| *b == b'\n');
+        let mut head = Vec::with_capacity(raw.len() - self.body().map_or(0, <[u8]>::len));
+        head.extend_from_slice(lines.next().unwrap_or_default());
+
+        for line in lines {
+            let content = line.strip_suffix(b"\n").unwrap_or(line);
+            let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+            let replacement =
+                overrides
+                    .iter()
+                    .zip(replaced.iter_mut())
+                    .find(|((name, value), _)| {
+                        value.is_some()
+                            && content
+                                .strip_prefix(name.as_bytes())
+                                .is_some_and(|rest| rest.starts_with(b":"))
+                    });
+
+            match replacement {
+                Some(((name, Some(value)), replaced)) => {
+                    if !*replaced {
+                        *replaced = true;
+                        head.extend_from_slice(name.as_bytes());
+                        head.push(b':');
+                        head.extend_from_slice(encode_str(value).as_bytes());
+                        head.push(b'\n');
+                    }
+                }
+                _ => head.extend_from_slice(line),
+            }
+
+            if content.is_empty() {
+                break;
+            }
+        }
+
+        head
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::server::{MessageFrameBuilder, ServerFrame};
+
+    #[test]
+    fn replaces_per_subscriber_headers() {
+        let frame =
+            MessageFrameBuilder::new("m1".to_owned(), "/topic/a".to_owned(), "s1".to_owned())
+                .add_custom_header("x".to_owned(), "1".to_owned())
+                .body(b"hello".to_vec())
+                .build();
+
+        let variant = frame
+            .with_subscription("s:2".to_owned())
+            .with_message_id("m2".to_owned());
+        assert_eq!("s:2", variant.subscription());
+        assert_eq!("m2", variant.message_id());
+        assert_eq!(
+            frame.body().unwrap().as_ptr(),
+            variant.to_vectored().body().as_ptr()
+        );
+
+        let Ok(ServerFrame::Message(parsed)) = ServerFrame::try_from(variant.to_bytes()) else {
+            panic!("Message Frame not parsed correctly")
+        };
+        assert_eq!("s\\c2", parsed.subscription().value());
+        assert_eq!("m2", parsed.message_id().value());
+        assert_eq!("/topic/a", parsed.destination().value());
+        assert_eq!("1", *parsed.custom_header("x").unwrap().value());
+        assert_eq!(Some(&b"hello"[..]), parsed.body());
+        assert!(parsed.repeated_headers().is_empty());
+
+        let unchanged = frame.with_message_id("m3".to_owned());
+        assert_eq!("s1", unchanged.subscription());
+    }
+}
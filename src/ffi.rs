@@ -0,0 +1,296 @@
+//! Exposes parsing and serialisation through a C interface, so that brokers and tools not written
+//! in Rust can use this parser. The crate is built as a Rust library only, so the shared or static
+//! library to link against is built with `cargo rustc --release --features ffi --crate-type cdylib`
+//! or `--crate-type staticlib` respectively. The functions are suitable for generating a header
+//! with [cbindgen](https://github.com/mozilla/cbindgen), using the `cbindgen.toml` at the root of
+//! the crate, which runs on a stable toolchain.
+//!
+//! A frame is parsed into an opaque `StompFrame`, which must be released with
+//! `stomp_frame_free`. The strings and bytes returned by the accessors belong to the frame, and
+//! remain valid until it is released. Header values are as they appear on the wire, i.e. they are
+//! not unescaped.
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use crate::any::{parse_any_with, AnyFrame};
+use crate::common::raw::RawParts;
+use crate::config::ParserConfig;
+
+/// A parsed frame, together with the C strings returned by its accessors.
+pub struct StompFrame {
+    frame: AnyFrame,
+    command: CString,
+    headers: Vec<(CString, CString)>,
+}
+
+impl StompFrame {
+    fn new(frame: AnyFrame) -> StompFrame {
+        let parts = RawParts::split(frame.as_bytes());
+
+        let command = c_string(&parts.command);
+        let headers = parts
+            .headers
+            .iter()
+            .map(|(name, value)| (c_string(name), c_string(value)))
+            .collect();
+
+        StompFrame {
+            frame,
+            command,
+            headers,
+        }
+    }
+}
+
+/// Converts `value` to a C string, truncating it at the first null character.
+fn c_string(value: &str) -> CString {
+    let value = value.split('\0').next().unwrap_or_default();
+    CString::new(value).unwrap_or_default()
+}
+
+/// Parses the `len` bytes at `bytes` as a client or server frame, returning null if they are not
+/// a valid frame. If `lenient` is true, the deviations from the specification accepted by
+/// `ParserConfig::lenient` are accepted.
+///
+/// If parsing fails and `error` is not null, a description of the problem is stored in it, which
+/// must be released with `stomp_string_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, and `error` must be null or point to writable
+/// memory for a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_parse(
+    bytes: *const u8,
+    len: usize,
+    lenient: bool,
+    error: *mut *mut c_char,
+) -> *mut StompFrame {
+    let bytes = if bytes.is_null() {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(bytes, len).to_vec()
+    };
+
+    let config = if lenient {
+        ParserConfig::lenient()
+    } else {
+        ParserConfig::default()
+    };
+
+    match parse_any_with(bytes, &config) {
+        Ok(frame) => Box::into_raw(Box::new(StompFrame::new(frame))),
+        Err(parse_error) => {
+            if !error.is_null() {
+                *error = c_string(parse_error.message()).into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a frame returned by `stomp_frame_parse`. Does nothing if `frame` is null.
+///
+/// # Safety
+///
+/// `frame` must be null or a frame returned by `stomp_frame_parse` which has not been released.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_free(frame: *mut StompFrame) {
+    if !frame.is_null() {
+        drop(Box::from_raw(frame));
+    }
+}
+
+/// Releases a string returned by this interface. Does nothing if `string` is null.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this interface which has not been released.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Returns true if the frame is one sent by a client, false if it is one sent by a server.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_is_client(frame: *const StompFrame) -> bool {
+    (*frame).frame.is_client()
+}
+
+/// The command of the frame, e.g. `SEND`.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_command(frame: *const StompFrame) -> *const c_char {
+    (*frame).command.as_ptr()
+}
+
+/// The number of headers in the frame, including repeated headers.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_header_count(frame: *const StompFrame) -> usize {
+    (*frame).headers.len()
+}
+
+/// The name of the header at `index`, in the order in which the headers appear in the frame, or
+/// null if `index` is out of range.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_header_name(
+    frame: *const StompFrame,
+    index: usize,
+) -> *const c_char {
+    (&*frame)
+        .headers
+        .get(index)
+        .map_or(ptr::null(), |(name, _)| name.as_ptr())
+}
+
+/// The value of the header at `index`, in the order in which the headers appear in the frame, or
+/// null if `index` is out of range.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_header_value(
+    frame: *const StompFrame,
+    index: usize,
+) -> *const c_char {
+    (&*frame)
+        .headers
+        .get(index)
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+/// The value of the first header called `name`, or null if the frame has no such header.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`, and `name` a null-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_header(
+    frame: *const StompFrame,
+    name: *const c_char,
+) -> *const c_char {
+    let name = CStr::from_ptr(name);
+
+    (&*frame)
+        .headers
+        .iter()
+        .find(|(header_name, _)| header_name.as_c_str() == name)
+        .map_or(ptr::null(), |(_, value)| value.as_ptr())
+}
+
+/// The body of the frame, whose length is stored in `len`. The body is empty if the frame has
+/// none.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`, and `len` must point to
+/// writable memory for a `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_body(frame: *const StompFrame, len: *mut usize) -> *const u8 {
+    let frame = &(*frame).frame;
+    let body_start = frame.headers_len();
+
+    *len = frame.body_len();
+    frame.as_bytes()[body_start..].as_ptr()
+}
+
+/// Serialises the frame into the `capacity` bytes at `buffer`, returning the length of the
+/// serialised frame. If the frame does not fit, nothing is written, so a caller can pass a null
+/// `buffer` to determine the capacity required.
+///
+/// # Safety
+///
+/// `frame` must be a valid frame returned by `stomp_frame_parse`, and `buffer` must be null or
+/// point to `capacity` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn stomp_frame_serialize(
+    frame: *const StompFrame,
+    buffer: *mut u8,
+    capacity: usize,
+) -> usize {
+    let bytes = (*frame).frame.as_bytes();
+
+    if !buffer.is_null() && bytes.len() <= capacity {
+        ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+    }
+
+    bytes.len()
+}
+
+#[cfg(test)]
+mod test {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    use super::*;
+
+    unsafe fn text<'a>(string: *const c_char) -> &'a str {
+        CStr::from_ptr(string).to_str().unwrap()
+    }
+
+    #[test]
+    fn parses_and_serializes_frame() {
+        let bytes = b"SEND\ndestination:/queue/a\nx:1\n\nhello\x00";
+
+        unsafe {
+            let frame = stomp_frame_parse(bytes.as_ptr(), bytes.len(), false, ptr::null_mut());
+            assert!(!frame.is_null());
+
+            assert!(stomp_frame_is_client(frame));
+            assert_eq!("SEND", text(stomp_frame_command(frame)));
+            assert_eq!(2, stomp_frame_header_count(frame));
+            assert_eq!("x", text(stomp_frame_header_name(frame, 1)));
+            assert!(stomp_frame_header_value(frame, 2).is_null());
+
+            let name = CString::new("destination").unwrap();
+            assert_eq!("/queue/a", text(stomp_frame_header(frame, name.as_ptr())));
+
+            let mut len = 0;
+            let body = stomp_frame_body(frame, &mut len);
+            assert_eq!(b"hello", std::slice::from_raw_parts(body, len));
+
+            let required = stomp_frame_serialize(frame, ptr::null_mut(), 0);
+            let mut buffer = vec![0u8; required];
+            stomp_frame_serialize(frame, buffer.as_mut_ptr(), buffer.len());
+            assert_eq!(&bytes[..], buffer.as_slice());
+
+            stomp_frame_free(frame);
+        }
+    }
+
+    #[test]
+    fn reports_parse_error() {
+        let bytes = b"UNKNOWN\n\n\x00";
+        let mut error = ptr::null_mut();
+
+        unsafe {
+            let frame = stomp_frame_parse(bytes.as_ptr(), bytes.len(), false, &mut error);
+
+            assert!(frame.is_null());
+            assert!(!error.is_null());
+            assert!(!text(error).is_empty());
+
+            stomp_string_free(error);
+        }
+    }
+}
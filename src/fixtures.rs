@@ -0,0 +1,270 @@
+//! The example frames from the STOMP specifications, for use in the tests of crates which build on
+//! this one. Each fixture provides the raw bytes of the example, along with the command, headers
+//! and body expected when it is parsed.
+//!
+//! Examples which contain placeholders (such as `<transaction-identifier>`), or which are not valid
+//! under the rules of version 1.2 (such as the version 1.0 SUBSCRIBE frame, which has no `id`), are
+//! omitted.
+use std::convert::TryFrom;
+
+use crate::any::AnyFrame;
+use crate::error::StompParseError;
+use crate::headers::StompVersion;
+
+/// An example frame from a specification.
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    /// A name describing the example.
+    pub name: &'static str,
+    /// The version of the specification from which the example is taken.
+    pub version: StompVersion,
+    /// The frame as sent over the wire.
+    pub raw: &'static [u8],
+    /// The expected command.
+    pub command: &'static str,
+    /// The expected headers, in the order they appear.
+    pub headers: &'static [(&'static str, &'static str)],
+    /// The expected body, without the terminating null octet.
+    pub body: &'static [u8],
+}
+
+impl Fixture {
+    /// Parses the raw bytes of the example.
+    pub fn parse(&self) -> Result<AnyFrame, StompParseError> {
+        AnyFrame::try_from(self.raw.to_vec())
+    }
+}
+
+/// The fixtures taken from the specification of the provided version.
+pub fn for_version(version: StompVersion) -> impl Iterator<Item = &'static Fixture> {
+    FIXTURES
+        .iter()
+        .filter(move |fixture| fixture.version == version)
+}
+
+/// All the fixtures.
+pub fn all() -> &'static [Fixture] {
+    FIXTURES
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "send",
+        version: StompVersion::V1_0,
+        raw: b"SEND\ndestination:/queue/a\n\nhello queue a\n\x00",
+        command: "SEND",
+        headers: &[("destination", "/queue/a")],
+        body: b"hello queue a\n",
+    },
+    Fixture {
+        name: "receipt",
+        version: StompVersion::V1_0,
+        raw: b"RECEIPT\nreceipt-id:message-12345\n\n\x00",
+        command: "RECEIPT",
+        headers: &[("receipt-id", "message-12345")],
+        body: b"",
+    },
+    Fixture {
+        name: "connect",
+        version: StompVersion::V1_1,
+        raw: b"CONNECT\naccept-version:1.0,1.1,2.0\nhost:stomp.github.org\n\n\x00",
+        command: "CONNECT",
+        headers: &[
+            ("accept-version", "1.0,1.1,2.0"),
+            ("host", "stomp.github.org"),
+        ],
+        body: b"",
+    },
+    Fixture {
+        name: "connected",
+        version: StompVersion::V1_1,
+        raw: b"CONNECTED\nversion:1.1\n\n\x00",
+        command: "CONNECTED",
+        headers: &[("version", "1.1")],
+        body: b"",
+    },
+    Fixture {
+        name: "error_unsupported_version",
+        version: StompVersion::V1_1,
+        raw: b"ERROR\nversion:1.2,2.1\ncontent-type:text/plain\n\nSupported protocol versions are 1.2 2.1\x00",
+        command: "ERROR",
+        headers: &[("version", "1.2,2.1"), ("content-type", "text/plain")],
+        body: b"Supported protocol versions are 1.2 2.1",
+    },
+    Fixture {
+        name: "connect",
+        version: StompVersion::V1_2,
+        raw: b"CONNECT\naccept-version:1.2\nhost:stomp.github.org\n\n\x00",
+        command: "CONNECT",
+        headers: &[("accept-version", "1.2"), ("host", "stomp.github.org")],
+        body: b"",
+    },
+    Fixture {
+        name: "connected",
+        version: StompVersion::V1_2,
+        raw: b"CONNECTED\nversion:1.2\n\n\x00",
+        command: "CONNECTED",
+        headers: &[("version", "1.2")],
+        body: b"",
+    },
+    Fixture {
+        name: "send",
+        version: StompVersion::V1_2,
+        raw: b"SEND\ndestination:/queue/a\ncontent-type:text/plain\n\nhello queue a\x00",
+        command: "SEND",
+        headers: &[("destination", "/queue/a"), ("content-type", "text/plain")],
+        body: b"hello queue a",
+    },
+    Fixture {
+        name: "send_in_transaction",
+        version: StompVersion::V1_2,
+        raw: b"SEND\ndestination:/queue/a\ntransaction:tx1\ncontent-type:text/plain\n\nhello queue a\x00",
+        command: "SEND",
+        headers: &[
+            ("destination", "/queue/a"),
+            ("transaction", "tx1"),
+            ("content-type", "text/plain"),
+        ],
+        body: b"hello queue a",
+    },
+    Fixture {
+        name: "subscribe",
+        version: StompVersion::V1_2,
+        raw: b"SUBSCRIBE\nid:0\ndestination:/queue/foo\nack:client\n\n\x00",
+        command: "SUBSCRIBE",
+        headers: &[("id", "0"), ("destination", "/queue/foo"), ("ack", "client")],
+        body: b"",
+    },
+    Fixture {
+        name: "unsubscribe",
+        version: StompVersion::V1_2,
+        raw: b"UNSUBSCRIBE\nid:0\n\n\x00",
+        command: "UNSUBSCRIBE",
+        headers: &[("id", "0")],
+        body: b"",
+    },
+    Fixture {
+        name: "ack",
+        version: StompVersion::V1_2,
+        raw: b"ACK\nid:12345\ntransaction:tx1\n\n\x00",
+        command: "ACK",
+        headers: &[("id", "12345"), ("transaction", "tx1")],
+        body: b"",
+    },
+    Fixture {
+        name: "nack",
+        version: StompVersion::V1_2,
+        raw: b"NACK\nid:12345\ntransaction:tx1\n\n\x00",
+        command: "NACK",
+        headers: &[("id", "12345"), ("transaction", "tx1")],
+        body: b"",
+    },
+    Fixture {
+        name: "begin",
+        version: StompVersion::V1_2,
+        raw: b"BEGIN\ntransaction:tx1\n\n\x00",
+        command: "BEGIN",
+        headers: &[("transaction", "tx1")],
+        body: b"",
+    },
+    Fixture {
+        name: "commit",
+        version: StompVersion::V1_2,
+        raw: b"COMMIT\ntransaction:tx1\n\n\x00",
+        command: "COMMIT",
+        headers: &[("transaction", "tx1")],
+        body: b"",
+    },
+    Fixture {
+        name: "abort",
+        version: StompVersion::V1_2,
+        raw: b"ABORT\ntransaction:tx1\n\n\x00",
+        command: "ABORT",
+        headers: &[("transaction", "tx1")],
+        body: b"",
+    },
+    Fixture {
+        name: "disconnect",
+        version: StompVersion::V1_2,
+        raw: b"DISCONNECT\nreceipt:77\n\n\x00",
+        command: "DISCONNECT",
+        headers: &[("receipt", "77")],
+        body: b"",
+    },
+    Fixture {
+        name: "receipt",
+        version: StompVersion::V1_2,
+        raw: b"RECEIPT\nreceipt-id:77\n\n\x00",
+        command: "RECEIPT",
+        headers: &[("receipt-id", "77")],
+        body: b"",
+    },
+    Fixture {
+        name: "message",
+        version: StompVersion::V1_2,
+        raw: b"MESSAGE\nsubscription:0\nmessage-id:007\ndestination:/queue/a\ncontent-type:text/plain\n\nhello queue a\x00",
+        command: "MESSAGE",
+        headers: &[
+            ("subscription", "0"),
+            ("message-id", "007"),
+            ("destination", "/queue/a"),
+            ("content-type", "text/plain"),
+        ],
+        body: b"hello queue a",
+    },
+    Fixture {
+        name: "error",
+        version: StompVersion::V1_2,
+        raw: b"ERROR\nreceipt-id:message-12345\ncontent-type:text/plain\ncontent-length:170\nmessage:malformed frame received\n\n\
+The message:\n-----\nMESSAGE\ndestined:/queue/a\nreceipt:message-12345\n\nHello queue a!\n-----\n\
+Did not contain a destination header, which is REQUIRED\nfor message propagation.\n\x00",
+        command: "ERROR",
+        headers: &[
+            ("receipt-id", "message-12345"),
+            ("content-type", "text/plain"),
+            ("content-length", "170"),
+            ("message", "malformed frame received"),
+        ],
+        body: b"The message:\n-----\nMESSAGE\ndestined:/queue/a\nreceipt:message-12345\n\nHello queue a!\n-----\n\
+Did not contain a destination header, which is REQUIRED\nfor message propagation.\n",
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use super::{all, for_version};
+    use crate::common::raw::RawParts;
+    use crate::headers::StompVersion;
+
+    #[test]
+    fn fixtures_parse_as_expected() {
+        for fixture in all() {
+            let frame = fixture
+                .parse()
+                .unwrap_or_else(|error| panic!("{} failed: {:?}", fixture.name, error));
+
+            let parts = RawParts::split(frame.as_bytes());
+
+            assert_eq!(fixture.command, parts.command, "{}", fixture.name);
+            assert_eq!(
+                fixture.headers.to_vec(),
+                parts
+                    .headers
+                    .iter()
+                    .map(|(name, value)| (name.as_ref(), value.as_ref()))
+                    .collect::<Vec<_>>(),
+                "{}",
+                fixture.name
+            );
+            assert_eq!(fixture.body, parts.body, "{}", fixture.name);
+        }
+    }
+
+    #[test]
+    fn fixtures_are_filtered_by_version() {
+        assert_eq!(2, for_version(StompVersion::V1_0).count());
+        assert!(
+            for_version(StompVersion::V1_2).all(|fixture| fixture.version == StompVersion::V1_2)
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Derives systematically broken variants of a valid frame, each labelled with how a decoder is
+//! expected to respond to it, so that the handling of errors can be verified.
+use crate::parser::trim_trailing_eols;
+
+/// A way in which a frame is broken.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Mutation {
+    /// The null octet terminating the frame is removed.
+    MissingNul,
+    /// An invalid escape sequence (`\t`) is inserted into the value of the first header.
+    BadEscape,
+    /// The frame is cut off part way through the body, as if the connection were lost.
+    TruncatedBody,
+    /// A header whose line is longer than the specified limit is added.
+    OversizeHeader(usize),
+    /// The first header, which for frames produced by a builder is a required header, is removed.
+    MissingRequiredHeader,
+    /// The command is replaced by one which does not exist.
+    UnknownCommand,
+}
+
+impl Mutation {
+    /// The error expected when decoding a frame broken by this mutation.
+    pub fn expected_error(&self) -> ExpectedError {
+        match self {
+            Mutation::MissingNul | Mutation::TruncatedBody => ExpectedError::Incomplete,
+            Mutation::OversizeHeader(_) => ExpectedError::TooLarge,
+            Mutation::BadEscape | Mutation::MissingRequiredHeader | Mutation::UnknownCommand => {
+                ExpectedError::Invalid
+            }
+        }
+    }
+}
+
+/// How a decoder is expected to respond to a malformed frame.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ExpectedError {
+    /// The frame is incomplete: a `FrameDecoder` waits for more bytes, and parsing the bytes as a
+    /// complete frame fails.
+    Incomplete,
+    /// The frame is rejected as invalid.
+    Invalid,
+    /// The frame is rejected by a decoder which limits the size of frames or headers, such as a
+    /// `FrameDecoder` whose `max_frame_len` is less than the length of the frame.
+    TooLarge,
+}
+
+/// A broken variant of a valid frame.
+#[derive(Debug, Clone)]
+pub struct MalformedFrame {
+    /// How the frame was broken.
+    pub mutation: Mutation,
+    /// How a decoder is expected to respond.
+    pub expected: ExpectedError,
+    /// The bytes of the broken frame.
+    pub bytes: Vec<u8>,
+}
+
+/// Applies `mutation` to the serialised, valid `frame`.
+pub fn mutate(frame: &[u8], mutation: Mutation) -> MalformedFrame {
+    let frame = trim_trailing_eols(frame);
+    let command_end = line_end(frame, 0);
+    let headers_end = find(frame, b"\n\n").map_or(frame.len(), |index| index + 2);
+
+    let bytes = match mutation {
+        Mutation::MissingNul => frame.strip_suffix(b"\0").unwrap_or(frame).to_vec(),
+        Mutation::BadEscape => {
+            let value_start = frame[command_end..]
+                .iter()
+                .position(|b| *b == b':')
+                .map_or(command_end, |index| command_end + index + 1);
+            splice(frame, value_start, value_start, b"\\t")
+        }
+        Mutation::TruncatedBody => {
+            let body_len = frame.len().saturating_sub(headers_end + 1);
+            frame[..headers_end + body_len / 2].to_vec()
+        }
+        Mutation::OversizeHeader(limit) => {
+            let mut header = b"x-oversize:".to_vec();
+            header.resize(limit + 1, b'a');
+            header.push(b'\n');
+            splice(frame, command_end, command_end, &header)
+        }
+        Mutation::MissingRequiredHeader => {
+            splice(frame, command_end, line_end(frame, command_end), b"")
+        }
+        Mutation::UnknownCommand => splice(frame, 0, command_end, b"UNKNOWN\n"),
+    };
+
+    MalformedFrame {
+        mutation,
+        expected: mutation.expected_error(),
+        bytes,
+    }
+}
+
+/// Applies every mutation to the serialised, valid `frame`, using `max_header_len` as the limit
+/// exceeded by `Mutation::OversizeHeader`.
+pub fn mutations(frame: &[u8], max_header_len: usize) -> Vec<MalformedFrame> {
+    [
+        Mutation::MissingNul,
+        Mutation::BadEscape,
+        Mutation::TruncatedBody,
+        Mutation::OversizeHeader(max_header_len),
+        Mutation::MissingRequiredHeader,
+        Mutation::UnknownCommand,
+    ]
+    .iter()
+    .map(|mutation| mutate(frame, *mutation))
+    .collect()
+}
+
+/// The index after the EOL ending the line starting at `start`.
+fn line_end(frame: &[u8], start: usize) -> usize {
+    frame[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(frame.len(), |index| start + index + 1)
+}
+
+fn find(frame: &[u8], needle: &[u8]) -> Option<usize> {
+    frame
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn splice(frame: &[u8], start: usize, end: usize, replacement: &[u8]) -> Vec<u8> {
+    [&frame[..start], replacement, &frame[end..]].concat()
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{mutations, ExpectedError};
+    use crate::client::ClientFrame;
+    use crate::config::ParserConfig;
+    use crate::decoder::FrameDecoder;
+    use crate::error::ErrorKind;
+    use crate::generator::{FrameGenerator, GeneratorConfig};
+
+    #[test]
+    fn mutations_are_handled_as_expected() {
+        let frame = FrameGenerator::new(GeneratorConfig::default()).send_frame();
+
+        for malformed in mutations(frame.as_bytes(), 1024) {
+            let mut decoder = FrameDecoder::<ClientFrame>::new();
+            decoder.extend(&malformed.bytes);
+            let decoded = decoder.decode();
+
+            match malformed.expected {
+                ExpectedError::Incomplete => {
+                    assert!(matches!(decoded, Ok(None)), "{:?}", malformed.mutation);
+                    assert!(ClientFrame::try_from(malformed.bytes).is_err());
+                }
+                ExpectedError::Invalid => {
+                    assert!(decoded.is_err(), "{:?}", malformed.mutation);
+                    assert!(ClientFrame::try_from(malformed.bytes).is_err());
+                }
+                ExpectedError::TooLarge => {
+                    let mut decoder = FrameDecoder::<ClientFrame>::with_config(ParserConfig {
+                        max_frame_len: Some(1024),
+                        ..ParserConfig::default()
+                    });
+                    decoder.extend(&malformed.bytes);
+                    assert!(
+                        matches!(
+                            decoder.decode().map_err(|error| error.kind().clone()),
+                            Err(ErrorKind::FrameTooLarge { limit: 1024, .. })
+                        ),
+                        "{:?}",
+                        malformed.mutation
+                    );
+                }
+            }
+        }
+    }
+}
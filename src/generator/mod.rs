@@ -0,0 +1,236 @@
+//! Generates randomised but valid SEND and MESSAGE frames, for benchmarking brokers and
+//! soak-testing decoders. Generation is deterministic for a given seed, so that a run can be
+//! reproduced.
+//!
+//! The `malformed` module derives systematically broken variants of such frames, for testing the
+//! handling of errors.
+pub mod malformed;
+
+use crate::client::{SendFrame, SendFrameBuilder};
+use crate::server::{MessageFrame, MessageFrameBuilder};
+
+/// The distribution from which the size of each body is drawn.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BodySize {
+    /// Every body has the specified size.
+    Fixed(usize),
+    /// Sizes are uniformly distributed between `min` and `max`, inclusive.
+    Uniform { min: usize, max: usize },
+    /// The logarithms of the sizes are uniformly distributed between those of `min` and `max`, so
+    /// that most bodies are small but some are large, as is typical of real traffic.
+    LogUniform { min: usize, max: usize },
+}
+
+/// Configures a `FrameGenerator`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GeneratorConfig {
+    /// The seed for the random number generator.
+    pub seed: u64,
+    /// The destinations to which the frames are addressed, chosen at random for each frame.
+    pub destinations: Vec<String>,
+    /// The minimum number of custom headers added to each frame.
+    pub min_custom_headers: usize,
+    /// The maximum number of custom headers added to each frame.
+    pub max_custom_headers: usize,
+    /// The distribution of body sizes.
+    pub body_size: BodySize,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        GeneratorConfig {
+            seed: 42,
+            destinations: vec!["/queue/test".to_owned()],
+            min_custom_headers: 0,
+            max_custom_headers: 4,
+            body_size: BodySize::Uniform { min: 0, max: 1024 },
+        }
+    }
+}
+
+/// Produces valid frames according to a `GeneratorConfig`.
+pub struct FrameGenerator {
+    config: GeneratorConfig,
+    rng: Rng,
+    next_id: u64,
+}
+
+const CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+impl FrameGenerator {
+    /// Creates a generator. Panics if `config` has no destinations.
+    pub fn new(config: GeneratorConfig) -> FrameGenerator {
+        assert!(
+            !config.destinations.is_empty(),
+            "At least one destination is required"
+        );
+
+        FrameGenerator {
+            rng: Rng::new(config.seed),
+            config,
+            next_id: 0,
+        }
+    }
+
+    /// Generates a SEND frame.
+    pub fn send_frame(&mut self) -> SendFrame<'static> {
+        let body = self.body();
+        let builder = SendFrameBuilder::new(self.destination())
+            .content_type("text/plain".to_owned())
+            .content_length(body.len());
+
+        self.custom_headers()
+            .into_iter()
+            .fold(builder, |builder, (name, value)| {
+                builder.add_custom_header(name, value)
+            })
+            .body(body)
+            .build()
+    }
+
+    /// Generates a MESSAGE frame, with a unique `message-id`.
+    pub fn message_frame(&mut self) -> MessageFrame<'static> {
+        self.next_id += 1;
+
+        let body = self.body();
+        let builder = MessageFrameBuilder::new(
+            format!("msg-{}", self.next_id),
+            self.destination(),
+            "sub-0".to_owned(),
+        )
+        .content_type("text/plain".to_owned())
+        .content_length(body.len());
+
+        self.custom_headers()
+            .into_iter()
+            .fold(builder, |builder, (name, value)| {
+                builder.add_custom_header(name, value)
+            })
+            .body(body)
+            .build()
+    }
+
+    fn destination(&mut self) -> String {
+        let index = self.rng.below(self.config.destinations.len());
+        self.config.destinations[index].clone()
+    }
+
+    fn custom_headers(&mut self) -> Vec<(String, String)> {
+        let count = self.rng.between(
+            self.config.min_custom_headers,
+            self.config.max_custom_headers,
+        );
+
+        (0..count)
+            .map(|index| (format!("x-generated-{}", index), self.text(8)))
+            .collect()
+    }
+
+    fn body(&mut self) -> Vec<u8> {
+        let len = match self.config.body_size {
+            BodySize::Fixed(len) => len,
+            BodySize::Uniform { min, max } => self.rng.between(min, max),
+            BodySize::LogUniform { min, max } => {
+                let (min, max) = ((min.max(1) as f64).ln(), (max.max(1) as f64).ln());
+                (min + (max - min) * self.rng.fraction()).exp().round() as usize
+            }
+        };
+
+        self.text(len).into_bytes()
+    }
+
+    fn text(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| CHARACTERS[self.rng.below(CHARACTERS.len())] as char)
+            .collect()
+    }
+}
+
+/// A small, fast pseudo-random number generator (xorshift64*); not suitable for cryptography.
+pub(crate) struct Rng(u64);
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        // The state must never be zero
+        Rng((seed ^ 0x9E37_79B9_7F4A_7C15).max(1))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in the range `0..bound`.
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// A number in the range `min..=max`.
+    pub(crate) fn between(&mut self, min: usize, max: usize) -> usize {
+        if max <= min {
+            min
+        } else {
+            min + (self.next_u64() % (max - min + 1) as u64) as usize
+        }
+    }
+
+    /// A number in the range `0.0..1.0`.
+    pub(crate) fn fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{BodySize, FrameGenerator, GeneratorConfig};
+    use crate::client::ClientFrame;
+    use crate::server::ServerFrame;
+
+    fn config() -> GeneratorConfig {
+        GeneratorConfig {
+            destinations: vec!["/queue/a".to_owned(), "/topic/b".to_owned()],
+            min_custom_headers: 1,
+            max_custom_headers: 3,
+            body_size: BodySize::LogUniform { min: 1, max: 4096 },
+            ..GeneratorConfig::default()
+        }
+    }
+
+    #[test]
+    fn generates_valid_frames() {
+        let mut generator = FrameGenerator::new(config());
+
+        for _ in 0..50 {
+            let bytes: Vec<u8> = generator.send_frame().into();
+            let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(bytes) else {
+                panic!("Not a Send Frame!")
+            };
+            assert!(["/queue/a", "/topic/b"].contains(&frame.destination().value()));
+            assert!((1..=3).contains(&frame.custom_headers().len()));
+            assert!(frame.body().unwrap().len() <= 4096);
+
+            let bytes: Vec<u8> = generator.message_frame().into();
+            assert!(matches!(
+                ServerFrame::try_from(bytes),
+                Ok(ServerFrame::Message(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn same_seed_generates_same_frames() {
+        let mut first = FrameGenerator::new(config());
+        let mut second = FrameGenerator::new(config());
+
+        for _ in 0..10 {
+            assert_eq!(
+                first.send_frame().as_bytes(),
+                second.send_frame().as_bytes()
+            );
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Performs the server's side of the CONNECT handshake: negotiating the version and heart-beats,
+//! and checking the host, so that a server can answer a CONNECT frame with a single call.
+use crate::client::ConnectFrame;
+use crate::headers::{HeartBeatIntervalls, StompVersion};
+use crate::server::{ConnectedFrame, ConnectedFrameBuilder, ErrorFrame, ErrorFrameBuilder};
+use crate::storage::Storage;
+
+/// The versions in order of preference, the most preferred last.
+const KNOWN_VERSIONS: [StompVersion; 3] =
+    [StompVersion::V1_0, StompVersion::V1_1, StompVersion::V1_2];
+
+/// What a server supports, against which a CONNECT frame is checked.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ServerCapabilities {
+    versions: Vec<StompVersion>,
+    heartbeat: HeartBeatIntervalls,
+    hosts: Vec<String>,
+    server: Option<String>,
+}
+
+impl Default for ServerCapabilities {
+    fn default() -> Self {
+        ServerCapabilities {
+            versions: KNOWN_VERSIONS.to_vec(),
+            heartbeat: HeartBeatIntervalls::new(0, 0),
+            hosts: Vec::new(),
+            server: None,
+        }
+    }
+}
+
+impl ServerCapabilities {
+    /// Capabilities supporting all versions, any host, and no heart-beats.
+    pub fn new() -> ServerCapabilities {
+        ServerCapabilities::default()
+    }
+
+    /// The versions supported, in place of all versions.
+    pub fn versions(mut self, versions: Vec<StompVersion>) -> ServerCapabilities {
+        self.versions = versions;
+        self
+    }
+
+    /// The heart-beats the server can supply, and would like to receive.
+    pub fn heartbeat(mut self, heartbeat: HeartBeatIntervalls) -> ServerCapabilities {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// Accepts connections to the virtual host `host`. If no hosts are allowed, any host is
+    /// accepted.
+    pub fn allow_host(mut self, host: String) -> ServerCapabilities {
+        self.hosts.push(host);
+        self
+    }
+
+    /// The value of the `server` header sent in the CONNECTED frame.
+    pub fn server(mut self, server: String) -> ServerCapabilities {
+        self.server = Some(server);
+        self
+    }
+
+    fn supported_versions(&self) -> String {
+        KNOWN_VERSIONS
+            .iter()
+            .filter(|version| self.versions.contains(version))
+            .map(StompVersion::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Builds the response to `connect`: a CONNECTED frame with the highest version supported by both
+/// client and server, and the heart-beats negotiated from those of both, or an ERROR frame if there
+/// is no such version or the host is not allowed.
+///
+/// A client which sends no `accept-version` header is taken to support only version 1.0.
+#[allow(clippy::result_large_err)]
+pub fn respond<'a, S: Storage>(
+    connect: &'a ConnectFrame<'a, S>,
+    caps: &ServerCapabilities,
+) -> Result<ConnectedFrame<'static>, ErrorFrame<'static>> {
+    let accepted = connect.accept_version().value();
+    let version = KNOWN_VERSIONS
+        .iter()
+        .rev()
+        .filter(|version| caps.versions.contains(version))
+        .find(|version| {
+            accepted.contains(version) || (accepted.is_empty() && **version == StompVersion::V1_0)
+        })
+        .ok_or_else(|| {
+            let supported = caps.supported_versions();
+            error(
+                format!(
+                    "Supported protocol versions are {}",
+                    supported.replace(',', " ")
+                ),
+                Some(supported),
+            )
+        })?;
+
+    let host = connect.host().value();
+    if !caps.hosts.is_empty() && !caps.hosts.iter().any(|allowed| allowed == host) {
+        return Err(error(format!("Unknown virtual host {}", host), None));
+    }
+
+    let heartbeat = caps.heartbeat.negotiate(connect.heartbeat().value());
+
+    let mut builder = ConnectedFrameBuilder::new(version.clone()).heartbeat(heartbeat);
+    if let Some(server) = &caps.server {
+        builder = builder.server(server.clone());
+    }
+
+    Ok(builder.build())
+}
+
+fn error(message: String, versions: Option<String>) -> ErrorFrame<'static> {
+    let mut builder = ErrorFrameBuilder::new()
+        .message(message.clone())
+        .add_custom_header("content-type".to_owned(), "text/plain".to_owned());
+    if let Some(versions) = versions {
+        builder = builder.add_custom_header("version".to_owned(), versions);
+    }
+
+    builder.body(message.into_bytes()).build()
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{respond, ServerCapabilities};
+    use crate::client::{ClientFrame, ConnectFrame};
+    use crate::headers::{HeartBeatIntervalls, StompVersion};
+
+    fn connect(bytes: &[u8]) -> ConnectFrame<'static> {
+        match ClientFrame::try_from(bytes.to_vec()) {
+            Ok(ClientFrame::Connect(frame)) => frame,
+            _ => panic!("Not a Connect Frame!"),
+        }
+    }
+
+    #[test]
+    fn negotiates_version_and_heartbeats() {
+        let frame =
+            connect(b"CONNECT\nhost:h\naccept-version:1.0,1.1\nheart-beat:1000,5000\n\n\x00");
+        let caps = ServerCapabilities::new()
+            .heartbeat(HeartBeatIntervalls::new(2000, 0))
+            .server("test/1.0".to_owned());
+
+        let connected = respond(&frame, &caps).unwrap();
+
+        assert_eq!(StompVersion::V1_1, *connected.version().value());
+        assert_eq!(
+            HeartBeatIntervalls::new(5000, 0),
+            *connected.heartbeat().unwrap().value()
+        );
+        assert_eq!("test/1.0", connected.server().unwrap().value());
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let frame = connect(b"CONNECT\nhost:h\naccept-version:1.0\n\n\x00");
+        let caps = ServerCapabilities::new().versions(vec![StompVersion::V1_2, StompVersion::V1_1]);
+
+        let error = respond(&frame, &caps).unwrap_err();
+
+        assert_eq!(
+            "Supported protocol versions are 1.1 1.2",
+            error.message().unwrap().value()
+        );
+        assert_eq!("1.1,1.2", *error.custom_header("version").unwrap().value());
+    }
+
+    #[test]
+    fn rejects_unknown_host() {
+        let frame = connect(b"CONNECT\nhost:other\naccept-version:1.2\n\n\x00");
+        let caps = ServerCapabilities::new().allow_host("stomp.example".to_owned());
+
+        assert!(respond(&frame, &caps).is_err());
+        assert!(respond(&frame, &caps.allow_host("other".to_owned())).is_ok());
+    }
+}
@@ -0,0 +1,106 @@
+//! Provides a scheduler for heart-beats which performs no IO itself: the time of each send and
+//! receive is reported to a `HeartBeatClock`, which can then be asked when the next heart-beat must
+//! be sent, and whether the peer has timed out.
+use std::time::{Duration, Instant};
+
+use crate::headers::HeartBeatIntervalls;
+
+/// Tracks the heart-beats of one side of a connection.
+#[derive(Debug, Clone)]
+pub struct HeartBeatClock {
+    send_interval: Option<Duration>,
+    receive_timeout: Option<Duration>,
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl HeartBeatClock {
+    /// Creates a clock for the `negotiated` intervalls, as returned by
+    /// `HeartBeatIntervalls::negotiate` for this side of the connection. The peer is considered to
+    /// have timed out once nothing has been received for the expected intervall scaled by
+    /// `multiplier`. `now` is the time at which the connection was established.
+    pub fn new(negotiated: &HeartBeatIntervalls, multiplier: f64, now: Instant) -> HeartBeatClock {
+        HeartBeatClock {
+            send_interval: negotiated.send_interval(),
+            receive_timeout: negotiated.expected_timeout(multiplier),
+            last_sent: now,
+            last_received: now,
+        }
+    }
+
+    /// Records that data - a frame or a heart-beat - was sent at `now`.
+    pub fn sent(&mut self, now: Instant) {
+        self.last_sent = self.last_sent.max(now);
+    }
+
+    /// Records that data - a frame or a heart-beat - was received at `now`.
+    pub fn received(&mut self, now: Instant) {
+        self.last_received = self.last_received.max(now);
+    }
+
+    /// The time by which something must next be sent, or `None` if no heart-beats need be sent.
+    pub fn next_send(&self) -> Option<Instant> {
+        self.send_interval.map(|interval| self.last_sent + interval)
+    }
+
+    /// Returns true if a heart-beat must be sent at `now`, as nothing has been sent for the send
+    /// intervall.
+    pub fn send_due(&self, now: Instant) -> bool {
+        self.next_send().is_some_and(|next_send| now >= next_send)
+    }
+
+    /// The time after which the peer is considered to have timed out, unless something is received
+    /// before then, or `None` if no heart-beats are expected.
+    pub fn receive_deadline(&self) -> Option<Instant> {
+        self.receive_timeout
+            .map(|timeout| self.last_received + timeout)
+    }
+
+    /// Returns true if the peer has timed out at `now`, as nothing has been received for longer
+    /// than the receive timeout.
+    pub fn peer_timed_out(&self, now: Instant) -> bool {
+        self.receive_deadline()
+            .is_some_and(|deadline| now > deadline)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::HeartBeatClock;
+    use crate::headers::HeartBeatIntervalls;
+
+    #[test]
+    fn send_is_due_after_interval() {
+        let start = Instant::now();
+        let mut clock = HeartBeatClock::new(&HeartBeatIntervalls::new(1000, 0), 1.5, start);
+
+        assert_eq!(Some(start + Duration::from_secs(1)), clock.next_send());
+        assert!(!clock.send_due(start + Duration::from_millis(999)));
+        assert!(clock.send_due(start + Duration::from_secs(1)));
+
+        clock.sent(start + Duration::from_millis(800));
+
+        assert!(!clock.send_due(start + Duration::from_secs(1)));
+        assert!(!clock.peer_timed_out(start + Duration::from_secs(3600)));
+    }
+
+    #[test]
+    fn peer_times_out_after_scaled_interval() {
+        let start = Instant::now();
+        let mut clock = HeartBeatClock::new(&HeartBeatIntervalls::new(0, 1000), 1.5, start);
+
+        assert_eq!(None, clock.next_send());
+        assert!(!clock.peer_timed_out(start + Duration::from_millis(1500)));
+        assert!(clock.peer_timed_out(start + Duration::from_millis(1501)));
+
+        clock.received(start + Duration::from_secs(1));
+
+        assert_eq!(
+            Some(start + Duration::from_millis(2500)),
+            clock.receive_deadline()
+        );
+        assert!(!clock.peer_timed_out(start + Duration::from_secs(2)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Generates the ids which clients and servers must supply in frames: `receipt` headers,
+//! `message-id` headers, and subscription ids. Each id is a prefix followed by a number drawn from
+//! an `IdSource`, which can be replaced so that tests see predictable ids.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::ids::IdGenerator;
+//!
+//! let receipts = IdGenerator::receipts();
+//! assert_eq!("receipt-1", receipts.next_id());
+//! assert_eq!("receipt-2", receipts.next_id());
+//!
+//! let fixed = IdGenerator::with_source("m-", || 42);
+//! assert_eq!("m-42", fixed.next_id());
+//! ```
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A source of the numbers from which ids are made.
+pub trait IdSource: Send + Sync {
+    /// The next number, which should differ from those returned before.
+    fn next_value(&self) -> u64;
+}
+
+impl<F: Fn() -> u64 + Send + Sync> IdSource for F {
+    fn next_value(&self) -> u64 {
+        self()
+    }
+}
+
+/// Counts upwards from a starting value.
+#[derive(Debug)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    /// A counter whose first value is `first`.
+    pub fn starting_at(first: u64) -> Counter {
+        Counter(AtomicU64::new(first))
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Counter {
+        Counter::starting_at(1)
+    }
+}
+
+impl IdSource for Counter {
+    fn next_value(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// The current time in milliseconds since the Unix epoch, incremented where necessary so that each
+/// value is greater than the last, even when several are drawn within a millisecond.
+#[derive(Debug, Default)]
+pub struct Clock {
+    last: AtomicU64,
+}
+
+impl IdSource for Clock {
+    fn next_value(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+
+        let mut last = self.last.load(Ordering::Relaxed);
+        loop {
+            let next = now.max(last + 1);
+            match self
+                .last
+                .compare_exchange_weak(last, next, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return next,
+                Err(current) => last = current,
+            }
+        }
+    }
+}
+
+/// Generates ids made of a prefix followed by a number drawn from its source. Generators can be
+/// shared between threads.
+#[derive(Debug)]
+pub struct IdGenerator<S: IdSource = Counter> {
+    prefix: String,
+    source: S,
+}
+
+impl IdGenerator {
+    /// A generator of ids with the specified prefix, numbered from 1.
+    pub fn new<P: Into<String>>(prefix: P) -> IdGenerator {
+        IdGenerator::with_source(prefix, Counter::default())
+    }
+
+    /// A generator for the `receipt` headers of client frames: `receipt-1`, `receipt-2`, ...
+    pub fn receipts() -> IdGenerator {
+        IdGenerator::new("receipt-")
+    }
+
+    /// A generator for the `message-id` headers of MESSAGE frames: `message-1`, `message-2`, ...
+    pub fn message_ids() -> IdGenerator {
+        IdGenerator::new("message-")
+    }
+
+    /// A generator for the ids of subscriptions: `sub-1`, `sub-2`, ...
+    pub fn subscription_ids() -> IdGenerator {
+        IdGenerator::new("sub-")
+    }
+}
+
+impl IdGenerator<Clock> {
+    /// A generator of ids with the specified prefix, numbered by the time at which they are
+    /// generated, so that they remain distinct across restarts.
+    pub fn time_based<P: Into<String>>(prefix: P) -> IdGenerator<Clock> {
+        IdGenerator::with_source(prefix, Clock::default())
+    }
+}
+
+impl<S: IdSource> IdGenerator<S> {
+    /// A generator of ids with the specified prefix, numbered by `source`.
+    pub fn with_source<P: Into<String>>(prefix: P, source: S) -> IdGenerator<S> {
+        IdGenerator {
+            prefix: prefix.into(),
+            source,
+        }
+    }
+
+    /// The prefix of the generated ids.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The next id.
+    pub fn next_id(&self) -> String {
+        format!("{}{}", self.prefix, self.source.next_value())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::thread;
+
+    use super::{Clock, Counter, IdGenerator, IdSource};
+
+    #[test]
+    fn generates_distinct_ids_across_threads() {
+        let generator = IdGenerator::subscription_ids();
+
+        let ids: HashSet<String> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| (0..100).map(|_| generator.next_id()).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(400, ids.len());
+        assert!(ids.contains("sub-400"));
+    }
+
+    #[test]
+    fn sources_are_pluggable() {
+        let next = AtomicU64::new(10);
+        let generator = IdGenerator::with_source("t-", || next.fetch_add(10, Ordering::Relaxed));
+        assert_eq!("t-10", generator.next_id());
+        assert_eq!("t-20", generator.next_id());
+
+        assert_eq!(7, Counter::starting_at(7).next_value());
+
+        let clock = Clock::default();
+        let first = clock.next_value();
+        assert!(clock.next_value() > first);
+        assert!(IdGenerator::time_based("r-").next_id().starts_with("r-"));
+    }
+}
@@ -0,0 +1,268 @@
+//! Chains transformations of frames, as applied by proxies and gateways: each `Interceptor` in a
+//! `Pipeline` may change the headers of a frame, such as to inject credentials or strip internal
+//! headers, or veto the frame so that it is not forwarded.
+//!
+//! # Example
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::client::ClientFrame;
+//! use stomp_parser::intercept::{Action, Interceptor, Pipeline};
+//!
+//! struct StripInternal;
+//!
+//! impl Interceptor for StripInternal {
+//!     fn on_client_frame(&mut self, frame: &mut ClientFrame) -> Action {
+//!         match frame.remove_header("x-internal") {
+//!             Ok(()) => Action::Continue,
+//!             Err(error) => Action::Veto(error.message().to_owned()),
+//!         }
+//!     }
+//! }
+//!
+//! let mut pipeline = Pipeline::new().with(StripInternal);
+//! let frame =
+//!     ClientFrame::try_from(b"SEND\ndestination:a\nx-internal:1\n\nhi\x00".to_vec()).unwrap();
+//!
+//! let frame = pipeline.client_frame(frame).unwrap();
+//! assert_eq!(b"SEND\ndestination:a\n\nhi\x00", frame.as_bytes());
+//! ```
+use std::convert::TryFrom;
+
+use crate::client::ClientFrame;
+use crate::common::functions::encode_str;
+use crate::error::StompParseError;
+use crate::server::ServerFrame;
+
+/// What should happen to a frame once an interceptor has seen it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Action {
+    /// Pass the frame, with any changes, to the next interceptor.
+    Continue,
+    /// Drop the frame, for the reason given.
+    Veto(String),
+}
+
+/// Transforms or vetoes the frames passing through a `Pipeline`. Both methods pass frames on
+/// unchanged by default, so an interceptor need only handle the direction it is concerned with.
+pub trait Interceptor {
+    /// Called with each frame sent by a client.
+    fn on_client_frame(&mut self, _frame: &mut ClientFrame) -> Action {
+        Action::Continue
+    }
+
+    /// Called with each frame sent by a server.
+    fn on_server_frame(&mut self, _frame: &mut ServerFrame) -> Action {
+        Action::Continue
+    }
+}
+
+/// The reason an interceptor gave for vetoing a frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Vetoed(pub String);
+
+impl std::fmt::Display for Vetoed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Frame vetoed: {}", self.0)
+    }
+}
+
+impl std::error::Error for Vetoed {}
+
+/// A sequence of interceptors, which see each frame in the order they were added.
+#[derive(Default)]
+pub struct Pipeline {
+    interceptors: Vec<Box<dyn Interceptor>>,
+}
+
+impl std::fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("interceptors", &self.interceptors.len())
+            .finish()
+    }
+}
+
+impl Pipeline {
+    /// A pipeline without interceptors, which passes frames on unchanged.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// This pipeline, with `interceptor` after those already added.
+    pub fn with<I: Interceptor + 'static>(mut self, interceptor: I) -> Pipeline {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Passes a frame sent by a client through the interceptors, returning the frame as changed by
+    /// them, or the reason the first to veto it gave. Later interceptors do not see vetoed frames.
+    pub fn client_frame(&mut self, mut frame: ClientFrame) -> Result<ClientFrame, Vetoed> {
+        for interceptor in &mut self.interceptors {
+            if let Action::Veto(reason) = interceptor.on_client_frame(&mut frame) {
+                return Err(Vetoed(reason));
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Passes a frame sent by a server through the interceptors, as `client_frame` does.
+    pub fn server_frame(&mut self, mut frame: ServerFrame) -> Result<ServerFrame, Vetoed> {
+        for interceptor in &mut self.interceptors {
+            if let Action::Veto(reason) = interceptor.on_server_frame(&mut frame) {
+                return Err(Vetoed(reason));
+            }
+        }
+        Ok(frame)
+    }
+}
+
+/// Implements the header editing methods for a group of frames.
+macro_rules! header_editing {
+    ( $group:ident ) => {
+        impl $group {
+            /// Sets the header `name` to `value`, replacing the first occurrence and dropping any
+            /// others, or adding it after the existing headers. The frame is serialised again with
+            /// the same body, and parsed with the default configuration; if the result does not
+            /// parse, such as because the frame does not allow the header, the frame is unchanged.
+            pub fn set_header(&mut self, name: &str, value: &str) -> Result<(), StompParseError> {
+                *self = $group::try_from(edit_header(self.as_bytes(), name, Some(value)))?;
+                Ok(())
+            }
+
+            /// Removes every occurrence of the header `name`, as `set_header` sets it.
+            pub fn remove_header(&mut self, name: &str) -> Result<(), StompParseError> {
+                *self = $group::try_from(edit_header(self.as_bytes(), name, None))?;
+                Ok(())
+            }
+        }
+    };
+}
+
+header_editing!(ClientFrame);
+header_editing!(ServerFrame);
+
+/// The serialised frame `raw` with the header `name` set to `value`, or removed if `value` is
+/// `None`.
+fn edit_header(raw: &[u8], name: &str, value: Option<&str>) -> Vec<u8> {
+    let name = encode_str(name);
+    let replacement = value.map(|value| format!("{}:{}\n", name, encode_str(value)));
+    let mut replacement = replacement.as_deref().map(str::as_bytes);
+
+    let mut edited = Vec::with_capacity(raw.len() + replacement.map_or(0, <[u8]>::len));
+    let mut lines = raw.split_inclusive(|b| *b == b'\n');
+    edited.extend_from_slice(lines.next().unwrap_or_default());
+
+    for line in lines.by_ref() {
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if content.is_empty() {
+            edited.extend_from_slice(replacement.take().unwrap_or_default());
+            edited.extend_from_slice(line);
+            break;
+        }
+
+        let matches = content
+            .strip_prefix(name.as_bytes())
+            .is_some_and(|rest| rest.starts_with(b":"));
+        if matches {
+            edited.extend_from_slice(replacement.take().unwrap_or_default());
+        } else {
+            edited.extend_from_slice(line);
+        }
+    }
+
+    // The body and terminator are copied unchanged
+    for line in lines {
+        edited.extend_from_slice(line);
+    }
+    edited
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{Action, Interceptor, Pipeline, Vetoed};
+    use crate::client::ClientFrame;
+    use crate::server::ServerFrame;
+
+    struct InjectLogin;
+
+    impl Interceptor for InjectLogin {
+        fn on_client_frame(&mut self, frame: &mut ClientFrame) -> Action {
+            if let ClientFrame::Connect(_) = frame {
+                frame.set_header("login", "gateway").unwrap();
+            }
+            Action::Continue
+        }
+    }
+
+    struct VetoDestination(&'static str);
+
+    impl Interceptor for VetoDestination {
+        fn on_client_frame(&mut self, frame: &mut ClientFrame) -> Action {
+            match frame {
+                ClientFrame::Send(send) if send.destination().value() == self.0 => {
+                    Action::Veto(format!("{} is closed", self.0))
+                }
+                _ => Action::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn chains_interceptors() {
+        let mut pipeline = Pipeline::new()
+            .with(InjectLogin)
+            .with(VetoDestination("/queue/closed"));
+
+        let connect = ClientFrame::try_from(
+            b"CONNECT\nhost:h\naccept-version:1.2\nlogin:a\nlogin:b\n\n\x00".to_vec(),
+        )
+        .unwrap();
+        let ClientFrame::Connect(connect) = pipeline.client_frame(connect).unwrap() else {
+            panic!("Not a Connect Frame!")
+        };
+        assert_eq!(
+            &b"CONNECT\nhost:h\naccept-version:1.2\nlogin:gateway\n\n\x00"[..],
+            connect.as_bytes()
+        );
+
+        let send =
+            ClientFrame::try_from(b"SEND\ndestination:/queue/closed\n\n\x00".to_vec()).unwrap();
+        assert_eq!(
+            Err(Vetoed("/queue/closed is closed".to_owned())),
+            pipeline.client_frame(send).map(|_| ())
+        );
+
+        let receipt = ServerFrame::try_from(b"RECEIPT\nreceipt-id:1\n\n\x00".to_vec()).unwrap();
+        assert!(pipeline.server_frame(receipt).is_ok());
+    }
+
+    #[test]
+    fn edits_headers() {
+        let mut frame =
+            ClientFrame::try_from(b"SEND\r\ndestination:a\r\n\r\nx\ny:z\x00".to_vec()).unwrap();
+
+        frame.set_header("x:y", "1\n2").unwrap();
+        assert_eq!(
+            &b"SEND\r\ndestination:a\r\nx\\cy:1\\n2\n\r\nx\ny:z\x00"[..],
+            frame.as_bytes()
+        );
+
+        frame.set_header("destination", "b").unwrap();
+        frame.remove_header("x:y").unwrap();
+        assert_eq!(
+            &b"SEND\r\ndestination:b\n\r\nx\ny:z\x00"[..],
+            frame.as_bytes()
+        );
+
+        assert!(frame.remove_header("destination").is_err());
+        assert_eq!(
+            &b"SEND\r\ndestination:b\n\r\nx\ny:z\x00"[..],
+            frame.as_bytes()
+        );
+    }
+}
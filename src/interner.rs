@@ -0,0 +1,138 @@
+//! Interns the names and values of custom headers as they are parsed, so that the many frames
+//! carrying the same correlation ids, application ids and the like share a single copy of each,
+//! rather than each holding its own.
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// A set of interned strings, which grows up to a maximum number of entries. Once full, strings
+/// not already interned are no longer shared, so that unbounded distinct values (such as message
+/// ids) cannot exhaust memory.
+#[derive(Debug)]
+pub struct Interner {
+    strings: Mutex<HashSet<Arc<str>>>,
+    max_entries: usize,
+}
+
+impl Interner {
+    /// An interner holding at most `max_entries` strings.
+    pub fn new(max_entries: usize) -> Interner {
+        Interner {
+            strings: Mutex::new(HashSet::new()),
+            max_entries,
+        }
+    }
+
+    /// The shared copy of `value`, added if not yet interned and there is room for it, or `None`
+    /// if the interner is full and does not hold it.
+    pub fn intern(&self, value: &str) -> Option<Arc<str>> {
+        let mut strings = self
+            .strings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(interned) = strings.get(value) {
+            return Some(Arc::clone(interned));
+        }
+        if strings.len() >= self.max_entries {
+            return None;
+        }
+
+        let interned: Arc<str> = Arc::from(value);
+        strings.insert(Arc::clone(&interned));
+        Some(interned)
+    }
+
+    /// The number of interned strings.
+    pub fn len(&self) -> usize {
+        self.strings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .len()
+    }
+
+    /// Returns true if no strings are interned.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all interned strings. Frames already parsed retain their copies.
+    pub fn clear(&self) {
+        self.strings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clear();
+    }
+}
+
+/// An interner shared between the clones of a `ParserConfig`. Configurations are equal only if
+/// they share the same interner.
+#[derive(Clone, Debug)]
+pub struct SharedInterner(Arc<Interner>);
+
+impl SharedInterner {
+    pub fn new(interner: Interner) -> SharedInterner {
+        SharedInterner(Arc::new(interner))
+    }
+
+    /// The interner.
+    pub fn interner(&self) -> &Interner {
+        &self.0
+    }
+}
+
+impl PartialEq for SharedInterner {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedInterner {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::Interner;
+    use crate::client::ClientFrame;
+    use crate::config::ParserConfig;
+
+    #[test]
+    fn shares_repeated_strings_up_to_limit() {
+        let interner = Interner::new(2);
+
+        let a = interner.intern("a").unwrap();
+        assert!(Arc::ptr_eq(&a, &interner.intern("a").unwrap()));
+
+        interner.intern("b");
+        assert_eq!(None, interner.intern("c"));
+        assert!(interner.intern("b").is_some());
+        assert_eq!(2, interner.len());
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert_eq!("a", &*a);
+    }
+
+    #[test]
+    fn interns_custom_headers_when_parsing() {
+        let config = ParserConfig::new().with_interner(Interner::new(100));
+        let parse = || {
+            let Ok(ClientFrame::Send(frame)) = ClientFrame::parse_with(
+                b"SEND\ndestination:a\napp-id:pricer\n\n\x00".to_vec(),
+                &config,
+            ) else {
+                panic!("Send Frame not parsed correctly")
+            };
+            frame
+        };
+
+        let (first, second) = (parse(), parse());
+        let (first, second) = (
+            first.custom_header("app-id").unwrap(),
+            second.custom_header("app-id").unwrap(),
+        );
+        assert_eq!("pricer", *first.value());
+        assert_eq!(first.value().as_ptr(), second.value().as_ptr());
+        assert_eq!(2, config.interner.unwrap().interner().len());
+    }
+}
@@ -3,6 +3,7 @@
 //!
 //! The types primarily of interest to users of the library are the enums `client::ClientFrame` and `server::ServerFrame`, which model the frames that can be sent
 //! by STOMP clients and STOMP servers respectively. Obtaining a frame from a message is achieved via `try_from` on those types.   
+//! Where the origin of a message is not known in advance, `parse_any` determines it from the command and returns an `any::AnyFrame`.
 //!
 //! # Example
 //! ```
@@ -24,13 +25,124 @@
 //!     panic!("Send Frame not parsed correctly");
 //! }
 //! ```
+//!
+//! Everything but `slice_writer`, which writes frames into a caller's buffer using only `core`,
+//! requires the default `std` feature; without it, the crate is `no_std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(clippy::all)]
+// Allows the code generated by the derive macros to refer to this crate by name
+extern crate self as stomp_parser;
+
+#[cfg(feature = "std")]
 #[macro_use]
 mod common;
+#[cfg(feature = "std")]
+pub mod anonymize;
+#[cfg(feature = "std")]
+pub mod base64;
+#[cfg(feature = "std")]
+pub mod charset;
+#[cfg(all(feature = "std", feature = "bytes"))]
+pub mod codec;
+#[cfg(feature = "std")]
+pub mod command;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod conformance;
+#[cfg(feature = "std")]
+pub mod decoder;
+#[cfg(feature = "std")]
+pub mod destination;
+#[cfg(feature = "std")]
+pub mod diff;
+#[cfg(feature = "std")]
+pub mod digest;
+#[cfg(feature = "test-support")]
+pub mod duplex;
+#[cfg(feature = "std")]
+pub mod encoder;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
+pub mod extensions;
+#[cfg(feature = "std")]
+pub mod fanout;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "test-support")]
+pub mod fixtures;
+#[cfg(feature = "test-support")]
+pub mod generator;
+#[cfg(feature = "std")]
+pub mod handshake;
+#[cfg(feature = "std")]
+pub mod heartbeat;
+#[cfg(feature = "std")]
+pub mod ids;
+#[cfg(feature = "std")]
+pub mod intercept;
+#[cfg(feature = "std")]
+pub mod interner;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod macro_support;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "test-support")]
+pub mod mock;
+#[cfg(feature = "std")]
 mod model;
+#[cfg(feature = "std")]
+pub mod observer;
+#[cfg(feature = "std")]
 mod parser;
+#[cfg(feature = "std")]
+pub mod peek;
+#[cfg(feature = "std")]
+pub mod poll;
+#[cfg(feature = "std")]
+pub mod pretty;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "std")]
+pub mod rewrite;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod slice_writer;
+#[cfg(feature = "std")]
+pub mod sockjs;
+#[cfg(feature = "std")]
+pub mod spans;
+#[cfg(feature = "std")]
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod streaming;
+#[cfg(feature = "std")]
+pub mod template;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
+pub mod vectored;
 
+#[cfg(feature = "std")]
+pub use model::any;
+#[cfg(feature = "std")]
+pub use model::any::parse_any;
+#[cfg(feature = "std")]
 pub use model::client;
+#[cfg(feature = "std")]
 pub use model::headers;
+#[cfg(feature = "std")]
 pub use model::server;
@@ -0,0 +1,177 @@
+//! Flags frames which parse, and are valid, but which suggest a mistake by their sender: bodies
+//! which are likely to be truncated by a peer that ignores `content-length`, oversized headers,
+//! headers superseded by a later version of the protocol, and values which look escaped twice.
+use crate::any::AnyFrame;
+use crate::client::ClientFrame;
+use crate::common::raw::RawParts;
+use crate::headers::HeaderName;
+use crate::server::ServerFrame;
+use crate::storage::Storage;
+
+/// The length, in bytes, of the name and value of a header above which it is reported as large.
+pub const LARGE_HEADER_LEN: usize = 4096;
+
+/// Headers, by the command they appear on, which a later version of the protocol replaced.
+const DEPRECATED_HEADERS: &[(&str, &str, &str)] = &[("UNSUBSCRIBE", "destination", "id")];
+
+/// A warning about a frame which is not a violation of the specification.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Warning {
+    /// The body is not UTF-8 text, but there is no `content-length` header.
+    MissingContentLength { body_len: usize },
+    /// The name and value of a header together exceed [`LARGE_HEADER_LEN`].
+    LargeHeader { name: String, len: usize },
+    /// The header was replaced by `replacement` in a later version of the protocol.
+    DeprecatedHeader { name: String, replacement: String },
+    /// The value contains an escaped backslash, which usually means it was escaped twice.
+    DoublyEscaped { name: String },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::MissingContentLength { body_len } => {
+                write!(f, "binary body of {} bytes has no content-length", body_len)
+            }
+            Warning::LargeHeader { name, len } => write!(f, "header {} is {} bytes", name, len),
+            Warning::DeprecatedHeader { name, replacement } => {
+                write!(
+                    f,
+                    "header {} is deprecated in favour of {}",
+                    name, replacement
+                )
+            }
+            Warning::DoublyEscaped { name } => {
+                write!(f, "value of header {} looks escaped twice", name)
+            }
+        }
+    }
+}
+
+impl<S: Storage> ClientFrame<S> {
+    /// Warnings about the frame which, unlike the results of `validate`, do not make it invalid.
+    pub fn lint(&self) -> Vec<Warning> {
+        lint(self.as_bytes())
+    }
+}
+
+impl<S: Storage> ServerFrame<S> {
+    /// Warnings about the frame which, unlike the results of `validate`, do not make it invalid.
+    pub fn lint(&self) -> Vec<Warning> {
+        lint(self.as_bytes())
+    }
+}
+
+impl AnyFrame {
+    /// Warnings about the frame which, unlike the results of `validate`, do not make it invalid.
+    pub fn lint(&self) -> Vec<Warning> {
+        match self {
+            AnyFrame::Client(frame) => frame.lint(),
+            AnyFrame::Server(frame) => frame.lint(),
+        }
+    }
+}
+
+fn lint(raw: &[u8]) -> Vec<Warning> {
+    let parts = RawParts::split(raw);
+    let mut warnings = Vec::new();
+
+    if parts.header(HeaderName::ContentLength.as_str()).is_none()
+        && std::str::from_utf8(parts.body).is_err()
+    {
+        warnings.push(Warning::MissingContentLength {
+            body_len: parts.body.len(),
+        });
+    }
+
+    // CONNECT and CONNECTED frames are exempt from escaping, so a backslash there is literal
+    let escaped = !matches!(parts.command.as_ref(), "CONNECT" | "CONNECTED");
+
+    for (name, value) in &parts.headers {
+        let len = name.len() + value.len();
+        if len > LARGE_HEADER_LEN {
+            warnings.push(Warning::LargeHeader {
+                name: name.to_string(),
+                len,
+            });
+        }
+
+        if let Some((_, _, replacement)) = DEPRECATED_HEADERS
+            .iter()
+            .find(|(command, header, _)| *command == parts.command && header == name)
+        {
+            warnings.push(Warning::DeprecatedHeader {
+                name: name.to_string(),
+                replacement: (*replacement).to_owned(),
+            });
+        }
+
+        if escaped && value.contains("\\\\") {
+            warnings.push(Warning::DoublyEscaped {
+                name: name.to_string(),
+            });
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{Warning, LARGE_HEADER_LEN};
+    use crate::client::ClientFrame;
+
+    fn client_frame(bytes: &[u8]) -> ClientFrame {
+        ClientFrame::try_from(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn accepts_clean_frame() {
+        let frame = client_frame(b"SEND\ndestination:a\ncontent-length:2\n\n\xff\xfe\x00");
+
+        assert!(frame.lint().is_empty());
+    }
+
+    #[test]
+    fn warns_of_smells() {
+        let frame = client_frame(b"SEND\ndestination:a\\\\n\n\n\xff\xfe\x00");
+        assert_eq!(
+            vec![
+                Warning::MissingContentLength { body_len: 2 },
+                Warning::DoublyEscaped {
+                    name: "destination".to_owned()
+                }
+            ],
+            frame.lint()
+        );
+
+        let frame = client_frame(b"UNSUBSCRIBE\nid:1\ndestination:a\n\n\x00");
+        assert_eq!(
+            vec![Warning::DeprecatedHeader {
+                name: "destination".to_owned(),
+                replacement: "id".to_owned()
+            }],
+            frame.lint()
+        );
+
+        let mut bytes = b"SEND\ndestination:".to_vec();
+        bytes.extend(std::iter::repeat_n(b'a', LARGE_HEADER_LEN));
+        bytes.extend_from_slice(b"\n\n\x00");
+        assert_eq!(
+            vec![Warning::LargeHeader {
+                name: "destination".to_owned(),
+                len: LARGE_HEADER_LEN + 11
+            }],
+            client_frame(&bytes).lint()
+        );
+    }
+
+    #[test]
+    fn ignores_backslashes_in_connect() {
+        let frame = client_frame(b"CONNECT\nhost:h\naccept-version:1.2\nlogin:a\\\\b\n\n\x00");
+
+        assert!(frame.lint().is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! The items used by the code which the exported `frames!` macro generates in other crates. This
+//! module is not part of the public API, and may change without notice.
+pub use either::Either;
+pub use nom;
+pub use nom::combinator::map_res;
+pub use nom::error::{context, VerboseError};
+pub use nom::Parser;
+pub use paste;
+pub use std::convert::TryFrom;
+
+pub use crate::common::constants::*;
+pub use crate::common::functions::*;
+pub use crate::config::ParserConfig;
+pub use crate::error::{FullError, StompParseError};
+pub use crate::extensions::typed::TypedCustomHeader;
+pub use crate::headers::*;
+pub use crate::parser::headers::{allows_header, headers_parser};
+pub use crate::parser::{
+    check_header_bytes, command_error, command_line, end_error, headers_error, missing_header,
+    null, unexpected_header, unknown_command,
+};
+pub use crate::pretty::DEFAULT_MAX_BODY_LEN;
+pub use crate::storage::Storage;
+pub use crate::vectored::VectoredFrame;
+
+pub fn pretty(raw: &[u8], max_body_len: usize) -> String {
+    crate::pretty::pretty(raw, max_body_len)
+}
+
+pub fn summary(raw: &[u8]) -> String {
+    crate::pretty::summary(raw)
+}
+
+pub fn split_raw<'a>(raw: &'a [u8], body: &[u8]) -> [std::io::IoSlice<'a>; 3] {
+    crate::vectored::split_raw(raw, body)
+}
+
+pub fn parse_typed<H: TypedCustomHeader>(
+    header: Option<CustomValue>,
+) -> Result<Option<H>, StompParseError> {
+    crate::extensions::typed::parse_typed(header)
+}
+
+pub fn new_vectored_frame<B: AsRef<[u8]>>(head: Vec<u8>, body: B) -> VectoredFrame<B> {
+    VectoredFrame::new(head, body)
+}
+
+pub fn new_body_encoder<W: std::io::Write>(
+    writer: W,
+    content_length: usize,
+) -> crate::encoder::BodyEncoder<W> {
+    crate::encoder::BodyEncoder::new(writer, content_length)
+}
+
+pub fn write_str<W: std::fmt::Write + ?Sized>(
+    raw: &[u8],
+    writer: &mut W,
+) -> Result<(), crate::error::EncodeError> {
+    crate::encoder::write_str(raw, writer)
+}
+
+/// The value of a header as shown by the `Debug` of frames, which does not reveal the passcode
+/// when the `zeroize` feature is enabled. This redaction is all the feature does for the header
+/// itself; its value is not zeroed.
+pub fn debug_value(name: &str, value: String) -> String {
+    if cfg!(feature = "zeroize") && name == "passcode" {
+        "[REDACTED]".to_owned()
+    } else {
+        value
+    }
+}
+
+/// The name by which repetitions of a custom header are detected, folded to lower case if the
+/// configuration ignores the case of header names.
+pub fn custom_header_key<'a>(name: &'a str, config: &ParserConfig) -> std::borrow::Cow<'a, str> {
+    if config.case_insensitive_headers {
+        std::borrow::Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        std::borrow::Cow::Borrowed(name)
+    }
+}
+
+pub fn raw_header<'a>(raw: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    crate::common::raw::raw_header(raw, name)
+}
+
+/// The body of the frame `raw`, where `input` follows its head.
+pub fn frame_body<'a>(
+    raw: &[u8],
+    input: &'a [u8],
+    config: &ParserConfig,
+) -> Result<&'a [u8], StompParseError> {
+    let content_length = crate::decoder::head_len(raw, config)?.and_then(|(_, length)| length);
+    crate::parser::body(input, content_length, config.content_length_policy)
+}
+
+pub fn header_spans(raw: &[u8]) -> Result<Vec<crate::spans::HeaderSpans>, StompParseError> {
+    crate::spans::header_spans(raw)
+}
+
+pub fn lossy_copy(raw: &[u8], config: &ParserConfig) -> Option<Box<[u8]>> {
+    match config.utf8_policy {
+        crate::config::Utf8Policy::Strict => None,
+        crate::config::Utf8Policy::Lossy => crate::common::raw::lossy_copy(raw),
+    }
+}
+
+pub fn content_hash(raw: &[u8], body: Option<&[u8]>) -> [u8; crate::digest::DIGEST_LEN] {
+    crate::digest::content_hash(raw, body)
+}
+
+#[cfg(feature = "compression")]
+pub fn decode_body<'a>(
+    encoding: Option<&[u8]>,
+    body: &'a [u8],
+    max_len: usize,
+) -> Result<std::borrow::Cow<'a, [u8]>, StompParseError> {
+    crate::compression::decode_body(encoding, body, max_len)
+}
+
+pub fn encode_base64_body(encoding: Option<&[u8]>, body: &[u8]) -> String {
+    crate::base64::encode_body(encoding, body)
+}
+
+pub fn decode_text<'a>(
+    content_type: Option<&[u8]>,
+    body: &'a [u8],
+) -> Result<std::borrow::Cow<'a, str>, StompParseError> {
+    crate::charset::decode_text(content_type, body)
+}
+
+pub fn decode_base64_body<'a>(
+    encoding: Option<&[u8]>,
+    body: &'a [u8],
+) -> Result<std::borrow::Cow<'a, [u8]>, StompParseError> {
+    crate::base64::decode_body(encoding, body)
+}
+
+#[cfg(feature = "signing")]
+pub fn verify_signature(
+    key: &[u8],
+    raw: &[u8],
+    body: Option<&[u8]>,
+) -> Result<(), StompParseError> {
+    crate::signing::verify(key, crate::signing::DEFAULT_SIGNATURE_HEADER, raw, body)
+}
+
+pub fn after_head(raw: &[u8]) -> &[u8] {
+    &raw[crate::common::raw::head_end(raw)..]
+}
+
+pub fn check_destination(config: &ParserConfig, destination: &str) -> Result<(), StompParseError> {
+    config.check_destination(destination)
+}
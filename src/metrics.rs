@@ -0,0 +1,49 @@
+//! Defines a hook through which the decoder reports what it has decoded, so that protocol metrics
+//! can be collected for each connection.
+use std::sync::Arc;
+
+use crate::error::ErrorKind;
+
+/// Receives notifications from a `FrameDecoder`. All methods have empty default implementations,
+/// so implementors need only override those of interest.
+///
+/// The methods take `&self`, so that an implementation - typically built on atomic counters - can
+/// be shared, via an `Arc`, between the decoder and the code which reports the metrics.
+pub trait ParserMetrics: Send + Sync {
+    /// A frame with the specified command was successfully parsed.
+    fn frame_parsed(&self, _command: &str) {}
+
+    /// The specified number of bytes were consumed from the input, whether as a frame, a
+    /// heart-beat, or the discarded remains of a frame in error.
+    fn bytes_consumed(&self, _bytes: usize) {}
+
+    /// An EOL was received between frames.
+    fn heartbeat_received(&self) {}
+
+    /// A frame could not be parsed, for the specified reason.
+    fn parse_error(&self, _kind: &ErrorKind) {}
+}
+
+impl<M: ParserMetrics + ?Sized> ParserMetrics for Arc<M> {
+    fn frame_parsed(&self, command: &str) {
+        (**self).frame_parsed(command)
+    }
+
+    fn bytes_consumed(&self, bytes: usize) {
+        (**self).bytes_consumed(bytes)
+    }
+
+    fn heartbeat_received(&self) {
+        (**self).heartbeat_received()
+    }
+
+    fn parse_error(&self, kind: &ErrorKind) {
+        (**self).parse_error(kind)
+    }
+}
+
+/// The `ParserMetrics` used when none are provided, which ignores all notifications.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMetrics;
+
+impl ParserMetrics for NoMetrics {}
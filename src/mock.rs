@@ -0,0 +1,196 @@
+//! A scriptable broker for the tests of client libraries: it answers CONNECT frames with CONNECTED
+//! frames, acknowledges frames requesting receipts, records the frames it receives, and delivers
+//! MESSAGE and ERROR frames when told to, so that exchanges with it are deterministic.
+//!
+//! The broker is connected to the client under test by an in-memory `duplex` connection, and
+//! handles the frames the client has sent whenever `process` is called.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::client::{SendFrameBuilder, SubscribeFrameBuilder};
+//! use stomp_parser::mock::MockBroker;
+//! use stomp_parser::server::ServerFrame;
+//!
+//! let (mut broker, mut client) = MockBroker::connect();
+//!
+//! client.send(SubscribeFrameBuilder::new("/queue/a", "sub-1").build());
+//! client.send(SendFrameBuilder::new("/queue/b").receipt("r1").build());
+//! broker.process().unwrap();
+//!
+//! assert_eq!(1, broker.sends().count());
+//! assert!(matches!(client.receive(), Ok(Some(ServerFrame::Receipt(_)))));
+//!
+//! assert_eq!(1, broker.deliver("/queue/a", b"hello"));
+//! let Ok(Some(ServerFrame::Message(message))) = client.receive() else {
+//!     panic!("No message delivered")
+//! };
+//! assert_eq!("sub-1", message.subscription().value());
+//! ```
+use crate::client::{ClientFrame, SendFrame, SubscribeFrame};
+use crate::common::functions::decode_or_raw;
+use crate::common::raw::raw_header;
+use crate::duplex::{duplex, ClientHalf, ServerHalf};
+use crate::error::StompParseError;
+use crate::handshake::{respond, ServerCapabilities};
+use crate::headers::HeaderName;
+use crate::ids::IdGenerator;
+use crate::server::{ErrorFrame, MessageFrameBuilder, ReceiptFrameBuilder};
+
+/// A broker which handles the frames of a single client.
+#[derive(Debug)]
+pub struct MockBroker {
+    connection: ServerHalf,
+    capabilities: ServerCapabilities,
+    received: Vec<ClientFrame>,
+    subscriptions: Vec<(String, String)>,
+    message_ids: IdGenerator,
+}
+
+impl MockBroker {
+    /// A broker supporting all versions and no heart-beats, and the client half of its connection.
+    pub fn connect() -> (MockBroker, ClientHalf) {
+        MockBroker::with_capabilities(ServerCapabilities::default())
+    }
+
+    /// A broker which answers CONNECT frames according to `capabilities`, and the client half of
+    /// its connection.
+    pub fn with_capabilities(capabilities: ServerCapabilities) -> (MockBroker, ClientHalf) {
+        let (client, connection) = duplex();
+        let broker = MockBroker {
+            connection,
+            capabilities,
+            received: Vec::new(),
+            subscriptions: Vec::new(),
+            message_ids: IdGenerator::message_ids(),
+        };
+        (broker, client)
+    }
+
+    /// Handles the frames the client has sent since the last call, returning how many there were.
+    /// Fails if the client sent bytes which are not a valid frame.
+    pub fn process(&mut self) -> Result<usize, StompParseError> {
+        let mut handled = 0;
+        while let Some(frame) = self.connection.receive()? {
+            self.handle(&frame);
+            self.received.push(frame);
+            handled += 1;
+        }
+        Ok(handled)
+    }
+
+    fn handle(&mut self, frame: &ClientFrame) {
+        match frame {
+            ClientFrame::Connect(connect) => match respond(connect, &self.capabilities) {
+                Ok(connected) => self.connection.send(connected),
+                Err(error) => self.connection.send(error),
+            },
+            ClientFrame::Subscribe(subscribe) => self.subscriptions.push((
+                subscribe.id().value().to_owned(),
+                subscribe.destination().value().to_owned(),
+            )),
+            ClientFrame::Unsubscribe(unsubscribe) => self
+                .subscriptions
+                .retain(|(id, _)| id != unsubscribe.id().value()),
+            _ => {}
+        }
+
+        if let Some(receipt) = raw_header(frame.as_bytes(), HeaderName::Receipt.as_str()) {
+            let receipt = decode_or_raw(&String::from_utf8_lossy(receipt));
+            self.connection
+                .send(ReceiptFrameBuilder::new(receipt).build());
+        }
+    }
+
+    /// All the frames received, in the order they were received.
+    pub fn received(&self) -> &[ClientFrame] {
+        &self.received
+    }
+
+    /// The SEND frames received.
+    pub fn sends(&self) -> impl Iterator<Item = &SendFrame<'static>> {
+        self.received.iter().filter_map(ClientFrame::as_send)
+    }
+
+    /// The SUBSCRIBE frames received, including those of subscriptions since ended.
+    pub fn subscribes(&self) -> impl Iterator<Item = &SubscribeFrame<'static>> {
+        self.received.iter().filter_map(ClientFrame::as_subscribe)
+    }
+
+    /// Delivers a MESSAGE frame with `body` to each current subscription to `destination`,
+    /// returning how many were delivered.
+    pub fn deliver(&mut self, destination: &str, body: &[u8]) -> usize {
+        let subscriptions: Vec<String> = self
+            .subscriptions
+            .iter()
+            .filter(|(_, subscribed)| subscribed == destination)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for subscription in &subscriptions {
+            let message = MessageFrameBuilder::new(
+                self.message_ids.next_id(),
+                destination.to_owned(),
+                subscription.clone(),
+            )
+            .body(body.to_vec())
+            .build();
+            self.connection.send(message);
+        }
+        subscriptions.len()
+    }
+
+    /// Sends an ERROR frame with the `message` header `message`.
+    pub fn send_error(&mut self, message: &str) {
+        self.connection.send(ErrorFrame::from_message(message));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MockBroker;
+    use crate::client::{
+        ConnectFrameBuilder, DisconnectFrameBuilder, SubscribeFrameBuilder, UnsubscribeFrameBuilder,
+    };
+    use crate::handshake::ServerCapabilities;
+    use crate::headers::{StompVersion, StompVersions};
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn plays_the_broker() {
+        let (mut broker, mut client) = MockBroker::with_capabilities(
+            ServerCapabilities::new().versions(vec![StompVersion::V1_2]),
+        );
+
+        client.send(ConnectFrameBuilder::new("h", StompVersions(vec![StompVersion::V1_1])).build());
+        client.send(ConnectFrameBuilder::new("h", StompVersions(vec![StompVersion::V1_2])).build());
+        client.send(SubscribeFrameBuilder::new("/topic/a", "1").build());
+        client.send(SubscribeFrameBuilder::new("/topic/a", "2").build());
+        client.send(UnsubscribeFrameBuilder::new("1").build());
+        assert_eq!(5, broker.process().unwrap());
+
+        assert!(matches!(client.receive(), Ok(Some(ServerFrame::Error(_)))));
+        assert!(matches!(
+            client.receive(),
+            Ok(Some(ServerFrame::Connected(_)))
+        ));
+        assert_eq!(2, broker.subscribes().count());
+
+        assert_eq!(1, broker.deliver("/topic/a", b""));
+        assert_eq!(0, broker.deliver("/topic/b", b""));
+        broker.send_error("closing");
+        let Ok(Some(ServerFrame::Message(message))) = client.receive() else {
+            panic!("No message delivered")
+        };
+        assert_eq!("2", message.subscription().value());
+        assert!(matches!(client.receive(), Ok(Some(ServerFrame::Error(_)))));
+
+        client.send(DisconnectFrameBuilder::new("bye").build());
+        client.send(b"SEND\n\n\x00".to_vec());
+        assert!(broker.process().is_err());
+        let Ok(Some(ServerFrame::Receipt(receipt))) = client.receive() else {
+            panic!("No receipt sent")
+        };
+        assert_eq!("bye", receipt.receipt_id().value());
+        assert_eq!(6, broker.received().len());
+    }
+}
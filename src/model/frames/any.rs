@@ -0,0 +1,271 @@
+//! Provides a model for frames whose origin - client or server - is not known in advance, as is
+//! the case for tools such as protocol analyzers which observe both sides of a connection.
+use std::convert::TryFrom;
+
+use nom::error::VerboseError;
+
+use crate::config::ParserConfig;
+use crate::error::StompParseError;
+use crate::parser::{command_error, command_line, unknown_command};
+
+use super::client::ClientFrame;
+use super::server::ServerFrame;
+
+/// A frame sent by either a client or a server; the variant is determined by the frame's command.
+#[derive(Debug)]
+pub enum AnyFrame {
+    Client(ClientFrame),
+    Server(ServerFrame),
+}
+
+crate::assert_send_sync!(AnyFrame);
+
+impl AnyFrame {
+    /// Returns true if the frame is one which a client sends.
+    pub fn is_client(&self) -> bool {
+        matches!(self, AnyFrame::Client(_))
+    }
+
+    /// Returns true if the frame is one which a server sends.
+    pub fn is_server(&self) -> bool {
+        matches!(self, AnyFrame::Server(_))
+    }
+
+    /// The frame's command.
+    pub fn command(&self) -> crate::command::Command {
+        match self {
+            AnyFrame::Client(frame) => frame.command(),
+            AnyFrame::Server(frame) => frame.command(),
+        }
+    }
+
+    /// The frame's command as it is written.
+    pub fn command_name(&self) -> &'static str {
+        self.command().as_str()
+    }
+
+    /// The serialised form of the frame.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            AnyFrame::Client(frame) => frame.as_bytes(),
+            AnyFrame::Server(frame) => frame.as_bytes(),
+        }
+    }
+
+    /// A copy of the serialised form of the frame.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Consumes the frame, returning its serialised form in an owned buffer, as completion based
+    /// IO such as `tokio-uring` requires.
+    pub fn into_write_buffer(self) -> Vec<u8> {
+        self.into()
+    }
+
+    /// Writes the serialised form of the frame as text, failing if it is not valid UTF-8.
+    pub fn write_str_to<W: std::fmt::Write + ?Sized>(
+        &self,
+        writer: &mut W,
+    ) -> Result<(), crate::error::EncodeError> {
+        crate::encoder::write_str(self.as_bytes(), writer)
+    }
+
+    /// The positions of the frame's headers, if they were recorded when it was parsed.
+    pub fn header_spans(&self) -> Option<&[crate::spans::HeaderSpans]> {
+        match self {
+            AnyFrame::Client(frame) => frame.header_spans(),
+            AnyFrame::Server(frame) => frame.header_spans(),
+        }
+    }
+
+    /// The serialised form of the frame, split into the head (command and headers), the body, and
+    /// the remainder, for use with `Write::write_vectored`.
+    pub fn as_io_slices(&self) -> [std::io::IoSlice<'_>; 3] {
+        match self {
+            AnyFrame::Client(frame) => frame.as_io_slices(),
+            AnyFrame::Server(frame) => frame.as_io_slices(),
+        }
+    }
+
+    /// The length in bytes of the serialised head of the frame: the command, the headers, and the
+    /// blank line ending them.
+    pub fn headers_len(&self) -> usize {
+        match self {
+            AnyFrame::Client(frame) => frame.headers_len(),
+            AnyFrame::Server(frame) => frame.headers_len(),
+        }
+    }
+
+    /// The length in bytes of the body, or 0 if the frame has none.
+    pub fn body_len(&self) -> usize {
+        match self {
+            AnyFrame::Client(frame) => frame.body_len(),
+            AnyFrame::Server(frame) => frame.body_len(),
+        }
+    }
+
+    /// The length in bytes of the serialised frame, including the terminating null octet and any
+    /// EOLs following it.
+    pub fn wire_len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    /// A SHA-256 digest of the frame's command, headers and body, for deduplication. It is
+    /// independent of header order, escaping and repeated headers, and of `content-length`.
+    pub fn content_hash(&self) -> [u8; crate::digest::DIGEST_LEN] {
+        match self {
+            AnyFrame::Client(frame) => frame.content_hash(),
+            AnyFrame::Server(frame) => frame.content_hash(),
+        }
+    }
+
+    /// An approximation of the heap memory retained by the frame, in bytes.
+    pub fn approximate_heap_size(&self) -> usize {
+        match self {
+            AnyFrame::Client(frame) => frame.approximate_heap_size(),
+            AnyFrame::Server(frame) => frame.approximate_heap_size(),
+        }
+    }
+
+    /// Renders the frame in a multi-line, human-readable form, for logging and bug reports.
+    pub fn pretty(&self) -> String {
+        self.pretty_with(crate::pretty::DEFAULT_MAX_BODY_LEN)
+    }
+
+    /// Like `pretty`, but rendering at most `max_body_len` bytes of the body.
+    pub fn pretty_with(&self, max_body_len: usize) -> String {
+        match self {
+            AnyFrame::Client(frame) => frame.pretty_with(max_body_len),
+            AnyFrame::Server(frame) => frame.pretty_with(max_body_len),
+        }
+    }
+
+    /// Renders the frame on a single line for logging; see the `summary` of the frames.
+    pub fn summary(&self) -> String {
+        crate::pretty::summary(self.as_bytes())
+    }
+
+    /// Renders the exact bytes of the frame on a single line; see `pretty::wire_dump`.
+    pub fn wire_dump(&self) -> String {
+        crate::pretty::wire_dump(self.as_bytes())
+    }
+
+    /// Renders the frame as a JSON object, for tools which process frames, such as `stomp-decode`.
+    pub fn to_json(&self) -> String {
+        crate::pretty::json(self.as_bytes())
+    }
+}
+
+/// Parses a frame without knowing whether it was sent by a client or a server, by inspecting
+/// the command before delegating to the parser for the appropriate side.
+pub fn parse_any(bytes: Vec<u8>) -> Result<AnyFrame, StompParseError> {
+    parse_any_with(bytes, &ParserConfig::default())
+}
+
+/// Like `parse_any`, but using the provided configuration.
+pub fn parse_any_with(bytes: Vec<u8>, config: &ParserConfig) -> Result<AnyFrame, StompParseError> {
+    let (_, command) = command_line::<VerboseError<&[u8]>, StompParseError>(bytes.as_slice())
+        .map_err(|_| command_error(&bytes))?;
+
+    let command = std::str::from_utf8(command)
+        .map_err(|_| StompParseError::new("badly formed command string, not utf8"))?;
+
+    if ClientFrame::COMMANDS.contains(&command) {
+        ClientFrame::parse_with(bytes, config).map(AnyFrame::Client)
+    } else if ServerFrame::COMMANDS.contains(&command) {
+        ServerFrame::parse_with(bytes, config).map(AnyFrame::Server)
+    } else {
+        Err(unknown_command(command))
+    }
+}
+
+/// Parses an `AnyFrame` from the data contained in the provided vector of bytes.
+impl TryFrom<Vec<u8>> for AnyFrame {
+    type Error = StompParseError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, StompParseError> {
+        parse_any(bytes)
+    }
+}
+
+impl AsRef<[u8]> for AnyFrame {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// This implementation serialises [`AnyFrame`] into a byte array.
+impl From<AnyFrame> for Vec<u8> {
+    fn from(frame: AnyFrame) -> Vec<u8> {
+        match frame {
+            AnyFrame::Client(frame) => frame.into(),
+            AnyFrame::Server(frame) => frame.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_any, AnyFrame};
+    use crate::client::ClientFrame;
+    use crate::server::ServerFrame;
+
+    #[test]
+    fn parses_client_frame() {
+        let frame = parse_any(b"SEND\ndestination:foo\n\nhello\x00".to_vec()).unwrap();
+
+        let AnyFrame::Client(ClientFrame::Send(frame)) = frame else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!("foo", frame.destination().value());
+    }
+
+    #[test]
+    fn parses_client_alias() {
+        let frame = parse_any(b"STOMP\naccept-version:1.2\nhost:b\n\n\x00".to_vec()).unwrap();
+
+        assert!(matches!(frame, AnyFrame::Client(ClientFrame::Connect(_))));
+    }
+
+    #[test]
+    fn parses_server_frame() {
+        let frame = parse_any(b"RECEIPT\nreceipt-id:rcpt-1\n\n\x00".to_vec()).unwrap();
+
+        let AnyFrame::Server(ServerFrame::Receipt(frame)) = frame else {
+            panic!("Not a Receipt Frame!")
+        };
+        assert_eq!("rcpt-1", frame.receipt_id().value());
+    }
+
+    #[test]
+    fn measures_frame() {
+        let frame = parse_any(b"SEND\ndestination:foo\n\nhello\x00\n".to_vec()).unwrap();
+        assert_eq!(22, frame.headers_len());
+        assert_eq!(5, frame.body_len());
+        assert_eq!(29, frame.wire_len());
+
+        let frame = parse_any(b"RECEIPT\nreceipt-id:rcpt-1\n\n\x00".to_vec()).unwrap();
+        assert_eq!(27, frame.headers_len());
+        assert_eq!(0, frame.body_len());
+        assert_eq!(28, frame.wire_len());
+    }
+
+    #[test]
+    fn rejects_unknown_command() {
+        let frame = parse_any(b"FUNK\nreceipt-id:rcpt-1\n\n\x00".to_vec());
+
+        assert!(frame.is_err());
+    }
+
+    #[test]
+    fn serialises_unchanged() {
+        let bytes = b"MESSAGE\nmessage-id:1\ndestination:a\nsubscription:s\n\nbody\x00".to_vec();
+
+        let frame = parse_any(bytes.clone()).unwrap();
+        assert!(frame.is_server());
+
+        let serialised: Vec<u8> = frame.into();
+        assert_eq!(bytes, serialised);
+    }
+}
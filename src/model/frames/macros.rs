@@ -1,99 +1,357 @@
+#[doc(hidden)]
+#[macro_export]
 macro_rules! frame {
     ( $name:ident,  $($comment:literal,)? $command:ident, $origin:ident $(, $header_name:ident : $header_type:ident )* $(,( $(  $opt_header_name:ident : $opt_header_type:ident $(: $opt_header_default:tt $(: $opt_header_default_comment:literal)?)?  ),* ))? $(,[custom: $has_custom:ident])? $(,[body: $has_body:ident])?  $(,$long_comment:literal)*) => {
-        paste::paste! {
+        $crate::macro_support::paste::paste! {
 
-            sender_frame!($name,  $($comment,)? $command, $origin $(, $header_name : $header_type )* $(,( $(  $opt_header_name : $opt_header_type $(: $opt_header_default $(: $opt_header_default_comment )?)?  ),* ))? $(,[custom: $has_custom])? $(,[body: $has_body])?  $(,$long_comment)*);
+            $crate::sender_frame!($name,  $($comment,)? $command, $origin $(, $header_name : $header_type )* $(,( $(  $opt_header_name : $opt_header_type $(: $opt_header_default $(: $opt_header_default_comment )?)?  ),* ))? $(,[custom: $has_custom])? $(,[body: $has_body])?  $(,$long_comment)*);
 
             $(#[doc = ""$comment]
             #[doc = ""])?
             #[doc = "This frame has required headers "$("`"$header_name"`")","* $(" and optional headers " $("`"$opt_header_name"`")","* )?"."]
             $(#[doc = ""]
             #[doc = ""$long_comment])?
-            pub struct $name<'a> {
-                raw: Vec<u8>,
+            #[doc = ""]
+            #[doc = "The frame holds its serialised form, and refers to its headers and body by their positions within it, so that parsing does not copy them. The lifetime `'a` is not used by the frame itself; it is retained so that the frame's type is unchanged from versions in which the frame referred to its storage directly."]
+            pub struct $name<'a, S: $crate::macro_support::Storage = Vec<u8>> {
+                raw: S,
             $(
                 #[doc = "The value of the `"$header_name"` header."]
-                $header_name: [<$header_type Value>]<'a>,
+                $header_name: $crate::macro_support::[<$header_type Stored>],
             )*
             $($(
                 #[doc = "The value of the `"$opt_header_name"` header."]
                 $($(#[doc = "Defaults to `"$opt_header_default_comment"` if not supplied."])?)?
-                $opt_header_name: choose_from_presence!($($opt_header_default)? ([<$opt_header_type Value>]<'a>),(Option<[<$opt_header_type Value>]<'a>>)),
+                $opt_header_name: $crate::choose_from_presence!($($opt_header_default)? ($crate::macro_support::[<$opt_header_type Stored>]),(Option<$crate::macro_support::[<$opt_header_type Stored>]>)),
             )*)?
             $(
                 #[allow(unused)]
                 $has_custom: (),
-                pub custom: Vec<CustomValue>,
+                custom: Vec<($crate::macro_support::StoredStr, $crate::macro_support::StoredStr)>,
             )?
             $(
                 #[allow(unused)]
-                $has_body: &'a [u8],
+                $has_body: Option<$crate::macro_support::Span>,
             )?
+            repeated: Vec<$crate::macro_support::StoredHeader>,
+            /// The lossily converted copy of the head of the frame from which the headers were parsed,
+            /// if the frame was parsed with `Utf8Policy::Lossy` and its headers were not valid UTF-8.
+            lossy: Option<Box<[u8]>>,
+            /// The positions of the headers, if they were recorded when the frame was parsed.
+            header_spans: Option<Vec<$crate::spans::HeaderSpans>>,
+            /// The body decoded as text, kept by the first call of `body_text` which had to copy it
+            /// to decode it.
+            text: std::sync::OnceLock<Box<str>>,
+            phantom: std::marker::PhantomData<&'a [u8]>,
         }
 
         impl <'a> $name<'a> {
             pub const NAME: &'static str = stringify!($command);
         }
 
-        impl <'a> $name<'a> {
+        impl <'a, S: $crate::macro_support::Storage> $name<'a, S> {
 
-            fn init(raw: Vec<u8>) -> Self {
+            fn init(raw: S) -> Self {
                 $name {
                     raw,
                      $(
-                $header_name: [<$header_type Value>]::default(),
+                $header_name: Default::default(),
             )*
                     $($(
-                $opt_header_name: choose_from_presence!($(($opt_header_default))? ([<$opt_header_type Value>]::default()),None),
+                $opt_header_name: $crate::choose_from_presence!($(($opt_header_default))? (Default::default()),None),
             )*)? $(
                  #[allow(unused)]
                 $has_custom: (),
                 custom: vec![],
             )? $(
-                $has_body: &EMPTY,
+                $has_body: None,
             )?
+                repeated: Vec::new(),
+                lossy: None,
+                header_spans: None,
+                text: std::sync::OnceLock::new(),
+                phantom: std::marker::PhantomData,
             }
         }
+
+            /// The bytes from which the headers were parsed: the lossily converted copy of the frame,
+            /// if there is one, and otherwise the serialised frame.
+            fn head(&self) -> &[u8] {
+                self.lossy.as_deref().unwrap_or(self.raw.as_ref())
+            }
+
+            /// The occurrences of headers after the first, in the order they were received. As required by
+            /// the specification, only the first occurrence of a repeated header determines its value.
+            pub fn repeated_headers(&self) -> Vec<$crate::macro_support::Header<'_>> {
+                let head = self.head();
+                self.repeated.iter().map(|header| header.load(head)).collect()
+            }
+
+            /// The serialised form of the frame.
+            pub fn as_bytes(&self) -> &[u8] {
+                self.raw.as_ref()
+            }
+
+            /// A copy of the serialised form of the frame, which is held in full, so that copying it
+            /// cannot fail.
+            pub fn to_bytes(&self) -> Vec<u8> {
+                self.as_bytes().to_vec()
+            }
+
+            /// The value of the first header with the specified name as it appears in the serialised
+            /// form, i.e. neither unescaped nor converted to UTF-8.
+            pub fn raw_header(&self, name: &str) -> Option<&[u8]> {
+                $crate::macro_support::raw_header(self.raw.as_ref(), name)
+            }
+
+            /// The positions within `as_bytes` of the headers, in the order they appear, including
+            /// repeated headers; or `None` unless the frame was parsed with
+            /// `ParserConfig::record_header_spans`.
+            pub fn header_spans(&self) -> Option<&[$crate::spans::HeaderSpans]> {
+                self.header_spans.as_deref()
+            }
+
+            /// Consumes the frame, returning the storage holding its serialised form.
+            pub fn into_storage(self) -> S {
+                self.raw
+            }
+
+            /// Consumes the frame, returning its serialised form in an owned buffer, as completion
+            /// based IO such as `tokio-uring` requires. The bytes are copied only if the storage
+            /// cannot be converted to a `Vec<u8>` in place.
+            pub fn into_write_buffer(self) -> Vec<u8> {
+                self.raw.into_vec()
+            }
+
+            /// Writes the serialised form of the frame as text, failing with
+            /// `EncodeError::NotUtf8`, and writing nothing, if it is not valid UTF-8, as a binary
+            /// body may not be.
+            pub fn write_str_to<W: std::fmt::Write + ?Sized>(
+                &self,
+                writer: &mut W,
+            ) -> Result<(), $crate::error::EncodeError> {
+                $crate::macro_support::write_str(self.as_bytes(), writer)
+            }
+
+            /// The frame's command.
+            pub fn command(&self) -> $crate::command::Command {
+                $crate::command::Command::from_name(self.command_name())
+            }
+
+            /// The frame's command as it is written; frames parsed from an alias of the command,
+            /// such as `STOMP`, are written with the command itself.
+            pub fn command_name(&self) -> &'static str {
+                stringify!($command)
+            }
+
+            /// The serialised form of the frame, split into the head (command and headers), the body,
+            /// and the remainder, for use with `Write::write_vectored`.
+            pub fn as_io_slices(&self) -> [std::io::IoSlice<'_>; 3] {
+                let body: &[u8] = $crate::choose_from_presence!($($has_body)? (self.body().unwrap_or_default()), (&[]));
+                $crate::macro_support::split_raw(self.raw.as_ref(), body)
+            }
+
+            /// The length in bytes of the serialised head of the frame: the command, the headers, and
+            /// the blank line ending them.
+            pub fn headers_len(&self) -> usize {
+                self.as_io_slices()[0].len()
+            }
+
+            /// The length in bytes of the body, or 0 if the frame has none.
+            pub fn body_len(&self) -> usize {
+                $crate::choose_from_presence!($($has_body)? (self.body().map_or(0, <[u8]>::len)), (0))
+            }
+
+            /// The length in bytes of the serialised frame, including the terminating null octet and
+            /// any EOLs following it.
+            pub fn wire_len(&self) -> usize {
+                self.raw.as_ref().len()
+            }
+
+            /// A SHA-256 digest of the frame's command, headers and body, for deduplication. It is
+            /// independent of header order, escaping and repeated headers, and of `content-length`.
+            pub fn content_hash(&self) -> [u8; $crate::digest::DIGEST_LEN] {
+                let body: Option<&[u8]> = $crate::choose_from_presence!($($has_body)? (self.body()), (None));
+                $crate::macro_support::content_hash(self.raw.as_ref(), body)
+            }
+
+            /// An approximation of the heap memory retained by the frame, in bytes: its serialised
+            /// form, and the allocations locating its headers. Storage shared with other frames is
+            /// counted in full; strings shared through an interner are not counted.
+            pub fn approximate_heap_size(&self) -> usize {
+                #[allow(unused_mut)]
+                let mut size = self.raw.as_ref().len()
+                    + self.lossy.as_ref().map_or(0, |lossy| lossy.len())
+                    + self.text.get().map_or(0, |text| text.len())
+                    + self.repeated.capacity() * std::mem::size_of::<$crate::macro_support::StoredHeader>()
+                    + self.header_spans.as_ref().map_or(0, |spans| {
+                        spans.capacity() * std::mem::size_of::<$crate::spans::HeaderSpans>()
+                    });
                 $(
-                pub fn body(&self) -> Option<&'a [u8]> {
-                    Some(self.$has_body)
+                    $crate::blank!($has_custom);
+                    size += self.custom.capacity() * std::mem::size_of::<($crate::macro_support::StoredStr, $crate::macro_support::StoredStr)>();
+                )?
+                size
+            }
+
+            $crate::signature_verifier!($($has_body)?);
+
+            /// Renders the frame in a multi-line, human-readable form, for logging and bug reports.
+            pub fn pretty(&self) -> String {
+                self.pretty_with($crate::pretty::DEFAULT_MAX_BODY_LEN)
+            }
+
+            /// Like `pretty`, but rendering at most `max_body_len` bytes of the body.
+            pub fn pretty_with(&self, max_body_len: usize) -> String {
+                $crate::macro_support::pretty(self.raw.as_ref(), max_body_len)
+            }
+
+            /// Renders the frame on a single line for logging, with the command, the headers which
+            /// identify it and the length of the body, such as
+            /// `SEND destination=/queue/a len=1432 tx=tx1`. The body is omitted, and long values
+            /// are truncated.
+            pub fn summary(&self) -> String {
+                $crate::macro_support::summary(self.raw.as_ref())
+            }
+
+            /// Renders the exact bytes of the frame on a single line, with control characters and
+            /// non-ASCII bytes escaped, for bug reports; see `pretty::wire_dump`.
+            pub fn wire_dump(&self) -> String {
+                $crate::pretty::wire_dump(self.raw.as_ref())
+            }
+                $(
+                /// The body of the frame, or `None` if it has none. A frame has no body if there are
+                /// no bytes between its headers and the null octet, and it has no `content-length`
+                /// header; with a `content-length` of 0, it has an empty body.
+                ///
+                /// Parsing only locates the body: it is neither copied nor checked, and is decoded
+                /// only by the accessors which need it, such as `body_text`.
+                ///
+                /// The body is held in the same buffer as the headers, so that `as_bytes` is the whole
+                /// frame. To share one body between the frames sent to many recipients, build them with
+                /// the builder's `build_with_body`, which holds the body separately.
+                pub fn body(&self) -> Option<&[u8]> {
+                    let raw = self.raw.as_ref();
+                    self.$has_body.map(|body| body.bytes(raw))
+                }
+
+                /// Returns true if the frame has a body, even if it is empty.
+                pub fn has_body(&self) -> bool {
+                    self.$has_body.is_some()
+                }
+
+                /// The body encoded as base64, or `None` if the frame has no body. A body marked as
+                /// base64 already by the `content-transfer-encoding` header is returned unchanged.
+                pub fn body_base64(&self) -> Option<String> {
+                    let encoding = self.raw_header($crate::base64::CONTENT_TRANSFER_ENCODING);
+                    self.body()
+                        .map(|body| $crate::macro_support::encode_base64_body(encoding, body))
+                }
+
+                /// The body decoded from base64 if the `content-transfer-encoding` header marks it
+                /// as base64, and otherwise unchanged; `None` if the frame has no body.
+                pub fn body_from_base64(
+                    &self,
+                ) -> Result<Option<std::borrow::Cow<'_, [u8]>>, $crate::error::StompParseError> {
+                    let encoding = self.raw_header($crate::base64::CONTENT_TRANSFER_ENCODING);
+                    self.body()
+                        .map(|body| $crate::macro_support::decode_base64_body(encoding, body))
+                        .transpose()
+                }
+
+                /// The body decoded as text according to the `content-type` header, or `None` if the
+                /// frame has no body. Bodies of `text/*` types without a `charset` are UTF-8; bodies
+                /// without a `content-type` are opaque, and are not decoded. See `charset`.
+                ///
+                /// The body is decoded when this is first called, not when the frame is parsed. A
+                /// body in a charset other than UTF-8 is decoded into a copy, which the frame keeps for
+                /// later calls.
+                pub fn body_text(
+                    &self,
+                ) -> Result<Option<std::borrow::Cow<'_, str>>, $crate::error::StompParseError> {
+                    let body = match self.body() {
+                        Some(body) => body,
+                        None => return Ok(None),
+                    };
+                    if let Some(text) = self.text.get() {
+                        return Ok(Some(std::borrow::Cow::Borrowed(text)));
+                    }
+
+                    let content_type = self.raw_header($crate::charset::CONTENT_TYPE);
+                    Ok(Some(match $crate::macro_support::decode_text(content_type, body)? {
+                        std::borrow::Cow::Owned(text) => {
+                            std::borrow::Cow::Borrowed(&**self.text.get_or_init(|| text.into_boxed_str()))
+                        }
+                        borrowed => borrowed,
+                    }))
+                }
+
+                $crate::decoded_body_accessor!($has_body);
+            )?
+            $(
+                /// The custom header with the specified name, if present.
+                pub fn custom_header(&self, name: &str) -> Option<$crate::macro_support::CustomValue<'_>> {
+                    $crate::blank!($has_custom);
+                    self.custom_headers().into_iter().find(|custom| $crate::macro_support::HeaderValue::header_name(custom) == name)
+                }
+
+                /// The custom headers, in the order they were received. Only the first occurrence of
+                /// a repeated header is included; see `repeated_headers`.
+                pub fn custom_headers(&self) -> Vec<$crate::macro_support::CustomValue<'_>> {
+                    let head = self.head();
+                    self.custom
+                        .iter()
+                        .map(|(name, value)| $crate::macro_support::CustomValue::new(name.str(head), value.str(head)))
+                        .collect()
+                }
+
+                /// The value of the custom header `H`, if present.
+                pub fn typed_custom<H: $crate::extensions::typed::TypedCustomHeader>(&self) -> Result<Option<H>, $crate::macro_support::StompParseError> {
+                    $crate::macro_support::parse_typed(self.custom_header(H::NAME))
                 }
             )?
 
             $(
                 #[doc = "The value of the `"$header_name"` header."]
-                pub fn $header_name(&'a self) -> &'a [<$header_type Value>]<'a> {
-                    &self.$header_name
+                pub fn $header_name(&self) -> $crate::macro_support::[<$header_type Ref>]<'_> {
+                    $crate::macro_support::[<$header_type Value>]::load(&self.$header_name, self.head())
                 }
             )*
             $($(
                 #[doc = "The value of the `"$opt_header_name"` header."]
                 $($(#[doc = "Defaults to `"$opt_header_default_comment"` if not supplied."])?)?
-                pub fn $opt_header_name(&'a self) -> choose_from_presence!($($opt_header_default)? (&'a [<$opt_header_type Value>]<'a>),(Option<&'a [<$opt_header_type Value>]<'a>>)) {
-                    choose_from_presence!($($opt_header_default)? (&self.$opt_header_name),(self.$opt_header_name.as_ref()))
+                pub fn $opt_header_name(&self) -> $crate::choose_from_presence!($($opt_header_default)? ($crate::macro_support::[<$opt_header_type Ref>]<'_>),(Option<$crate::macro_support::[<$opt_header_type Ref>]<'_>>)) {
+                    let head = self.head();
+                    $crate::choose_from_presence!($($opt_header_default)? ($crate::macro_support::[<$opt_header_type Value>]::load(&self.$opt_header_name, head)),(self.$opt_header_name.as_ref().map(|stored| $crate::macro_support::[<$opt_header_type Value>]::load(stored, head))))
                 }
             )*)?
         }
 
+        impl <'a, S: $crate::macro_support::Storage> AsRef<[u8]> for $name<'a, S> {
+            fn as_ref(&self) -> &[u8] {
+                self.as_bytes()
+            }
+        }
+
         #[doc = "This implementation serialises [`"$name"`] into a byte array."]
-        impl <'a> Into<Vec<u8>> for $name<'a> {
-            fn into(self) -> Vec<u8> {
-                self.raw
+        impl <'a> From<$name<'a>> for Vec<u8> {
+            fn from(frame: $name<'a>) -> Vec<u8> {
+                frame.raw
             }
         }
 
-        impl <'a> std::fmt::Debug for $name<'a> {
+        impl <'a, S: $crate::macro_support::Storage> std::fmt::Debug for $name<'a, S> {
              fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-                write!(f, "{}{{", Self::NAME)?;
+                write!(f, "{}{{", stringify!($command))?;
                 $(
-                    write!(f, " {}: '{}', ", stringify!($header_name), self.$header_name.value())?;
+                    write!(f, " {}: '{}', ", stringify!($header_name), self.$header_name().value())?;
                 )*
 
                 $($(
                     write!(f, " {}: '{}', ", stringify!($opt_header_name),
-                    choose_from_presence!($( $opt_header_default )?
-                    {self.$opt_header_name.value().to_string() },
-                    { self.$opt_header_name.as_ref().map(|header|header.value().to_string()).unwrap_or("None".to_owned()) }))?;
+                    $crate::choose_from_presence!($( $opt_header_default )?
+                    {self.$opt_header_name().value().to_string() },
+                    { self.$opt_header_name().map(|header| $crate::macro_support::debug_value(stringify!($opt_header_name), header.value().to_string())).unwrap_or("None".to_owned()) }))?;
                 )*)?
                 $(
                     self.$has_body;
@@ -108,106 +366,199 @@ macro_rules! frame {
     }
 }
 
+#[doc(hidden)]
+#[macro_export]
 macro_rules! frame_parser {
     ( $name:ident, $origin:ident $(, $header_name:ident : $header_type:ident )* $(,( $(  $opt_header_name:ident : $opt_header_type:ident $(: $opt_header_default:tt)?),* ))? $(,[custom: $has_custom:ident])? $(,[body: $has_body:ident])? ) => {
-        paste::paste! {
+        $crate::macro_support::paste::paste! {
             #[allow(unused)]
-            pub fn [<$name:lower _frame>]<E: 'static + FullError<&'static [u8], StompParseError>>(
-                mut frame: [<$name Frame>]<'static>
-            ) -> Result<[<$origin Frame>], StompParseError>{
+            pub fn [<$name:lower _frame>]<S: $crate::macro_support::Storage>(
+                mut frame: [<$name Frame>]<'static, S>,
+                config: &$crate::macro_support::ParserConfig,
+            ) -> Result<[<$origin Frame>]<S>, $crate::macro_support::StompParseError>{
 
-                let bytes : *const [u8] = frame.raw.as_slice();
+                frame.lossy = $crate::macro_support::lossy_copy(frame.raw.as_ref(), config);
 
-                let input = unsafe { bytes.as_ref().unwrap() };
+                let raw = frame.raw.as_ref();
+                let head = frame.lossy.as_deref().unwrap_or(raw);
 
-                let (input,_) = command_line::<VerboseError<&[u8]>, StompParseError>(input).map_err(|_|StompParseError::new("Error parsing frame"))?;
+                $crate::macro_support::check_header_bytes(head)?;
 
-                        let headers_parser = headers_parser::<'static, E>(
-                                vec![$(
-                            HeaderType::$header_type,
-                        )*],
-                        vec![$($(
-                            HeaderType::$opt_header_type,
-                        )*)?],
-                        true_if_present!(
-                        $(
-                            $has_custom
-                        )?)
-                            );
-
-                        let body_section = if true_if_present!($($has_body)?) {
-                            remaining_without_null::<'static>
-                        } else {
-                            null
-                        };
-
-                        let mut fnmut = context(
-                            stringify!([<$name _frame>]),
-                            map_res(tuple((headers_parser, body_section)), |x| {
-                                let headers = x.0;
-                                $(
-                                    let mut $header_name: Option<[<$header_type Value>]> = None;
-                                )*
-
-                                for header in headers {
-                                    match header {
-                                        $(
-                                        Header::$header_type(val) => { $header_name = Some(val); }
-                                        )*
-                                        $($(
-                                        Header::$opt_header_type(val) => { frame.$opt_header_name = choose_from_presence!( $($opt_header_default)? val, (Some(val))); }
-                                        )*)?
-                                        $(
-                                        Header::Custom(val)=> {
-                                            blank!($has_custom);
-                                            frame.custom.push(val);
-                                        }
-                                        )?
-                                        _ => {Err(StompParseError::new(format!("Unexpected header: {:?}",header)))?;}
-                                    }
-                                }
+                if config.record_header_spans {
+                    frame.header_spans = Some($crate::macro_support::header_spans(raw)?);
+                }
 
+                let (input,_) = $crate::macro_support::command_line::<$crate::macro_support::VerboseError<&[u8]>, $crate::macro_support::StompParseError>(head).map_err(|_| $crate::macro_support::command_error(head))?;
+
+                let required = vec![$(
+                    $crate::macro_support::HeaderType::$header_type,
+                )*];
+                let optional = vec![$($(
+                    $crate::macro_support::HeaderType::$opt_header_type,
+                )*)?];
+                let allows_custom = $crate::true_if_present!($($has_custom)?);
+
+                let (input, headers) = $crate::macro_support::headers_parser::<$crate::macro_support::VerboseError<&[u8]>>(
+                    required.clone(),
+                    optional.clone(),
+                    allows_custom,
+                    config
+                )
+                .parse(input)
+                .map_err(|_: $crate::macro_support::nom::Err<$crate::macro_support::VerboseError<&[u8]>>| $crate::macro_support::headers_error(input, |name| {
+                    $crate::macro_support::allows_header(name, &required, &optional, allows_custom, config)
+                }))?;
+
+                // A lossy copy holds only the head, so the body is read from the original bytes
+                let input = match &frame.lossy {
+                    Some(_) => $crate::macro_support::after_head(raw),
+                    None => input,
+                };
+
+                let body = if $crate::true_if_present!($($has_body)?) {
+                    $crate::macro_support::frame_body(raw, input, config)?
+                } else {
+                    $crate::macro_support::null(input)
+                        .map_err(|_: $crate::macro_support::nom::Err<$crate::macro_support::VerboseError<&[u8]>>| $crate::macro_support::end_error(input))?
+                        .1
+                };
 
-                                $(
-                                    frame.$header_name = $header_name.ok_or_else(|| StompParseError::new(format!("Missing required header of type: {:?}",HeaderType::$header_type)))?;
-                                )*
-
-                                //         $has_custom,
-                                //     )?
-                                $(
-                                frame.$has_body =  x.1;
-                                )?
+                $(
+                    let mut $header_name: Option<$crate::macro_support::[<$header_type Value>]> = None;
+                )*
+                $($(
+                    let mut [<$opt_header_name _seen>] = false;
+                )*)?
+                $(
+                    $crate::blank!($has_custom);
+                    let mut custom_seen = std::collections::HashSet::new();
+                )?
 
-                                Ok(())
+                for header in headers {
+                    match header {
+                        $(
+                        $crate::macro_support::Header::$header_type(val) => {
+                            if $header_name.is_none() {
+                                $header_name = Some(val);
+                            } else {
+                                frame.repeated.push($crate::macro_support::Header::$header_type(val).into_stored(head));
                             }
-                        ));
-
-                        fnmut(input).map_err(|_|StompParseError::new("Error parsing frame"))?;
-                        drop(fnmut);
-                        Ok([<$origin Frame>]::$name(frame))
+                        }
+                        )*
+                        $($(
+                        $crate::macro_support::Header::$opt_header_type(val) => {
+                            if [<$opt_header_name _seen>] {
+                                frame.repeated.push($crate::macro_support::Header::$opt_header_type(val).into_stored(head));
+                            } else {
+                                [<$opt_header_name _seen>] = true;
+                                let val = val.into_stored(head);
+                                frame.$opt_header_name = $crate::choose_from_presence!( $($opt_header_default)? val, (Some(val)));
+                            }
+                        }
+                        )*)?
+                        $(
+                        $crate::macro_support::Header::Custom(val)=> {
+                            $crate::blank!($has_custom);
+                            if !custom_seen.insert($crate::macro_support::custom_header_key(val.name(), config)) {
+                                frame.repeated.push($crate::macro_support::Header::Custom(val).into_stored(head));
+                            } else {
+                                let store = |part: &str| config
+                                    .interner
+                                    .as_ref()
+                                    .and_then(|interner| interner.interner().intern(part))
+                                    .map_or_else(|| $crate::macro_support::StoredStr::Span($crate::macro_support::Span::of(part.as_bytes(), head)), $crate::macro_support::StoredStr::Shared);
+                                frame.custom.push((store($crate::macro_support::HeaderValue::header_name(&val)), store(val.value())));
+                            }
+                        }
+                        )?
+                        _ => {Err($crate::macro_support::unexpected_header(header.name()))?;}
                     }
+                }
 
+                $(
+                    let $header_name = $header_name.ok_or_else(|| $crate::macro_support::missing_header(&$crate::macro_support::HeaderType::$header_type.to_string()))?;
+                    $crate::check_header!($header_type, config, $header_name);
+                    frame.$header_name = $header_name.into_stored(head);
+                )*
 
+                $(
+                    frame.$has_body = if body.is_empty() && $crate::macro_support::raw_header(raw, $crate::headers::HeaderName::ContentLength.as_str()).is_none() {
+                        None
+                    } else {
+                        Some($crate::macro_support::Span::of(body, raw))
+                    };
+                )?
+
+                Ok([<$origin Frame>]::$name(frame))
+            }
         }
     };
 }
 
+/// Defines a set of frames: a struct and a builder for each frame, and an enum of the frames, named
+/// after the group, with the same parsing and serialisation as the frames of this crate. This
+/// allows a crate to define the frames of a broker-specific extension of the protocol, such as
+/// proprietary management commands.
+///
+/// Each frame is defined by its name, an optional description, its command (with any aliases
+/// separated by `|`), the group, its required headers, its optional headers in parentheses, and
+/// whether it allows custom headers and a body. Headers are named by their field and the type of
+/// the header in `headers`; other headers are carried as custom headers, for which typed access is
+/// provided by `custom_header!`. The generated code refers to the items of this crate by their full
+/// paths, so nothing need be imported, and nothing is imported into the calling module.
+///
+/// # Example
+/// ```
+/// use std::convert::TryFrom;
+///
+/// #[allow(non_snake_case, unused_parens, clippy::new_without_default)]
+/// mod management {
+///     stomp_parser::frames! {
+///         Management,
+///         (
+///             Purge,
+///             "Removes all messages from a destination.",
+///             PURGE,
+///             Management,
+///             destination: Destination,
+///             (receipt: Receipt),
+///             [custom: cus]
+///         ),
+///         (
+///             Inject,
+///             "Adds a message to a destination.",
+///             INJECT|PUT,
+///             Management,
+///             destination: Destination,
+///             (content_type: ContentType),
+///             [custom: cus],
+///             [body: body]
+///         )
+///     }
+/// }
+///
+/// use management::{ManagementFrame, PurgeFrameBuilder};
+///
+/// let bytes: Vec<u8> = PurgeFrameBuilder::new("/queue/a".to_owned()).build().into();
+///
+/// if let Ok(ManagementFrame::Purge(frame)) = ManagementFrame::try_from(bytes) {
+///     assert_eq!("/queue/a", frame.destination().value());
+/// } else {
+///     panic!("Purge frame not parsed");
+/// }
+///
+/// assert!(ManagementFrame::try_from(b"PUT\ndestination:a\n\nbody\x00".to_vec()).is_ok());
+/// ```
+#[macro_export]
 macro_rules! frames {
     { $group_name:ident,
         $(
             ( $name:ident, $($comment:literal,)? $command:ident$(|$alias:ident)*, $origin:ident $(, $header_name:ident : $header_type:ident )* $(,( $(  $opt_header_name:ident : $opt_header_type:ident $(: $opt_header_default:tt$(: $opt_header_default_comment:literal)?)?),* ))? $(,[custom: $has_custom:ident])? $(,[body: $has_body:ident])? $(,$long_comment:literal)* )
         ),+
     } => {
-        use crate::common::constants::*;
-        use crate::common::functions::*;
-
-        use crate::error::StompParseError;
-
-        use std::convert::TryFrom;
-
-        paste::paste! {
+        $crate::macro_support::paste::paste! {
             $(
-                frame! (
+                $crate::frame!(
                     [<$name Frame>],
                     $($comment,)?
                     $command,
@@ -223,17 +574,230 @@ macro_rules! frames {
             #[doc = "The `" $group_name "Frame` enum contains a variant for each frame that the "$group_name:lower" can send."]
             #[doc = ""]
             #[doc = "The `try_from(bytes: Vec<u8>)` method, provided via an implementaton of `TryFrom<Vec<u8>>`, is the recommended way to obtain a Frame from a received message."]
-            pub enum [<$group_name Frame>] {
+            #[doc = ""]
+            #[doc = "The frame's serialised form is held in its `Storage`, which by default is a `Vec<u8>`."]
+            pub enum [<$group_name Frame>]<S: $crate::macro_support::Storage = Vec<u8>> {
                 $(
                     $(#[doc=$comment])?
-                    $name([<$name Frame>]<'static>)
+                    $name([<$name Frame>]<'static, S>)
                 ),+
             }
 
-            #[doc = "This implementation serialises [`"$group_name Frame"`] into a byte array."]
-            impl Into<Vec<u8>> for [<$group_name Frame>] {
-                fn into(self) -> Vec<u8> {
+            #[doc = "Handles each kind of `" $group_name "Frame` in its own method, called by `" $group_name "Frame::accept`. Kinds whose method is not overridden are passed to `visit_other`."]
+            pub trait [<$group_name FrameVisitor>]<S: $crate::macro_support::Storage = Vec<u8>> {
+                /// The result of visiting a frame.
+                type Output;
+
+                /// Handles frames whose kind has no overridden method, given their command.
+                fn visit_other(&mut self, command: &'static str) -> Self::Output;
+
+                $(
+                    #[doc = "Handles a `" $name "Frame`."]
+                    fn [<visit_ $name:snake>](&mut self, _frame: &[<$name Frame>]<'static, S>) -> Self::Output {
+                        self.visit_other([<$name Frame>]::NAME)
+                    }
+                )+
+            }
+
+            // Frames are shared between tasks and threads, so must remain `Send` and `Sync`
+            $crate::assert_send_sync!([<$group_name Frame>], $([<$name Frame>]<'static>),+);
+
+            impl [<$group_name Frame>] {
+                #[doc = "The commands, including aliases, of all frames that the "$group_name:lower" can send."]
+                pub const COMMANDS: &'static [&'static str] = &[$(stringify!($command), $(stringify!($alias),)*)+];
+            }
+
+            impl<S: $crate::macro_support::Storage> [<$group_name Frame>]<S> {
+
+                /// The serialised form of the frame.
+                pub fn as_bytes(&self) -> &[u8] {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.as_bytes(),
+                        )+
+                    }
+                }
+
+                /// The serialised form of the frame, split into the head (command and headers), the
+                /// body, and the remainder, for use with `Write::write_vectored`.
+                pub fn as_io_slices(&self) -> [std::io::IoSlice<'_>; 3] {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.as_io_slices(),
+                        )+
+                    }
+                }
+
+                /// The length in bytes of the serialised head of the frame: the command, the headers,
+                /// and the blank line ending them.
+                pub fn headers_len(&self) -> usize {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.headers_len(),
+                        )+
+                    }
+                }
+
+                /// The length in bytes of the body, or 0 if the frame has none.
+                pub fn body_len(&self) -> usize {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.body_len(),
+                        )+
+                    }
+                }
+
+                /// The length in bytes of the serialised frame, including the terminating null octet
+                /// and any EOLs following it.
+                pub fn wire_len(&self) -> usize {
+                    self.as_bytes().len()
+                }
+
+                /// A SHA-256 digest of the frame's command, headers and body, for deduplication. It
+                /// is independent of header order, escaping and repeated headers, and of
+                /// `content-length`.
+                pub fn content_hash(&self) -> [u8; $crate::digest::DIGEST_LEN] {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.content_hash(),
+                        )+
+                    }
+                }
+
+                /// An approximation of the heap memory retained by the frame, in bytes.
+                pub fn approximate_heap_size(&self) -> usize {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.approximate_heap_size(),
+                        )+
+                    }
+                }
+
+                /// Renders the frame in a multi-line, human-readable form, for logging and bug reports.
+                pub fn pretty(&self) -> String {
+                    self.pretty_with($crate::pretty::DEFAULT_MAX_BODY_LEN)
+                }
+
+                /// Like `pretty`, but rendering at most `max_body_len` bytes of the body.
+                pub fn pretty_with(&self, max_body_len: usize) -> String {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.pretty_with(max_body_len),
+                        )+
+                    }
+                }
+
+                /// Renders the frame on a single line for logging; see the `summary` of the frames.
+                pub fn summary(&self) -> String {
+                    $crate::macro_support::summary(self.as_bytes())
+                }
+
+                /// Renders the exact bytes of the frame on a single line; see `pretty::wire_dump`.
+                pub fn wire_dump(&self) -> String {
+                    $crate::pretty::wire_dump(self.as_bytes())
+                }
+
+                /// The positions of the frame's headers; see the `header_spans` of the frames.
+                pub fn header_spans(&self) -> Option<&[$crate::spans::HeaderSpans]> {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.header_spans(),
+                        )+
+                    }
+                }
+
+                /// Consumes the frame, returning the storage holding its serialised form.
+                pub fn into_storage(self) -> S {
                     match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.into_storage(),
+                        )+
+                    }
+                }
+
+                /// A copy of the serialised form of the frame; see the `to_bytes` of the frames.
+                pub fn to_bytes(&self) -> Vec<u8> {
+                    self.as_bytes().to_vec()
+                }
+
+                /// Consumes the frame, returning its serialised form in an owned buffer; see the
+                /// `into_write_buffer` of the frames.
+                pub fn into_write_buffer(self) -> Vec<u8> {
+                    self.into_storage().into_vec()
+                }
+
+                /// Writes the serialised form of the frame as text; see the `write_str_to` of the
+                /// frames.
+                pub fn write_str_to<W: std::fmt::Write + ?Sized>(
+                    &self,
+                    writer: &mut W,
+                ) -> Result<(), $crate::error::EncodeError> {
+                    $crate::macro_support::write_str(self.as_bytes(), writer)
+                }
+
+                /// The frame's command.
+                pub fn command(&self) -> $crate::command::Command {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.command(),
+                        )+
+                    }
+                }
+
+                /// The frame's command as it is written.
+                pub fn command_name(&self) -> &'static str {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => frame.command_name(),
+                        )+
+                    }
+                }
+
+                /// Passes the frame to the method of `visitor` for its kind.
+                pub fn accept<V: [<$group_name FrameVisitor>]<S>>(&self, visitor: &mut V) -> V::Output {
+                    match self {
+                        $(
+                            [<$group_name Frame>]::$name(frame) => visitor.[<visit_ $name:snake>](frame),
+                        )+
+                    }
+                }
+
+                $(
+                    #[doc = "Returns true if this is a `" $name "Frame`."]
+                    pub fn [<is_ $name:snake>](&self) -> bool {
+                        matches!(self, [<$group_name Frame>]::$name(_))
+                    }
+
+                    #[doc = "The `" $name "Frame`, if this is one."]
+                    #[allow(unreachable_patterns)]
+                    pub fn [<as_ $name:snake>](&self) -> Option<&[<$name Frame>]<'static, S>> {
+                        match self {
+                            [<$group_name Frame>]::$name(frame) => Some(frame),
+                            _ => None,
+                        }
+                    }
+
+                    #[doc = "The `" $name "Frame`, if this is one, and otherwise this frame unchanged."]
+                    #[allow(unreachable_patterns, clippy::result_large_err)]
+                    pub fn [<into_ $name:snake>](self) -> Result<[<$name Frame>]<'static, S>, Self> {
+                        match self {
+                            [<$group_name Frame>]::$name(frame) => Ok(frame),
+                            other => Err(other),
+                        }
+                    }
+                )+
+            }
+
+            impl<S: $crate::macro_support::Storage> AsRef<[u8]> for [<$group_name Frame>]<S> {
+                fn as_ref(&self) -> &[u8] {
+                    self.as_bytes()
+                }
+            }
+
+            #[doc = "This implementation serialises [`"$group_name Frame"`] into a byte array."]
+            impl From<[<$group_name Frame>]> for Vec<u8> {
+                fn from(frame: [<$group_name Frame>]) -> Vec<u8> {
+                    match frame {
                         $(
                             [<$group_name Frame>]::$name(frame) => frame.into(),
                         )+
@@ -241,7 +805,7 @@ macro_rules! frames {
                 }
             }
 
-            impl std::fmt::Debug for [<$group_name Frame>] {
+            impl<S: $crate::macro_support::Storage> std::fmt::Debug for [<$group_name Frame>]<S> {
                 fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
                     match self {
                         $(
@@ -251,26 +815,49 @@ macro_rules! frames {
                 }
             }
 
+            impl<S: $crate::macro_support::Storage> [<$group_name Frame>]<S> {
+                #[doc = "Parses a `" $group_name "Frame` from the data held in the provided storage, using the provided configuration."]
+                pub fn parse_with(bytes: S, config: &$crate::macro_support::ParserConfig) -> Result<Self, $crate::macro_support::StompParseError> {
+                    self::parsers::[<$group_name:lower _frame>](bytes, config)
+                }
+            }
+
             #[doc = "Parses a `" $group_name "Frame`  from the data contained in the provided vector of bytes."]
-            impl TryFrom<Vec<u8>> for [<$group_name Frame>]{
-                        type Error = StompParseError;
-                        fn try_from(bytes: Vec<u8>) -> Result<Self, StompParseError> {
-                            self::parsers::[<$group_name:lower _frame>](bytes)
+            impl $crate::macro_support::TryFrom<Vec<u8>> for [<$group_name Frame>]{
+                        type Error = $crate::macro_support::StompParseError;
+                        fn try_from(bytes: Vec<u8>) -> Result<Self, $crate::macro_support::StompParseError> {
+                            Self::parse_with(bytes, &$crate::macro_support::ParserConfig::default())
                          }
             }
 
+            #[doc = "Parses a `" $group_name "Frame` from a copy of the provided text."]
+            impl $crate::macro_support::TryFrom<&str> for [<$group_name Frame>] {
+                type Error = $crate::macro_support::StompParseError;
+                fn try_from(text: &str) -> Result<Self, $crate::macro_support::StompParseError> {
+                    Self::parse_with(text.as_bytes().to_vec(), &$crate::macro_support::ParserConfig::default())
+                }
+            }
+
+            #[doc = "Parses a `" $group_name "Frame` from a copy of the provided text."]
+            impl std::str::FromStr for [<$group_name Frame>] {
+                type Err = $crate::macro_support::StompParseError;
+                fn from_str(text: &str) -> Result<Self, $crate::macro_support::StompParseError> {
+                    <Self as $crate::macro_support::TryFrom<&str>>::try_from(text)
+                }
+            }
+
+            #[doc = "Parses a `" $group_name "Frame` referring to the provided slice, without copying it."]
+            impl<'b> $crate::macro_support::TryFrom<&'b [u8]> for [<$group_name Frame>]<&'b [u8]> {
+                type Error = $crate::macro_support::StompParseError;
+                fn try_from(bytes: &'b [u8]) -> Result<Self, $crate::macro_support::StompParseError> {
+                    Self::parse_with(bytes, &$crate::macro_support::ParserConfig::default())
+                }
+            }
+
             mod parsers {
                 use super::*;
-                use crate::parser::headers::headers_parser;
-                use crate::parser::{null,remaining_without_null, command_line};
-                use crate::error::FullError;
-                use crate::error::StompParseError;
-                use nom::combinator::map_res;
-                use nom::error::context;
-                use nom::error::VerboseError;
-                use nom::sequence::tuple;
                  $(
-                    frame_parser! (
+                    $crate::frame_parser!(
                         $name,
                         $group_name
                         $(, $header_name : $header_type )*
@@ -280,23 +867,23 @@ macro_rules! frames {
                     );
                 )+
 
-                pub fn [<$group_name:lower _frame>](input: Vec<u8>) -> Result<[<$group_name Frame>], StompParseError>
+                pub fn [<$group_name:lower _frame>]<S: $crate::macro_support::Storage>(input: S, config: &$crate::macro_support::ParserConfig) -> Result<[<$group_name Frame>]<S>, $crate::macro_support::StompParseError>
                 {
-                    let slice = input.as_slice();
+                    let slice = input.as_ref();
 
-                    let (_,command_string) = command_line::<VerboseError<&[u8]>, StompParseError>(slice).map_err(|_|StompParseError::new("Error parsing frame"))?;
+                    let (_,command_string) = $crate::macro_support::command_line::<$crate::macro_support::VerboseError<&[u8]>, $crate::macro_support::StompParseError>(slice).map_err(|_| $crate::macro_support::command_error(slice))?;
 
-                    let initialiser: Box<dyn FnOnce(Vec<u8>)-> [<$group_name Frame>]> = std::str::from_utf8(command_string)
-                        .map_err(|_|StompParseError::new("badly formed command string, not utf8"))
+                    let initialiser: fn(S) -> [<$group_name Frame>]<S> = std::str::from_utf8(command_string)
+                        .map_err(|_|$crate::macro_support::StompParseError::new("badly formed command string, not utf8"))
                         .and_then(move |command_string| match command_string {
                             $(
 
-                                stringify!($command) => Ok(Box::new(|input|[<$group_name Frame>]::$name([<$name Frame>]::init(input))) as Box<dyn FnOnce(Vec<u8>)-> [<$group_name Frame>]>),
+                                stringify!($command) => Ok((|input|[<$group_name Frame>]::$name([<$name Frame>]::init(input))) as fn(S) -> [<$group_name Frame>]<S>),
                                 $(
-                                    stringify!($alias) => Ok(Box::new(|input|[<$group_name Frame>]::$name([<$name Frame>]::init(input)))),
+                                    stringify!($alias) => Ok((|input|[<$group_name Frame>]::$name([<$name Frame>]::init(input))) as fn(S) -> [<$group_name Frame>]<S>),
                                 )*
                             )+
-                            _ => Err(StompParseError::new(format!("Unknown command {}", command_string)))
+                            _ => Err($crate::macro_support::unknown_command(command_string))
                         })?;
 
                     let frame = initialiser(input);
@@ -304,7 +891,7 @@ macro_rules! frames {
                     match frame {
                         $(
                         [<$group_name Frame>]::$name(inner) =>  {
-                            [<$name:lower _frame>]::<VerboseError<&[u8]>>(inner)
+                            [<$name:lower _frame>](inner, config)
                         }
                         )+
                     }
@@ -5,6 +5,8 @@ mod macros;
 
 mod utils;
 
+pub mod any;
+
 #[allow(non_snake_case)]
 #[allow(unused_parens)]
 #[allow(clippy::new_without_default)]
@@ -108,7 +110,8 @@ pub mod client {
             UNSUBSCRIBE,
             Client,
             id: Id,
-            (receipt: Receipt)
+            (receipt: Receipt),
+            [custom: cus]
         )
     }
 
@@ -121,7 +124,6 @@ pub mod client {
 pub mod server {
     //! Implements the model for the frames that a STOMP server can send, as specified in the
     //! [STOMP Protocol Specification,Version 1.2](https://stomp.github.io/stomp-specification-1.2.html).
-    use crate::model::headers::*;
     frames! {
         Server,
         (
@@ -132,13 +134,15 @@ pub mod server {
             (
                 heartbeat: HeartBeat,
                 session: Session, server: Server
-            )
+            ),
+            [custom: cus]
         ),
         (
             Receipt,
             RECEIPT,
             Server,
-            receipt_id: ReceiptId
+            receipt_id: ReceiptId,
+            [custom: cus]
         ),
         (
             Error,
@@ -163,10 +167,40 @@ pub mod server {
         )
     }
 
+    /// The number of bytes of the offending input included in the body of an ERROR frame built by
+    /// `ErrorFrame::from_parse_error`.
+    pub const ERROR_EXCERPT_LEN: usize = 256;
+
     impl<'a> ErrorFrame<'a> {
         pub fn from_message(message: &str) -> Self {
             ErrorFrameBuilder::new().message(message.to_owned()).build()
         }
+
+        /// Builds the ERROR frame to send when `input` could not be parsed: the `message` header
+        /// summarises `error`, and the plain text body repeats it, followed by an excerpt of at
+        /// most `ERROR_EXCERPT_LEN` bytes of `input`, in which any bytes other than printable ASCII
+        /// and line feeds are escaped.
+        pub fn from_parse_error(error: &crate::error::StompParseError, input: &[u8]) -> Self {
+            let shown = &input[..input.len().min(ERROR_EXCERPT_LEN)];
+
+            let mut body = format!("{}\n\nThe frame:\n-----\n", error.message());
+            for byte in shown {
+                match byte {
+                    b'\n' | b' '..=b'~' => body.push(*byte as char),
+                    byte => body.push_str(&format!("\\x{:02x}", byte)),
+                }
+            }
+            if input.len() > shown.len() {
+                body.push_str(&format!("\n... ({} more bytes)", input.len() - shown.len()));
+            }
+            body.push_str("\n-----\n");
+
+            ErrorFrameBuilder::new()
+                .message(error.message().to_owned())
+                .add_custom_header("content-type".to_owned(), "text/plain".to_owned())
+                .body(body.into_bytes())
+                .build()
+        }
     }
 }
 
@@ -176,9 +210,9 @@ mod test {
     use super::client::*;
     use super::server::*;
 
+    use crate::error::BuildError;
     use crate::model::headers::*;
     use std::convert::TryFrom;
-    use std::convert::TryInto;
     use std::thread;
 
     #[test]
@@ -241,6 +275,24 @@ mod test {
         assert_eq!("rcpt-1", frame.receipt_id().value());
     }
 
+    #[test]
+    fn server_frames_retain_custom_headers() {
+        let bytes = b"CONNECTED\nversion:1.2\nx-broker-node:b2\n\n\x00".to_vec();
+        let Ok(ServerFrame::Connected(frame)) = ServerFrame::try_from(bytes.clone()) else {
+            panic!("Connected Frame not parsed correctly")
+        };
+        assert_eq!("b2", *frame.custom_header("x-broker-node").unwrap().value());
+        assert_eq!(bytes, Vec::<u8>::from(frame));
+
+        let frame = ReceiptFrameBuilder::new("r")
+            .add_custom_header("x-latency", "3")
+            .build();
+        let Ok(ServerFrame::Receipt(frame)) = ServerFrame::try_from(Vec::<u8>::from(frame)) else {
+            panic!("Receipt Frame not parsed correctly")
+        };
+        assert_eq!("3", *frame.custom_header("x-latency").unwrap().value());
+    }
+
     #[test]
     fn writes_message_frame() {
         let body = b"Lorem ipsum dolor sit amet,".to_vec();
@@ -292,13 +344,14 @@ mod test {
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn assert_message_frame_roundtrip(
         frame: MessageFrame,
         expected_id: &str,
         expected_dest: &str,
         expected_sub: &str,
         expected_content_type: Option<&str>,
-        expected_content_length: Option<u32>,
+        expected_content_length: Option<usize>,
         expected_custom: &Vec<(&str, &str)>,
         expected_body: Option<&[u8]>,
     ) {
@@ -313,7 +366,7 @@ mod test {
             expected_body,
         );
 
-        let bytes: Vec<u8> = frame.try_into().expect("Error writing bytes");
+        let bytes: Vec<u8> = frame.into();
 
         if let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(bytes) {
             assert_message_frame(
@@ -331,13 +384,14 @@ mod test {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn assert_message_frame(
         frame: &MessageFrame,
         expected_id: &str,
         expected_dest: &str,
         expected_sub: &str,
         expected_content_type: Option<&str>,
-        expected_content_length: Option<u32>,
+        expected_content_length: Option<usize>,
         expected_custom: &Vec<(&str, &str)>,
         expected_body: Option<&[u8]>,
     ) {
@@ -370,7 +424,7 @@ mod test {
         expected_custom.iter().for_each(|(name, value)| {
             assert!(
                 frame
-                    .custom
+                    .custom_headers()
                     .iter()
                     .any(|custom_value| custom_value.header_name() == *name
                         && custom_value.value() == value),
@@ -452,11 +506,15 @@ mod test {
 
         assert_in_range(source_ptr, source_len, frame.body().unwrap().as_ptr());
         assert_in_range(source_ptr, source_len, frame.destination().value().as_ptr());
-        assert_in_range(source_ptr, source_len, frame.custom[0].value().as_ptr());
         assert_in_range(
             source_ptr,
             source_len,
-            frame.custom[0].header_name().as_ptr(),
+            frame.custom_headers()[0].value().as_ptr(),
+        );
+        assert_in_range(
+            source_ptr,
+            source_len,
+            frame.custom_headers()[0].header_name().as_ptr(),
         );
     }
 
@@ -483,7 +541,7 @@ mod test {
             );
 
             assert_eq!("stairway/to/heaven", frame.destination().value());
-            return frame.body().unwrap().as_ptr() as u64;
+            frame.body().unwrap().as_ptr() as u64
         });
 
         let Ok(address) = handle.join() else {
@@ -498,6 +556,42 @@ mod test {
         );
     }
 
+    #[test]
+    fn approximates_heap_size() {
+        let small = ClientFrame::try_from(b"SEND\ndestination:a\n\n\x00".to_vec()).unwrap();
+        let large = ClientFrame::try_from(
+            b"SEND\ndestination:a\nx:1\nx:2\ny:3\n\nLorem ipsum dolor sit amet\x00".to_vec(),
+        )
+        .unwrap();
+
+        assert!(small.approximate_heap_size() >= small.as_bytes().len());
+        assert!(large.approximate_heap_size() > large.as_bytes().len());
+        assert!(large.approximate_heap_size() > small.approximate_heap_size());
+    }
+
+    #[test]
+    fn can_be_shared_between_threads() {
+        let message = b"SEND\n\
+            destination:stairway/to/heaven\n\
+            \n\
+            Lorem ipsum dolor sit amet,...\x00"
+            .to_vec();
+
+        let frame = ClientFrame::try_from(message).unwrap();
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let ClientFrame::Send(frame) = &frame else {
+                        panic!("Send Frame not parsed correctly")
+                    };
+                    assert_eq!("stairway/to/heaven", frame.destination().value());
+                    assert_eq!(b"Lorem ipsum dolor sit amet,...", frame.body().unwrap());
+                });
+            }
+        });
+    }
+
     #[test]
     fn parses_binary_send_frame() {
         let message = b"SEND\n\
@@ -513,4 +607,250 @@ mod test {
 
         assert_eq!(&[0u8, 1, 1, 2, 3, 5, 8, 13], frame.body().unwrap());
     }
+
+    #[test]
+    fn builds_error_frame_from_parse_error() {
+        let input = b"SEND\ndestination:a\nbad\x01header\n\n\x00".to_vec();
+        let error = ClientFrame::try_from(input.clone()).unwrap_err();
+
+        let frame = ErrorFrame::from_parse_error(&error, &input);
+        let bytes: Vec<u8> = frame.into();
+
+        let Ok(ServerFrame::Error(frame)) = ServerFrame::try_from(bytes) else {
+            panic!("Error Frame not parsed correctly")
+        };
+
+        let body = std::str::from_utf8(frame.body().unwrap()).unwrap();
+        assert!(body.starts_with(error.message()));
+        assert!(body.contains("destination:a\nbad\\x01header\n\n\\x00"));
+        assert_eq!(
+            "text/plain",
+            *frame.custom_header("content-type").unwrap().value()
+        );
+    }
+
+    #[test]
+    fn truncates_excerpt_of_offending_input() {
+        let input = vec![b'x'; ERROR_EXCERPT_LEN + 10];
+        let frame =
+            ErrorFrame::from_parse_error(&crate::error::StompParseError::new("a: b"), &input);
+
+        assert_eq!("a\\c b", frame.message().unwrap().value());
+        let body = std::str::from_utf8(frame.body().unwrap()).unwrap();
+        assert!(body.contains("... (10 more bytes)"));
+    }
+
+    #[test]
+    fn distinguishes_empty_body_from_no_body() {
+        let Ok(ClientFrame::Send(frame)) =
+            ClientFrame::try_from(b"SEND\ndestination:a\n\n\x00".to_vec())
+        else {
+            panic!("Send Frame not parsed correctly")
+        };
+        assert!(!frame.has_body());
+        assert_eq!(None, frame.body());
+
+        let Ok(ClientFrame::Send(frame)) =
+            ClientFrame::try_from(b"SEND\ndestination:a\ncontent-length:0\n\n\x00".to_vec())
+        else {
+            panic!("Send Frame not parsed correctly")
+        };
+        assert_eq!(Some(&b""[..]), frame.body());
+    }
+
+    #[test]
+    fn writes_content_length_for_empty_body() {
+        let bytes: Vec<u8> = SendFrameBuilder::new("a".to_owned()).build().into();
+        assert_eq!(b"SEND\ndestination:a\n\n\x00".to_vec(), bytes);
+
+        let frame = SendFrameBuilder::new("a".to_owned())
+            .body(Vec::new())
+            .build();
+        assert_eq!(
+            Some(0),
+            frame.content_length().map(|length| *length.value())
+        );
+        assert!(frame.has_body());
+
+        let bytes: Vec<u8> = ErrorFrameBuilder::new().body(Vec::new()).build().into();
+        assert_eq!(b"ERROR\ncontent-length:0\n\n\x00".to_vec(), bytes);
+    }
+
+    #[test]
+    fn builds_frames_inline() {
+        let frame = SubscribeFrameBuilder::new("/queue/a".to_owned(), "0".to_owned())
+            .destination("/queue/b".to_owned())
+            .id("1".to_owned())
+            .ack_type(AckType::Client)
+            .build();
+
+        assert_eq!("/queue/b", frame.destination().value());
+        assert_eq!("1", frame.id().value());
+        assert_eq!(&AckType::Client, frame.ack_type().value());
+    }
+
+    #[test]
+    fn visitors_handle_each_kind_of_frame() {
+        struct Router(Vec<String>);
+
+        impl ClientFrameVisitor for Router {
+            type Output = usize;
+
+            fn visit_other(&mut self, command: &'static str) -> usize {
+                self.0.push(format!("ignored {}", command));
+                0
+            }
+
+            fn visit_send(&mut self, frame: &SendFrame<'static>) -> usize {
+                self.0
+                    .push(format!("routed to {}", frame.destination().value()));
+                1
+            }
+        }
+
+        let mut router = Router(Vec::new());
+        let routed: usize = [
+            &b"SEND\ndestination:a\n\n\x00"[..],
+            b"BEGIN\ntransaction:t\n\n\x00",
+        ]
+        .iter()
+        .map(|bytes| {
+            ClientFrame::try_from(bytes.to_vec())
+                .unwrap()
+                .accept(&mut router)
+        })
+        .sum();
+
+        assert_eq!(1, routed);
+        assert_eq!(vec!["routed to a", "ignored BEGIN"], router.0);
+    }
+
+    #[test]
+    fn frame_enums_identify_and_extract_frames() {
+        let frame = ClientFrame::try_from(b"SEND\ndestination:a\n\n\x00".to_vec()).unwrap();
+
+        assert!(frame.is_send());
+        assert!(!frame.is_subscribe());
+        assert_eq!("a", frame.as_send().unwrap().destination().value());
+        assert!(frame.as_ack().is_none());
+
+        let frame = frame.into_begin().unwrap_err();
+        assert_eq!("a", frame.into_send().unwrap().destination().value());
+
+        let frame = ServerFrame::try_from(b"RECEIPT\nreceipt-id:1\n\n\x00".to_vec()).unwrap();
+        assert!(frame.is_receipt() && !frame.is_connected());
+    }
+
+    #[test]
+    fn builders_accept_string_like_values() {
+        let id = String::from("1");
+        let frame = SubscribeFrameBuilder::new("/queue/a", &id)
+            .receipt(std::borrow::Cow::Borrowed("r"))
+            .add_custom_header("x", String::from("y"))
+            .build();
+
+        assert_eq!("/queue/a", frame.destination().value());
+        assert_eq!("1", frame.id().value());
+        assert_eq!("r", frame.receipt().unwrap().value());
+        assert_eq!("y", *frame.custom_header("x").unwrap().value());
+
+        let frame = SendFrameBuilder::new("a").content_length(3).build();
+        assert_eq!(3, *frame.content_length().unwrap().value());
+    }
+
+    #[test]
+    fn try_setters_validate_immediately() {
+        let frame = ConnectFrameBuilder::new("h".to_owned(), StompVersions(vec![]))
+            .try_accept_version("1.1,1.2")
+            .and_then(|builder| builder.try_heartbeat("1000,0"))
+            .unwrap()
+            .build();
+        assert_eq!(
+            &StompVersions(vec![StompVersion::V1_1, StompVersion::V1_2]),
+            frame.accept_version().value()
+        );
+        assert_eq!(1000, frame.heartbeat().value().supplied);
+
+        let builder = || SendFrameBuilder::new("a".to_owned());
+        assert!(builder().try_destination("").is_err());
+        assert!(builder().try_destination("a\0b").is_err());
+        assert!(builder().try_content_length("-1").is_err());
+        assert!(builder().try_add_custom_header("", "v").is_err());
+        assert!(builder().try_destination("/queue/a\nlogin:admin").is_err());
+        assert!(builder()
+            .try_add_custom_header("x", "1\r\nlogin:admin")
+            .is_err());
+        assert!(builder()
+            .try_add_custom_header("x\nlogin", "admin")
+            .is_err());
+        assert_eq!(
+            Some(BuildError::InvalidHeaderName {
+                name: "login:admin".to_owned()
+            }),
+            builder().try_add_custom_header("login:admin", "x").err()
+        );
+
+        let error = ConnectFrameBuilder::new("h".to_owned(), StompVersions(vec![]))
+            .try_heartbeat("99999999999,0")
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            BuildError::InvalidHeaderValue { ref name, .. } if name == "heart-beat"
+        ));
+    }
+
+    #[test]
+    fn built_frames_always_parse() {
+        let text = "a\nb:c\\d";
+        let frame = SendFrameBuilder::new(text.to_owned())
+            .add_custom_header(text, text)
+            .add_custom_header("x", "1\x002")
+            .build();
+
+        let Ok(ClientFrame::Send(parsed)) = ClientFrame::try_from(frame.to_bytes()) else {
+            panic!("Built frame not parsed")
+        };
+        let custom = parsed.custom_headers();
+        assert_eq!(
+            text,
+            parsed.destination().decoded_value().unwrap().to_string()
+        );
+        assert_eq!(text, custom[0].decoded_name().unwrap().to_string());
+        assert_eq!(text, custom[0].decoded_value().unwrap().to_string());
+        assert_eq!("1\u{fffd}2", *custom[1].value());
+
+        let frame = ConnectFrameBuilder::new(
+            "h\nlogin:admin".to_owned(),
+            StompVersions(vec![StompVersion::V1_2]),
+        )
+        .build();
+        let Ok(ClientFrame::Connect(parsed)) = ClientFrame::try_from(frame.to_bytes()) else {
+            panic!("Built frame not parsed")
+        };
+        assert_eq!("h\u{fffd}login\u{fffd}admin", parsed.host().value());
+        assert_eq!(None, parsed.login());
+    }
+
+    #[test]
+    fn parses_frames_from_str() {
+        let frame: ClientFrame = "SEND\ndestination:a\n\nhi\0".parse().unwrap();
+        assert!(frame.is_send());
+
+        let frame = ServerFrame::try_from("RECEIPT\nreceipt-id:1\n\n\0").unwrap();
+        assert!(frame.is_receipt());
+
+        assert!("SEND\n\n\0".parse::<ClientFrame>().is_err());
+    }
+
+    #[test]
+    fn serialises_without_consuming() {
+        let frame = SendFrameBuilder::new("a".to_owned())
+            .body(b"hi".to_vec())
+            .build();
+        let bytes = frame.to_bytes();
+
+        assert_eq!(frame.as_bytes(), &bytes[..]);
+        assert_eq!(bytes, ClientFrame::Send(frame).to_bytes());
+    }
 }
@@ -1,7 +1,9 @@
+#[doc(hidden)]
+#[macro_export]
 macro_rules! sender_frame {
     ( $name:ident,  $($comment:literal,)? $command:ident, $origin:ident $(, $header_name:ident : $header_type:ident )* $(,( $(  $opt_header_name:ident : $opt_header_type:ident $(: $opt_header_default:tt $(: $opt_header_default_comment:literal)?)?  ),* ))? $(,[custom: $has_custom:ident])? $(,[body: $has_body:ident])?  $(,$long_comment:literal)*) => {
 
-        paste::paste! {
+        $crate::macro_support::paste::paste! {
             $(#[doc = ""$comment]
             #[doc = ""])?
             #[doc = "This frame has required headers "$("`"$header_name"`")","* $(" and optional headers " $("`"$opt_header_name"`")","* )?"."]
@@ -9,10 +11,10 @@ macro_rules! sender_frame {
             #[doc = ""$long_comment])?
             pub struct [<$name Builder>] {
                 $(
-                    $header_name: <[<$header_type Value>]<'static> as HeaderValue>::OwnedValue,
+                    $header_name: <$crate::macro_support::[<$header_type Value>]<'static> as $crate::macro_support::HeaderValue>::OwnedValue,
                 )*
                 $($(
-                    $opt_header_name: Option<<[<$opt_header_type Value>]<'static> as HeaderValue>::OwnedValue>,
+                    $opt_header_name: Option<<$crate::macro_support::[<$opt_header_type Value>]<'static> as $crate::macro_support::HeaderValue>::OwnedValue>,
                 )*)?
                 $(
                     #[doc(hidden)]
@@ -30,47 +32,159 @@ macro_rules! sender_frame {
                 $($(
                     #[doc = "The value of the `"$opt_header_name"` header."]
                     $($(#[doc = "Defaults to `"$opt_header_default_comment"` if not supplied."])?)?
-                    pub fn $opt_header_name(mut self, new_val: <[<$opt_header_type Value>] as HeaderValue>::OwnedValue) -> [<$name Builder>] {
-                        self.$opt_header_name = Some(new_val);
+                    pub fn $opt_header_name(mut self, new_val: impl $crate::macro_support::IntoOwnedValue<<$crate::macro_support::[<$opt_header_type Value>]<'static> as $crate::macro_support::HeaderValue>::OwnedValue>) -> [<$name Builder>] {
+                        self.$opt_header_name = Some(new_val.into_owned_value());
 
                         self
                     }
+
+                    #[doc = "Sets the `"$opt_header_name"` header to `value` as it would appear in a frame, failing immediately if the value is empty, contains a null octet or EOL, or cannot be parsed."]
+                    pub fn [<try_ $opt_header_name>](mut self, value: &str) -> Result<[<$name Builder>], $crate::error::BuildError> {
+                        self.$opt_header_name = Some($crate::macro_support::parse_builder_value($crate::macro_support::[<$opt_header_type Value>]::NAME, value)?);
+                        Ok(self)
+                    }
                 )*)?
+                $(
+                    #[doc = "Replaces the `"$header_name"` header, which was set by `new`."]
+                    pub fn $header_name(mut self, new_val: impl $crate::macro_support::IntoOwnedValue<<$crate::macro_support::[<$header_type Value>]<'static> as $crate::macro_support::HeaderValue>::OwnedValue>) -> [<$name Builder>] {
+                        self.$header_name = new_val.into_owned_value();
+                        self
+                    }
+
+                    #[doc = "Replaces the `"$header_name"` header with `value` as it would appear in a frame, failing immediately if the value is empty, contains a null octet or EOL, or cannot be parsed."]
+                    pub fn [<try_ $header_name>](mut self, value: &str) -> Result<[<$name Builder>], $crate::error::BuildError> {
+                        self.$header_name = $crate::macro_support::parse_builder_value($crate::macro_support::[<$header_type Value>]::NAME, value)?;
+                        Ok(self)
+                    }
+                )*
                 $(
                     #[doc = "Useseless doc: `"$has_custom"`."]
-                    pub fn add_custom_header(mut self, name: String, value: String) -> [<$name Builder>] {
-                        self.custom.push((name, value));
+                    pub fn add_custom_header(mut self, name: impl Into<String>, value: impl Into<String>) -> [<$name Builder>] {
+                        self.custom.push((name.into(), value.into()));
                         self
                     }
+
+                    /// Like `add_custom_header`, but failing immediately if the name or value is empty or
+                    /// contains a null octet or EOL, or if the name contains a colon.
+                    pub fn try_add_custom_header(self, name: &str, value: &str) -> Result<[<$name Builder>], $crate::error::BuildError> {
+                        let value: String = $crate::macro_support::parse_builder_value(name, value)?;
+                        Ok(self.add_custom_header(name.to_owned(), value))
+                    }
+
+                    /// Like `add_custom_header`, but replacing any custom headers with the same name.
+                    pub fn set_custom_header(mut self, name: impl Into<String>, value: impl Into<String>) -> [<$name Builder>] {
+                        let name = name.into();
+                        self.custom.retain(|(existing, _)| *existing != name);
+                        self.custom.push((name, value.into()));
+                        self
+                    }
+
+                    /// Sets the custom header `H` to the specified value, replacing any previous value.
+                    pub fn typed_custom_header<H: $crate::extensions::typed::TypedCustomHeader>(self, header: H) -> [<$name Builder>] {
+                        self.set_custom_header(H::NAME, header.to_value())
+                    }
                 )?
+                $crate::base64_body_builder!([<$name Builder>] $(, $has_custom)? $(, $has_body)?);
+                $crate::compressed_body_builder!([<$name Builder>] $(, $has_custom)? $(, $has_body)?; $($($opt_header_name : $opt_header_type),*)?);
+
                 $(
                     #[doc = "Useseless doc: `"$has_body"`."]
                     pub fn body(mut self, new_value: Vec<u8>) -> [<$name Builder>] {
                         self.body = Some(new_value);
                         self
                     }
+
+                    /// Builds the serialised frame with the body held separately from the headers,
+                    /// so that it can be written with vectored IO without copying the body.
+                    pub fn build_vectored(mut self) -> $crate::vectored::VectoredFrame {
+                        $crate::blank!($has_body);
+                        let body = self.body.take().unwrap_or_default();
+
+                        self.build_with_body(body)
+                    }
+
+                    /// Like `build_vectored`, but using the provided body, which may be shared
+                    /// between frames (e.g. `Arc<[u8]>` or `bytes::Bytes`), in place of any body
+                    /// previously set.
+                    pub fn build_with_body<B: AsRef<[u8]>>(mut self, body: B) -> $crate::vectored::VectoredFrame<B> {
+                        $crate::blank!($has_body);
+                        self.body = None;
+
+                        let mut head: Vec<u8> = self.build().into();
+                        // Remove the terminating null octet, which follows the body
+                        head.pop();
+
+                        $crate::macro_support::new_vectored_frame(head, body)
+                    }
+
+                    /// Writes the command and headers to `writer`, with a `content-length` of
+                    /// `content_length` in place of any previously set, returning an encoder to which
+                    /// the body is then written. Any body previously set is not written.
+                    pub fn encode_head<W: std::io::Write>(mut self, content_length: usize, mut writer: W) -> Result<$crate::encoder::BodyEncoder<W>, $crate::error::EncodeError> {
+                        $crate::blank!($has_body);
+                        self.body = None;
+                        self.set_content_length(content_length);
+
+                        let mut head: Vec<u8> = self.build().into();
+                        // Remove the terminating null octet, which follows the body
+                        head.pop();
+                        writer.write_all(&head)?;
+
+                        Ok($crate::macro_support::new_body_encoder(writer, content_length))
+                    }
                 )?
 
+                /// Sets the `content-length` header, as a custom header if the frame has no
+                /// `content-length` field.
+                #[allow(dead_code, unused_mut, unused_variables, unused_assignments)]
+                fn set_content_length(&mut self, content_length: usize) {
+                    let mut set = false;
+                    $($(
+                        if $crate::macro_support::[<$opt_header_type Value>]::NAME == "content-length" {
+                            self.$opt_header_name = $crate::macro_support::FromHeaderValue::from_header_value(&content_length.to_string()).ok();
+                            set = true;
+                        }
+                    )*)?
+                    $(
+                        $crate::blank!($has_custom);
+                        self.custom.retain(|(name, _)| name != "content-length");
+                        if !set {
+                            self.custom.push(("content-length".to_owned(), content_length.to_string()));
+                        }
+                    )?
+                }
+
                 pub fn new($(
-                            $header_name: <[<$header_type Value>]<'static> as HeaderValue>::OwnedValue,
+                            $header_name: impl $crate::macro_support::IntoOwnedValue<<$crate::macro_support::[<$header_type Value>]<'static> as $crate::macro_support::HeaderValue>::OwnedValue>,
                         )*) -> [<$name Builder>] {
                     [<$name Builder>] {
                         $(
-                            $header_name,
+                            $header_name: $header_name.into_owned_value(),
                         )*
                         $($(
-                            $opt_header_name: choose_from_presence!($($opt_header_default)? {Some($($opt_header_default)?().into())},{None}),
+                            $opt_header_name: $crate::choose_from_presence!($($opt_header_default)? {Some($($opt_header_default)?().into())},{None}),
                         )*)?
                         $(
-                            custom: choose_from_presence!($has_custom {Vec::new()}, {Vec::new()}),
+                            custom: $crate::choose_from_presence!($has_custom {Vec::new()}, {Vec::new()}),
                         )?
                         $(
-                            body: choose_from_presence!($has_body None, None),
+                            body: $crate::choose_from_presence!($has_body None, None),
                         )?
                     }
                 }
 
-                #[allow(unused_mut)]
+                /// A template for frames with the headers set so far, followed by the headers named in
+                /// `variable_headers`, whose values, along with the body, are supplied when the
+                /// template is rendered.
+                pub fn template(self, variable_headers: &[&str]) -> $crate::template::FrameTemplate {
+                    $crate::template::FrameTemplate::new(self.build().as_bytes(), variable_headers)
+                        .expect("built frames are complete")
+                }
+
+                /// Builds the frame. Header names and values are escaped as the specification requires,
+                /// and characters which cannot be written, such as null octets, are replaced by U+FFFD,
+                /// so that the frame always parses; the `try_` setters reject such values instead.
+                #[allow(unused_mut, unused_variables, unused_assignments)]
                 pub fn build(mut self) -> $name<'static> {
                     // First, build the byte array
                     let mut bytes : Vec<u8> = Vec::with_capacity(1000);
@@ -78,108 +192,73 @@ macro_rules! sender_frame {
 
                     let mut frame = $name::init(Vec::new());
 
-                    write_command(bytes_ref, $name::NAME);
-
-                    $(
-                        // Write the required header, returning an error if the value was not set
-                        let (_,[<$header_name _range>]) = if [<$header_type Value>]::OWNED {
-                            // Owned values are already in the right form for the frame, but also need to be written to the
-                            // output buffer
-                            let mut bytes = self.[<$header_name>].to_string().into_bytes();
-                            frame.$header_name = [<$header_type Value>]::from_owned(self.[<$header_name>]);
-                            write_header(bytes_ref, [<$header_type Value>]::NAME, &mut bytes)
-                        } else {
-                            // Non-owned values strings; the value for the header on the frame needs to be in the byte buffer
-                            let mut bytes = self.[<$header_name>].to_string().into_bytes();
-                            write_header(bytes_ref, [<$header_type Value>]::NAME, &mut bytes)
-                        };
-                    )*
+                    $crate::macro_support::write_command(bytes_ref, $name::NAME);
 
+                    // An empty body is distinguished from no body by a content-length of 0
+                    let mut needs_content_length = $crate::choose_from_presence!($($has_body)? {self.body.as_ref().map_or(false, Vec::is_empty)}, {false});
                     $($(
-                        // Write the required header, returning an error if the value was not set
-                        let [<$opt_header_name _range>] = if [<$opt_header_type Value>]::OWNED {
-                            // Owned values are already in the right form for the frame, but also need to be written to the
-                            // output buffer
-                            self.[<$opt_header_name>].take().map(|value| {
-                                let mut bytes = value.to_string().into_bytes();
-                                 choose_from_presence!($($opt_header_default)? {
-                                    frame.$opt_header_name = [<$opt_header_type Value>]::from_owned(value);
-                                }, {
-                                    frame.$opt_header_name = Some([<$opt_header_type Value>]::from_owned(value));
-                                });
-                                write_header(bytes_ref, [<$opt_header_type Value>]::NAME, &mut bytes)
-                            })
-                        } else {
-                            // Non-owned values strings; the value for the header on the frame needs to be in the byte buffer
-                            self.[<$opt_header_name>].take().map(|value| {
-                                let mut bytes = value.to_string().into_bytes();
-                                write_header(bytes_ref, [<$opt_header_type Value>]::NAME, &mut bytes)
-                            })
-                        };
+                        if needs_content_length && $crate::macro_support::[<$opt_header_type Value>]::NAME == "content-length" {
+                            if self.$opt_header_name.is_none() {
+                                self.$opt_header_name = $crate::macro_support::FromHeaderValue::from_header_value("0").ok();
+                            }
+                            needs_content_length = false;
+                        }
                     )*)?
-
-                    $(
-                        let $has_custom : Vec<((usize, usize),(usize,usize))> = self.custom.iter().map(|(name, value)| {
-                             let mut bytes = value.to_string().into_bytes();
-                             write_header(bytes_ref, &name, &mut bytes)
-                        }).collect();
-                    )?
-
-                    // End the headers
-                    write_headers_end(bytes_ref);
-
                     $(
-                    let mut [<_ $has_body>] = ();
-
-                    let body_range = self.body.take().as_mut().map(|body| write_body(bytes_ref, body));
+                        $crate::blank!($has_custom);
+                        if needs_content_length && !self.custom.iter().any(|(name, _)| name == "content-length") {
+                            self.custom.push(("content-length".to_owned(), "0".to_owned()));
+                        }
                     )?
 
-                    // End the frame
-                    write_frame_end(bytes_ref);
-
-                    let ptr : *const [u8] = bytes.as_slice();
-                    let slice = unsafe { ptr.as_ref().unwrap() };
-
-                    frame.raw = bytes;
+                    // The headers of CONNECT and CONNECTED frames are not escaped
+                    let escape = !matches!($name::NAME, "CONNECT" | "CONNECTED");
+                    let encode = |text: &str| $crate::macro_support::encode_header_text(text, escape).into_bytes();
 
                     $(
-                        if ![<$header_type Value>]::OWNED {
-                            let value = unsafe { std::str::from_utf8_unchecked(&slice[[<$header_name _range>].0..[<$header_name _range>].1]) };
-                            frame.$header_name = [<$header_type Value>]::from_str(value).expect("Should never fail because string valued");
-                        }
+                        let mut value_bytes = encode(&$crate::headers::ToHeaderValue::to_header_value(&self.$header_name));
+                        let (_, range) = $crate::macro_support::write_header(bytes_ref, $crate::macro_support::[<$header_type Value>]::NAME, &mut value_bytes);
+                        frame.$header_name = $crate::macro_support::[<$header_type Value>]::stored_from_owned(self.$header_name, $crate::macro_support::Span::new(range.0, range.1));
                     )*
 
                     $($(
-                        if let Some((_,[<$opt_header_name _range>])) = [<$opt_header_name _range>] {
-                            if ![<$opt_header_type Value>]::OWNED {
-                                let value = unsafe { std::str::from_utf8_unchecked(&slice[[<$opt_header_name _range>].0..[<$opt_header_name _range>].1]) };
-                                choose_from_presence!($($opt_header_default)? {
-                                    frame.$opt_header_name = [<$opt_header_type Value>]::from_str(value).expect("Should never fail because string valued");
-                                }, {
-                                    frame.$opt_header_name = Some([<$opt_header_type Value>]::from_str(value).expect("Should never fail because string valued"));
-                                });
-                            }
-                        };
+                        if let Some(value) = self.$opt_header_name.take() {
+                            let mut value_bytes = encode(&$crate::headers::ToHeaderValue::to_header_value(&value));
+                            let (_, range) = $crate::macro_support::write_header(bytes_ref, $crate::macro_support::[<$opt_header_type Value>]::NAME, &mut value_bytes);
+                            let stored = $crate::macro_support::[<$opt_header_type Value>]::stored_from_owned(value, $crate::macro_support::Span::new(range.0, range.1));
+                            $crate::choose_from_presence!($($opt_header_default)? {
+                                frame.$opt_header_name = stored;
+                            }, {
+                                frame.$opt_header_name = Some(stored);
+                            });
+                        }
                     )*)?
 
                     $(
-                        frame.custom = $has_custom.iter().map(|ranges| {
-                            let name = unsafe { std::str::from_utf8_unchecked(&slice[ranges.0.0..ranges.0.1]) };
-                            let value = unsafe { std::str::from_utf8_unchecked(&slice[ranges.1.0..ranges.1.1]) };
-
-                            CustomValue::new(name, value)
+                        $crate::blank!($has_custom);
+                        frame.custom = self.custom.iter().map(|(name, value)| {
+                            let mut value_bytes = encode(value);
+                            let name = $crate::macro_support::encode_header_text(name, escape);
+                            let (name_range, value_range) = $crate::macro_support::write_header(bytes_ref, &name, &mut value_bytes);
+                            ($crate::macro_support::StoredStr::Span($crate::macro_support::Span::new(name_range.0, name_range.1)), $crate::macro_support::StoredStr::Span($crate::macro_support::Span::new(value_range.0, value_range.1)))
                         }).collect();
                     )?
 
+                    // End the headers
+                    $crate::macro_support::write_headers_end(bytes_ref);
 
                     $(
-                        [<_ $has_body>] = ();
-                        body_range.iter().for_each(|body_range|{
-                            frame.body = &slice[body_range.0..body_range.1]
+                        frame.$has_body = self.body.take().as_mut().map(|body| {
+                            let range = $crate::macro_support::write_body(bytes_ref, body);
+                            $crate::macro_support::Span::new(range.0, range.1)
                         });
-
                     )?
 
+                    // End the frame
+                    $crate::macro_support::write_frame_end(bytes_ref);
+
+                    frame.raw = bytes;
+
                     frame
                 }
             }
@@ -1,24 +1,33 @@
+#[doc(hidden)]
+#[macro_export]
 macro_rules! header_display {
     ( ) => {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-            write!(f, "{}:{}", self.header_name(), self.value)
+            write!(
+                f,
+                "{}:{}",
+                $crate::macro_support::HeaderValue::header_name(self),
+                self.value
+            )
         }
     };
 }
+#[doc(hidden)]
+#[macro_export]
 macro_rules! header {
     ( $header:ident, $name:expr $(,$types:ty $(, $default:expr )?)? ) => {
-        paste! {
+        $crate::macro_support::paste::paste! {
 
                 #[derive(Eq, PartialEq, Clone)]
                 pub struct [<$header Value>]<'a> {
-                    value: or_else_type!($($types)?,&'a str),
-                    phantom: std::marker::PhantomData<&'a or_else_type!($($types)?, str)>,
+                    value: $crate::or_else_type!($($types)?,&'a str),
+                    phantom: std::marker::PhantomData<&'a $crate::or_else_type!($($types)?, str)>,
                 }
 
                 impl <'a> Default for [<$header Value>]<'a> {
                     fn default() -> Self {
                         [<$header Value>] {
-                            value: or_else!($($($default)?)?,EMPTY),
+                            value: $crate::or_else!($($($default)?)?,""),
                             phantom: std::marker::PhantomData
                         }
                     }
@@ -28,100 +37,152 @@ macro_rules! header {
 
                     pub const NAME: &'static str =  $name;
 
-                    pub(crate) fn new(value: or_else_type!($($types)?,&'a str)) -> Self {
+                    pub(crate) fn new(value: $crate::or_else_type!($($types)?,&'a str)) -> Self {
                         [<$header Value>] {
                             value,
                             phantom: std::marker::PhantomData
                         }
                     }
 
-                    pub(crate) fn from_owned(_value: or_else_type!($($types)?,String)) -> Self {
-                        choose_from_presence!($($types)? {
+                    #[doc(hidden)]
+                    pub fn from_owned(_value: $crate::or_else_type!($($types)?,String)) -> Self {
+                        $crate::choose_from_presence!($($types)? {
                             Self::new(_value)
                         }, {
                             panic!("Macro error, should never be called");
                         })
                     }
 
-                    pub(crate) fn from_str<'b>(input: &'b str) -> Result<[<$header Value>]<'b>, StompParseError> {
-                        choose_from_presence!($($types)? ($($types)?::from_str(input).map([<$header Value>]::new)
-                            .map_err(|_| StompParseError::new("[<Error Parsing $header Value>]"))), (Ok([<$header Value>]::new(
+                    #[doc(hidden)]
+                    #[allow(clippy::should_implement_trait)]
+                    pub fn from_str<'b>(input: &'b str) -> Result<[<$header Value>]<'b>, $crate::macro_support::StompParseError> {
+                        $crate::choose_from_presence!($($types)? (<$($types)? as $crate::macro_support::FromHeaderValue>::from_header_value(input).map([<$header Value>]::new)
+                            .map_err(|error| $crate::macro_support::StompParseError::with_kind(
+                                error.kind().clone(),
+                                format!("Error parsing {} header: {}", $name, error.message())
+                            ))), (Ok([<$header Value>]::new(
                                 input
                             ))))
                     }
 
-                    pub fn value(&self) -> & or_else_type!($($types)?,str) {
-                        choose_from_presence!($($types)? {&self.value}, {&self.value})
+                    $crate::if_present!($($types)? (pub fn value(&self) -> & $crate::or_else_type!($($types)?, str) {
+                        &self.value
+                    }));
+
+                    $crate::if_not_present!($($types)? (pub fn value(&self) -> &'a str {
+                        self.value
+                    }));
+
+                    #[doc(hidden)]
+                    pub fn into_stored(self, _base: &[u8]) -> [<$header Stored>] {
+                        $crate::choose_from_presence!($($types)? {
+                            [<$header Value>]::new(self.value)
+                        }, {
+                            $crate::macro_support::Span::of(self.value.as_bytes(), _base)
+                        })
+                    }
+
+                    #[doc(hidden)]
+                    pub fn stored_from_owned(_value: $crate::or_else_type!($($types)?,String), _span: $crate::macro_support::Span) -> [<$header Stored>] {
+                        $crate::choose_from_presence!($($types)? {
+                            [<$header Value>]::new(_value)
+                        }, {
+                            _span
+                        })
+                    }
+
+                    #[doc(hidden)]
+                    pub fn load<'s>(stored: &'s [<$header Stored>], _base: &'s [u8]) -> [<$header Ref>]<'s> {
+                        $crate::choose_from_presence!($($types)? {
+                            stored
+                        }, {
+                            [<$header Value>]::new(stored.str(_base))
+                        })
                     }
                 }
 
-                if_not_present!($($types)? (impl <'a> DecodableValue for [<$header Value>]<'a> {
-                        fn decoded_value(&self) -> Result<Either<&str, String>, StompParseError> {
-                            decode_str(self.value())
+                #[doc(hidden)]
+                #[allow(unused_parens)]
+                pub type [<$header Stored>] = $crate::choose_from_presence!($($types)? ([<$header Value>]<'static>), ($crate::macro_support::Span));
+
+                #[doc = "The value of the `"$name"` header, as returned by the accessors of frames."]
+                #[allow(unused_parens)]
+                pub type [<$header Ref>]<'s> = $crate::choose_from_presence!($($types)? (&'s [<$header Value>]<'s>), ([<$header Value>]<'s>));
+
+                $crate::if_not_present!($($types)? (impl <'a> $crate::macro_support::DecodableValue for [<$header Value>]<'a> {
+                        fn decoded_value(&self) -> Result<$crate::macro_support::Either<&str, String>, $crate::macro_support::StompParseError> {
+                            $crate::macro_support::decode_str(self.value())
                         }
                     }
                 ));
 
-                impl <'a> HeaderValue  for [<$header Value>]<'a> {
-                    type OwnedValue = or_else_type!($($types)?,String);
-                    type Value=or_else_type!($($types)?,&'static str);
-                    const OWNED: bool = choose_from_presence!($($types)? true, false);
+                impl <'a> $crate::macro_support::HeaderValue  for [<$header Value>]<'a> {
+                    type OwnedValue = $crate::or_else_type!($($types)?,String);
+                    type Value=$crate::or_else_type!($($types)?,&'static str);
+                    const OWNED: bool = $crate::choose_from_presence!($($types)? true, false);
 
                     fn header_name(&self) -> &str {
                         [<$header Value>]::NAME
                     }
                 }
 
-                impl  <'a> Into<or_else_type!($($types)?,&'a str)> for [<$header Value>]<'a> {
-                    fn into(self) -> or_else_type!($($types)?,&'a str) {
-                        self.value
+                impl <'a> From<[<$header Value>]<'a>> for $crate::or_else_type!($($types)?,&'a str) {
+                    fn from(header: [<$header Value>]<'a>) -> $crate::or_else_type!($($types)?,&'a str) {
+                        header.value
                     }
                 }
 
                 impl <'a> std::fmt::Display for [<$header Value>]<'a> {
-                    header_display!( );
+                    $crate::header_display!( );
                 }
 
                 impl <'a> std::fmt::Debug for [<$header Value>]<'a> {
-                    header_display!( );
+                    $crate::header_display!( );
                 }
 
         }
     };
 }
+#[doc(hidden)]
+#[macro_export]
 macro_rules! headers {
         ( $( ($header:ident, $name:literal $(,$types:ty $(, $default:expr )?)? ) ),*  ) => {
 
              #[derive(Debug, Eq, PartialEq, Clone)]
-            pub struct CustomValue {
-                name: &'static str,
-                value: &'static str
+            pub struct CustomValue<'a> {
+                name: &'a str,
+                value: &'a str
             }
 
-             impl  CustomValue {
-                pub fn new(name: &'static  str, value: &'static  str) -> Self {
+             impl<'a> CustomValue<'a> {
+                pub fn new(name: &'a str, value: &'a str) -> Self {
                     CustomValue {
                         name,
                         value
                     }
                 }
 
-                pub fn value(&self) -> &&'static str {
+                pub fn value(&self) -> &&'a str {
                     &self.value
                 }
 
-                pub fn decoded_name(&self) -> Result<Either<&str, String>, StompParseError> {
-                    decode_str(self.name)
+                /// The name of the header, for as long as the frame from which it was parsed.
+                pub fn name(&self) -> &'a str {
+                    self.name
+                }
+
+                pub fn decoded_name(&self) -> Result<$crate::macro_support::Either<&str, String>, $crate::macro_support::StompParseError> {
+                    $crate::macro_support::decode_str(self.name)
                 }
             }
 
-            impl DecodableValue for CustomValue {
-                fn decoded_value(&self) -> Result<Either<&str, String>, StompParseError> {
-                    decode_str(self.value())
+            impl<'a> $crate::macro_support::DecodableValue for CustomValue<'a> {
+                fn decoded_value(&self) -> Result<$crate::macro_support::Either<&str, String>, $crate::macro_support::StompParseError> {
+                    $crate::macro_support::decode_str(self.value())
                 }
             }
 
-            impl  HeaderValue for CustomValue {
+            impl<'a> $crate::macro_support::HeaderValue for CustomValue<'a> {
                 type OwnedValue = String;
                 type Value = &'static str;
                 const OWNED: bool = false;
@@ -131,8 +192,8 @@ macro_rules! headers {
                 }
             }
 
-             impl  std::fmt::Display for CustomValue {
-                header_display!( );
+             impl<'a> std::fmt::Display for CustomValue<'a> {
+                $crate::header_display!( );
             }
 
 
@@ -153,11 +214,20 @@ macro_rules! headers {
 //                        HeaderType::Custom(header_name) => &name == header_name
                     }
             }
+
+            /// Like `matches`, but ignoring ASCII case.
+            pub fn matches_ignore_case(&self, name: &str) -> bool {
+                match self {
+                        $(
+                            HeaderType::$header => name.eq_ignore_ascii_case($name),
+                        )*
+                    }
+            }
         }
 
-        impl TryFrom<&'static str> for HeaderType {
-            type Error = StompParseError;
-            fn try_from(input: &'static str) -> std::result::Result<HeaderType, StompParseError> {
+        impl std::convert::TryFrom<&'static str> for HeaderType {
+            type Error = $crate::macro_support::StompParseError;
+            fn try_from(input: &'static str) -> std::result::Result<HeaderType, $crate::macro_support::StompParseError> {
                 match(input) {
                         $(
                             $name => Ok(HeaderType::$header),
@@ -178,10 +248,96 @@ macro_rules! headers {
             }
         }
 
+        /// The name of a header: one of the headers defined by the specification, or a custom
+        /// header. Names are compared by how they are written, so a custom name equal to that of a
+        /// standard header is equal to the standard header.
+        #[derive(Debug, Clone)]
+        pub enum HeaderName<'a> {
+            $(
+            #[doc = concat!("The `", $name, "` header.")]
+            $header,
+            )*
+            /// A header not defined by the specification.
+            Custom(std::borrow::Cow<'a, str>),
+        }
+
+        impl<'a> HeaderName<'a> {
+            /// The names of the headers defined by the specification.
+            pub fn standard() -> impl Iterator<Item = HeaderName<'static>> {
+                IntoIterator::into_iter([$(HeaderName::$header),*])
+            }
+
+            /// The name as it is written in a frame.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $(HeaderName::$header => $name,)*
+                    HeaderName::Custom(name) => name,
+                }
+            }
+
+            /// True if the header is defined by the specification, even if held as a custom name.
+            pub fn is_standard(&self) -> bool {
+                !matches!(HeaderName::from(self.as_str()), HeaderName::Custom(_))
+            }
+
+            /// The name, holding a custom name in its own storage.
+            pub fn into_owned(self) -> HeaderName<'static> {
+                match self {
+                    $(HeaderName::$header => HeaderName::$header,)*
+                    HeaderName::Custom(name) => HeaderName::Custom(std::borrow::Cow::Owned(name.into_owned())),
+                }
+            }
+        }
+
+        impl<'a, 'b> PartialEq<HeaderName<'b>> for HeaderName<'a> {
+            fn eq(&self, other: &HeaderName<'b>) -> bool {
+                self.as_str() == other.as_str()
+            }
+        }
+
+        impl<'a> Eq for HeaderName<'a> {}
+
+        impl<'a> std::hash::Hash for HeaderName<'a> {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.as_str().hash(state)
+            }
+        }
+
+        impl<'a> From<&'a str> for HeaderName<'a> {
+            fn from(name: &'a str) -> Self {
+                match name {
+                    $($name => HeaderName::$header,)*
+                    name => HeaderName::Custom(std::borrow::Cow::Borrowed(name)),
+                }
+            }
+        }
+
+        impl From<HeaderType> for HeaderName<'static> {
+            fn from(header_type: HeaderType) -> Self {
+                match header_type {
+                    $(HeaderType::$header => HeaderName::$header,)*
+                }
+            }
+        }
+
+        impl std::str::FromStr for HeaderName<'static> {
+            type Err = std::convert::Infallible;
+
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                Ok(HeaderName::from(name).into_owned())
+            }
+        }
 
-        paste! {
+        impl<'a> std::fmt::Display for HeaderName<'a> {
+            fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                formatter.write_str(self.as_str())
+            }
+        }
+
+
+        $crate::macro_support::paste::paste! {
             $(
-                header!($header, $name $(,$types $(, $default )?)? );
+                $crate::header!($header, $name $(,$types $(, $default )?)? );
             )*
 
                 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -189,7 +345,63 @@ macro_rules! headers {
                     $(
                     $header([<$header Value>]<'a>),
                     )*
-                    Custom(CustomValue)
+                    Custom(CustomValue<'a>)
+                }
+
+                /// A header as held by a frame, by its value or its position in the frame.
+                #[doc(hidden)]
+                #[derive(Debug, Clone)]
+                pub enum StoredHeader {
+                    $(
+                    $header([<$header Stored>]),
+                    )*
+                    Custom($crate::macro_support::Span, $crate::macro_support::Span)
+                }
+
+                // Header values are held by frames, which must remain `Send` and `Sync`
+                $crate::assert_send_sync!(
+                    Header<'static>,
+                    StoredHeader,
+                    CustomValue<'static>,
+                    $([<$header Value>]<'static>),*
+                );
+
+                impl<'a> Header<'a> {
+                    /// The name of the header.
+                    pub fn name(&self) -> &str {
+                        match self {
+                            $(
+                            Header::$header(value) => $crate::macro_support::HeaderValue::header_name(value),
+                            )*
+                            Header::Custom(custom) => custom.name,
+                        }
+                    }
+
+                    #[doc(hidden)]
+                    pub fn into_stored(self, base: &[u8]) -> StoredHeader {
+                        match self {
+                            $(
+                            Header::$header(value) => StoredHeader::$header(value.into_stored(base)),
+                            )*
+                            Header::Custom(custom) => StoredHeader::Custom(
+                                $crate::macro_support::Span::of(custom.name.as_bytes(), base),
+                                $crate::macro_support::Span::of(custom.value.as_bytes(), base),
+                            ),
+                        }
+                    }
+                }
+
+                impl StoredHeader {
+                    #[doc(hidden)]
+                    #[allow(clippy::clone_on_copy, clippy::redundant_clone)]
+                    pub fn load<'s>(&'s self, base: &'s [u8]) -> Header<'s> {
+                        match self {
+                            $(
+                            StoredHeader::$header(stored) => Header::$header([<$header Value>]::load(stored, base).clone()),
+                            )*
+                            StoredHeader::Custom(name, value) => Header::Custom(CustomValue::new(name.str(base), value.str(base))),
+                        }
+                    }
                 }
 
                 #[doc(hidden)]
@@ -197,9 +409,9 @@ macro_rules! headers {
                     #![allow(non_snake_case)]
 
                     use super::*;
-                    pub type HeaderValueConverter = dyn Fn(&str) -> Result<Header, StompParseError>;
+                    pub type HeaderValueConverter<'a> = dyn Fn(&str) -> Result<Header<'_>, $crate::macro_support::StompParseError> + 'a;
 
-                    pub fn find_header_parser(header_type: HeaderType) -> Box<HeaderValueConverter> {
+                    pub fn find_header_parser(header_type: HeaderType) -> Box<HeaderValueConverter<'static>> {
                         match header_type {
                             $(
                                 HeaderType::$header => Box::new([<parse_ $header _header>]),
@@ -208,7 +420,7 @@ macro_rules! headers {
                     }
 
                     $(
-                        pub fn [<parse_ $header _header>]<'a>(input: &'a str) -> Result<Header, StompParseError> {
+                        pub fn [<parse_ $header _header>](input: &str) -> Result<Header<'_>, $crate::macro_support::StompParseError> {
                             [<$header Value>]::from_str(input).map(Header::$header)
                         }
                     )*
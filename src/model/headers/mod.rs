@@ -2,12 +2,18 @@
 //! [STOMP Protocol Specification,Version 1.2](https://stomp.github.io/stomp-specification-1.2.html).
 #[macro_use]
 mod macros;
-use crate::common::functions::decode_str;
+#[cfg(feature = "zeroize")]
+mod secret;
+
+use crate::common::functions::parse_number;
+#[doc(hidden)]
+pub use crate::common::span::{Span, StoredStr};
 use crate::error::StompParseError;
 use either::Either;
-use paste::paste;
-use std::convert::TryFrom;
+#[cfg(feature = "zeroize")]
+pub use secret::Secret;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// A Header that reveals it's type and it's value, and can be displayed
 pub trait HeaderValue: std::fmt::Display {
@@ -21,6 +27,55 @@ pub trait HeaderValue: std::fmt::Display {
 pub trait DecodableValue {
     fn decoded_value(&self) -> Result<Either<&str, String>, StompParseError>;
 }
+
+/// A type which is parsed from the value of a typed header.
+pub trait FromHeaderValue: Sized {
+    fn from_header_value(input: &str) -> Result<Self, StompParseError>;
+}
+
+/// Implements `FromHeaderValue` using the type's implementation of `FromStr`.
+macro_rules! from_header_value_via_from_str {
+    ( $($type:ty),* ) => {
+        $(
+            impl FromHeaderValue for $type {
+                fn from_header_value(input: &str) -> Result<Self, StompParseError> {
+                    <$type>::from_str(input)
+                }
+            }
+        )*
+    };
+}
+
+/// Implements `FromHeaderValue` for unsigned integers, with precise errors for invalid values.
+macro_rules! from_header_value_via_parse_number {
+    ( $($type:ty),* ) => {
+        $(
+            impl FromHeaderValue for $type {
+                fn from_header_value(input: &str) -> Result<Self, StompParseError> {
+                    parse_number(input)
+                }
+            }
+        )*
+    };
+}
+
+from_header_value_via_parse_number!(u8, u16, u32, u64, usize);
+
+impl FromHeaderValue for String {
+    fn from_header_value(input: &str) -> Result<Self, StompParseError> {
+        Ok(input.to_owned())
+    }
+}
+
+impl FromHeaderValue for bool {
+    fn from_header_value(input: &str) -> Result<Self, StompParseError> {
+        match input {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(StompParseError::new(format!("Invalid boolean: {}", input))),
+        }
+    }
+}
 #[derive(Eq, PartialEq, Debug, Clone)]
 pub struct NameValue {
     pub name: String,
@@ -63,6 +118,68 @@ impl HeartBeatIntervalls {
     pub fn new(supplied: u32, expected: u32) -> HeartBeatIntervalls {
         HeartBeatIntervalls { expected, supplied }
     }
+
+    /// The supplied and expected intervalls as durations, with `None` where the intervall is 0,
+    /// i.e. where no heartbeats are supplied or expected.
+    pub fn as_durations(&self) -> (Option<Duration>, Option<Duration>) {
+        (
+            Self::as_duration(self.supplied),
+            Self::as_duration(self.expected),
+        )
+    }
+
+    /// The intervall at which heartbeats must be sent, or `None` if they need not be.
+    pub fn send_interval(&self) -> Option<Duration> {
+        Self::as_duration(self.supplied)
+    }
+
+    /// The time after which, if nothing has been received, the peer should be considered dead; this
+    /// is the expected intervall scaled by `multiplier`, which allows for network latency. Returns
+    /// `None` if no heartbeats are expected, or if the scaled intervall is not a valid duration, as
+    /// when `multiplier` is negative, not a number, or so large that the peer never times out.
+    pub fn expected_timeout(&self, multiplier: f64) -> Option<Duration> {
+        Self::as_duration(self.expected).and_then(|expected| {
+            Duration::try_from_secs_f64(expected.as_secs_f64() * multiplier).ok()
+        })
+    }
+
+    /// The intervalls in effect once these intervalls, sent by one side of the connection, are
+    /// combined with those sent by the `peer`. As described in the specification, each is the
+    /// greater of what one side can supply and the other expects, or 0 if either is 0.
+    pub fn negotiate(&self, peer: &HeartBeatIntervalls) -> HeartBeatIntervalls {
+        HeartBeatIntervalls {
+            supplied: Self::negotiate_intervall(self.supplied, peer.expected),
+            expected: Self::negotiate_intervall(self.expected, peer.supplied),
+        }
+    }
+
+    fn negotiate_intervall(supplied: u32, expected: u32) -> u32 {
+        if supplied == 0 || expected == 0 {
+            0
+        } else {
+            supplied.max(expected)
+        }
+    }
+
+    fn as_duration(millis: u32) -> Option<Duration> {
+        (millis > 0).then(|| Duration::from_millis(millis.into()))
+    }
+
+    /// Parses `input` accepting the malformed values sent by some peers: whitespace around either
+    /// number is ignored, a missing expected intervall is taken as 0, and an empty value as `0,0`.
+    /// Values which are not numbers, or are too large, are still rejected.
+    pub fn parse_lenient(input: &str) -> Result<HeartBeatIntervalls, StompParseError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(HeartBeatIntervalls::default());
+        }
+
+        let (supplied, expected) = split_once(input, ',').unwrap_or((input, "0"));
+        Ok(HeartBeatIntervalls {
+            supplied: parse_number(supplied.trim())?,
+            expected: parse_number(expected.trim())?,
+        })
+    }
 }
 
 impl std::fmt::Display for HeartBeatIntervalls {
@@ -78,14 +195,10 @@ impl FromStr for HeartBeatIntervalls {
         split_once(input, ',')
             .ok_or_else(|| StompParseError::new(format!("Poorly formatted heartbeats: {}", input)))
             .and_then(|(supplied, expected)| {
-                u32::from_str(expected)
-                    .and_then(|expected| {
-                        u32::from_str(supplied)
-                            .map(|supplied| HeartBeatIntervalls { expected, supplied })
-                    })
-                    .map_err(|_| {
-                        StompParseError::new(format!("Poorly formatted heartbeats: {}", input))
-                    })
+                Ok(HeartBeatIntervalls {
+                    supplied: parse_number(supplied)?,
+                    expected: parse_number(expected)?,
+                })
             })
     }
 }
@@ -112,7 +225,7 @@ impl FromStr for StompVersions {
     fn from_str(input: &str) -> Result<StompVersions, StompParseError> {
         input
             .split(',')
-            .map(|section| StompVersion::from_str(section))
+            .map(StompVersion::from_str)
             .try_fold(Vec::new(), |mut vec, result| {
                 result
                     .map(|version| {
@@ -127,6 +240,29 @@ impl FromStr for StompVersions {
     }
 }
 
+impl StompVersions {
+    /// The versions offered, in the order offered, including those which are not known.
+    pub fn offered(&self) -> &[StompVersion] {
+        &self.0
+    }
+
+    /// The latest of the versions offered which is also in `supported`, if any.
+    pub fn highest_common(&self, supported: &[StompVersion]) -> Option<&StompVersion> {
+        self.0
+            .iter()
+            .filter(|version| supported.contains(version))
+            .max()
+    }
+
+    /// The versions offered which are not known, in the order offered.
+    pub fn unknown(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().filter_map(|version| match version {
+            StompVersion::Unknown(version) => Some(version.as_str()),
+            _ => None,
+        })
+    }
+}
+
 impl std::ops::Deref for StompVersions {
     type Target = Vec<StompVersion>;
 
@@ -135,10 +271,11 @@ impl std::ops::Deref for StompVersions {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
 /// The Ack approach to be used for the subscription
 pub enum AckType {
     /// The client need not send Acks. Messages are assumed received as soon as sent.
+    #[default]
     Auto,
     /// Client must send Ack frames. Ack frames are cummulative, acknowledging also all previous messages.
     Client,
@@ -146,12 +283,6 @@ pub enum AckType {
     ClientIndividual,
 }
 
-impl Default for AckType {
-    fn default() -> Self {
-        AckType::Auto
-    }
-}
-
 impl std::fmt::Display for AckType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str(match self {
@@ -184,15 +315,58 @@ pub enum StompVersion {
     Unknown(String),
 }
 
-impl std::fmt::Display for StompVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
-        let text = match self {
+impl StompVersion {
+    /// The text of the version, as it appears in headers.
+    pub fn as_str(&self) -> &str {
+        match self {
             StompVersion::V1_0 => "1.0",
             StompVersion::V1_1 => "1.1",
             StompVersion::V1_2 => "1.2",
-            _ => return Err(std::fmt::Error {}),
-        };
-        f.write_str(text)
+            StompVersion::Unknown(version) => version,
+        }
+    }
+
+    /// Returns true if the version is one of those defined by the specification.
+    pub fn is_known(&self) -> bool {
+        !matches!(self, StompVersion::Unknown(_))
+    }
+
+    /// The major and minor numbers of the version, or `None` if it is unknown and not of the form
+    /// `major.minor`.
+    pub fn major_minor(&self) -> Option<(u32, u32)> {
+        match self {
+            StompVersion::V1_0 => Some((1, 0)),
+            StompVersion::V1_1 => Some((1, 1)),
+            StompVersion::V1_2 => Some((1, 2)),
+            StompVersion::Unknown(version) => {
+                let (major, minor) = version.split_once('.')?;
+                Some((major.parse().ok()?, minor.parse().ok()?))
+            }
+        }
+    }
+
+    fn sort_key(&self) -> (Option<(u32, u32)>, bool, &str) {
+        (self.major_minor(), !self.is_known(), self.as_str())
+    }
+}
+
+/// Versions are ordered by their major and minor numbers, so that an unknown version such as `2.0`
+/// is later than all known versions. Unknown versions without numbers precede all others.
+impl Ord for StompVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for StompVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl std::fmt::Display for StompVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        f.write_str(self.as_str())
     }
 }
 
@@ -208,7 +382,71 @@ impl FromStr for StompVersion {
     }
 }
 
-const EMPTY: &str = "";
+from_header_value_via_from_str!(AckType, StompVersions, HeartBeatIntervalls, StompVersion);
+
+/// A value accepted by the constructors and setters of builders for headers whose value is a `T`:
+/// the value itself or, for string headers, anything which can be converted to a `String`, such as
+/// `&str` or `Cow<str>`.
+pub trait IntoOwnedValue<T> {
+    fn into_owned_value(self) -> T;
+}
+
+impl<T> IntoOwnedValue<T> for T {
+    fn into_owned_value(self) -> T {
+        self
+    }
+}
+
+impl IntoOwnedValue<String> for &str {
+    fn into_owned_value(self) -> String {
+        self.to_owned()
+    }
+}
+
+impl IntoOwnedValue<String> for &String {
+    fn into_owned_value(self) -> String {
+        self.clone()
+    }
+}
+
+impl IntoOwnedValue<String> for std::borrow::Cow<'_, str> {
+    fn into_owned_value(self) -> String {
+        self.into_owned()
+    }
+}
+
+impl IntoOwnedValue<String> for Box<str> {
+    fn into_owned_value(self) -> String {
+        self.into()
+    }
+}
+
+/// A type which is written as the value of a typed header.
+pub trait ToHeaderValue {
+    fn to_header_value(&self) -> String;
+}
+
+/// Implements `ToHeaderValue` using the type's implementation of `Display`.
+macro_rules! to_header_value_via_display {
+    ( $($type:ty),* ) => {
+        $(
+            impl ToHeaderValue for $type {
+                fn to_header_value(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+to_header_value_via_display!(
+    String,
+    usize,
+    AckType,
+    StompVersions,
+    HeartBeatIntervalls,
+    StompVersion
+);
 
 headers!(
     (Ack, "ack", AckType, (AckType::Auto)),
@@ -218,7 +456,7 @@ headers!(
         StompVersions,
         (StompVersions(Vec::new()))
     ),
-    (ContentLength, "content-length", u32, 0),
+    (ContentLength, "content-length", usize, 0),
     (ContentType, "content-type"),
     (Destination, "destination"),
     (
@@ -242,22 +480,64 @@ headers!(
     (Version, "version", StompVersion, (StompVersion::V1_2))
 );
 
+impl<'a> ContentTypeValue<'a> {
+    /// The type and subtype, without parameters, such as `text/plain`.
+    pub fn essence(&self) -> &'a str {
+        crate::charset::essence(self.value())
+    }
+
+    /// The value of the parameter `name`, such as `charset`, ignoring the case of the name.
+    pub fn parameter(&self, name: &str) -> Option<&'a str> {
+        crate::charset::parameter(self.value(), name)
+    }
+
+    /// The parameters, as names and values without any quotes.
+    pub fn parameters(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+        crate::charset::parameters(self.value())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::common::functions::decode_str;
-    use crate::error::StompParseError;
+    use crate::error::{ErrorKind, NumberError, StompParseError};
     use crate::headers::{HeartBeatIntervalls, HeartBeatValue};
     use either::Either;
 
-    use std::{fmt::Display, str::FromStr};
+    use std::{fmt::Display, str::FromStr, time::Duration};
 
-    use super::{ContentLengthValue, DecodableValue, DestinationValue, HeaderValue};
+    use super::{ContentLengthValue, DecodableValue, DestinationValue, HeaderName, HeaderValue};
 
     fn do_something(value: &str) {
         println!("Value: {}", value);
     }
 
     #[test]
+    fn header_names_round_trip() {
+        assert_eq!(
+            Ok(HeaderName::ContentLength),
+            HeaderName::from_str("content-length")
+        );
+        assert_eq!("heart-beat", HeaderName::HeartBeat.to_string());
+
+        let custom = HeaderName::from("x-trace");
+        assert_eq!(HeaderName::Custom("x-trace".into()), custom);
+        assert!(!custom.is_standard());
+        assert_eq!("x-trace", custom.as_str());
+
+        assert!(HeaderName::standard()
+            .all(|name| name.is_standard() && HeaderName::from(name.as_str()) == name));
+        assert_eq!(19, HeaderName::standard().count());
+
+        let spelled_out = HeaderName::Custom("destination".into());
+        assert_eq!(HeaderName::Destination, spelled_out);
+        assert!(spelled_out.is_standard());
+        let names: std::collections::HashSet<_> = [HeaderName::Destination, spelled_out].into();
+        assert_eq!(1, names.len());
+    }
+
+    #[test]
+    #[allow(clippy::drop_non_drop)]
     fn header_value() {
         let d = DestinationValue::new("Foo");
 
@@ -270,6 +550,62 @@ mod test {
         //        println!("Value: {}", value);
     }
 
+    #[test]
+    fn accept_version_keeps_offered_versions() {
+        use super::{StompVersion, StompVersions};
+
+        let versions = StompVersions::from_str("1.2,funk,1.0,2.0").unwrap();
+
+        assert_eq!(
+            &[
+                StompVersion::V1_2,
+                StompVersion::Unknown("funk".to_owned()),
+                StompVersion::V1_0,
+                StompVersion::Unknown("2.0".to_owned())
+            ],
+            versions.offered()
+        );
+        assert_eq!(vec!["funk", "2.0"], versions.unknown().collect::<Vec<_>>());
+        assert_eq!("1.2,funk,1.0,2.0", versions.to_string());
+    }
+
+    #[test]
+    fn orders_versions() {
+        use super::{StompVersion, StompVersions};
+
+        let unknown = |version: &str| StompVersion::Unknown(version.to_owned());
+        let mut versions = vec![
+            unknown("2.0"),
+            StompVersion::V1_2,
+            unknown("funk"),
+            StompVersion::V1_0,
+            unknown("1.10"),
+            StompVersion::V1_1,
+        ];
+        versions.sort();
+
+        assert_eq!(
+            vec![
+                unknown("funk"),
+                StompVersion::V1_0,
+                StompVersion::V1_1,
+                StompVersion::V1_2,
+                unknown("1.10"),
+                unknown("2.0")
+            ],
+            versions
+        );
+        assert!(StompVersion::V1_0 < StompVersion::V1_2);
+        assert_eq!(None, unknown("funk").major_minor());
+
+        let offered = StompVersions::from_str("1.0,1.1,1.2,3.0").unwrap();
+        assert_eq!(
+            Some(&StompVersion::V1_1),
+            offered.highest_common(&[StompVersion::V1_0, StompVersion::V1_1])
+        );
+        assert_eq!(None, offered.highest_common(&[unknown("2.0")]));
+    }
+
     #[test]
     fn header_value_display() {
         let x = ContentLengthValue::new(10);
@@ -285,6 +621,67 @@ mod test {
         assert_eq!(200, hb.expected);
     }
 
+    #[test]
+    fn heartbeat_rejects_invalid_numbers() {
+        let error = HeartBeatIntervalls::from_str("100,-200").unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidNumber(NumberError::Negative),
+            error.kind()
+        );
+
+        let error = HeartBeatIntervalls::from_str("99999999999,0").unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidNumber(NumberError::Overflow),
+            error.kind()
+        );
+    }
+
+    #[test]
+    fn heartbeat_is_parsed_leniently_on_request() {
+        for (input, supplied, expected) in [(" 100 , 200 ", 100, 200), ("100", 100, 0), ("", 0, 0)]
+        {
+            assert!(HeartBeatIntervalls::from_str(input).is_err());
+            assert_eq!(
+                HeartBeatIntervalls::new(supplied, expected),
+                HeartBeatIntervalls::parse_lenient(input).unwrap()
+            );
+        }
+
+        let error = HeartBeatIntervalls::parse_lenient("0, 99999999999").unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidNumber(NumberError::Overflow),
+            error.kind()
+        );
+        assert!(HeartBeatIntervalls::parse_lenient("a,b").is_err());
+    }
+
+    #[test]
+    fn content_length_is_parsed_with_precise_errors() {
+        assert_eq!(
+            1234usize,
+            *ContentLengthValue::from_str("1234").unwrap().value()
+        );
+
+        let error = ContentLengthValue::from_str("12x").unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidNumber(NumberError::NotNumeric),
+            error.kind()
+        );
+        assert!(error.message().contains("content-length"));
+    }
+
+    #[test]
+    fn content_type_exposes_parameters() {
+        let content_type = super::ContentTypeValue::new("text/plain; charset=\"utf-8\"; q=1");
+
+        assert_eq!("text/plain", content_type.essence());
+        assert_eq!(Some("utf-8"), content_type.parameter("Charset"));
+        assert_eq!(
+            vec![("charset", "utf-8"), ("q", "1")],
+            content_type.parameters().collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn heartbeat_writes_supplied_then_expected() {
         let hb = HeartBeatIntervalls::new(500, 300);
@@ -292,6 +689,29 @@ mod test {
         assert_eq!("500,300", hb.to_string());
     }
 
+    #[test]
+    fn heartbeat_durations() {
+        let hb = HeartBeatIntervalls::new(0, 1000);
+
+        assert_eq!((None, Some(Duration::from_secs(1))), hb.as_durations());
+        assert_eq!(None, hb.send_interval());
+        assert_eq!(Some(Duration::from_millis(1500)), hb.expected_timeout(1.5));
+        assert_eq!(None, HeartBeatIntervalls::new(10, 0).expected_timeout(2.0));
+        assert_eq!(None, hb.expected_timeout(-1.0));
+        assert_eq!(None, hb.expected_timeout(f64::NAN));
+        assert_eq!(None, hb.expected_timeout(f64::INFINITY));
+        assert_eq!(None, hb.expected_timeout(f64::MAX));
+    }
+
+    #[test]
+    fn heartbeat_negotiate() {
+        let client = HeartBeatIntervalls::new(1000, 5000);
+        let server = HeartBeatIntervalls::new(2000, 0);
+
+        assert_eq!(HeartBeatIntervalls::new(0, 5000), client.negotiate(&server));
+        assert_eq!(HeartBeatIntervalls::new(5000, 0), server.negotiate(&client));
+    }
+
     #[test]
     fn heartbeat_into_intervalls() {
         let hb = HeartBeatValue::new(HeartBeatIntervalls::new(123, 987));
@@ -411,7 +831,7 @@ mod test {
 
         let result = instance.decoded_value();
 
-        if let Ok(_) = result {
+        if result.is_ok() {
             panic!("Unexpected return");
         }
     }
@@ -423,7 +843,7 @@ mod test {
 
         let result = instance.decoded_value();
 
-        if let Ok(_) = result {
+        if result.is_ok() {
             panic!("Unexpected return");
         }
     }
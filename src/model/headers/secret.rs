@@ -0,0 +1,128 @@
+//! Implements `Secret`, which holds a copy of the credentials of a CONNECT frame that is zeroed
+//! when dropped, for the `zeroize` feature.
+//!
+//! Otherwise the feature only redacts the `passcode` header from the `Debug` output of frames.
+//! Nothing else is zeroed: not the `String` given to `ConnectFrameBuilder::passcode`, nor the
+//! copies made of it while building, nor the serialised bytes of a frame, which hold the `login`
+//! and `passcode` headers as written and should be zeroed by the application if necessary (see
+//! `into_storage`).
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use super::FromHeaderValue;
+use crate::client::ConnectFrame;
+use crate::error::StompParseError;
+use crate::storage::Storage;
+
+/// A secret, such as a passcode, which is overwritten with zeros when dropped, and which is never
+/// revealed by its `Debug` or `Display` implementations.
+///
+/// Only the copy of the secret held by this type is zeroed, so it cannot be converted into a header
+/// value, which would be a copy that is not.
+#[derive(Clone, Default, Eq, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(secret: String) -> Secret {
+        Secret(secret)
+    }
+
+    /// The secret itself.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret(secret)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret(secret.to_owned())
+    }
+}
+
+impl PartialEq<Secret> for str {
+    fn eq(&self, other: &Secret) -> bool {
+        self == other.expose()
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret([REDACTED])")
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+impl FromHeaderValue for Secret {
+    fn from_header_value(input: &str) -> Result<Self, StompParseError> {
+        Ok(Secret(input.to_owned()))
+    }
+}
+
+impl<'a, S: Storage> ConnectFrame<'a, S> {
+    /// A copy of the `login` header, which is zeroed when dropped.
+    pub fn login_secret(&self) -> Option<Secret> {
+        self.login().map(|login| Secret::from(login.value()))
+    }
+
+    /// A copy of the `passcode` header, which is zeroed when dropped.
+    pub fn passcode_secret(&self) -> Option<Secret> {
+        self.passcode()
+            .map(|passcode| Secret::from(passcode.value()))
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        // Safety: the bytes are only overwritten with zeros, which are valid UTF-8, and the whole
+        // allocation is written, including any spare capacity which may hold earlier contents
+        unsafe {
+            let bytes = self.0.as_mut_vec();
+            let ptr = bytes.as_mut_ptr();
+            for index in 0..bytes.capacity() {
+                std::ptr::write_volatile(ptr.add(index), 0);
+            }
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::Secret;
+    use crate::client::{ClientFrame, ConnectFrameBuilder};
+    use crate::headers::{StompVersion, StompVersions};
+
+    #[test]
+    fn secrets_are_not_revealed() {
+        let bytes: Vec<u8> =
+            ConnectFrameBuilder::new("h".to_owned(), StompVersions(vec![StompVersion::V1_2]))
+                .login("ali baba")
+                .passcode(Secret::from("open sesame").expose())
+                .build()
+                .into();
+        assert!(bytes.windows(11).any(|window| window == b"open sesame"));
+
+        let Ok(ClientFrame::Connect(frame)) = ClientFrame::try_from(bytes) else {
+            panic!("Not a Connect Frame!")
+        };
+
+        let passcode: &str = frame.passcode().unwrap().value();
+        assert_eq!("open sesame", passcode);
+        assert_eq!("open sesame", frame.passcode_secret().unwrap().expose());
+        assert_eq!("ali baba", frame.login_secret().unwrap().expose());
+        assert!(!format!("{:?}", frame).contains("open sesame"));
+        assert!(!format!("{:?}", frame.passcode_secret()).contains("open sesame"));
+    }
+}
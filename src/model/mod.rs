@@ -1,6 +1,7 @@
 mod frames;
 pub mod headers;
 
+pub use frames::any;
 pub use frames::client;
 pub use frames::server;
 
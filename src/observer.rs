@@ -0,0 +1,159 @@
+//! Scans a serialised frame and reports its command, headers and body to an observer as they are
+//! found, without building a typed frame, for code such as routers which only needs one or two
+//! headers and must not pay for the rest.
+//!
+//! As with `spans`, only the structure of the frame is parsed: header names and values are passed
+//! as they appear on the wire, still escaped, and are not checked against the command.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::observer::{observe, FrameObserver, Scan};
+//!
+//! #[derive(Default)]
+//! struct Destination(Option<Vec<u8>>);
+//!
+//! impl FrameObserver for Destination {
+//!     fn header(&mut self, name: &[u8], value: &[u8]) -> Scan {
+//!         if name == b"destination" {
+//!             self.0 = Some(value.to_vec());
+//!             return Scan::Stop;
+//!         }
+//!         Scan::Continue
+//!     }
+//! }
+//!
+//! let buffer = b"SEND\ndestination:/queue/a\n\nhello\x00";
+//! let mut destination = Destination::default();
+//!
+//! assert_eq!(Some(buffer.len()), observe(buffer, &mut destination).unwrap());
+//! assert_eq!(Some(b"/queue/a".to_vec()), destination.0);
+//! ```
+use crate::config::ParserConfig;
+use crate::error::StompParseError;
+use crate::spans::{frame_bounds, header_separator, lines};
+
+/// Whether a scan should go on to the rest of the frame.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Scan {
+    Continue,
+    /// Skip the rest of the frame; the length of the frame is still returned.
+    Stop,
+}
+
+/// Receives the parts of a frame as it is scanned.
+pub trait FrameObserver {
+    /// Called with the command of the frame.
+    fn command(&mut self, _command: &[u8]) -> Scan {
+        Scan::Continue
+    }
+
+    /// Called with the name and the still escaped value of each header, in the order they appear,
+    /// including repeated headers.
+    fn header(&mut self, name: &[u8], value: &[u8]) -> Scan;
+
+    /// Called with the body of the frame, if it has one.
+    fn body(&mut self, _body: &[u8]) {}
+}
+
+/// Scans the frame at the start of `buffer`, reporting its parts to `observer`, using the default
+/// `ParserConfig`. Returns the length of the frame, including its terminating NUL, or `None` if
+/// the frame is incomplete, in which case nothing is reported.
+pub fn observe<O: FrameObserver>(
+    buffer: &[u8],
+    observer: &mut O,
+) -> Result<Option<usize>, StompParseError> {
+    observe_with(buffer, &ParserConfig::default(), observer)
+}
+
+/// Scans the frame at the start of `buffer` as `observe` does, with the limits of `config`.
+pub fn observe_with<O: FrameObserver>(
+    buffer: &[u8],
+    config: &ParserConfig,
+    observer: &mut O,
+) -> Result<Option<usize>, StompParseError> {
+    let bounds = match frame_bounds(buffer, config)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+    let len = bounds.len;
+
+    let mut lines = lines(&buffer[..bounds.body_start]);
+    let command = lines.next().unwrap_or_default();
+    if observer.command(&buffer[command]) == Scan::Stop {
+        return Ok(Some(len));
+    }
+
+    for line in lines.take_while(|line| !line.is_empty()) {
+        let line = &buffer[line];
+        let separator = header_separator(line)?;
+        if observer.header(&line[..separator], &line[separator + 1..]) == Scan::Stop {
+            return Ok(Some(len));
+        }
+    }
+
+    if let Some(body) = bounds.body() {
+        observer.body(&buffer[body]);
+    }
+
+    Ok(Some(len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{observe, FrameObserver, Scan};
+
+    #[derive(Default)]
+    struct Recorder {
+        parts: Vec<String>,
+        stop_at: Option<&'static str>,
+    }
+
+    impl FrameObserver for Recorder {
+        fn command(&mut self, command: &[u8]) -> Scan {
+            self.parts
+                .push(String::from_utf8_lossy(command).into_owned());
+            Scan::Continue
+        }
+
+        fn header(&mut self, name: &[u8], value: &[u8]) -> Scan {
+            self.parts.push(format!(
+                "{}={}",
+                String::from_utf8_lossy(name),
+                String::from_utf8_lossy(value)
+            ));
+            match self.stop_at {
+                Some(stop_at) if stop_at.as_bytes() == name => Scan::Stop,
+                _ => Scan::Continue,
+            }
+        }
+
+        fn body(&mut self, body: &[u8]) {
+            self.parts
+                .push(format!("body={}", String::from_utf8_lossy(body)));
+        }
+    }
+
+    #[test]
+    fn reports_parts_until_stopped() {
+        let buffer = b"MESSAGE\r\nsubscription:0\nmessage-id:a\\cb\n\nhi\x00\nSEND";
+
+        let mut recorder = Recorder::default();
+        assert_eq!(Some(44), observe(buffer, &mut recorder).unwrap());
+        assert_eq!(
+            vec!["MESSAGE", "subscription=0", "message-id=a\\cb", "body=hi"],
+            recorder.parts
+        );
+
+        let mut recorder = Recorder {
+            stop_at: Some("subscription"),
+            ..Recorder::default()
+        };
+        assert_eq!(Some(44), observe(buffer, &mut recorder).unwrap());
+        assert_eq!(vec!["MESSAGE", "subscription=0"], recorder.parts);
+
+        let mut recorder = Recorder::default();
+        assert_eq!(None, observe(&buffer[..40], &mut recorder).unwrap());
+        assert!(recorder.parts.is_empty());
+        assert!(observe(b"SEND\nbad\n\n\x00", &mut recorder).is_err());
+    }
+}
@@ -1,14 +1,16 @@
 use core::ops::FnMut;
 
+use nom::branch::alt;
 use nom::bytes::complete::{escaped, is_not};
 use nom::character::complete::{char, line_ending, one_of};
-use nom::combinator::{flat_map, map_res};
+use nom::combinator::{flat_map, map, map_res};
 use nom::error::context;
 use nom::multi::many0;
 use nom::sequence::terminated;
 use nom::IResult;
 use nom::Parser;
 
+use crate::config::ParserConfig;
 use crate::error::{FullError, StompParseError};
 use crate::model::headers::parser::*;
 use crate::model::headers::*;
@@ -31,11 +33,12 @@ pub fn headers_parser<'a, E>(
     required: Vec<HeaderType>,
     optional: Vec<HeaderType>,
     allows_custom: bool,
+    config: &ParserConfig,
 ) -> Box<dyn Parser<&'a [u8], Vec<Header<'a>>, E> + 'a>
 where
     E: 'a + FullError<&'a [u8], StompParseError>,
 {
-    let parser_selector = init_headers_parser(required, optional, allows_custom);
+    let parser_selector = init_headers_parser(required, optional, allows_custom, config.clone());
 
     Box::new(terminated(
         many0(flat_map(header_name, parser_selector)), // Accept many headers...
@@ -47,12 +50,13 @@ fn init_headers_parser<'a, E>(
     required: Vec<HeaderType>,
     optional: Vec<HeaderType>,
     allows_custom: bool,
+    config: ParserConfig,
 ) -> Box<dyn Fn(&'a str) -> Box<dyn HeaderParser<'a, E>> + 'a>
 where
     E: 'a + FullError<&'a [u8], StompParseError>,
 {
     // The part that deals with the specified required and optional headers
-    let known_headers = init_known_header_parser(required, optional, allows_custom);
+    let known_headers = init_known_header_parser(required, optional, allows_custom, config);
 
     // The part that deals with any other headers encountered
     //let custom_header_parser_provider = custom_header_parser_provider_factory(allows_custom);
@@ -65,52 +69,76 @@ where
 
 fn find_header<'a, 'b, E>(
     name: &'a str,
-    required: &'b Vec<HeaderType>,
-    optional: &'b Vec<HeaderType>,
+    required: &'b [HeaderType],
+    optional: &'b [HeaderType],
     allows_custom: bool,
+    config: &'b ParserConfig,
 ) -> Result<Box<dyn HeaderParser<'a, E> + 'a>, StompParseError>
 where
     'a: 'b,
     E: 'a + FullError<&'a [u8], StompParseError>,
 {
-    required
-        .iter()
-        .find(|header_type| header_type.matches(name))
-        .or_else(|| {
-            optional
-                .iter()
-                .find(|header_type| header_type.matches(name))
-        })
-        .map(|header_type| {
-            Ok(known_header_parser::<'a, E>(find_header_parser(
+    find_header_type(name, required, optional, config)
+        .map(|header_type| match header_type {
+            HeaderType::HeartBeat if config.lenient_heart_beat => {
+                Ok(lenient_heart_beat_parser::<'a, E>())
+            }
+            _ => Ok(known_header_parser::<'a, E>(find_header_parser(
                 *header_type,
-            )))
+            ))),
         })
         .unwrap_or_else(|| {
             if allows_custom {
-                Ok(known_header_parser::<'a, E>(Box::new(
-                    move |value: &str| {
-                        Ok(Header::Custom(CustomValue::new(
-                            unsafe { std::mem::transmute::<&'a str, &'static str>(name) },
-                            unsafe { std::mem::transmute::<&'_ str, &'static str>(value) },
-                        )))
-                    },
-                )))
+                Ok(custom_header_parser::<'a, E>(name))
             } else {
                 Err(StompParseError::new(format!("Unknown header: {}", name)))
             }
         })
 }
 
+/// The type of the header `name`, if it is one of `required` or `optional`.
+fn find_header_type<'b>(
+    name: &str,
+    required: &'b [HeaderType],
+    optional: &'b [HeaderType],
+    config: &ParserConfig,
+) -> Option<&'b HeaderType> {
+    let matches = |header_type: &&HeaderType| {
+        if config.case_insensitive_headers {
+            header_type.matches_ignore_case(name)
+        } else {
+            header_type.matches(name)
+        }
+    };
+
+    required
+        .iter()
+        .find(matches)
+        .or_else(|| optional.iter().find(matches))
+}
+
+/// True if the header `name` is allowed by a parser created by `headers_parser` with the same
+/// arguments.
+pub fn allows_header(
+    name: &str,
+    required: &[HeaderType],
+    optional: &[HeaderType],
+    allows_custom: bool,
+    config: &ParserConfig,
+) -> bool {
+    allows_custom || find_header_type(name, required, optional, config).is_some()
+}
+
 fn init_known_header_parser<'a, E>(
     required: Vec<HeaderType>,
     optional: Vec<HeaderType>,
     allows_custom: bool,
+    config: ParserConfig,
 ) -> impl Fn(&'a str) -> Result<Box<dyn HeaderParser<'a, E>>, StompParseError> + 'a
 where
     E: 'a + FullError<&'a [u8], StompParseError>,
 {
-    move |name: &'a str| find_header(name, &required, &optional, allows_custom)
+    move |name: &'a str| find_header(name, &required, &optional, allows_custom, &config)
 }
 
 fn header_section<'a, E: FullError<&'a [u8], StompParseError>>(
@@ -119,7 +147,7 @@ fn header_section<'a, E: FullError<&'a [u8], StompParseError>>(
     escaped(is_not("\\:\n\r"), '\\', one_of("rnc\\"))(input)
 }
 
-fn into_string<'a>(input: &'a [u8]) -> Result<&'a str, StompParseError> {
+fn into_string(input: &[u8]) -> Result<&str, StompParseError> {
     std::str::from_utf8(input).map_err(|_| StompParseError::new("bytes are not utf8"))
 }
 
@@ -141,6 +169,19 @@ fn header_value<'a, E: FullError<&'a [u8], StompParseError>>(
     )(input)
 }
 
+/// Accepts an empty value, which `header_value` does not, as well as the other malformed values
+/// accepted by `HeartBeatIntervalls::parse_lenient`.
+fn lenient_heart_beat_parser<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
+) -> Box<dyn HeaderParser<'a, E>> {
+    Box::new(map_res(
+        alt((header_value, map(line_ending, |_| ""))),
+        |value: &'a str| {
+            HeartBeatIntervalls::parse_lenient(value)
+                .map(|intervalls| Header::HeartBeat(HeartBeatValue::new(intervalls)))
+        },
+    ))
+}
+
 fn disallowed_header_parser<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
     name: &'a str,
 ) -> Box<dyn HeaderParser<'a, E>> {
@@ -152,8 +193,16 @@ fn disallowed_header_parser<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
     }))
 }
 
+fn custom_header_parser<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
+    name: &'a str,
+) -> Box<dyn HeaderParser<'a, E>> {
+    Box::new(map_res(header_value, move |value: &'a str| {
+        Ok::<_, StompParseError>(Header::Custom(CustomValue::new(name, value)))
+    }))
+}
+
 fn known_header_parser<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
-    parser: Box<dyn Fn(&str) -> Result<Header, StompParseError> + 'a>,
+    parser: Box<HeaderValueConverter<'a>>,
 ) -> Box<dyn HeaderParser<'a, E>> {
     Box::new(map_res(header_value, parser))
 }
@@ -165,6 +214,7 @@ mod tests {
     use nom::error::VerboseError;
 
     use super::headers_parser;
+    use crate::config::ParserConfig;
     use crate::error::{FullError, StompParseError};
     use crate::model::headers::*;
     use nom::IResult;
@@ -172,7 +222,7 @@ mod tests {
 
     fn header<E: 'static + FullError<&'static [u8], StompParseError> + std::fmt::Debug>(
         input: &'static [u8],
-    ) -> IResult<&'static [u8], Header, E> {
+    ) -> IResult<&'static [u8], Header<'static>, E> {
         headers(input).map(|x| {
             let bytes = x.0;
             let mut vec = x.1;
@@ -181,7 +231,7 @@ mod tests {
     }
     fn headers<E: 'static + FullError<&'static [u8], StompParseError> + std::fmt::Debug>(
         input: &'static [u8],
-    ) -> IResult<&'static [u8], Vec<Header>, E> {
+    ) -> IResult<&'static [u8], Vec<Header<'static>>, E> {
         dbg_dmp(
             |input| {
                 headers_parser(
@@ -192,6 +242,7 @@ mod tests {
                         HeaderType::Host,
                     ],
                     true,
+                    &ParserConfig::default(),
                 )
                 .parse(input)
             },
@@ -203,7 +254,7 @@ mod tests {
         E: 'static + FullError<&'static [u8], StompParseError> + std::fmt::Debug,
     >(
         input: &'static [u8],
-    ) -> IResult<&'static [u8], Vec<Header>, E> {
+    ) -> IResult<&'static [u8], Vec<Header<'static>>, E> {
         dbg_dmp(
             |input| {
                 headers_parser(
@@ -214,6 +265,7 @@ mod tests {
                         HeaderType::Host,
                     ],
                     false,
+                    &ParserConfig::default(),
                 )
                 .parse(input)
             },
@@ -458,7 +510,7 @@ mod tests {
             b"heart-beat:10,20\r\nabc:d\\nef\n\n",
         );
 
-        assert_eq!(true, result.is_err());
+        assert!(result.is_err());
     }
 
     #[test]
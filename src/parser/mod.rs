@@ -4,41 +4,214 @@ use nom::bytes::complete::is_not;
 use nom::character::complete::{char, line_ending};
 use nom::combinator::eof;
 use nom::error::context;
-use nom::sequence::terminated;
+use nom::multi::many0;
+use nom::sequence::{preceded, terminated};
 use nom::{IResult, Needed};
 
+use crate::config::ContentLengthPolicy;
 use crate::error::FullError;
-use crate::error::StompParseError;
+use crate::error::{ErrorKind, StompParseError};
 
 pub fn null<'a, E: 'a + FullError<&'a [u8], StompParseError>>(
     input: &'a [u8],
-) -> IResult<&[u8], &'a [u8], E> {
+) -> IResult<&'a [u8], &'a [u8], E> {
     terminated(
         context("Null Octet", char('\x00')),
-        context("Data after null", eof),
+        context("Data after null", preceded(many0(line_ending), eof)),
     )(input)
-    .map(|(rem, _)| (rem, &input[input.len() - 1..]))
+    .map(|(rem, _)| (rem, &input[..1]))
 }
 
 pub fn command_line<'a, E: FullError<&'a [u8], E2>, E2>(
     input: &'a [u8],
-) -> IResult<&[u8], &'a [u8], E> {
+) -> IResult<&'a [u8], &'a [u8], E> {
     terminated(is_not("\r\n"), line_ending)(input)
 }
 
 pub fn remaining_without_null<'a, E: FullError<&'a [u8], StompParseError>>(
     input: &'a [u8],
 ) -> IResult<&'a [u8], &'a [u8], E> {
-    match input.split_last() {
+    match trim_trailing_eols(input).split_last() {
         Some((&0u8, bytes)) => Ok((&bytes[0..0], bytes)),
         _ => Err(nom::Err::Incomplete(Needed::Unknown)),
     }
 }
 
+/// The body of a complete frame, where `input` follows its head, delimited according to `policy`
+/// when the frame has a `content-length` header.
+pub fn body(
+    input: &[u8],
+    content_length: Option<usize>,
+    policy: ContentLengthPolicy,
+) -> Result<&[u8], StompParseError> {
+    let (_, body) = remaining_without_null::<nom::error::VerboseError<&[u8]>>(input)
+        .map_err(|_| incomplete(NOT_TERMINATED))?;
+
+    match (content_length, policy) {
+        (Some(content_length), ContentLengthPolicy::Strict) if content_length != body.len() => {
+            Err(content_length_mismatch(content_length))
+        }
+        (Some(content_length), ContentLengthPolicy::TrustContentLength) => {
+            // The null octet after the body may be the one ending the input, or an earlier one
+            match body.get(content_length) {
+                None if content_length == body.len() => Ok(body),
+                Some(0) => Ok(&body[..content_length]),
+                _ => Err(content_length_mismatch(content_length)),
+            }
+        }
+        // As when decoding, the body ends at the first null octet
+        (_, ContentLengthPolicy::TrustNull) => Ok(body.split(|b| *b == 0).next().unwrap_or(body)),
+        _ => Ok(body),
+    }
+}
+
+/// The error for a frame whose command line, at the start of `input`, could not be parsed.
+pub fn command_error(input: &[u8]) -> StompParseError {
+    match input.iter().position(|b| *b == b'\n') {
+        None => incomplete("Frame ends in its command line"),
+        Some(end) if matches!(&input[..end], b"" | b"\r") => {
+            StompParseError::with_kind(ErrorKind::MissingCommand, "Missing command")
+        }
+        Some(_) => StompParseError::new("Malformed command line"),
+    }
+}
+
+pub fn unknown_command(command: &str) -> StompParseError {
+    StompParseError::with_kind(
+        ErrorKind::UnknownCommand {
+            command: command.to_owned(),
+        },
+        format!("Unknown command {}", command),
+    )
+}
+
+pub fn missing_header(name: &str) -> StompParseError {
+    StompParseError::with_kind(
+        ErrorKind::MissingHeader {
+            name: name.to_owned(),
+        },
+        format!("Missing required header {}", name),
+    )
+}
+
+pub fn unexpected_header(name: &str) -> StompParseError {
+    StompParseError::with_kind(
+        ErrorKind::UnexpectedHeader {
+            name: name.to_owned(),
+        },
+        format!("Unexpected header {}", name),
+    )
+}
+
+/// The error for a frame whose headers, at the start of `input`, could not be parsed, where
+/// `allows_header` determines whether a header is allowed in the frame.
+pub fn headers_error(input: &[u8], allows_header: impl Fn(&str) -> bool) -> StompParseError {
+    for line in input.split_inclusive(|b| *b == b'\n') {
+        let line = match line.strip_suffix(b"\n") {
+            Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+            None => break,
+        };
+        if line.is_empty() {
+            return StompParseError::new("Error parsing frame");
+        }
+        if let Some(separator) = line.iter().position(|b| *b == b':') {
+            let name = String::from_utf8_lossy(&line[..separator]);
+            if !allows_header(&name) {
+                return unexpected_header(&name);
+            }
+        }
+    }
+
+    incomplete("Frame ends in its headers")
+}
+
+/// The error for a frame without a body whose headers are followed by `input`, which could not
+/// be parsed.
+pub fn end_error(input: &[u8]) -> StompParseError {
+    if input.contains(&0) {
+        StompParseError::new("Error parsing frame")
+    } else {
+        incomplete(NOT_TERMINATED)
+    }
+}
+
+const NOT_TERMINATED: &str = "Frame not terminated by a null octet";
+
+fn incomplete(message: &str) -> StompParseError {
+    StompParseError::with_kind(ErrorKind::Incomplete, message)
+}
+
+pub fn content_length_mismatch(content_length: usize) -> StompParseError {
+    StompParseError::with_kind(
+        ErrorKind::ContentLengthMismatch { content_length },
+        "Frame body not terminated after content-length bytes",
+    )
+}
+
+/// Checks the header lines of the serialised frame `frame` for null octets, and for carriage
+/// returns and line feeds within a header, which would otherwise cause the frame to be truncated or
+/// fail to parse without saying why.
+pub fn check_header_bytes(frame: &[u8]) -> Result<(), StompParseError> {
+    let mut offset = match frame.iter().position(|b| *b == b'\n') {
+        Some(index) => index + 1,
+        None => return Ok(()),
+    };
+    let mut previous: Option<&[u8]> = None;
+
+    for line in frame[offset..].split(|b| *b == b'\n') {
+        let content = line.strip_suffix(b"\r").unwrap_or(line);
+        if content.is_empty() {
+            break;
+        }
+
+        let name = content.split(|b| *b == b':').next().unwrap_or(content);
+        let invalid = match content.iter().position(|b| *b == b'\0' || *b == b'\r') {
+            Some(index) => Some((name, offset + index, content[index])),
+            // A line without a separator continues the previous header after a bare line feed
+            None if !content.contains(&b':') => {
+                previous.map(|previous| (previous, offset - 1, b'\n'))
+            }
+            None => None,
+        };
+
+        if let Some((header, offset, byte)) = invalid {
+            let header = String::from_utf8_lossy(header).into_owned();
+            return Err(StompParseError::with_kind(
+                ErrorKind::InvalidHeaderByte {
+                    header: header.clone(),
+                    offset,
+                    byte,
+                },
+                format!(
+                    "Invalid byte {:#04x} in header '{}' at offset {}",
+                    byte, header, offset
+                ),
+            ));
+        }
+
+        previous = Some(name);
+        offset += line.len() + 1;
+    }
+
+    Ok(())
+}
+
+/// Removes the EOLs which the specification allows to follow the null octet terminating a frame.
+pub fn trim_trailing_eols(mut input: &[u8]) -> &[u8] {
+    while let Some((b'\n', rest)) = input.split_last() {
+        input = rest.strip_suffix(b"\r").unwrap_or(rest);
+    }
+    input
+}
+
 #[cfg(test)]
 mod tests {
     use crate::client::ClientFrame;
-    use crate::headers::{AckType, HeartBeatIntervalls, StompVersion, StompVersions};
+    use crate::config::{ContentLengthPolicy, ParserConfig, Utf8Policy};
+    use crate::error::ErrorKind;
+    use crate::headers::{
+        AckType, Header, HeaderValue, HeartBeatIntervalls, StompVersion, StompVersions,
+    };
     use std::convert::TryFrom;
 
     #[test]
@@ -93,7 +266,7 @@ mod tests {
                 .to_owned(),
         );
 
-        assert!(matches!(frame, Err(_)));
+        assert!(frame.is_err());
     }
 
     #[test]
@@ -134,6 +307,18 @@ mod tests {
         assert!(frame.is_err());
     }
 
+    #[test]
+    fn it_accepts_eols_after_null() {
+        let frame = ClientFrame::try_from(b"BEGIN\ntransaction:trn-1\n\n\x00\n\r\n\n".to_vec());
+        assert!(frame.is_ok());
+
+        let frame = ClientFrame::try_from(b"SEND\ndestination:foo\n\nhello\n\x00\r\n".to_vec());
+        let Ok(ClientFrame::Send(frame)) = frame else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(b"hello\n", frame.body().unwrap());
+    }
+
     #[test]
     fn it_is_case_sensitive() {
         let frame = ClientFrame::try_from(
@@ -144,6 +329,163 @@ mod tests {
         assert!(frame.is_err());
     }
 
+    #[test]
+    fn it_is_case_sensitive_for_header_names_by_default() {
+        let frame = ClientFrame::try_from(b"SEND\nDestination:foo\n\nhello\x00".to_vec());
+
+        assert!(frame.is_err());
+    }
+
+    #[test]
+    fn it_optionally_ignores_case_of_header_names() {
+        let bytes =
+            b"SEND\nDestination:foo\nContent-Type:text/plain\nX-Funk:y\n\nhello\x00".to_vec();
+
+        let frame = ClientFrame::parse_with(bytes.clone(), &ParserConfig::lenient()).unwrap();
+
+        let ClientFrame::Send(frame) = frame else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!("foo", frame.destination().value());
+        assert_eq!("text/plain", frame.content_type().unwrap().value());
+        assert_eq!("X-Funk", frame.custom_headers()[0].header_name());
+
+        let serialised: Vec<u8> = frame.into();
+        assert_eq!(bytes, serialised);
+    }
+
+    #[test]
+    fn it_optionally_accepts_malformed_heart_beats() {
+        for (bytes, intervalls) in [
+            (
+                &b"CONNECT\nhost:h\naccept-version:1.2\nheart-beat: 10,20 \n\n\x00"[..],
+                (10, 20),
+            ),
+            (
+                b"CONNECT\nhost:h\naccept-version:1.2\nheart-beat:10\n\n\x00",
+                (10, 0),
+            ),
+            (
+                b"CONNECT\nhost:h\naccept-version:1.2\nheart-beat:\n\n\x00",
+                (0, 0),
+            ),
+        ] {
+            assert!(ClientFrame::try_from(bytes.to_vec()).is_err());
+
+            let ClientFrame::Connect(frame) =
+                ClientFrame::parse_with(bytes.to_vec(), &ParserConfig::lenient()).unwrap()
+            else {
+                panic!("Not a Connect Frame!")
+            };
+            assert_eq!(
+                HeartBeatIntervalls::new(intervalls.0, intervalls.1),
+                *frame.heartbeat().value()
+            );
+        }
+    }
+
+    #[test]
+    fn it_optionally_replaces_invalid_utf8_in_headers() {
+        let bytes = b"SEND\ndestination:caf\xe9\n\n\xe9t\xe9\x00".to_vec();
+
+        assert!(ClientFrame::try_from(bytes.clone()).is_err());
+
+        let config = ParserConfig {
+            utf8_policy: Utf8Policy::Lossy,
+            ..ParserConfig::default()
+        };
+        let ClientFrame::Send(frame) = ClientFrame::parse_with(bytes.clone(), &config).unwrap()
+        else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!("caf\u{fffd}", frame.destination().value());
+        assert_eq!(Some(&b"caf\xe9"[..]), frame.raw_header("destination"));
+        assert_eq!(b"\xe9t\xe9", frame.body().unwrap());
+        assert_eq!(&bytes[..], frame.as_bytes());
+        // Only the head is copied
+        assert!(frame.approximate_heap_size() < 2 * bytes.len());
+    }
+
+    #[test]
+    fn it_reports_control_characters_in_headers() {
+        let invalid_byte =
+            |bytes: &[u8]| match ClientFrame::try_from(bytes.to_vec()).unwrap_err().kind() {
+                ErrorKind::InvalidHeaderByte {
+                    header,
+                    offset,
+                    byte,
+                } => (header.clone(), *offset, *byte),
+                kind => panic!("Unexpected error kind {:?}", kind),
+            };
+
+        assert_eq!(
+            ("destination".to_owned(), 18, 0),
+            invalid_byte(b"SEND\ndestination:a\x00b\n\n\x00")
+        );
+        assert_eq!(
+            ("x-funk".to_owned(), 27, b'\r'),
+            invalid_byte(b"SEND\ndestination:a\nx-funk:a\rb\n\n\x00")
+        );
+        assert_eq!(
+            ("destination".to_owned(), 19, b'\n'),
+            invalid_byte(b"SEND\ndestination:ab\ncd\n\n\x00")
+        );
+    }
+
+    #[test]
+    fn first_occurrence_of_repeated_header_wins() {
+        let frame = ClientFrame::try_from(
+            b"SUBSCRIBE\ndestination:a\nid:1\ndestination:b\nack:client\nack:auto\n\n\x00".to_vec(),
+        )
+        .unwrap();
+
+        let ClientFrame::Subscribe(frame) = frame else {
+            panic!("Not a Subscribe Frame!")
+        };
+        assert_eq!("a", frame.destination().value());
+        assert_eq!(AckType::Client, *frame.ack_type().value());
+
+        let repeated = frame.repeated_headers();
+        assert_eq!(2, repeated.len());
+        assert!(matches!(&repeated[0], Header::Destination(value) if value.value() == "b"));
+        assert!(matches!(&repeated[1], Header::Ack(value) if value.value() == &AckType::Auto));
+    }
+
+    #[test]
+    fn first_occurrence_of_repeated_custom_header_wins() {
+        let frame =
+            ClientFrame::try_from(b"SEND\ndestination:a\nx:1\ny:2\nx:3\n\n\x00".to_vec()).unwrap();
+
+        let ClientFrame::Send(frame) = frame else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(2, frame.custom_headers().len());
+        assert_eq!("1", *frame.custom_headers()[0].value());
+
+        let repeated = frame.repeated_headers();
+        assert_eq!(1, repeated.len());
+        assert!(matches!(&repeated[0], Header::Custom(value) if value.value() == &"3"));
+    }
+
+    #[test]
+    fn repeated_custom_headers_ignore_case_when_configured() {
+        let bytes = b"SEND\ndestination:a\nx:1\nX:2\nx:3\n\n\x00";
+
+        let Ok(ClientFrame::Send(strict)) = ClientFrame::try_from(bytes.to_vec()) else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(2, strict.custom_headers().len());
+        assert_eq!(1, strict.repeated_headers().len());
+
+        let Ok(ClientFrame::Send(lenient)) =
+            ClientFrame::parse_with(bytes.to_vec(), &ParserConfig::lenient())
+        else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(1, lenient.custom_headers().len());
+        assert_eq!(2, lenient.repeated_headers().len());
+    }
+
     #[test]
     fn it_doesnt_match_rubbish() {
         let frame =
@@ -378,4 +720,72 @@ mod tests {
         assert_eq!("foo", frame.id().value());
         assert_eq!("recpt-x", frame.receipt().unwrap().value());
     }
+
+    #[test]
+    fn applies_content_length_policy() {
+        let body = |bytes: &[u8], policy| {
+            let config = ParserConfig {
+                content_length_policy: policy,
+                ..ParserConfig::default()
+            };
+            match ClientFrame::parse_with(bytes.to_vec(), &config) {
+                Ok(ClientFrame::Send(frame)) => Ok(frame.body().unwrap().to_vec()),
+                Ok(_) => panic!("Not a Send Frame!"),
+                Err(error) => Err(error.kind().clone()),
+            }
+        };
+
+        let bytes = b"SEND\ndestination:a\ncontent-length:3\n\nhello\x00";
+        assert_eq!(
+            Err(ErrorKind::ContentLengthMismatch { content_length: 3 }),
+            body(bytes, ContentLengthPolicy::Strict)
+        );
+        assert_eq!(
+            Ok(b"hello".to_vec()),
+            body(bytes, ContentLengthPolicy::TrustNull)
+        );
+        assert_eq!(
+            Err(ErrorKind::ContentLengthMismatch { content_length: 3 }),
+            body(bytes, ContentLengthPolicy::TrustContentLength)
+        );
+
+        let bytes = b"SEND\ndestination:a\ncontent-length:3\n\nhel\x00lo\x00";
+        assert_eq!(
+            Ok(b"hel".to_vec()),
+            body(bytes, ContentLengthPolicy::TrustContentLength)
+        );
+    }
+
+    #[test]
+    fn classifies_parse_errors() {
+        let kind = |bytes: &[u8]| {
+            ClientFrame::try_from(bytes.to_vec())
+                .unwrap_err()
+                .kind()
+                .clone()
+        };
+
+        assert_eq!(
+            ErrorKind::UnknownCommand {
+                command: "FOO".to_owned()
+            },
+            kind(b"FOO\n\n\x00")
+        );
+        assert_eq!(ErrorKind::MissingCommand, kind(b"\n\n\x00"));
+        assert_eq!(
+            ErrorKind::MissingHeader {
+                name: "destination".to_owned()
+            },
+            kind(b"SEND\n\n\x00")
+        );
+        assert_eq!(
+            ErrorKind::UnexpectedHeader {
+                name: "foo".to_owned()
+            },
+            kind(b"CONNECT\nhost:h\naccept-version:1.2\nfoo:bar\n\n\x00")
+        );
+        assert_eq!(ErrorKind::Incomplete, kind(b"SEND"));
+        assert_eq!(ErrorKind::Incomplete, kind(b"SEND\ndestination:a"));
+        assert_eq!(ErrorKind::Incomplete, kind(b"SEND\ndestination:a\n\nhi"));
+    }
 }
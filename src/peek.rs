@@ -0,0 +1,169 @@
+//! Parses the command and headers of a frame without touching its body, so that brokers can make
+//! routing and access decisions before deciding whether to copy the payload, or even before it has
+//! been received.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::peek::peek_headers;
+//!
+//! let buffer = b"SEND\ndestination:/queue/a\ncontent-length:5\n\nhel";
+//! let peeked = peek_headers(buffer).unwrap().unwrap();
+//!
+//! assert_eq!("SEND", peeked.command);
+//! assert_eq!(Some("/queue/a"), peeked.header("destination"));
+//! assert_eq!(44..49, peeked.body);
+//! assert!(!peeked.is_complete(buffer));
+//! ```
+use std::borrow::Cow;
+use std::ops::Range;
+
+use either::Either;
+
+use crate::common::functions::decode_str;
+use crate::config::{ContentLengthPolicy, ParserConfig};
+use crate::decoder::{body_end, head_len};
+use crate::error::{ErrorKind, StompParseError};
+use crate::parser::check_header_bytes;
+use crate::spans::{header_separator, lines};
+
+/// The command and headers of a frame, with the position of its body.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PeekedHeaders<'a> {
+    /// The command.
+    pub command: &'a str,
+    /// The names and unescaped values of the headers, in the order they appear, including
+    /// repeated headers.
+    pub headers: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+    /// The position of the body. When the frame has a `content-length` header this is known from
+    /// the head alone, so the range may extend beyond the bytes received so far.
+    pub body: Range<usize>,
+}
+
+impl<'a> PeekedHeaders<'a> {
+    /// The value of the first header with the specified name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Returns true if `bytes`, the buffer from which the headers were peeked, holds the whole
+    /// frame, including its terminating null octet.
+    pub fn is_complete(&self, bytes: &[u8]) -> bool {
+        bytes.get(self.body.end) == Some(&0)
+    }
+
+    /// The length of the frame, including the null octet but not any EOLs following it.
+    pub fn frame_len(&self) -> usize {
+        self.body.end + 1
+    }
+}
+
+/// Parses the command and headers of the frame at the start of `bytes`, using the default
+/// `ParserConfig`. Returns `None` if the head has not been completely received, or if the frame
+/// has no `content-length` header and its terminating null octet has not yet been received.
+///
+/// The body is neither copied nor validated.
+pub fn peek_headers(bytes: &[u8]) -> Result<Option<PeekedHeaders<'_>>, StompParseError> {
+    peek_headers_with(bytes, &ParserConfig::default())
+}
+
+/// Like `peek_headers`, but using the specified configuration.
+pub fn peek_headers_with<'a>(
+    bytes: &'a [u8],
+    config: &ParserConfig,
+) -> Result<Option<PeekedHeaders<'a>>, StompParseError> {
+    let (body_start, content_length) = match head_len(bytes, config)? {
+        Some(head) => head,
+        None => return Ok(None),
+    };
+
+    let body_end = match content_length {
+        Some(content_length) if config.content_length_policy != ContentLengthPolicy::TrustNull => {
+            body_end(body_start, content_length)?
+        }
+        _ => match bytes[body_start..].iter().position(|b| *b == 0) {
+            Some(null_index) => body_start + null_index,
+            None => return Ok(None),
+        },
+    };
+
+    let head = &bytes[..body_start];
+    check_header_bytes(head)?;
+    let head = std::str::from_utf8(head)
+        .map_err(|_| StompParseError::new("Frame head is not valid UTF-8"))?;
+
+    let mut lines = lines(head.as_bytes());
+    let command = &head[lines.next().unwrap_or_default()];
+    if command.is_empty() {
+        return Err(StompParseError::with_kind(
+            ErrorKind::MissingCommand,
+            "Missing command",
+        ));
+    }
+
+    let headers = lines
+        .take_while(|line| !line.is_empty())
+        .map(|line| {
+            let line = &head[line];
+            let separator = header_separator(line.as_bytes())?;
+            Ok((decode(&line[..separator])?, decode(&line[separator + 1..])?))
+        })
+        .collect::<Result<Vec<_>, StompParseError>>()?;
+
+    Ok(Some(PeekedHeaders {
+        command,
+        headers,
+        body: body_start..body_end,
+    }))
+}
+
+fn decode(raw: &str) -> Result<Cow<'_, str>, StompParseError> {
+    Ok(match decode_str(raw)? {
+        Either::Left(raw) => Cow::Borrowed(raw),
+        Either::Right(decoded) => Cow::Owned(decoded),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::peek_headers;
+    use crate::error::{ErrorKind, NumberError};
+
+    #[test]
+    fn peeks_without_the_body() {
+        let buffer = b"MESSAGE\r\nsubscription:0\nmessage-id:a\\cb\n\nhi\x00";
+
+        let peeked = peek_headers(buffer).unwrap().unwrap();
+        assert_eq!("MESSAGE", peeked.command);
+        assert_eq!(Some("a:b"), peeked.header("message-id"));
+        assert_eq!(41..43, peeked.body);
+        assert!(peeked.is_complete(buffer));
+        assert_eq!(buffer.len(), peeked.frame_len());
+
+        assert_eq!(None, peek_headers(&buffer[..42]).unwrap());
+        assert_eq!(None, peek_headers(&buffer[..30]).unwrap());
+
+        let buffer = b"SEND
+destination:a
+content-length:3
+
+";
+        let peeked = peek_headers(buffer).unwrap().unwrap();
+        assert_eq!(37..40, peeked.body);
+        assert!(!peeked.is_complete(buffer));
+        assert!(peek_headers(b"SEND\nbad\n\n\x00").is_err());
+        assert!(peek_headers(b"SEND\na:\\q\n\n\x00").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_content_length() {
+        let error = peek_headers(b"SEND\ncontent-length:18446744073709551615\n\n").unwrap_err();
+
+        assert_eq!(
+            &ErrorKind::InvalidContentLength(NumberError::Overflow),
+            error.kind()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Runtime-agnostic async IO: drives a `FrameDecoder`, and writes serialised frames, through
+//! poll-style read and write functions, such as those of `futures::io::{AsyncRead, AsyncWrite}`,
+//! used by async-std and smol, so that the crate can be used with any runtime without depending on
+//! one.
+//!
+//! The functions are passed as closures, so that the crate depends on no particular IO traits.
+//! With `futures::io`, for example:
+//!
+//! ```ignore
+//! let item = poll_fn(|cx| decoder.poll_decode(cx, |cx, buf| Pin::new(&mut reader).poll_read(cx, buf))).await?;
+//! ```
+use std::io;
+use std::task::{Context, Poll};
+
+use crate::decoder::{DecodableFrame, DecodedItem, FrameDecoder};
+use crate::error::StompParseError;
+use crate::storage::FrameAllocator;
+
+/// The number of bytes read at a time by `poll_decode`.
+const READ_LEN: usize = 8 * 1024;
+
+impl<F: DecodableFrame<A::Buffer>, A: FrameAllocator> FrameDecoder<F, A> {
+    /// Decodes the next item, reading more bytes with `poll_read` while none is complete.
+    /// `poll_read` has the signature of `AsyncRead::poll_read`, with the reader bound; it is not
+    /// called if an item can be decoded from the bytes already received.
+    ///
+    /// Returns `None` once the reader is exhausted between frames, and fails if it is exhausted
+    /// within a frame.
+    pub fn poll_decode<R>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut poll_read: R,
+    ) -> Poll<Result<Option<DecodedItem<F>>, StompParseError>>
+    where
+        R: FnMut(&mut Context<'_>, &mut [u8]) -> Poll<io::Result<usize>>,
+    {
+        let mut buf = [0; READ_LEN];
+        loop {
+            if let Some(item) = self.decode()? {
+                return Poll::Ready(Ok(Some(item)));
+            }
+
+            match poll_read(cx, &mut buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error.into())),
+                Poll::Ready(Ok(0)) if self.is_mid_frame() || self.is_discarding() => {
+                    return Poll::Ready(Err(StompParseError::new("Stream ended within a frame")))
+                }
+                Poll::Ready(Ok(0)) => return Poll::Ready(Ok(None)),
+                Poll::Ready(Ok(read)) => self.extend(&buf[..read]),
+            }
+        }
+    }
+}
+
+/// Serialised bytes, such as a frame, being written through a poll-style write function.
+#[derive(Debug, Clone)]
+pub struct PollWrite<B> {
+    bytes: B,
+    written: usize,
+}
+
+impl<B: AsRef<[u8]>> PollWrite<B> {
+    /// Starts writing `bytes`, such as those of a frame from `as_bytes` or `Vec::from`.
+    pub fn new(bytes: B) -> PollWrite<B> {
+        PollWrite { bytes, written: 0 }
+    }
+
+    /// Writes as many of the remaining bytes as `poll_write` accepts, until all have been
+    /// written. `poll_write` has the signature of `AsyncWrite::poll_write`, with the writer bound.
+    pub fn poll_write_all<W>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut poll_write: W,
+    ) -> Poll<io::Result<()>>
+    where
+        W: FnMut(&mut Context<'_>, &[u8]) -> Poll<io::Result<usize>>,
+    {
+        while self.written < self.bytes.as_ref().len() {
+            match poll_write(cx, &self.bytes.as_ref()[self.written..]) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(error)) if error.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(error)) => return Poll::Ready(Err(error)),
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole frame",
+                    )))
+                }
+                Poll::Ready(Ok(written)) => self.written += written,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Returns true once all the bytes have been written.
+    pub fn is_complete(&self) -> bool {
+        self.written == self.bytes.as_ref().len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::task::{Context, Poll, Waker};
+
+    use super::PollWrite;
+    use crate::client::ClientFrame;
+    use crate::decoder::{DecodedItem, FrameDecoder};
+
+    #[test]
+    fn decodes_from_pending_reader() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut chunks = vec![
+            Poll::Ready(Ok(&b"SEND\ndestination:a\n\nhi"[..])),
+            Poll::Pending,
+            Poll::Ready(Ok(&b"\x00SEND\n"[..])),
+            Poll::Ready(Ok(&b""[..])),
+        ]
+        .into_iter();
+        let mut read = |_: &mut Context<'_>, buf: &mut [u8]| {
+            chunks.next().unwrap().map(|chunk: std::io::Result<&[u8]>| {
+                chunk.map(|chunk| {
+                    buf[..chunk.len()].copy_from_slice(chunk);
+                    chunk.len()
+                })
+            })
+        };
+
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        assert!(decoder.poll_decode(&mut cx, &mut read).is_pending());
+        let Poll::Ready(Ok(Some(DecodedItem::Frame(ClientFrame::Send(_))))) =
+            decoder.poll_decode(&mut cx, &mut read)
+        else {
+            panic!("Send Frame not decoded")
+        };
+        assert!(matches!(
+            decoder.poll_decode(&mut cx, &mut read),
+            Poll::Ready(Err(_))
+        ));
+    }
+
+    #[test]
+    fn writes_in_parts() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut written = Vec::new();
+        let mut pending = true;
+        let mut write = |_: &mut Context<'_>, bytes: &[u8]| {
+            pending = !pending;
+            if pending {
+                return Poll::Pending;
+            }
+            let len = bytes.len().min(4);
+            written.extend_from_slice(&bytes[..len]);
+            Poll::Ready(Ok(len))
+        };
+
+        let mut frame = PollWrite::new(b"DISCONNECT\n\n\x00");
+        assert!(frame.poll_write_all(&mut cx, &mut write).is_pending());
+        while frame.poll_write_all(&mut cx, &mut write).is_pending() {}
+        assert!(frame.is_complete());
+        assert_eq!(b"DISCONNECT\n\n\x00".to_vec(), written);
+    }
+}
@@ -0,0 +1,342 @@
+//! Renders frames in human-readable forms suitable for logs and bug reports.
+use std::fmt::Write;
+
+use crate::common::raw::RawParts;
+use crate::error::StompParseError;
+
+/// The number of body bytes rendered by `pretty()` before the body is truncated.
+pub const DEFAULT_MAX_BODY_LEN: usize = 1024;
+
+const BYTES_PER_LINE: usize = 16;
+
+/// The number of characters of a header value rendered by `summary()` before it is truncated.
+pub const SUMMARY_MAX_VALUE_LEN: usize = 64;
+
+/// The headers rendered by `summary()` before the length of the body, with their labels.
+const SUMMARY_HEADERS: &[(&str, &str)] = &[
+    ("destination", "destination"),
+    ("id", "id"),
+    ("subscription", "subscription"),
+    ("message-id", "message-id"),
+    ("ack", "ack"),
+    ("receipt", "receipt"),
+    ("receipt-id", "receipt-id"),
+];
+
+/// The headers rendered by `summary()` after the length of the body, with their labels.
+const SUMMARY_TRAILING_HEADERS: &[(&str, &str)] = &[("transaction", "tx"), ("message", "message")];
+
+/// Renders the serialised frame `raw`: the command, the headers with their values aligned, and
+/// the body. A body which is valid UTF-8 text is rendered as is; any other body is rendered as a
+/// hex and ASCII dump. At most `max_body_len` bytes of the body are rendered.
+pub(crate) fn pretty(raw: &[u8], max_body_len: usize) -> String {
+    let RawParts {
+        command,
+        headers,
+        body,
+    } = RawParts::split(raw);
+
+    let mut output = String::new();
+    writeln!(output, "{}", command).unwrap();
+
+    let name_width = headers
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, value) in &headers {
+        writeln!(output, "  {:width$} : {}", name, value, width = name_width).unwrap();
+    }
+
+    write_body(&mut output, body, max_body_len);
+
+    output
+}
+
+/// Renders the serialised frame `raw` on a single line: the command, the headers which identify
+/// the frame (such as `destination` and `transaction`), and the length of the body, if any, such as
+/// `SEND destination=/queue/a len=1432 tx=tx1`. Long values are truncated.
+pub(crate) fn summary(raw: &[u8]) -> String {
+    let RawParts {
+        command,
+        headers,
+        body,
+    } = RawParts::split(raw);
+
+    let mut output = command.into_owned();
+    let write_headers = |output: &mut String, labels: &[(&str, &str)]| {
+        for (name, label) in labels {
+            if let Some((_, value)) = headers.iter().find(|(header, _)| header == name) {
+                write_summary_value(output, label, value);
+            }
+        }
+    };
+
+    write_headers(&mut output, SUMMARY_HEADERS);
+    if !body.is_empty() {
+        write_summary_value(&mut output, "len", &body.len().to_string());
+    }
+    write_headers(&mut output, SUMMARY_TRAILING_HEADERS);
+
+    output
+}
+
+fn write_summary_value(output: &mut String, label: &str, value: &str) {
+    write!(output, " {}=", label).unwrap();
+    match value.char_indices().nth(SUMMARY_MAX_VALUE_LEN) {
+        Some((end, _)) => write!(output, "{}...", &value[..end]).unwrap(),
+        None => output.push_str(value),
+    }
+}
+
+/// Renders the serialised frame `raw` exactly, on a single line, with backslashes, control
+/// characters and non-ASCII bytes escaped: `\\`, `\n`, `\r`, `\t` and `\0`, and `\xHH` for
+/// other bytes. `parse_wire_dump` reconstructs the bytes.
+pub fn wire_dump(raw: &[u8]) -> String {
+    let mut output = String::with_capacity(raw.len() + raw.len() / 8);
+    for byte in raw {
+        match byte {
+            b'\\' => output.push_str("\\\\"),
+            b'\n' => output.push_str("\\n"),
+            b'\r' => output.push_str("\\r"),
+            b'\t' => output.push_str("\\t"),
+            b'\0' => output.push_str("\\0"),
+            b' '..=b'~' => output.push(char::from(*byte)),
+            _ => write!(output, "\\x{:02x}", byte).unwrap(),
+        }
+    }
+    output
+}
+
+/// The bytes rendered by `wire_dump` as `dump`.
+pub fn parse_wire_dump(dump: &str) -> Result<Vec<u8>, StompParseError> {
+    let invalid = || StompParseError::new(format!("Invalid wire dump: {}", dump));
+
+    let mut bytes = Vec::with_capacity(dump.len());
+    let mut input = dump.bytes();
+    while let Some(byte) = input.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        bytes.push(match input.next().ok_or_else(invalid)? {
+            b'\\' => b'\\',
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'0' => b'\0',
+            b'x' => {
+                let digits = [
+                    input.next().ok_or_else(invalid)?,
+                    input.next().ok_or_else(invalid)?,
+                ];
+                std::str::from_utf8(&digits)
+                    .ok()
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        });
+    }
+    Ok(bytes)
+}
+
+/// Renders the serialised frame `raw` as a JSON object, with the command, the headers as an array
+/// of name and value pairs, and either the `body` as a string, if it is valid UTF-8, or the
+/// `body_hex` as a string of hex digits.
+pub(crate) fn json(raw: &[u8]) -> String {
+    let RawParts {
+        command,
+        headers,
+        body,
+    } = RawParts::split(raw);
+
+    let mut output = String::from("{\"command\":");
+    write_json_string(&mut output, &command);
+
+    output.push_str(",\"headers\":[");
+    for (index, (name, value)) in headers.iter().enumerate() {
+        if index > 0 {
+            output.push(',');
+        }
+        output.push('[');
+        write_json_string(&mut output, name);
+        output.push(',');
+        write_json_string(&mut output, value);
+        output.push(']');
+    }
+    output.push(']');
+
+    match std::str::from_utf8(body) {
+        Ok(text) => {
+            output.push_str(",\"body\":");
+            write_json_string(&mut output, text);
+        }
+        Err(_) => {
+            output.push_str(",\"body_hex\":\"");
+            for byte in body {
+                write!(output, "{:02x}", byte).unwrap();
+            }
+            output.push('"');
+        }
+    }
+
+    output.push('}');
+    output
+}
+
+/// Writes `value` as a JSON string. Control characters, and the characters which some JavaScript
+/// engines treat as line terminators (as SockJS requires), are escaped.
+pub(crate) fn write_json_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                write!(output, "\\u{:04x}", c as u32).unwrap()
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn write_body(output: &mut String, body: &[u8], max_body_len: usize) {
+    if body.is_empty() {
+        output.push_str("(no body)\n");
+        return;
+    }
+
+    let shown = &body[..body.len().min(max_body_len)];
+
+    match std::str::from_utf8(body) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+            writeln!(output, "body ({} bytes, text):", body.len()).unwrap();
+            // Truncate on a character boundary, so that the text remains valid
+            let end = (0..=shown.len())
+                .rev()
+                .find(|end| text.is_char_boundary(*end))
+                .unwrap_or(0);
+            for line in text[..end].lines() {
+                writeln!(output, "  {}", line).unwrap();
+            }
+        }
+        _ => {
+            writeln!(output, "body ({} bytes, binary):", body.len()).unwrap();
+            for (index, chunk) in shown.chunks(BYTES_PER_LINE).enumerate() {
+                write_hex_line(output, index * BYTES_PER_LINE, chunk);
+            }
+        }
+    }
+
+    if body.len() > shown.len() {
+        writeln!(output, "  ... ({} more bytes)", body.len() - shown.len()).unwrap();
+    }
+}
+
+fn write_hex_line(output: &mut String, offset: usize, chunk: &[u8]) {
+    write!(output, "  {:08x} ", offset).unwrap();
+
+    for index in 0..BYTES_PER_LINE {
+        match chunk.get(index) {
+            Some(byte) => write!(output, " {:02x}", byte).unwrap(),
+            None => output.push_str("   "),
+        }
+    }
+
+    output.push_str("  |");
+    output.extend(chunk.iter().map(|byte| {
+        if byte.is_ascii_graphic() || *byte == b' ' {
+            *byte as char
+        } else {
+            '.'
+        }
+    }));
+    output.push_str("|\n");
+}
+
+#[cfg(test)]
+mod test {
+    use super::{json, parse_wire_dump, pretty, summary, wire_dump};
+
+    #[test]
+    fn renders_text_body_and_aligned_headers() {
+        let rendered = pretty(
+            b"SEND\ndestination:/queue/a\nreceipt:1\n\nhello\nworld\x00\n",
+            1024,
+        );
+
+        assert_eq!(
+            "SEND\n  destination : /queue/a\n  receipt     : 1\nbody (11 bytes, text):\n  hello\n  world\n",
+            rendered
+        );
+    }
+
+    #[test]
+    fn renders_binary_body_as_hexdump() {
+        let rendered = pretty(b"SEND\ndestination:a\n\n\x00\x01AB\x00", 1024);
+
+        assert!(rendered
+            .contains("  00000000  00 01 41 42                                      |..AB|\n"));
+    }
+
+    #[test]
+    fn truncates_body() {
+        let rendered = pretty(b"SEND\ndestination:a\n\n0123456789\x00", 4);
+
+        assert!(rendered.ends_with("  0123\n  ... (6 more bytes)\n"));
+    }
+
+    #[test]
+    fn summarises_on_one_line() {
+        assert_eq!(
+            "SEND destination=/queue/a len=5 tx=tx1",
+            summary(b"SEND\ntransaction:tx1\ndestination:/queue/a\nx:y\n\nhello\x00")
+        );
+
+        let long = format!("MESSAGE\nmessage-id:{}\n\n\x00", "\u{e9}".repeat(100));
+        assert_eq!(
+            format!("MESSAGE message-id={}...", "\u{e9}".repeat(64)),
+            summary(long.as_bytes())
+        );
+    }
+
+    #[test]
+    fn dumps_wire_bytes_reversibly() {
+        let raw = b"SEND\r\npath:c:\\x\n\ncaf\xc3\xa9\t\x01\x00\n";
+        let dump = wire_dump(raw);
+
+        assert_eq!(
+            "SEND\\r\\npath:c:\\\\x\\n\\ncaf\\xc3\\xa9\\t\\x01\\0\\n",
+            dump
+        );
+        assert_eq!(raw.to_vec(), parse_wire_dump(&dump).unwrap());
+
+        assert!(parse_wire_dump("a\\").is_err());
+        assert!(parse_wire_dump("\\xg0").is_err());
+        assert!(parse_wire_dump("\\q").is_err());
+    }
+
+    #[test]
+    fn renders_json() {
+        assert_eq!(
+            r#"{"command":"SEND","headers":[["destination","/queue/\"a\""]],"body":"hi\n"}"#,
+            json(b"SEND\ndestination:/queue/\"a\"\n\nhi\n\x00")
+        );
+        assert_eq!(
+            r#"{"command":"SEND","headers":[],"body_hex":"00ff"}"#,
+            json(b"SEND\n\n\x00\xff\x00")
+        );
+    }
+
+    #[test]
+    fn renders_missing_body() {
+        let rendered = pretty(b"BEGIN\ntransaction:a\n\n\x00", 4);
+
+        assert_eq!("BEGIN\n  transaction : a\n(no body)\n", rendered);
+    }
+}
@@ -0,0 +1,124 @@
+//! Reads frames from a blocking `Read`, such as a `TcpStream` or a file, for command line tools and
+//! simple synchronous clients which do not need an async runtime.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::decoder::DecodedItem;
+//! use stomp_parser::reader::FrameReader;
+//! use stomp_parser::server::ServerFrame;
+//!
+//! let input = &b"CONNECTED\nversion:1.2\n\n\x00\nRECEIPT\nreceipt-id:1\n\n\x00"[..];
+//! let items: Vec<_> = FrameReader::<_, ServerFrame>::new(input)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//!
+//! assert_eq!(3, items.len());
+//! assert!(matches!(items[1], DecodedItem::HeartBeat));
+//! ```
+use std::io::{ErrorKind, Read};
+
+use crate::any::AnyFrame;
+use crate::decoder::{DecodableFrame, DecodedItem, FrameDecoder};
+use crate::transport::TransportError;
+
+/// The size of the buffer into which bytes are read.
+pub const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// An iterator over the frames and heartbeats read from `R`, decoded as frames of type `F`.
+///
+/// A frame which fails to parse is yielded as an error, and reading continues with the next frame.
+/// Iteration ends when the reader is exhausted between frames, or after an error which is fatal.
+pub struct FrameReader<R, F = AnyFrame> {
+    reader: R,
+    decoder: FrameDecoder<F>,
+    buffer: Box<[u8]>,
+    finished: bool,
+}
+
+impl<R: Read, F: DecodableFrame> FrameReader<R, F> {
+    /// Creates a reader which decodes with the default configuration, and reports heartbeats.
+    pub fn new(reader: R) -> Self {
+        Self::with_decoder(reader, FrameDecoder::new().report_heartbeats(true))
+    }
+
+    /// Creates a reader which decodes with `decoder`, such as one with a custom configuration.
+    pub fn with_decoder(reader: R, decoder: FrameDecoder<F>) -> Self {
+        FrameReader {
+            reader,
+            decoder,
+            buffer: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+            finished: false,
+        }
+    }
+
+    /// The underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consumes the frame reader, returning the underlying reader. Any bytes read but not yet
+    /// decoded are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read, F: DecodableFrame> Iterator for FrameReader<R, F> {
+    type Item = Result<DecodedItem<F>, TransportError<std::io::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let reader = &mut self.reader;
+        let result = self.decoder.read_item(&mut self.buffer, |buffer| loop {
+            match reader.read(buffer) {
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                result => return result,
+            }
+        });
+
+        match result {
+            Ok(item) => {
+                self.finished = item.is_none();
+                item.map(Ok)
+            }
+            Err(error) => {
+                self.finished = error.is_fatal();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FrameReader;
+    use crate::client::ClientFrame;
+    use crate::decoder::DecodedItem;
+    use crate::transport::TransportError;
+
+    #[test]
+    fn continues_after_invalid_frame() {
+        let input = &b"SEND\n\nhello\x00BEGIN\ntransaction:t\n\n\x00"[..];
+        let mut reader = FrameReader::<_, ClientFrame>::new(input);
+
+        assert!(matches!(reader.next(), Some(Err(TransportError::Parse(_)))));
+        assert!(matches!(
+            reader.next(),
+            Some(Ok(DecodedItem::Frame(ClientFrame::Begin(_))))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn stops_at_truncated_frame() {
+        let input = &b"BEGIN\ntransaction:t\n\n\x00SEND\ndestination:a\n\nhel"[..];
+        let mut reader = FrameReader::<_, ClientFrame>::new(input);
+
+        assert!(matches!(reader.next(), Some(Ok(DecodedItem::Frame(_)))));
+        assert!(matches!(reader.next(), Some(Err(TransportError::Closed))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,94 @@
+//! Rewrites a single header of a serialised frame within its bytes, leaving every other byte as it
+//! was received, for proxies which change one header, such as mapping destination prefixes, and
+//! would otherwise parse, model and serialise each frame.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::rewrite::map_header;
+//!
+//! let mut bytes = b"SEND\ndestination:/queue/a\ncontent-length:2\n\nhi\x00".to_vec();
+//!
+//! map_header(&mut bytes, "destination", |value| value.replacen("/queue/", "/queue/tenant.", 1))
+//!     .unwrap();
+//! assert_eq!(
+//!     &b"SEND\ndestination:/queue/tenant.a\ncontent-length:2\n\nhi\x00"[..],
+//!     &bytes[..]
+//! );
+//! ```
+use either::Either;
+
+use crate::common::functions::{decode_str, encode_str};
+use crate::error::{ErrorKind, StompParseError};
+use crate::spans::parse_spans;
+
+/// Replaces the value of the first header named `name` in the serialised frame `bytes` with
+/// `value`, which is escaped as required. Returns false, leaving `bytes` unchanged, if the frame has
+/// no such header. Later occurrences of the header, which do not determine its value, are left as
+/// they are.
+///
+/// Only the structure of the frame is parsed; fails if `bytes` does not begin with a complete
+/// frame.
+pub fn rewrite_header(
+    bytes: &mut Vec<u8>,
+    name: &str,
+    value: &str,
+) -> Result<bool, StompParseError> {
+    map_header(bytes, name, |_| value.to_owned())
+}
+
+/// Replaces the value of the first header named `name` in the serialised frame `bytes` with the
+/// result of `f`, which is passed the current, unescaped, value. Otherwise as `rewrite_header`.
+pub fn map_header<F: FnOnce(&str) -> String>(
+    bytes: &mut Vec<u8>,
+    name: &str,
+    f: F,
+) -> Result<bool, StompParseError> {
+    let spans = parse_spans(bytes)?.ok_or_else(|| {
+        StompParseError::with_kind(ErrorKind::Incomplete, "Incomplete frame for header rewrite")
+    })?;
+
+    let name = encode_str(name);
+    let header = match spans
+        .headers
+        .iter()
+        .find(|header| &bytes[header.name.clone()] == name.as_bytes())
+    {
+        Some(header) => header.value.clone(),
+        None => return Ok(false),
+    };
+
+    let current = std::str::from_utf8(&bytes[header.clone()])
+        .map_err(|_| StompParseError::new(format!("Header {} is not valid UTF-8", name)))?;
+    let replacement = match decode_str(current)? {
+        Either::Left(current) => f(current),
+        Either::Right(current) => f(&current),
+    };
+
+    bytes.splice(header, encode_str(&replacement).into_bytes());
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{map_header, rewrite_header};
+
+    #[test]
+    fn rewrites_only_the_first_occurrence() {
+        let mut bytes = b"MESSAGE\r\nx:a\\cb\nx:2\nsubscription:0\n\n\x00\n".to_vec();
+
+        assert!(map_header(&mut bytes, "x", |value| value.replace(':', "\n")).unwrap());
+        assert_eq!(
+            &b"MESSAGE\r\nx:a\\nb\nx:2\nsubscription:0\n\n\x00\n"[..],
+            &bytes[..]
+        );
+
+        assert!(rewrite_header(&mut bytes, "subscription", "12").unwrap());
+        assert_eq!(
+            &b"MESSAGE\r\nx:a\\nb\nx:2\nsubscription:12\n\n\x00\n"[..],
+            &bytes[..]
+        );
+
+        assert!(!rewrite_header(&mut bytes, "y", "1").unwrap());
+        assert!(rewrite_header(&mut b"SEND\nx:1\n\n".to_vec(), "x", "2").is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Checks the frames received from a client against the state of its session, so that a server
+//! can detect protocol violations in one place and answer them with the ERROR frame the
+//! specification requires.
+//!
+//! # Example
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::client::ClientFrame;
+//! use stomp_parser::session::SessionState;
+//!
+//! let mut session = SessionState::new();
+//! let send = ClientFrame::try_from(b"SEND\ndestination:a\nreceipt:r1\n\n\x00".to_vec()).unwrap();
+//!
+//! let error = session.check(&send).unwrap_err();
+//! assert_eq!("r1", *error.custom_header("receipt-id").unwrap().value());
+//! ```
+use std::collections::{HashMap, HashSet};
+
+use crate::client::ClientFrame;
+use crate::common::functions::decode_or_raw;
+use crate::common::raw::raw_header;
+use crate::headers::{AckType, HeaderName, StompVersion};
+use crate::server::{ErrorFrame, ErrorFrameBuilder};
+use crate::storage::Storage;
+
+/// The state of a client's session, as determined by the frames it has sent.
+#[derive(Debug, Clone, Default)]
+pub struct SessionState {
+    connected: bool,
+    version: Option<StompVersion>,
+    subscriptions: HashMap<String, AckType>,
+    transactions: HashSet<String>,
+}
+
+impl SessionState {
+    /// The state of a session which has not yet been connected.
+    pub fn new() -> SessionState {
+        SessionState::default()
+    }
+
+    /// Returns true if the client has connected, and not since disconnected.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The version negotiated for the session, if it has been recorded.
+    pub fn version(&self) -> Option<&StompVersion> {
+        self.version.as_ref()
+    }
+
+    /// Records the version negotiated for the session, as sent in the CONNECTED frame.
+    pub fn set_version(&mut self, version: StompVersion) {
+        self.version = Some(version);
+    }
+
+    /// The ids of the client's active subscriptions.
+    pub fn subscriptions(&self) -> impl Iterator<Item = &str> {
+        self.subscriptions.keys().map(String::as_str)
+    }
+
+    /// The client's open transactions.
+    pub fn transactions(&self) -> impl Iterator<Item = &str> {
+        self.transactions.iter().map(String::as_str)
+    }
+
+    /// Checks `frame` against the session's state, updating the state if it is allowed. If it is
+    /// not, returns the ERROR frame to send, which echoes the frame's `receipt` header, if any, as
+    /// `receipt-id`; as the specification requires, the server must then close the connection.
+    #[allow(clippy::result_large_err)]
+    pub fn check<S: Storage>(&mut self, frame: &ClientFrame<S>) -> Result<(), ErrorFrame<'static>> {
+        self.apply(frame).map_err(|reason| {
+            let receipt = raw_header(frame.as_bytes(), HeaderName::Receipt.as_str())
+                .map(|receipt| decode_or_raw(&String::from_utf8_lossy(receipt)));
+            violation(reason, receipt)
+        })
+    }
+
+    fn apply<S: Storage>(&mut self, frame: &ClientFrame<S>) -> Result<(), String> {
+        match frame {
+            ClientFrame::Connect(_) if self.connected => {
+                return Err("Already connected".to_owned());
+            }
+            ClientFrame::Connect(_) => {
+                self.connected = true;
+                return Ok(());
+            }
+            _ if !self.connected => return Err("Not connected".to_owned()),
+            _ => {}
+        }
+
+        match frame {
+            ClientFrame::Disconnect(_) => {
+                *self = SessionState::new();
+            }
+            ClientFrame::Subscribe(subscribe) => {
+                let id = subscribe.id().value();
+                if self.subscriptions.contains_key(id) {
+                    return Err(format!("Subscription {} already exists", id));
+                }
+                self.subscriptions
+                    .insert(id.to_owned(), subscribe.ack_type().value().clone());
+            }
+            ClientFrame::Unsubscribe(unsubscribe) => {
+                let id = unsubscribe.id().value();
+                if self.subscriptions.remove(id).is_none() {
+                    return Err(format!("Unknown subscription {}", id));
+                }
+            }
+            ClientFrame::Begin(begin) => {
+                let transaction = begin.transaction().value();
+                if !self.transactions.insert(transaction.to_owned()) {
+                    return Err(format!("Transaction {} already begun", transaction));
+                }
+            }
+            ClientFrame::Commit(commit) => self.end_transaction(commit.transaction().value())?,
+            ClientFrame::Abort(abort) => self.end_transaction(abort.transaction().value())?,
+            ClientFrame::Ack(ack) => {
+                self.check_acknowledgement("ACK")?;
+                self.check_transaction(ack.transaction().value())?;
+            }
+            ClientFrame::Nack(nack) => {
+                if self.version == Some(StompVersion::V1_0) {
+                    return Err("NACK is not supported in STOMP 1.0".to_owned());
+                }
+                self.check_acknowledgement("NACK")?;
+                self.check_transaction(nack.transaction().value())?;
+            }
+            ClientFrame::Send(send) => {
+                if let Some(transaction) = send.transaction() {
+                    self.check_transaction(transaction.value())?;
+                }
+            }
+            ClientFrame::Connect(_) => {}
+        }
+
+        Ok(())
+    }
+
+    fn end_transaction(&mut self, transaction: &str) -> Result<(), String> {
+        if self.transactions.remove(transaction) {
+            Ok(())
+        } else {
+            Err(format!("Unknown transaction {}", transaction))
+        }
+    }
+
+    fn check_transaction(&self, transaction: &str) -> Result<(), String> {
+        if self.transactions.contains(transaction) {
+            Ok(())
+        } else {
+            Err(format!("Unknown transaction {}", transaction))
+        }
+    }
+
+    fn check_acknowledgement(&self, command: &str) -> Result<(), String> {
+        if self
+            .subscriptions
+            .values()
+            .any(|ack_type| *ack_type != AckType::Auto)
+        {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} received, but no subscription requires acknowledgement",
+                command
+            ))
+        }
+    }
+}
+
+fn violation(reason: String, receipt: Option<String>) -> ErrorFrame<'static> {
+    let mut builder = ErrorFrameBuilder::new()
+        .message(reason.clone())
+        .add_custom_header("content-type".to_owned(), "text/plain".to_owned());
+    if let Some(receipt) = receipt {
+        builder = builder.add_custom_header("receipt-id".to_owned(), receipt);
+    }
+
+    builder.body(reason.into_bytes()).build()
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::SessionState;
+    use crate::client::ClientFrame;
+    use crate::headers::StompVersion;
+
+    fn frame(bytes: &[u8]) -> ClientFrame {
+        ClientFrame::try_from(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn tracks_session_state() {
+        let mut session = SessionState::new();
+
+        assert!(session
+            .check(&frame(b"CONNECT\nhost:h\naccept-version:1.2\n\n\x00"))
+            .is_ok());
+        session.set_version(StompVersion::V1_2);
+
+        let steps: [&[u8]; 6] = [
+            b"SUBSCRIBE\ndestination:a\nid:1\nack:client\n\n\x00",
+            b"BEGIN\ntransaction:t1\n\n\x00",
+            b"SEND\ndestination:a\ntransaction:t1\n\n\x00",
+            b"ACK\nid:m1\ntransaction:t1\n\n\x00",
+            b"COMMIT\ntransaction:t1\n\n\x00",
+            b"UNSUBSCRIBE\nid:1\n\n\x00",
+        ];
+        for step in steps.iter() {
+            assert!(session.check(&frame(step)).is_ok());
+        }
+        assert_eq!(0, session.subscriptions().count());
+        assert_eq!(0, session.transactions().count());
+
+        assert!(session
+            .check(&frame(b"DISCONNECT\nreceipt:bye\n\n\x00"))
+            .is_ok());
+        assert!(!session.is_connected());
+        assert_eq!(None, session.version());
+    }
+
+    #[test]
+    fn reports_violations() {
+        let mut session = SessionState::new();
+        session
+            .check(&frame(b"CONNECT\nhost:h\naccept-version:1.0\n\n\x00"))
+            .unwrap();
+        session.set_version(StompVersion::V1_0);
+
+        let violations: [(&[u8], &str); 6] = [
+            (
+                b"CONNECT\nhost:h\naccept-version:1.0\n\n\x00",
+                "Already connected",
+            ),
+            (b"UNSUBSCRIBE\nid:9\n\n\x00", "Unknown subscription 9"),
+            (b"COMMIT\ntransaction:t9\n\n\x00", "Unknown transaction t9"),
+            (
+                b"ACK\nid:m1\ntransaction:t1\n\n\x00",
+                "ACK received, but no subscription requires acknowledgement",
+            ),
+            (
+                b"NACK\nid:m1\ntransaction:t1\n\n\x00",
+                "NACK is not supported in STOMP 1.0",
+            ),
+            (
+                b"SEND\ndestination:a\ntransaction:t9\n\n\x00",
+                "Unknown transaction t9",
+            ),
+        ];
+        for (bytes, message) in violations.iter() {
+            let error = session.check(&frame(bytes)).unwrap_err();
+            assert_eq!(*message, error.message().unwrap().value());
+        }
+
+        let error = SessionState::new()
+            .check(&frame(b"BEGIN\ntransaction:t1\nreceipt:77\n\n\x00"))
+            .unwrap_err();
+        assert_eq!("Not connected", error.message().unwrap().value());
+        assert_eq!("77", *error.custom_header("receipt-id").unwrap().value());
+    }
+}
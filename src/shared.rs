@@ -0,0 +1,120 @@
+//! Shares one parsed frame between many owners by reference counting, as a broker does when
+//! delivering a message to many subscribers, without copying the frame for each.
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A frame shared by reference counting; cloning it clones only the reference.
+///
+/// It dereferences to the frame, so provides the same accessors.
+///
+/// # Example
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use stomp_parser::server::{MessageFrame, ServerFrame};
+/// use stomp_parser::shared::SharedFrame;
+///
+/// let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(
+///     b"MESSAGE\nmessage-id:m1\ndestination:/topic/a\nsubscription:s1\n\nhello\x00".to_vec(),
+/// ) else {
+///     panic!("Message Frame not parsed correctly")
+/// };
+///
+/// let shared: SharedFrame<MessageFrame> = SharedFrame::new(frame);
+/// let copy = shared.clone();
+///
+/// assert_eq!("/topic/a", copy.destination().value());
+/// assert!(SharedFrame::ptr_eq(&shared, &copy));
+/// ```
+#[derive(Debug)]
+pub struct SharedFrame<F>(Arc<F>);
+
+impl<F> SharedFrame<F> {
+    /// Shares `frame`.
+    pub fn new(frame: F) -> SharedFrame<F> {
+        SharedFrame(Arc::new(frame))
+    }
+
+    /// Returns true if `this` and `other` share the same frame.
+    pub fn ptr_eq(this: &SharedFrame<F>, other: &SharedFrame<F>) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// The number of owners of the frame.
+    pub fn owners(this: &SharedFrame<F>) -> usize {
+        Arc::strong_count(&this.0)
+    }
+
+    /// Returns the frame if this is its only owner, and otherwise this shared frame unchanged.
+    pub fn try_unwrap(this: SharedFrame<F>) -> Result<F, SharedFrame<F>> {
+        Arc::try_unwrap(this.0).map_err(SharedFrame)
+    }
+}
+
+impl<F> Clone for SharedFrame<F> {
+    fn clone(&self) -> Self {
+        SharedFrame(Arc::clone(&self.0))
+    }
+}
+
+impl<F> Deref for SharedFrame<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.0
+    }
+}
+
+impl<F> AsRef<F> for SharedFrame<F> {
+    fn as_ref(&self) -> &F {
+        &self.0
+    }
+}
+
+impl<F> From<F> for SharedFrame<F> {
+    fn from(frame: F) -> SharedFrame<F> {
+        SharedFrame::new(frame)
+    }
+}
+
+impl<F> From<Arc<F>> for SharedFrame<F> {
+    fn from(frame: Arc<F>) -> SharedFrame<F> {
+        SharedFrame(frame)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::SharedFrame;
+    use crate::server::MessageFrameBuilder;
+
+    #[test]
+    fn shares_frame_between_subscribers() {
+        let frame = SharedFrame::new(
+            MessageFrameBuilder::new("m1".to_owned(), "/topic/a".to_owned(), "s1".to_owned())
+                .body(b"hello".to_vec())
+                .build(),
+        );
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let frame = frame.clone();
+                thread::spawn(move || {
+                    assert_eq!("m1", frame.message_id().value());
+                    frame.body().unwrap().as_ptr() as usize
+                })
+            })
+            .collect();
+
+        let body = frame.body().unwrap().as_ptr() as usize;
+        for handle in handles {
+            assert_eq!(body, handle.join().unwrap());
+        }
+
+        assert_eq!(1, SharedFrame::owners(&frame));
+        let frame = SharedFrame::try_unwrap(frame).unwrap();
+        assert_eq!(b"hello", frame.body().unwrap());
+    }
+}
@@ -0,0 +1,193 @@
+//! Serialises frames into a buffer supplied by the caller, without allocating, for constrained
+//! devices which must produce frames such as CONNECT and SEND with statically bounded memory.
+//! Writing uses only `core`, so does not depend on the allocator, and the module remains available
+//! when the crate is built without its default `std` feature.
+//!
+//! Unlike the builders, the writer does not check that the frame has the headers its command
+//! requires; it writes the command and headers as given, escaping header values as the
+//! specification requires. As with the builders, characters which cannot be written are replaced
+//! by U+FFFD.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::slice_writer::SliceWriter;
+//!
+//! let mut buffer = [0u8; 64];
+//! let len = SliceWriter::new(&mut buffer, "SEND")
+//!     .and_then(|writer| writer.header("destination", "/queue/a"))
+//!     .and_then(|writer| writer.body(b"hello"))
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     &b"SEND\ndestination:/queue/a\ncontent-length:5\n\nhello\x00"[..],
+//!     &buffer[..len]
+//! );
+//! ```
+use core::fmt;
+
+/// The error returned when a frame does not fit in the buffer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BufferTooSmall;
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("frame does not fit in the buffer")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BufferTooSmall {}
+
+/// Writes a frame into a fixed buffer. Each step consumes the writer, and `finish` or `body`
+/// returns the length of the complete frame.
+#[derive(Debug)]
+pub struct SliceWriter<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+    escape: bool,
+}
+
+impl<'b> SliceWriter<'b> {
+    /// Starts a frame with the command `command`.
+    pub fn new(buffer: &'b mut [u8], command: &str) -> Result<SliceWriter<'b>, BufferTooSmall> {
+        let mut writer = SliceWriter {
+            buffer,
+            len: 0,
+            // The headers of CONNECT and CONNECTED frames are not escaped
+            escape: !matches!(command, "CONNECT" | "CONNECTED"),
+        };
+        writer.write(command.as_bytes())?;
+        writer.write(b"\n")?;
+        Ok(writer)
+    }
+
+    /// Writes the header `name`, with the value `value`, escaping both if the command requires it.
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self, BufferTooSmall> {
+        self.write_escaped(name)?;
+        self.write(b":")?;
+        self.write_escaped(value)?;
+        self.write(b"\n")?;
+        Ok(self)
+    }
+
+    /// Ends a frame without a body, returning the length of the frame.
+    pub fn finish(mut self) -> Result<usize, BufferTooSmall> {
+        self.write(b"\n\0")?;
+        Ok(self.len)
+    }
+
+    /// Ends the frame with `body`, preceded by a `content-length` header, returning the length of
+    /// the frame.
+    pub fn body(mut self, body: &[u8]) -> Result<usize, BufferTooSmall> {
+        let mut digits = [0u8; 20];
+        self.write(b"content-length:")?;
+        self.write(decimal(body.len(), &mut digits))?;
+        self.write(b"\n\n")?;
+        self.write(body)?;
+        self.write(b"\0")?;
+        Ok(self.len)
+    }
+
+    fn write_escaped(&mut self, text: &str) -> Result<(), BufferTooSmall> {
+        for byte in text.bytes() {
+            match byte {
+                b'\0' => self.write("\u{fffd}".as_bytes())?,
+                b'\\' if self.escape => self.write(b"\\\\")?,
+                b'\n' if self.escape => self.write(b"\\n")?,
+                b'\r' if self.escape => self.write(b"\\r")?,
+                b':' if self.escape => self.write(b"\\c")?,
+                b'\n' | b'\r' | b':' => self.write("\u{fffd}".as_bytes())?,
+                byte => self.write(&[byte])?,
+            }
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), BufferTooSmall> {
+        let end = self.len + bytes.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Formats `value` in decimal into `digits`, returning the digits written.
+fn decimal(mut value: usize, digits: &mut [u8; 20]) -> &[u8] {
+    let mut start = digits.len();
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            return &digits[start..];
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{BufferTooSmall, SliceWriter};
+    use crate::client::{ClientFrame, ConnectFrameBuilder, SendFrameBuilder};
+    use crate::headers::{DecodableValue, StompVersion, StompVersions};
+
+    #[test]
+    fn writes_same_bytes_as_builders() {
+        let mut buffer = [0u8; 128];
+
+        let len = SliceWriter::new(&mut buffer, "CONNECT")
+            .and_then(|writer| writer.header("host", "a:b"))
+            .and_then(|writer| writer.header("accept-version", "1.2"))
+            .and_then(|writer| writer.header("heart-beat", "0,0"))
+            .and_then(SliceWriter::finish)
+            .unwrap();
+        let built: Vec<u8> =
+            ConnectFrameBuilder::new("a:b", StompVersions(vec![StompVersion::V1_2]))
+                .build()
+                .into();
+        assert_eq!(&built[..], &buffer[..len]);
+
+        let len = SliceWriter::new(&mut buffer, "SEND")
+            .and_then(|writer| writer.header("destination", "a/b"))
+            .and_then(|writer| writer.body(&[0; 12]))
+            .unwrap();
+        let built: Vec<u8> = SendFrameBuilder::new("a/b")
+            .content_length(12)
+            .body(vec![0; 12])
+            .build()
+            .into();
+        assert_eq!(&built[..], &buffer[..len]);
+    }
+
+    #[test]
+    fn escapes_header_values() {
+        let mut buffer = [0u8; 64];
+        let len = SliceWriter::new(&mut buffer, "SEND")
+            .and_then(|writer| writer.header("destination", "a:b\\c"))
+            .and_then(SliceWriter::finish)
+            .unwrap();
+
+        assert_eq!(b"SEND\ndestination:a\\cb\\\\c\n\n\x00", &buffer[..len]);
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(&buffer[..len]) else {
+            panic!("Not a Send Frame!")
+        };
+        let destination: String = frame.destination().decoded_value().unwrap().either_into();
+        assert_eq!("a:b\\c", destination);
+    }
+
+    #[test]
+    fn reports_full_buffer() {
+        let mut buffer = [0u8; 16];
+
+        assert_eq!(
+            Err(BufferTooSmall),
+            SliceWriter::new(&mut buffer, "SEND")
+                .and_then(|writer| writer.header("destination", "/queue/a"))
+                .and_then(|writer| writer.body(b"hello"))
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Encodes and decodes frames carried in [SockJS](https://github.com/sockjs/sockjs-protocol)
+//! messages, as used by stomp.js and Spring deployments. SockJS carries frames as JSON-escaped
+//! strings in arrays: servers send `a["frame",...]`, and clients send `["frame",...]`.
+//!
+//! # Example
+//! ```
+//! use std::convert::TryFrom;
+//!
+//! use stomp_parser::client::ClientFrame;
+//! use stomp_parser::sockjs::{self, SockJsMessage};
+//!
+//! let message = sockjs::encode_client(&["SEND\ndestination:/queue/a\n\nhello\u{0}"]);
+//! assert_eq!(r#"["SEND\ndestination:/queue/a\n\nhello\u0000"]"#, message);
+//!
+//! if let SockJsMessage::Frames(frames) = sockjs::decode_client(&message).unwrap() {
+//!     assert!(ClientFrame::try_from(frames[0].clone().into_bytes()).is_ok());
+//! } else {
+//!     panic!("Frames not decoded");
+//! }
+//! ```
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::error::StompParseError;
+use crate::pretty::write_json_string;
+
+/// A message received over SockJS.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SockJsMessage {
+    /// The session has been opened (`o`).
+    Open,
+    /// A SockJS heartbeat (`h`), which is distinct from STOMP heart-beats.
+    Heartbeat,
+    /// Frames, in the order sent.
+    Frames(Vec<String>),
+    /// The session has been closed (`c`), with the given code and reason.
+    Close { code: u32, reason: String },
+}
+
+/// The message carrying `frames` from a server to a client: `a["frame",...]`.
+pub fn encode<S: AsRef<str>>(frames: &[S]) -> String {
+    let mut message = String::from("a");
+    message.push_str(&encode_client(frames));
+    message
+}
+
+/// The message carrying `frames` from a client to a server: `["frame",...]`.
+pub fn encode_client<S: AsRef<str>>(frames: &[S]) -> String {
+    let mut message = String::from("[");
+    for (index, frame) in frames.iter().enumerate() {
+        if index > 0 {
+            message.push(',');
+        }
+        write_json_string(&mut message, frame.as_ref());
+    }
+    message.push(']');
+    message
+}
+
+/// Decodes a message sent by a server.
+pub fn decode(message: &str) -> Result<SockJsMessage, StompParseError> {
+    let mut chars = message.trim().chars().peekable();
+    match chars.next() {
+        Some('o') => expect_end(chars, SockJsMessage::Open),
+        Some('h') => expect_end(chars, SockJsMessage::Heartbeat),
+        Some('a') => {
+            let frames = read_array(&mut chars, read_json_string)?;
+            expect_end(chars, SockJsMessage::Frames(frames))
+        }
+        Some('c') => {
+            let mut code = None;
+            let mut reason = None;
+            let mut index = 0;
+            read_array(&mut chars, |chars| {
+                index += 1;
+                match index {
+                    1 => code = Some(read_json_number(chars)?),
+                    2 => reason = Some(read_json_string(chars)?),
+                    _ => return Err(invalid("too many elements in close message")),
+                }
+                Ok(())
+            })?;
+            match (code, reason) {
+                (Some(code), Some(reason)) => {
+                    expect_end(chars, SockJsMessage::Close { code, reason })
+                }
+                _ => Err(invalid("missing code or reason in close message")),
+            }
+        }
+        _ => Err(invalid("unknown message type")),
+    }
+}
+
+/// Decodes a message sent by a client, which is always an array of frames.
+pub fn decode_client(message: &str) -> Result<SockJsMessage, StompParseError> {
+    let mut chars = message.trim().chars().peekable();
+    let frames = read_array(&mut chars, read_json_string)?;
+    expect_end(chars, SockJsMessage::Frames(frames))
+}
+
+fn invalid(reason: &str) -> StompParseError {
+    StompParseError::new(format!("Invalid SockJS message: {}", reason))
+}
+
+fn expect_end(
+    mut chars: Peekable<Chars<'_>>,
+    message: SockJsMessage,
+) -> Result<SockJsMessage, StompParseError> {
+    match chars.next() {
+        None => Ok(message),
+        Some(_) => Err(invalid("data after message")),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars<'_>>) {
+    while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+}
+
+/// Reads a JSON array, reading each element with `read_element`.
+fn read_array<T, F>(
+    chars: &mut Peekable<Chars<'_>>,
+    mut read_element: F,
+) -> Result<Vec<T>, StompParseError>
+where
+    F: FnMut(&mut Peekable<Chars<'_>>) -> Result<T, StompParseError>,
+{
+    skip_whitespace(chars);
+    if chars.next() != Some('[') {
+        return Err(invalid("expected an array"));
+    }
+
+    let mut elements = Vec::new();
+    skip_whitespace(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Ok(elements);
+    }
+
+    loop {
+        skip_whitespace(chars);
+        elements.push(read_element(chars)?);
+        skip_whitespace(chars);
+        match chars.next() {
+            Some(',') => {}
+            Some(']') => return Ok(elements),
+            _ => return Err(invalid("expected ',' or ']'")),
+        }
+    }
+}
+
+fn read_json_number(chars: &mut Peekable<Chars<'_>>) -> Result<u32, StompParseError> {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    digits.parse().map_err(|_| invalid("expected a number"))
+}
+
+fn read_json_string(chars: &mut Peekable<Chars<'_>>) -> Result<String, StompParseError> {
+    if chars.next() != Some('"') {
+        return Err(invalid("expected a string"));
+    }
+
+    let mut string = String::new();
+    loop {
+        match chars.next().ok_or_else(|| invalid("unterminated string"))? {
+            '"' => return Ok(string),
+            '\\' => string.push(match chars.next() {
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('/') => '/',
+                Some('b') => '\u{8}',
+                Some('f') => '\u{c}',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => read_escaped_char(chars)?,
+                _ => return Err(invalid("invalid escape sequence")),
+            }),
+            c => string.push(c),
+        }
+    }
+}
+
+/// Reads the character escaped as `\uXXXX`, or as a surrogate pair `\uXXXX\uXXXX`, after the
+/// initial `\u`.
+fn read_escaped_char(chars: &mut Peekable<Chars<'_>>) -> Result<char, StompParseError> {
+    let high = read_hex4(chars)?;
+    if !(0xd800..0xdc00).contains(&high) {
+        return char::from_u32(high).ok_or_else(|| invalid("invalid escaped character"));
+    }
+
+    if chars.next() != Some('\\') || chars.next() != Some('u') {
+        return Err(invalid("unpaired surrogate"));
+    }
+    let low = read_hex4(chars)?;
+    if !(0xdc00..0xe000).contains(&low) {
+        return Err(invalid("unpaired surrogate"));
+    }
+    char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+        .ok_or_else(|| invalid("invalid escaped character"))
+}
+
+fn read_hex4(chars: &mut Peekable<Chars<'_>>) -> Result<u32, StompParseError> {
+    (0..4).try_fold(0, |value, _| {
+        chars
+            .next()
+            .and_then(|c| c.to_digit(16))
+            .map(|digit| value << 4 | digit)
+            .ok_or_else(|| invalid("invalid \\u escape"))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, decode_client, encode, SockJsMessage};
+
+    #[test]
+    fn round_trips_frames() {
+        let frames = [
+            "MESSAGE\nsubscription:1\n\n\"quoted\" \\ \u{2028} \u{1F600}\u{0}",
+            "RECEIPT\nreceipt-id:2\n\n\u{0}",
+        ];
+
+        let message = encode(&frames);
+        assert!(message.starts_with("a[\"MESSAGE\\nsubscription:1\\n\\n\\\"quoted\\\""));
+        assert!(message.contains("\\u2028"));
+        assert!(message.contains("\\u0000"));
+
+        assert_eq!(
+            SockJsMessage::Frames(frames.iter().map(|frame| frame.to_string()).collect()),
+            decode(&message).unwrap()
+        );
+    }
+
+    #[test]
+    fn decodes_control_messages() {
+        assert_eq!(SockJsMessage::Open, decode("o").unwrap());
+        assert_eq!(SockJsMessage::Heartbeat, decode("h").unwrap());
+        assert_eq!(
+            SockJsMessage::Close {
+                code: 3000,
+                reason: "Go away!".to_owned()
+            },
+            decode("c[3000,\"Go away!\"]").unwrap()
+        );
+        assert_eq!(
+            SockJsMessage::Frames(vec!["\u{1F600}/".to_owned()]),
+            decode_client(" [ \"\\ud83d\\ude00\\/\" ] ").unwrap()
+        );
+        assert_eq!(SockJsMessage::Frames(vec![]), decode("a[]").unwrap());
+
+        assert!(decode("x").is_err());
+        assert!(decode("a[\"unterminated]").is_err());
+        assert!(decode("a[\"\\ud83d\"]").is_err());
+        assert!(decode("o extra").is_err());
+    }
+}
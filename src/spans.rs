@@ -0,0 +1,267 @@
+//! Locates the parts of a serialised frame as ranges of byte positions, rather than as slices, for
+//! callers whose buffers the parser must not hold references into, such as ring buffers or
+//! registered IO buffers which are reused once the frame has been handled.
+//!
+//! Only the structure of the frame is parsed: header names and values are neither unescaped nor
+//! checked against those the frame's command allows.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::spans::parse_spans;
+//!
+//! let buffer = b"SEND\ndestination:/queue/a\n\nhello\x00";
+//! let spans = parse_spans(buffer).unwrap().unwrap();
+//!
+//! assert_eq!(b"SEND", &buffer[spans.command.clone()]);
+//! assert_eq!(Some(&b"/queue/a"[..]), spans.header(buffer, "destination"));
+//! assert_eq!(Some(27..32), spans.body);
+//! assert_eq!(buffer.len(), spans.len);
+//! ```
+use std::ops::Range;
+
+use crate::config::ParserConfig;
+use crate::decoder::{body_bounds, head_len};
+use crate::error::{ErrorKind, StompParseError};
+use crate::parser::check_header_bytes;
+
+/// The positions of a header's name and value, as they appear on the wire.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HeaderSpans {
+    pub name: Range<usize>,
+    pub value: Range<usize>,
+}
+
+impl HeaderSpans {
+    /// The position of the header's line, without its EOL.
+    pub fn line(&self) -> Range<usize> {
+        self.name.start..self.value.end
+    }
+}
+
+/// The positions of the parts of a frame within the buffer from which it was parsed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FrameSpans {
+    /// The command, without its EOL.
+    pub command: Range<usize>,
+    /// The headers, in the order they appear, including repeated headers.
+    pub headers: Vec<HeaderSpans>,
+    /// The body, or `None` if the frame has none; as for parsed frames, a frame has no body if
+    /// there are no bytes between its headers and the null octet, and it has no `content-length`
+    /// header.
+    pub body: Option<Range<usize>>,
+    /// The length of the frame, including the null octet but not any EOLs following it.
+    pub len: usize,
+}
+
+impl FrameSpans {
+    /// The value, as it appears on the wire, of the first header in `buffer` with the specified
+    /// name. `buffer` must be the buffer from which the spans were parsed.
+    pub fn header<'b>(&self, buffer: &'b [u8], name: &str) -> Option<&'b [u8]> {
+        self.headers
+            .iter()
+            .find(|header| &buffer[header.name.clone()] == name.as_bytes())
+            .map(|header| &buffer[header.value.clone()])
+    }
+}
+
+/// Locates the parts of the frame at the start of `buffer`, returning `None` if the frame has not
+/// yet been completely received.
+pub fn parse_spans(buffer: &[u8]) -> Result<Option<FrameSpans>, StompParseError> {
+    parse_spans_with(buffer, &ParserConfig::default())
+}
+
+/// Like `parse_spans`, but using the specified configuration.
+pub fn parse_spans_with(
+    buffer: &[u8],
+    config: &ParserConfig,
+) -> Result<Option<FrameSpans>, StompParseError> {
+    let bounds = match frame_bounds(buffer, config)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+
+    let head = &buffer[..bounds.body_start];
+    let command = lines(head).next().unwrap_or_default();
+
+    Ok(Some(FrameSpans {
+        command,
+        headers: header_spans(head)?,
+        body: bounds.body(),
+        len: bounds.len,
+    }))
+}
+
+/// The positions of the headers of the frame at the start of `buffer`, which must contain at least
+/// its head.
+pub(crate) fn header_spans(buffer: &[u8]) -> Result<Vec<HeaderSpans>, StompParseError> {
+    lines(buffer)
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .map(|line| {
+            let separator = header_separator(&buffer[line.clone()])?;
+            Ok(HeaderSpans {
+                name: line.start..line.start + separator,
+                value: line.start + separator + 1..line.end,
+            })
+        })
+        .collect()
+}
+
+/// The positions of the head and body of a complete frame.
+pub(crate) struct FrameBounds {
+    pub body_start: usize,
+    pub body_end: usize,
+    /// The length of the frame, including the null octet.
+    pub len: usize,
+    pub content_length: Option<usize>,
+}
+
+impl FrameBounds {
+    /// The body, or `None` if the frame has none.
+    pub fn body(&self) -> Option<Range<usize>> {
+        if self.body_start == self.body_end && self.content_length.is_none() {
+            None
+        } else {
+            Some(self.body_start..self.body_end)
+        }
+    }
+}
+
+/// Locates the head and body of the frame at the start of `buffer`, checking that the head has a
+/// command and contains no invalid bytes. Returns `None` if the frame is incomplete.
+pub(crate) fn frame_bounds(
+    buffer: &[u8],
+    config: &ParserConfig,
+) -> Result<Option<FrameBounds>, StompParseError> {
+    let (body_start, content_length) = match head_len(buffer, config)? {
+        Some(head) => head,
+        None => return Ok(None),
+    };
+
+    let (body_len, null_index) = match body_bounds(buffer, body_start, content_length, config)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+
+    check_header_bytes(&buffer[..body_start])?;
+
+    if lines(buffer).next().unwrap_or_default().is_empty() {
+        return Err(StompParseError::with_kind(
+            ErrorKind::MissingCommand,
+            "Missing command",
+        ));
+    }
+
+    Ok(Some(FrameBounds {
+        body_start,
+        body_end: body_start + body_len,
+        len: null_index + 1,
+        content_length,
+    }))
+}
+
+/// The position of the `:` separating the name and value of the header `line`.
+pub(crate) fn header_separator(line: &[u8]) -> Result<usize, StompParseError> {
+    line.iter().position(|b| *b == b':').ok_or_else(|| {
+        StompParseError::new(format!(
+            "Header without a separator: {}",
+            String::from_utf8_lossy(line)
+        ))
+    })
+}
+
+/// The positions of the lines of `head`, without their EOLs.
+pub(crate) fn lines(head: &[u8]) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start = 0;
+    head.split_inclusive(|b| *b == b'\n').map(move |line| {
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+        let range = start..start + content.len();
+        start += line.len();
+        range
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::{parse_spans, HeaderSpans};
+    use crate::client::ClientFrame;
+    use crate::config::ParserConfig;
+    use crate::error::{ErrorKind, NumberError};
+    use crate::rewrite::rewrite_header;
+
+    #[test]
+    fn locates_parts_of_frame() {
+        let buffer = b"MESSAGE\r\nx:1\ncontent-length:3\nx:2\n\na\x00b\x00\n\nCONNECTED";
+        let spans = parse_spans(buffer).unwrap().unwrap();
+
+        assert_eq!(0..7, spans.command);
+        assert_eq!(
+            HeaderSpans {
+                name: 9..10,
+                value: 11..12
+            },
+            spans.headers[0]
+        );
+        assert_eq!(3, spans.headers.len());
+        assert_eq!(Some(&b"1"[..]), spans.header(buffer, "x"));
+        assert_eq!(Some(&b"a\x00b"[..]), spans.body.map(|body| &buffer[body]));
+        assert_eq!(b'\x00', buffer[spans.len - 1]);
+    }
+
+    #[test]
+    fn rejects_overflowing_content_length() {
+        let buffer = b"SEND\ndestination:a\ncontent-length:18446744073709551615\n\nabc\x00";
+
+        let error = parse_spans(buffer).unwrap_err();
+        assert_eq!(
+            &ErrorKind::InvalidContentLength(NumberError::Overflow),
+            error.kind()
+        );
+        assert!(rewrite_header(&mut buffer.to_vec(), "destination", "b").is_err());
+    }
+
+    #[test]
+    fn distinguishes_empty_from_missing_body() {
+        let spans = parse_spans(b"BEGIN\ntransaction:a\n\n\x00")
+            .unwrap()
+            .unwrap();
+        assert_eq!(None, spans.body);
+
+        let spans = parse_spans(b"SEND\ncontent-length:0\n\n\x00")
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(23..23), spans.body);
+    }
+
+    #[test]
+    fn reports_incomplete_and_malformed_frames() {
+        assert_eq!(None, parse_spans(b"SEND\ndestination:a\n\nhel").unwrap());
+        assert_eq!(
+            None,
+            parse_spans(b"SEND\ncontent-length:5\n\nhel\x00").unwrap()
+        );
+        assert!(parse_spans(b"SEND\ndestination\n\n\x00").is_err());
+        assert!(parse_spans(b"SEND\ncontent-length:1\n\nhel\x00").is_err());
+    }
+
+    #[test]
+    fn records_spans_of_parsed_frames() {
+        let bytes = b"SEND\r\ndestination:a\r\nx:1\n\nhello\x00".to_vec();
+        let frame = ClientFrame::try_from(bytes.clone()).unwrap();
+        assert_eq!(None, frame.header_spans());
+
+        let config = ParserConfig {
+            record_header_spans: true,
+            ..ParserConfig::default()
+        };
+        let frame = ClientFrame::parse_with(bytes, &config).unwrap();
+        let spans = frame.header_spans().unwrap();
+
+        assert_eq!(2, spans.len());
+        assert_eq!(b"destination:a", &frame.as_bytes()[spans[0].line()]);
+        assert_eq!(b"1", &frame.as_bytes()[spans[1].value.clone()]);
+    }
+}
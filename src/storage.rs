@@ -0,0 +1,188 @@
+//! Abstracts over the storage holding the serialised form of a frame, so that frames can be parsed
+//! without copying from whatever holds the received bytes: a `Vec<u8>` owned by the frame, a
+//! reference-counted buffer such as `bytes::Bytes` shared with the network layer, a borrowed slice,
+//! or a memory-mapped file.
+
+/// Storage for the bytes of a frame.
+///
+/// A frame refers to its headers and body by their positions in the bytes returned by `as_ref`,
+/// which must therefore return the same bytes whenever it is called. If it does not, the frame's
+/// accessors may panic or return wrong values.
+pub trait Storage: AsRef<[u8]> {
+    /// Converts the storage into a `Vec<u8>`, copying the bytes only if it cannot be reused.
+    fn into_vec(self) -> Vec<u8>
+    where
+        Self: Sized,
+    {
+        self.as_ref().to_vec()
+    }
+}
+
+impl Storage for Vec<u8> {
+    fn into_vec(self) -> Vec<u8> {
+        self
+    }
+}
+
+impl Storage for Box<[u8]> {
+    fn into_vec(self) -> Vec<u8> {
+        self.into()
+    }
+}
+
+impl Storage for std::sync::Arc<[u8]> {}
+
+impl Storage for std::rc::Rc<[u8]> {}
+
+impl Storage for &[u8] {}
+
+#[cfg(feature = "bytes")]
+impl Storage for bytes::Bytes {
+    fn into_vec(self) -> Vec<u8> {
+        self.into()
+    }
+}
+
+/// Allocates the storage for each frame produced by a `FrameDecoder`, so that frames can be held
+/// in memory provided by the application, such as a pool or an arena.
+pub trait FrameAllocator {
+    /// The storage holding each frame.
+    type Buffer: Storage;
+
+    /// Returns storage holding a copy of `bytes`, the serialised form of one frame.
+    fn allocate(&self, bytes: &[u8]) -> Self::Buffer;
+}
+
+/// Allocates each frame as a `Vec<u8>` from the global allocator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlobalAllocator;
+
+impl FrameAllocator for GlobalAllocator {
+    type Buffer = Vec<u8>;
+
+    fn allocate(&self, bytes: &[u8]) -> Vec<u8> {
+        bytes.to_vec()
+    }
+}
+
+impl<B: Storage, A: Fn(&[u8]) -> B> FrameAllocator for A {
+    type Buffer = B;
+
+    fn allocate(&self, bytes: &[u8]) -> B {
+        self(bytes)
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl std::convert::TryFrom<bytes::Bytes> for crate::client::ClientFrame<bytes::Bytes> {
+    type Error = crate::error::StompParseError;
+    fn try_from(bytes: bytes::Bytes) -> Result<Self, crate::error::StompParseError> {
+        Self::parse_with(bytes, &crate::config::ParserConfig::default())
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl std::convert::TryFrom<bytes::Bytes> for crate::server::ServerFrame<bytes::Bytes> {
+    type Error = crate::error::StompParseError;
+    fn try_from(bytes: bytes::Bytes) -> Result<Self, crate::error::StompParseError> {
+        Self::parse_with(bytes, &crate::config::ParserConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use std::convert::TryFrom;
+
+    use crate::client::ClientFrame;
+    use crate::config::ParserConfig;
+    use crate::server::ServerFrame;
+
+    const FRAME: &[u8] = b"SEND\ndestination:/queue/a\n\nhello\x00";
+
+    #[test]
+    fn parses_from_borrowed_slice() {
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::parse_with(FRAME, &ParserConfig::default())
+        else {
+            panic!("Send Frame not parsed correctly")
+        };
+
+        assert_eq!("/queue/a", frame.destination().value());
+        assert_eq!(
+            FRAME[FRAME.len() - 6..].as_ptr(),
+            frame.body().unwrap().as_ptr()
+        );
+    }
+
+    #[test]
+    fn parses_from_shared_storage() {
+        let storage: Arc<[u8]> = Arc::from(FRAME);
+
+        let frame = ClientFrame::parse_with(storage.clone(), &ParserConfig::default()).unwrap();
+
+        assert_eq!(FRAME, frame.as_bytes());
+        assert!(Arc::ptr_eq(&storage, &frame.into_storage()));
+    }
+
+    #[test]
+    fn parses_server_frames_without_copying() {
+        const MESSAGE: &[u8] =
+            b"MESSAGE\ndestination:/queue/a\nmessage-id:1\nsubscription:0\n\nhi\x00";
+
+        let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(MESSAGE) else {
+            panic!("Message Frame not parsed correctly")
+        };
+        assert_eq!(MESSAGE.as_ptr(), frame.as_bytes().as_ptr());
+        assert_eq!(MESSAGE[20..].as_ptr(), frame.destination().value().as_ptr());
+        assert_eq!(
+            MESSAGE[MESSAGE.len() - 3..].as_ptr(),
+            frame.body().unwrap().as_ptr()
+        );
+
+        let owned = MESSAGE.to_vec();
+        let source = owned.as_ptr();
+        let Ok(ServerFrame::Message(frame)) = ServerFrame::try_from(owned) else {
+            panic!("Message Frame not parsed correctly")
+        };
+        assert_eq!(source, frame.as_bytes().as_ptr());
+    }
+
+    #[test]
+    fn returns_owned_write_buffer() {
+        let bytes = FRAME.to_vec();
+        let source = bytes.as_ptr();
+
+        let buffer = ClientFrame::try_from(bytes).unwrap().into_write_buffer();
+        assert_eq!(source, buffer.as_ptr());
+
+        let frame = ClientFrame::parse_with(Arc::<[u8]>::from(FRAME), &ParserConfig::default());
+        assert_eq!(FRAME, &frame.unwrap().into_write_buffer()[..]);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn parses_from_bytes() {
+        let storage = bytes::Bytes::from_static(FRAME);
+
+        let Ok(ClientFrame::Send(frame)) =
+            ClientFrame::parse_with(storage.clone(), &ParserConfig::default())
+        else {
+            panic!("Send Frame not parsed correctly")
+        };
+
+        assert_eq!(
+            storage.slice(27..32).as_ptr(),
+            frame.body().unwrap().as_ptr()
+        );
+
+        let storage = bytes::Bytes::from_static(b"RECEIPT\nreceipt-id:7\n\n\x00");
+        let Ok(ServerFrame::Receipt(frame)) = ServerFrame::try_from(storage.clone()) else {
+            panic!("Receipt Frame not parsed correctly")
+        };
+        assert_eq!(
+            storage.slice(19..20).as_ptr(),
+            frame.receipt_id().value().as_ptr()
+        );
+    }
+}
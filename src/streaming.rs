@@ -0,0 +1,557 @@
+//! Supports streaming the bodies of frames, so that large bodies need not be held in memory.
+//!
+//! When writing, a `StreamingFrame`, obtained from the `build_streaming` methods of
+//! `SendFrameBuilder` and `MessageFrameBuilder`, reads its body from a reader as it is written.
+//!
+//! When reading, a `StreamingDecoder` delivers the head of each frame as soon as it is received,
+//! and then the body in chunks, as it arrives, to a `FrameSink`.
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+use crate::client::SendFrameBuilder;
+use crate::config::{ContentLengthPolicy, ParserConfig};
+use crate::decoder::{frame_too_large, head_len, leading_eol_len, DecodableFrame};
+use crate::error::StompParseError;
+use crate::parser::content_length_mismatch;
+use crate::server::MessageFrameBuilder;
+
+/// The size of the chunks in which the body is copied from the reader to the writer.
+pub const CHUNK_SIZE: usize = 8 * 1024;
+
+/// A frame whose body is read from `R` while the frame is written.
+#[derive(Debug)]
+pub struct StreamingFrame<R> {
+    head: Vec<u8>,
+    body: R,
+    body_len: usize,
+}
+
+impl<R> StreamingFrame<R> {
+    /// The command and headers, including the blank line which ends them.
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    /// The length of the body, as given in the `content-length` header.
+    pub fn body_len(&self) -> usize {
+        self.body_len
+    }
+
+    fn missing_body_error(&self, copied: usize) -> std::io::Error {
+        std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!("Body ended after {} of {} bytes", copied, self.body_len),
+        )
+    }
+}
+
+impl<R: Read> StreamingFrame<R> {
+    /// Writes the frame to `writer`, copying the body from the reader in chunks of `CHUNK_SIZE`.
+    /// Returns an error if the reader provides fewer bytes than the length of the body; any
+    /// further bytes it could provide are not read.
+    pub fn write_to<W: Write>(mut self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(&self.head)?;
+
+        let mut chunk = vec![0u8; CHUNK_SIZE.min(self.body_len)];
+        let mut copied = 0;
+
+        while copied < self.body_len {
+            let wanted = chunk.len().min(self.body_len - copied);
+            match self.body.read(&mut chunk[..wanted]) {
+                Ok(0) => return Err(self.missing_body_error(copied)),
+                Ok(read) => {
+                    writer.write_all(&chunk[..read])?;
+                    copied += read;
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+        }
+
+        writer.write_all(b"\0")
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + Unpin> StreamingFrame<R> {
+    /// Like `write_to`, but reading the body from an `AsyncRead` and writing to an `AsyncWrite`.
+    pub async fn write_to_async<W: tokio::io::AsyncWrite + Unpin>(
+        mut self,
+        writer: &mut W,
+    ) -> std::io::Result<()> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        writer.write_all(&self.head).await?;
+
+        let mut chunk = vec![0u8; CHUNK_SIZE.min(self.body_len)];
+        let mut copied = 0;
+
+        while copied < self.body_len {
+            let wanted = chunk.len().min(self.body_len - copied);
+            match self.body.read(&mut chunk[..wanted]).await? {
+                0 => return Err(self.missing_body_error(copied)),
+                read => {
+                    writer.write_all(&chunk[..read]).await?;
+                    copied += read;
+                }
+            }
+        }
+
+        writer.write_all(b"\0").await
+    }
+}
+
+macro_rules! streaming_builder {
+    ( $($builder:ident),* ) => {
+        $(
+            impl $builder {
+                /// Builds a frame whose body of `body_len` bytes is read from `body` as the frame is
+                /// written. The `content-length` header is set to `body_len`, and any body previously
+                /// set is ignored.
+                pub fn build_streaming<R>(self, body: R, body_len: usize) -> StreamingFrame<R> {
+                    let (head, _) = self
+                        .content_length(body_len)
+                        .build_with_body([])
+                        .into_parts();
+
+                    StreamingFrame {
+                        head,
+                        body,
+                        body_len,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+streaming_builder!(SendFrameBuilder, MessageFrameBuilder);
+
+/// Receives the parts of the frames decoded by a `StreamingDecoder`.
+pub trait FrameSink<F> {
+    /// The head of a frame was received. `frame` has the frame's headers, but an empty body.
+    fn frame_head(&mut self, frame: F);
+
+    /// The next part of the body of the current frame was received.
+    fn body_chunk(&mut self, chunk: &[u8]);
+
+    /// The body of the current frame is complete.
+    fn frame_end(&mut self);
+
+    /// An EOL was received between frames.
+    fn heartbeat(&mut self) {}
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum State {
+    Head,
+    /// Within a body; the number of bytes remaining if given by a `content-length` header.
+    Body(Option<usize>),
+    /// Expecting the null octet after a body whose length was given by a `content-length` header.
+    Terminator,
+    /// Discarding the remainder of a frame in error.
+    Discard,
+}
+
+/// Decodes frames of type `F` from a stream of bytes without holding their bodies in memory. Only
+/// the head of a frame is buffered; the body is passed to the `FrameSink` directly from the bytes
+/// supplied to `feed`.
+///
+/// The end of a body is determined by the `content-length` header if present, otherwise by the
+/// first null octet, subject to the `content_length_policy` of the configuration. As only heads are
+/// buffered, the `max_frame_len` of the configuration limits the length of heads, a longer head
+/// being reported as `ErrorKind::FrameTooLarge`.
+pub struct StreamingDecoder<F> {
+    buffer: Vec<u8>,
+    /// How far the buffered bytes have been searched for the end of a head.
+    scanned: usize,
+    /// Parses heads, which are parsed without their bodies, so with `TrustNull`.
+    config: ParserConfig,
+    policy: ContentLengthPolicy,
+    /// The `content-length` of the frame being decoded.
+    content_length: usize,
+    state: State,
+    frame: PhantomData<F>,
+}
+
+impl<F: DecodableFrame> Default for StreamingDecoder<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: DecodableFrame> StreamingDecoder<F> {
+    /// Creates a decoder using the default `ParserConfig`.
+    pub fn new() -> Self {
+        Self::with_config(ParserConfig::default())
+    }
+
+    /// Creates a decoder which parses the heads of frames using the provided configuration.
+    pub fn with_config(config: ParserConfig) -> Self {
+        let policy = config.content_length_policy;
+        StreamingDecoder {
+            buffer: Vec::new(),
+            scanned: 0,
+            config: ParserConfig {
+                content_length_policy: ContentLengthPolicy::TrustNull,
+                ..config
+            },
+            policy,
+            content_length: 0,
+            state: State::Head,
+            frame: PhantomData,
+        }
+    }
+
+    /// Decodes the received `bytes`, passing the parts of frames to `sink` as they become
+    /// available.
+    ///
+    /// If an error is returned, the bytes of the offending frame are discarded; any bytes after
+    /// it are retained, and decoded by the next call.
+    pub fn feed<S: FrameSink<F>>(
+        &mut self,
+        bytes: &[u8],
+        sink: &mut S,
+    ) -> Result<(), StompParseError> {
+        if self.buffer.is_empty() {
+            return self.process(bytes, sink);
+        }
+
+        // Only part of a head is buffered, so the bytes are not decoded until it is complete, or
+        // too long, and only the bytes not already searched are searched for its end
+        self.buffer.extend_from_slice(bytes);
+        let search_from = self.scanned.saturating_sub(2);
+        let complete = self.buffer[search_from..]
+            .windows(2)
+            .any(|window| window == b"\n\n" || window == b"\n\r");
+        if !complete && !self.exceeds_limit(self.buffer.len()) {
+            self.scanned = self.buffer.len();
+            return Ok(());
+        }
+
+        let input = std::mem::take(&mut self.buffer);
+        self.scanned = 0;
+        self.process(&input, sink)
+    }
+
+    /// Like `feed`, but decoding the bytes held by `buf`, which need not be contiguous, chunk by
+    /// chunk, consuming them from `buf`.
+    #[cfg(feature = "bytes")]
+    pub fn feed_buf<B: bytes::Buf, S: FrameSink<F>>(
+        &mut self,
+        buf: &mut B,
+        sink: &mut S,
+    ) -> Result<(), StompParseError> {
+        while buf.has_remaining() {
+            let chunk_len = buf.chunk().len();
+            let result = self.feed(buf.chunk(), sink);
+            buf.advance(chunk_len);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Discards any partially decoded frame and any bytes retained from earlier calls to `feed`,
+    /// so that decoding starts afresh with the next bytes fed, with the same configuration. A sink
+    /// which has received part of a frame is not notified.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.scanned = 0;
+        self.state = State::Head;
+    }
+
+    fn process<S: FrameSink<F>>(
+        &mut self,
+        mut input: &[u8],
+        sink: &mut S,
+    ) -> Result<(), StompParseError> {
+        while !input.is_empty() {
+            match self.step(input, sink) {
+                Ok(consumed) => input = &input[consumed..],
+                Err(error) => {
+                    self.state = State::Discard;
+                    let consumed = self.step(input, sink)?;
+                    self.buffer.extend_from_slice(&input[consumed..]);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decodes the next part of `input`, returning the number of bytes consumed.
+    fn step<S: FrameSink<F>>(
+        &mut self,
+        input: &[u8],
+        sink: &mut S,
+    ) -> Result<usize, StompParseError> {
+        match self.state {
+            State::Head => match leading_eol_len(input) {
+                None => self.buffer_head(input),
+                Some(0) => match head_len(input, &self.config)? {
+                    None => self.buffer_head(input),
+                    Some((head_len, content_length)) => {
+                        let mut head = input[..head_len].to_vec();
+                        head.push(0);
+                        sink.frame_head(F::decode_frame(head, &self.config)?);
+
+                        self.content_length = content_length.unwrap_or_default();
+                        self.state = match content_length {
+                            _ if self.policy == ContentLengthPolicy::TrustNull => State::Body(None),
+                            Some(0) => State::Terminator,
+                            remaining => State::Body(remaining),
+                        };
+                        Ok(head_len)
+                    }
+                },
+                Some(eol_len) => {
+                    sink.heartbeat();
+                    Ok(eol_len)
+                }
+            },
+            State::Body(Some(remaining)) => {
+                let chunk_len = remaining.min(input.len());
+                sink.body_chunk(&input[..chunk_len]);
+                if chunk_len == remaining {
+                    self.state = State::Terminator;
+                } else {
+                    self.state = State::Body(Some(remaining - chunk_len));
+                }
+                Ok(chunk_len)
+            }
+            State::Body(None) => match input.iter().position(|b| *b == 0) {
+                Some(null_index) => {
+                    if null_index > 0 {
+                        sink.body_chunk(&input[..null_index]);
+                    }
+                    sink.frame_end();
+                    self.state = State::Head;
+                    Ok(null_index + 1)
+                }
+                None => {
+                    sink.body_chunk(input);
+                    Ok(input.len())
+                }
+            },
+            State::Terminator => match input.first() {
+                Some(0) => {
+                    sink.frame_end();
+                    self.state = State::Head;
+                    Ok(1)
+                }
+                Some(_) => Err(content_length_mismatch(self.content_length)),
+                None => Ok(0),
+            },
+            State::Discard => match input.iter().position(|b| *b == 0) {
+                Some(null_index) => {
+                    self.state = State::Head;
+                    Ok(null_index + 1)
+                }
+                None => Ok(input.len()),
+            },
+        }
+    }
+
+    fn buffer_head(&mut self, input: &[u8]) -> Result<usize, StompParseError> {
+        if self.exceeds_limit(input.len()) {
+            return Err(frame_too_large(
+                input,
+                self.config.max_frame_len.unwrap_or_default(),
+            ));
+        }
+
+        self.buffer.extend_from_slice(input);
+        self.scanned = self.buffer.len();
+        Ok(input.len())
+    }
+
+    fn exceeds_limit(&self, len: usize) -> bool {
+        self.config.max_frame_len.is_some_and(|max| len > max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::io::Read;
+
+    use super::{FrameSink, StreamingDecoder};
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::config::ParserConfig;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn streams_body_in_chunks() {
+        let body: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+
+        let frame = SendFrameBuilder::new("/queue/a".to_owned())
+            .build_streaming(body.as_slice(), body.len());
+
+        let mut written = Vec::new();
+        frame.write_to(&mut written).unwrap();
+
+        let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(written) else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!(Some(&20_000), frame.content_length().map(|len| len.value()));
+        assert_eq!(body.as_slice(), frame.body().unwrap());
+    }
+
+    #[test]
+    fn reads_only_body_len_bytes() {
+        let mut source: &[u8] = b"hello world";
+
+        let frame = SendFrameBuilder::new("/queue/a".to_owned()).build_streaming(&mut source, 5);
+
+        let mut written = Vec::new();
+        frame.write_to(&mut written).unwrap();
+
+        assert!(written.ends_with(b"\n\nhello\x00"));
+
+        let mut rest = String::new();
+        source.read_to_string(&mut rest).unwrap();
+        assert_eq!(" world", rest);
+    }
+
+    #[test]
+    fn short_body_is_an_error() {
+        let frame = SendFrameBuilder::new("/queue/a".to_owned()).build_streaming(&b"abc"[..], 5);
+
+        let error = frame.write_to(&mut Vec::new()).unwrap_err();
+
+        assert_eq!(std::io::ErrorKind::UnexpectedEof, error.kind());
+    }
+
+    #[derive(Default)]
+    struct CollectingSink {
+        heads: Vec<ClientFrame>,
+        body: Vec<u8>,
+        chunks: usize,
+        ends: usize,
+    }
+
+    impl FrameSink<ClientFrame> for CollectingSink {
+        fn frame_head(&mut self, frame: ClientFrame) {
+            self.heads.push(frame);
+        }
+
+        fn body_chunk(&mut self, chunk: &[u8]) {
+            self.body.extend_from_slice(chunk);
+            self.chunks += 1;
+        }
+
+        fn frame_end(&mut self) {
+            self.ends += 1;
+        }
+    }
+
+    #[test]
+    fn delivers_head_then_body_chunks() {
+        let mut decoder = StreamingDecoder::<ClientFrame>::new();
+        let mut sink = CollectingSink::default();
+
+        let input = b"SEND\ndestination:a\ncontent-length:4\n\n\x00\x01\x02\x03\x00\n\
+            SEND\ndestination:b\n\nhello\x00";
+
+        for byte in input.iter() {
+            decoder.feed(std::slice::from_ref(byte), &mut sink).unwrap();
+        }
+
+        assert_eq!(2, sink.heads.len());
+        let ClientFrame::Send(frame) = &sink.heads[1] else {
+            panic!("Not a Send Frame!")
+        };
+        assert_eq!("b", frame.destination().value());
+        assert_eq!(b"\x00\x01\x02\x03hello", sink.body.as_slice());
+        assert_eq!(9, sink.chunks);
+        assert_eq!(2, sink.ends);
+    }
+
+    #[test]
+    fn passes_body_through_in_one_chunk() {
+        let mut decoder = StreamingDecoder::<ClientFrame>::new();
+        let mut sink = CollectingSink::default();
+
+        decoder
+            .feed(b"SEND\ndestination:a\ncontent-length:5\n\n", &mut sink)
+            .unwrap();
+        assert_eq!(1, sink.heads.len());
+
+        decoder.feed(b"hello\x00", &mut sink).unwrap();
+        assert_eq!(1, sink.chunks);
+        assert_eq!(1, sink.ends);
+    }
+
+    #[test]
+    fn recovers_after_error() {
+        let mut decoder = StreamingDecoder::<ClientFrame>::new();
+        let mut sink = CollectingSink::default();
+
+        assert!(decoder
+            .feed(
+                b"SEND\ncontent-length:2\n\nabc\x00BEGIN\ntransaction:t\n\n\x00",
+                &mut sink
+            )
+            .is_err());
+
+        decoder.feed(b"", &mut sink).unwrap();
+
+        assert!(matches!(sink.heads.last(), Some(ClientFrame::Begin(_))));
+    }
+
+    #[test]
+    fn limits_length_of_heads() {
+        let mut decoder = StreamingDecoder::<ClientFrame>::with_config(ParserConfig {
+            max_frame_len: Some(64),
+            ..ParserConfig::default()
+        });
+        let mut sink = CollectingSink::default();
+
+        let head = format!("SEND\ndestination:{}", "a".repeat(100));
+        let errors: Vec<_> = head
+            .as_bytes()
+            .iter()
+            .filter_map(|byte| decoder.feed(std::slice::from_ref(byte), &mut sink).err())
+            .collect();
+        assert_eq!(1, errors.len());
+        assert!(matches!(
+            errors[0].kind(),
+            ErrorKind::FrameTooLarge { limit: 64, command: Some(command), .. } if command == "SEND"
+        ));
+
+        decoder
+            .feed(b"\n\nbody\x00BEGIN\ntransaction:t\n\n\x00", &mut sink)
+            .unwrap();
+
+        assert_eq!(1, sink.heads.len());
+        assert!(matches!(sink.heads[0], ClientFrame::Begin(_)));
+    }
+
+    #[test]
+    fn resets_partial_frame() {
+        let mut decoder = StreamingDecoder::<ClientFrame>::new();
+        let mut sink = CollectingSink::default();
+
+        decoder
+            .feed(b"SEND\ndestination:a\ncontent-length:5\n\nhel", &mut sink)
+            .unwrap();
+        decoder.reset();
+        decoder
+            .feed(b"BEGIN\ntransaction:t\n\n\x00", &mut sink)
+            .unwrap();
+
+        assert!(matches!(sink.heads.last(), Some(ClientFrame::Begin(_))));
+        assert_eq!(1, sink.ends);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn streams_body_asynchronously() {
+        let frame = SendFrameBuilder::new("/queue/a".to_owned()).build_streaming(&b"hello"[..], 5);
+
+        let mut written = Vec::new();
+        frame.write_to_async(&mut written).await.unwrap();
+
+        assert!(written.ends_with(b"content-length:5\n\nhello\x00"));
+    }
+}
@@ -0,0 +1,167 @@
+//! Serialises frames which differ only in a few headers and the body, as sent on hot paths such
+//! as market-data publishing, without formatting the constant part of each frame again.
+//!
+//! # Example
+//! ```
+//! use stomp_parser::client::SendFrameBuilder;
+//!
+//! let template = SendFrameBuilder::new("/topic/prices".to_owned())
+//!     .content_type("application/json".to_owned())
+//!     .template(&["instrument"]);
+//!
+//! let frame = template.render(&["ACME"], Some(b"{\"bid\":1}")).unwrap();
+//! assert_eq!(
+//!     &b"SEND\ndestination:/topic/prices\ncontent-type:application/json\n\
+//!        instrument:ACME\ncontent-length:9\n\n{\"bid\":1}\x00"[..],
+//!     &frame[..]
+//! );
+//! ```
+use crate::common::functions::encode_str;
+use crate::config::ParserConfig;
+use crate::decoder::head_len;
+use crate::error::{ErrorKind, StompParseError};
+
+/// A frame whose command and fixed headers are serialised once, and whose variable headers and
+/// body are supplied each time it is rendered. Obtained from the `template` method of a builder.
+///
+/// Rendered frames with a body have a `content-length` header, so bodies may contain null octets.
+#[derive(Debug, Clone)]
+pub struct FrameTemplate {
+    prefix: Vec<u8>,
+    variable_headers: Vec<Vec<u8>>,
+}
+
+impl FrameTemplate {
+    /// A template with the command and headers of the serialised frame `frame`, followed by the
+    /// headers named in `variable_headers`. Any body and `content-length` header of `frame` are
+    /// ignored, as the `content-length` is set when rendering.
+    pub fn new(frame: &[u8], variable_headers: &[&str]) -> Result<FrameTemplate, StompParseError> {
+        let body_start = head_len(frame, &ParserConfig::default())?
+            .map(|(head_len, _)| head_len)
+            .ok_or_else(|| {
+                StompParseError::with_kind(ErrorKind::Incomplete, "Incomplete frame for template")
+            })?;
+
+        // The blank line ending the headers is omitted, as it follows the variable headers instead
+        let prefix = frame[..body_start]
+            .split_inclusive(|b| *b == b'\n')
+            .filter(|line| {
+                !line.starts_with(b"content-length:") && *line != b"\n" && *line != b"\r\n"
+            })
+            .flatten()
+            .copied()
+            .collect();
+
+        Ok(FrameTemplate {
+            prefix,
+            variable_headers: variable_headers
+                .iter()
+                .map(|name| {
+                    let mut name = encode_str(name).into_bytes();
+                    name.push(b':');
+                    name
+                })
+                .collect(),
+        })
+    }
+
+    /// The number of variable headers, and so of values required to render the template.
+    pub fn variable_header_count(&self) -> usize {
+        self.variable_headers.len()
+    }
+
+    /// Renders the template with the variable headers set to `values`, in the order in which they
+    /// were named, and the body `body`.
+    pub fn render(&self, values: &[&str], body: Option<&[u8]>) -> Result<Vec<u8>, StompParseError> {
+        let mut out = Vec::new();
+        self.render_into(&mut out, values, body)?;
+        Ok(out)
+    }
+
+    /// Like `render`, but appending the frame to `out`, so that a buffer can be reused between
+    /// frames.
+    pub fn render_into(
+        &self,
+        out: &mut Vec<u8>,
+        values: &[&str],
+        body: Option<&[u8]>,
+    ) -> Result<(), StompParseError> {
+        if values.len() != self.variable_headers.len() {
+            return Err(StompParseError::new(format!(
+                "Template requires {} header values, but {} were supplied",
+                self.variable_headers.len(),
+                values.len()
+            )));
+        }
+
+        let values_len: usize = values.iter().map(|value| value.len() + 1).sum();
+        let names_len: usize = self.variable_headers.iter().map(Vec::len).sum();
+        let body_len = body.map_or(0, <[u8]>::len);
+        out.reserve(self.prefix.len() + names_len + values_len + body_len + 40);
+
+        out.extend_from_slice(&self.prefix);
+        for (name, value) in self.variable_headers.iter().zip(values) {
+            out.extend_from_slice(name);
+            if value
+                .bytes()
+                .any(|b| matches!(b, b'\\' | b'\r' | b'\n' | b':'))
+            {
+                out.extend_from_slice(encode_str(value).as_bytes());
+            } else {
+                out.extend_from_slice(value.as_bytes());
+            }
+            out.push(b'\n');
+        }
+
+        if let Some(body) = body {
+            out.extend_from_slice(b"content-length:");
+            out.extend_from_slice(body.len().to_string().as_bytes());
+            out.push(b'\n');
+        }
+        out.push(b'\n');
+        out.extend_from_slice(body.unwrap_or_default());
+        out.push(b'\0');
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use crate::client::{ClientFrame, SendFrameBuilder};
+
+    #[test]
+    fn renders_parseable_frames() {
+        let template = SendFrameBuilder::new("/topic/a".to_owned())
+            .add_custom_header("app".to_owned(), "pricer".to_owned())
+            .content_length(7)
+            .body(b"ignored".to_vec())
+            .template(&["seq", "key"]);
+        assert_eq!(2, template.variable_header_count());
+
+        let mut buffer = Vec::new();
+        for seq in 0..3 {
+            buffer.clear();
+            template
+                .render_into(&mut buffer, &[&seq.to_string(), "a:b"], Some(b"x\x00y"))
+                .unwrap();
+
+            let Ok(ClientFrame::Send(frame)) = ClientFrame::try_from(buffer.clone()) else {
+                panic!("Send Frame not parsed correctly")
+            };
+            assert_eq!("/topic/a", frame.destination().value());
+            assert_eq!(
+                seq.to_string(),
+                *frame.custom_header("seq").unwrap().value()
+            );
+            assert_eq!("a\\cb", *frame.custom_header("key").unwrap().value());
+            assert_eq!(Some(&b"x\x00y"[..]), frame.body());
+        }
+
+        let empty = template.render(&["3", "k"], None).unwrap();
+        assert!(empty.ends_with(b"key:k\n\n\x00"));
+        assert!(template.render(&["3"], None).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Reads and writes frames through blocking read and write functions with any error type, such as
+//! those of `embedded_io::{Read, Write}`, so that gateways on constrained devices can use the
+//! decoder and the serialised forms of frames over their own transports. Bytes are read into a
+//! buffer supplied by the caller, so that its size is bounded.
+//!
+//! With `embedded_io`, for example:
+//!
+//! ```ignore
+//! let item = decoder.read_item(&mut buffer, |buf| uart.read(buf))?;
+//! write_all_with(frame.as_bytes(), |bytes| uart.write(bytes))?;
+//! ```
+use crate::decoder::{DecodableFrame, DecodedItem, FrameDecoder};
+use crate::error::StompParseError;
+use crate::storage::FrameAllocator;
+
+/// An error reading or writing frames through a transport whose errors are of type `E`.
+#[derive(Debug)]
+pub enum TransportError<E> {
+    /// The transport failed.
+    Transport(E),
+    /// The bytes received were not a valid frame.
+    Parse(StompParseError),
+    /// The transport was closed within a frame, or would accept no more bytes of one.
+    Closed,
+}
+
+impl<E> TransportError<E> {
+    /// True unless the error concerns only a frame which was discarded, after which reading can
+    /// continue.
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            TransportError::Parse(error) => error.is_fatal(),
+            _ => true,
+        }
+    }
+}
+
+impl<E> From<StompParseError> for TransportError<E> {
+    fn from(error: StompParseError) -> Self {
+        TransportError::Parse(error)
+    }
+}
+
+impl<F: DecodableFrame<A::Buffer>, A: FrameAllocator> FrameDecoder<F, A> {
+    /// Decodes the next item, reading more bytes into `buffer` with `read` while none is complete.
+    /// `read` returns the number of bytes read, 0 meaning that the transport is closed.
+    ///
+    /// Returns `None` once the transport is closed between frames.
+    pub fn read_item<E, R>(
+        &mut self,
+        buffer: &mut [u8],
+        mut read: R,
+    ) -> Result<Option<DecodedItem<F>>, TransportError<E>>
+    where
+        R: FnMut(&mut [u8]) -> Result<usize, E>,
+    {
+        loop {
+            if let Some(item) = self.decode()? {
+                return Ok(Some(item));
+            }
+
+            match read(buffer).map_err(TransportError::Transport)? {
+                0 if self.is_mid_frame() || self.is_discarding() => {
+                    return Err(TransportError::Closed)
+                }
+                0 => return Ok(None),
+                read => self.extend(&buffer[..read]),
+            }
+        }
+    }
+}
+
+/// Writes all of `bytes`, such as a serialised frame, with `write`, which returns the number of
+/// bytes written.
+pub fn write_all_with<E, W>(mut bytes: &[u8], mut write: W) -> Result<(), TransportError<E>>
+where
+    W: FnMut(&[u8]) -> Result<usize, E>,
+{
+    while !bytes.is_empty() {
+        match write(bytes).map_err(TransportError::Transport)? {
+            0 => return Err(TransportError::Closed),
+            written => bytes = &bytes[written..],
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_all_with, TransportError};
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::decoder::{DecodedItem, FrameDecoder};
+
+    #[derive(Debug, PartialEq)]
+    struct UartError;
+
+    #[test]
+    fn round_trips_through_a_small_buffer() {
+        let frame: Vec<u8> = SendFrameBuilder::new("/queue/a")
+            .body(b"hello".to_vec())
+            .build()
+            .into();
+
+        let mut wire = Vec::new();
+        write_all_with(&frame, |bytes: &[u8]| {
+            let len = bytes.len().min(3);
+            wire.extend_from_slice(&bytes[..len]);
+            Ok::<_, UartError>(len)
+        })
+        .unwrap();
+        assert_eq!(frame, wire);
+
+        let mut received = &wire[..];
+        let mut read = |buf: &mut [u8]| {
+            let len = buf.len().min(received.len());
+            buf[..len].copy_from_slice(&received[..len]);
+            received = &received[len..];
+            Ok::<_, UartError>(len)
+        };
+
+        let mut buffer = [0; 4];
+        let mut decoder = FrameDecoder::<ClientFrame>::new();
+        let Ok(Some(DecodedItem::Frame(ClientFrame::Send(send)))) =
+            decoder.read_item(&mut buffer, &mut read)
+        else {
+            panic!("Send Frame not decoded")
+        };
+        assert_eq!(Some(&b"hello"[..]), send.body());
+        assert!(matches!(
+            decoder.read_item(&mut buffer, &mut read),
+            Ok(None)
+        ));
+
+        assert!(matches!(
+            decoder.read_item(&mut buffer, |_| Err(UartError)),
+            Err(TransportError::Transport(UartError))
+        ));
+        assert!(matches!(
+            write_all_with(b"x", |_| Ok::<_, UartError>(0)),
+            Err(TransportError::Closed)
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+//! Checks frames against the constraints of the specification which go beyond the presence and
+//! syntax of their headers, and so are not enforced when parsing: that the `content-length`
+//! matches the body, for frames parsed with a lenient `ContentLengthPolicy`, and that the frame is
+//! valid for the version of the protocol in use.
+use crate::any::AnyFrame;
+use crate::client::ClientFrame;
+use crate::common::raw::RawParts;
+use crate::headers::{AckType, HeaderName, StompVersion};
+use crate::server::ServerFrame;
+use crate::storage::Storage;
+
+/// A constraint violated by a frame.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Violation {
+    /// The `content-length` header does not match the length of the body.
+    ContentLengthMismatch { declared: usize, actual: usize },
+    /// The `content-length` header is not a number.
+    InvalidContentLength(String),
+    /// The `accept-version` header lists no versions.
+    EmptyAcceptVersion,
+    /// A version header names a version not defined by the specification.
+    UnknownVersion(String),
+    /// The `version` header of a CONNECTED frame is not the version in use.
+    VersionMismatch {
+        declared: StompVersion,
+        expected: StompVersion,
+    },
+    /// The command is not defined in the version in use.
+    CommandNotInVersion {
+        command: String,
+        version: StompVersion,
+    },
+    /// The ack mode of a subscription is not defined in the version in use.
+    AckModeNotInVersion { ack: AckType, version: StompVersion },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::ContentLengthMismatch { declared, actual } => write!(
+                f,
+                "content-length is {}, but the body is {} bytes",
+                declared, actual
+            ),
+            Violation::InvalidContentLength(value) => {
+                write!(f, "content-length '{}' is not a number", value)
+            }
+            Violation::EmptyAcceptVersion => f.write_str("accept-version lists no versions"),
+            Violation::UnknownVersion(version) => write!(f, "unknown version '{}'", version),
+            Violation::VersionMismatch { declared, expected } => {
+                write!(f, "version is {}, but {} is in use", declared, expected)
+            }
+            Violation::CommandNotInVersion { command, version } => {
+                write!(f, "{} is not defined in version {}", command, version)
+            }
+            Violation::AckModeNotInVersion { ack, version } => {
+                write!(f, "ack mode {} is not defined in version {}", ack, version)
+            }
+        }
+    }
+}
+
+impl<S: Storage> ClientFrame<S> {
+    /// Checks the frame against the constraints of the specification not enforced by parsing,
+    /// where `version` is the version of the protocol in use. For a CONNECT frame, which precedes
+    /// the negotiation of the version, this is the version the server would negotiate.
+    pub fn validate(&self, version: &StompVersion) -> Result<(), Vec<Violation>> {
+        let parts = RawParts::split(self.as_bytes());
+        let mut violations = Vec::new();
+
+        check_content_length(&parts, &mut violations);
+        check_command(&parts.command, version, &["NACK", "STOMP"], &mut violations);
+
+        match self {
+            ClientFrame::Connect(frame) => {
+                let versions = frame.accept_version().value();
+                if versions
+                    .iter()
+                    .all(|version| *version == StompVersion::Unknown(String::new()))
+                {
+                    violations.push(Violation::EmptyAcceptVersion);
+                } else {
+                    check_known_versions(versions, &mut violations);
+                }
+            }
+            ClientFrame::Subscribe(frame) => {
+                let ack = frame.ack_type().value();
+                if *version == StompVersion::V1_0 && *ack == AckType::ClientIndividual {
+                    violations.push(Violation::AckModeNotInVersion {
+                        ack: ack.clone(),
+                        version: version.clone(),
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        result(violations)
+    }
+}
+
+impl<S: Storage> ServerFrame<S> {
+    /// Checks the frame against the constraints of the specification not enforced by parsing,
+    /// where `version` is the version of the protocol in use.
+    pub fn validate(&self, version: &StompVersion) -> Result<(), Vec<Violation>> {
+        let parts = RawParts::split(self.as_bytes());
+        let mut violations = Vec::new();
+
+        check_content_length(&parts, &mut violations);
+
+        if let ServerFrame::Connected(frame) = self {
+            let declared = frame.version().value();
+            if let StompVersion::Unknown(_) = declared {
+                check_known_versions(std::slice::from_ref(declared), &mut violations);
+            } else if declared != version {
+                violations.push(Violation::VersionMismatch {
+                    declared: declared.clone(),
+                    expected: version.clone(),
+                });
+            }
+        }
+
+        result(violations)
+    }
+}
+
+impl AnyFrame {
+    /// Checks the frame against the constraints of the specification not enforced by parsing,
+    /// where `version` is the version of the protocol in use.
+    pub fn validate(&self, version: &StompVersion) -> Result<(), Vec<Violation>> {
+        match self {
+            AnyFrame::Client(frame) => frame.validate(version),
+            AnyFrame::Server(frame) => frame.validate(version),
+        }
+    }
+}
+
+fn check_content_length(parts: &RawParts, violations: &mut Vec<Violation>) {
+    if let Some(value) = parts.header(HeaderName::ContentLength.as_str()) {
+        match value.parse::<usize>() {
+            Ok(declared) if declared != parts.body.len() => {
+                violations.push(Violation::ContentLengthMismatch {
+                    declared,
+                    actual: parts.body.len(),
+                })
+            }
+            Ok(_) => {}
+            Err(_) => violations.push(Violation::InvalidContentLength(value.to_owned())),
+        }
+    }
+}
+
+/// Checks that `command` is not one of `since_1_1`, the commands added in version 1.1, if
+/// `version` is 1.0.
+fn check_command(
+    command: &str,
+    version: &StompVersion,
+    since_1_1: &[&str],
+    violations: &mut Vec<Violation>,
+) {
+    if *version == StompVersion::V1_0 && since_1_1.contains(&command) {
+        violations.push(Violation::CommandNotInVersion {
+            command: command.to_owned(),
+            version: version.clone(),
+        });
+    }
+}
+
+fn check_known_versions(versions: &[StompVersion], violations: &mut Vec<Violation>) {
+    violations.extend(versions.iter().filter_map(|version| match version {
+        StompVersion::Unknown(version) => Some(Violation::UnknownVersion(version.clone())),
+        _ => None,
+    }));
+}
+
+fn result(violations: Vec<Violation>) -> Result<(), Vec<Violation>> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use super::Violation;
+    use crate::client::ClientFrame;
+    use crate::config::{ContentLengthPolicy, ParserConfig};
+    use crate::headers::{AckType, StompVersion};
+    use crate::server::ServerFrame;
+
+    fn client_frame(bytes: &[u8]) -> ClientFrame {
+        ClientFrame::try_from(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn accepts_valid_frame() {
+        let frame = client_frame(b"SEND\ndestination:a\ncontent-length:5\n\nhello\x00");
+
+        assert_eq!(Ok(()), frame.validate(&StompVersion::V1_2));
+    }
+
+    #[test]
+    fn reports_content_length_mismatch() {
+        let config = ParserConfig {
+            content_length_policy: ContentLengthPolicy::TrustNull,
+            ..ParserConfig::default()
+        };
+        let frame = ClientFrame::parse_with(
+            b"SEND\ndestination:a\ncontent-length:3\n\nhello\x00".to_vec(),
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(vec![Violation::ContentLengthMismatch {
+                declared: 3,
+                actual: 5
+            }]),
+            frame.validate(&StompVersion::V1_2)
+        );
+    }
+
+    #[test]
+    fn reports_features_not_in_version() {
+        let frame = client_frame(b"SUBSCRIBE\ndestination:a\nid:1\nack:client-individual\n\n\x00");
+        assert_eq!(
+            Err(vec![Violation::AckModeNotInVersion {
+                ack: AckType::ClientIndividual,
+                version: StompVersion::V1_0
+            }]),
+            frame.validate(&StompVersion::V1_0)
+        );
+        assert!(frame.validate(&StompVersion::V1_1).is_ok());
+
+        let frame = client_frame(b"NACK\nid:1\ntransaction:t\n\n\x00");
+        assert!(frame.validate(&StompVersion::V1_0).is_err());
+    }
+
+    #[test]
+    fn reports_bad_versions() {
+        let frame = client_frame(b"CONNECT\nhost:h\naccept-version:1.2,2.0\n\n\x00");
+        assert_eq!(
+            Err(vec![Violation::UnknownVersion("2.0".to_owned())]),
+            frame.validate(&StompVersion::V1_2)
+        );
+
+        let frame = ServerFrame::try_from(b"CONNECTED\nversion:1.1\n\n\x00".to_vec()).unwrap();
+        assert_eq!(
+            Err(vec![Violation::VersionMismatch {
+                declared: StompVersion::V1_1,
+                expected: StompVersion::V1_2
+            }]),
+            frame.validate(&StompVersion::V1_2)
+        );
+    }
+}
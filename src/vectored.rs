@@ -0,0 +1,284 @@
+//! Supports writing frames with vectored IO, so that the body is written directly from its own
+//! buffer rather than being copied into the same buffer as the headers.
+use std::io::{IoSlice, Write};
+
+const TERMINATOR: &[u8] = b"\0";
+
+/// A serialised frame whose headers and body are held in separate buffers; obtained from the
+/// `build_vectored` or `build_with_body` methods of the builders of frames with a body.
+///
+/// The body may be held in any type which provides its bytes. With a reference-counted type, such
+/// as `Arc<[u8]>` or `bytes::Bytes`, a broker delivering one message to many subscribers can build
+/// a frame for each subscriber while sharing a single copy of the body.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VectoredFrame<B = Vec<u8>> {
+    head: Vec<u8>,
+    body: B,
+}
+
+impl<B: AsRef<[u8]>> VectoredFrame<B> {
+    pub(crate) fn new(head: Vec<u8>, body: B) -> VectoredFrame<B> {
+        VectoredFrame { head, body }
+    }
+
+    /// The command and headers, including the blank line which ends them.
+    pub fn head(&self) -> &[u8] {
+        &self.head
+    }
+
+    /// The body, without the terminating null octet.
+    pub fn body(&self) -> &[u8] {
+        self.body.as_ref()
+    }
+
+    /// Consumes the frame, returning the head and the body.
+    pub fn into_parts(self) -> (Vec<u8>, B) {
+        (self.head, self.body)
+    }
+
+    /// The head, body and terminating null octet, for use with `Write::write_vectored`.
+    pub fn as_io_slices(&self) -> [IoSlice<'_>; 3] {
+        [
+            IoSlice::new(&self.head),
+            IoSlice::new(self.body.as_ref()),
+            IoSlice::new(TERMINATOR),
+        ]
+    }
+
+    /// Writes the whole frame to `writer`, using vectored writes.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write_all_vectored(writer, &mut self.as_io_slices())
+    }
+
+    /// Consumes the frame, returning it as a `bytes::Buf`.
+    #[cfg(feature = "bytes")]
+    pub fn into_buf(self) -> EncodedFrame<B> {
+        EncodedFrame::from(self)
+    }
+}
+
+/// A serialised frame, as a `bytes::Buf` over the head, the body and the terminating null octet,
+/// which does not copy the body; for use with, for example, tokio's `AsyncWriteExt::write_all_buf`.
+/// Obtained from `VectoredFrame::into_buf`.
+#[cfg(feature = "bytes")]
+#[derive(Debug, Clone)]
+pub struct EncodedFrame<B = Vec<u8>> {
+    frame: VectoredFrame<B>,
+    position: usize,
+}
+
+#[cfg(feature = "bytes")]
+impl<B: AsRef<[u8]>> EncodedFrame<B> {
+    /// The parts of the frame remaining to be consumed.
+    fn parts(&self) -> impl Iterator<Item = &[u8]> {
+        let mut skip = self.position;
+        IntoIterator::into_iter([&self.frame.head[..], self.frame.body.as_ref(), TERMINATOR])
+            .filter_map(move |part| {
+                let start = skip.min(part.len());
+                skip -= start;
+                Some(&part[start..]).filter(|rest| !rest.is_empty())
+            })
+    }
+
+    /// Consumes the buffer, returning the frame.
+    pub fn into_inner(self) -> VectoredFrame<B> {
+        self.frame
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B: AsRef<[u8]>> From<VectoredFrame<B>> for EncodedFrame<B> {
+    fn from(frame: VectoredFrame<B>) -> Self {
+        EncodedFrame { frame, position: 0 }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl<B: AsRef<[u8]>> bytes::Buf for EncodedFrame<B> {
+    fn remaining(&self) -> usize {
+        self.frame.head.len() + self.frame.body.as_ref().len() + TERMINATOR.len() - self.position
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.parts().next().unwrap_or_default()
+    }
+
+    fn chunks_vectored<'a>(&'a self, dst: &mut [IoSlice<'a>]) -> usize {
+        dst.iter_mut()
+            .zip(self.parts())
+            .map(|(slice, part)| *slice = IoSlice::new(part))
+            .count()
+    }
+
+    fn advance(&mut self, count: usize) {
+        assert!(
+            count <= self.remaining(),
+            "cannot advance past the end of the frame"
+        );
+        self.position += count;
+    }
+}
+
+/// Writes all of `slices` to `writer`, as `Write::write_all_vectored` does (which is not yet
+/// stable).
+pub fn write_all_vectored<W: Write>(
+    writer: &mut W,
+    mut slices: &mut [IoSlice<'_>],
+) -> std::io::Result<()> {
+    while !slices.is_empty() {
+        match writer.write_vectored(slices) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::WriteZero,
+                    "failed to write whole frame",
+                ))
+            }
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(())
+}
+
+/// Splits the serialised frame `raw`, whose body is `body`, into head, body and tail slices.
+pub(crate) fn split_raw<'a>(raw: &'a [u8], body: &[u8]) -> [IoSlice<'a>; 3] {
+    let raw_start = raw.as_ptr() as usize;
+    let body_start = body.as_ptr() as usize;
+
+    let (head_end, body_end) =
+        if !body.is_empty() && body_start >= raw_start && body_start < raw_start + raw.len() {
+            let offset = body_start - raw_start;
+            (offset, offset + body.len())
+        } else {
+            // No body, so the head extends to the terminating null octet
+            let end = raw.iter().rposition(|b| *b == 0).unwrap_or(raw.len());
+            (end, end)
+        };
+
+    [
+        IoSlice::new(&raw[..head_end]),
+        IoSlice::new(&raw[head_end..body_end]),
+        IoSlice::new(&raw[body_end..]),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+
+    use crate::client::{ClientFrame, SendFrameBuilder};
+    use crate::server::MessageFrameBuilder;
+
+    #[test]
+    fn build_vectored_matches_build() {
+        let builder = || {
+            SendFrameBuilder::new("/queue/a".to_owned())
+                .content_type("text/plain".to_owned())
+                .body(b"hello world".to_vec())
+        };
+
+        let vectored = builder().build_vectored();
+        assert_eq!(b"hello world", vectored.body());
+
+        let mut written = Vec::new();
+        vectored.write_to(&mut written).unwrap();
+
+        assert_eq!(builder().build().as_bytes(), written.as_slice());
+    }
+
+    #[test]
+    fn build_with_body_shares_body() {
+        let body: Arc<[u8]> = Arc::from(&b"payload"[..]);
+
+        let frames: Vec<_> = (0..3)
+            .map(|index| {
+                MessageFrameBuilder::new(
+                    format!("msg-{}", index),
+                    "/topic/a".to_owned(),
+                    format!("sub-{}", index),
+                )
+                .build_with_body(body.clone())
+            })
+            .collect();
+
+        assert_eq!(4, Arc::strong_count(&body));
+        assert!(frames
+            .iter()
+            .all(|frame| frame.body().as_ptr() == body.as_ptr()));
+
+        let mut written = Vec::new();
+        frames[1].write_to(&mut written).unwrap();
+        assert_eq!(
+            MessageFrameBuilder::new(
+                "msg-1".to_owned(),
+                "/topic/a".to_owned(),
+                "sub-1".to_owned()
+            )
+            .body(b"payload".to_vec())
+            .build()
+            .as_bytes(),
+            written.as_slice()
+        );
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn build_with_bytes_body() {
+        let body = bytes::Bytes::from_static(b"payload");
+
+        let frame = SendFrameBuilder::new("/queue/a".to_owned()).build_with_body(body.clone());
+
+        assert_eq!(body.as_ptr(), frame.body().as_ptr());
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn encoded_frame_is_buf_over_parts() {
+        use bytes::Buf;
+
+        let body = bytes::Bytes::from_static(b"payload");
+        let mut buf = SendFrameBuilder::new("/queue/a".to_owned())
+            .build_with_body(body.clone())
+            .into_buf();
+
+        let expected = b"SEND\ndestination:/queue/a\n\npayload\x00";
+        assert_eq!(expected.len(), buf.remaining());
+
+        buf.advance(expected.len() - 8);
+        assert_eq!(b"payload\x00".len(), buf.remaining());
+        assert_eq!(body.as_ptr(), buf.chunk().as_ptr());
+
+        let mut slices = [std::io::IoSlice::new(&[]); 3];
+        assert_eq!(2, buf.chunks_vectored(&mut slices));
+        assert_eq!(b"\x00", &*slices[1]);
+
+        buf.advance(3);
+        assert_eq!(b"load\x00", &buf.copy_to_bytes(5)[..]);
+        assert!(!buf.has_remaining());
+    }
+
+    #[test]
+    fn parsed_frame_splits_into_slices() {
+        let bytes = b"SEND\ndestination:a\n\nhello\x00\n".to_vec();
+        let frame = ClientFrame::try_from(bytes).unwrap();
+
+        let slices = frame.as_io_slices();
+
+        assert_eq!(b"SEND\ndestination:a\n\n", &*slices[0]);
+        assert_eq!(b"hello", &*slices[1]);
+        assert_eq!(b"\x00\n", &*slices[2]);
+    }
+
+    #[test]
+    fn frame_without_body_has_empty_body_slice() {
+        let frame = ClientFrame::try_from(b"BEGIN\ntransaction:a\n\n\x00".to_vec()).unwrap();
+
+        let slices = frame.as_io_slices();
+
+        assert_eq!(b"BEGIN\ntransaction:a\n\n", &*slices[0]);
+        assert!(slices[1].is_empty());
+        assert_eq!(b"\x00", &*slices[2]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Procedural macros for the `stomp-parser` crate, which re-exports them when its `derive` feature
+//! is enabled.
+//!
+//! The macros are implemented without any dependencies, so parse only the simple forms of item
+//! they are applied to.
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Implements `TypedCustomHeader` for a tuple struct with a single field, so that the struct
+/// represents the custom header with the specified name:
+///
+/// ```ignore
+/// #[stomp_header(name = "x-priority")]
+/// pub struct Priority(pub u8);
+/// ```
+///
+/// The value of the field is parsed with its implementation of `FromHeaderValue`, and written with
+/// its implementation of `Display`.
+#[proc_macro_attribute]
+pub fn stomp_header(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let result = header_name(attr).and_then(|name| {
+        struct_parts(item.clone())
+            .map(|(struct_name, field_type)| typed_header_impl(&struct_name, field_type, &name))
+    });
+
+    match result {
+        Ok(implementation) => item.into_iter().chain(implementation).collect(),
+        Err((message, span)) => compile_error(&message, span),
+    }
+}
+
+type Error = (String, Span);
+
+/// Extracts the literal `name` from the attribute arguments `name = "..."`.
+fn header_name(attr: TokenStream) -> Result<Literal, Error> {
+    let tokens: Vec<TokenTree> = attr.into_iter().collect();
+
+    match tokens.as_slice() {
+        [TokenTree::Ident(key), TokenTree::Punct(equals), TokenTree::Literal(name)]
+            if key.to_string() == "name"
+                && equals.as_char() == '='
+                && name.to_string().starts_with('"') =>
+        {
+            Ok(name.clone())
+        }
+        _ => Err((
+            "expected `name = \"<header name>\"`".to_owned(),
+            tokens
+                .first()
+                .map_or_else(Span::call_site, |token| token.span()),
+        )),
+    }
+}
+
+/// Extracts the name of the struct, and the type of its single field, from a tuple struct.
+fn struct_parts(item: TokenStream) -> Result<(Ident, TokenStream), Error> {
+    let mut tokens = item.into_iter().peekable();
+
+    // Skip attributes and visibility, up to the struct keyword
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "struct" => break,
+            Some(_) => {}
+            None => {
+                return Err((
+                    "stomp_header can only be applied to a struct".to_owned(),
+                    Span::call_site(),
+                ))
+            }
+        }
+    }
+
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(name)) => name,
+        _ => {
+            return Err((
+                "expected the name of the struct".to_owned(),
+                Span::call_site(),
+            ))
+        }
+    };
+
+    match tokens.next() {
+        Some(TokenTree::Group(fields)) if fields.delimiter() == Delimiter::Parenthesis => {
+            Ok((name, field_type(fields)?))
+        }
+        _ => Err((
+            "stomp_header requires a tuple struct with a single field".to_owned(),
+            name.span(),
+        )),
+    }
+}
+
+/// The type of the single field of a tuple struct, without its visibility.
+fn field_type(fields: Group) -> Result<TokenStream, Error> {
+    let mut tokens: Vec<TokenTree> = fields.stream().into_iter().collect();
+
+    if let Some(TokenTree::Ident(ident)) = tokens.first() {
+        if ident.to_string() == "pub" {
+            tokens.remove(0);
+            if let Some(TokenTree::Group(group)) = tokens.first() {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    tokens.remove(0);
+                }
+            }
+        }
+    }
+
+    if matches!(tokens.last(), Some(TokenTree::Punct(punct)) if punct.as_char() == ',') {
+        tokens.pop();
+    }
+
+    // Any other comma outside of angle brackets separates fields
+    let mut depth = 0;
+    let has_other_fields = tokens.iter().any(|token| match token {
+        TokenTree::Punct(punct) => {
+            match punct.as_char() {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => {}
+            }
+            punct.as_char() == ',' && depth == 0
+        }
+        _ => false,
+    });
+
+    if tokens.is_empty() || has_other_fields {
+        return Err((
+            "stomp_header requires a tuple struct with a single field".to_owned(),
+            fields.span(),
+        ));
+    }
+
+    Ok(tokens.into_iter().collect())
+}
+
+fn typed_header_impl(name: &Ident, field_type: TokenStream, header_name: &Literal) -> TokenStream {
+    let code = format!(
+        "impl ::stomp_parser::extensions::typed::TypedCustomHeader for {name} {{
+            const NAME: &'static str = {header_name};
+
+            fn parse(value: &str) -> ::std::result::Result<Self, ::stomp_parser::error::StompParseError> {{
+                <{field_type} as ::stomp_parser::headers::FromHeaderValue>::from_header_value(value).map({name})
+            }}
+
+            fn to_value(&self) -> ::std::string::String {{
+                ::std::string::ToString::to_string(&self.0)
+            }}
+        }}",
+        name = name,
+        header_name = header_name,
+        field_type = field_type,
+    );
+
+    code.parse().expect("generated implementation is valid")
+}
+
+/// Expands to `compile_error!(message)`, reported at `span`.
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let tokens = vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(Literal::string(message)).into(),
+        )),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ];
+
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            token.set_span(span);
+            token
+        })
+        .collect()
+}